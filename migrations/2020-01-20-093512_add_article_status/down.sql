@@ -0,0 +1,2 @@
+DROP INDEX articles_status_publish_at_idx;
+ALTER TABLE articles DROP COLUMN status, DROP COLUMN publish_at;
@@ -0,0 +1,9 @@
+-- 'draft', 'scheduled', 'published' or 'archived'.
+-- `publish_at` is when an article became (or will become) public: it is NULL for drafts.
+ALTER TABLE articles
+    ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'published',
+    ADD COLUMN publish_at TIMESTAMPTZ;
+
+UPDATE articles SET publish_at = created_at;
+
+CREATE INDEX articles_status_publish_at_idx ON articles (status, publish_at);
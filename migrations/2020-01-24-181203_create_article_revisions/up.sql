@@ -0,0 +1,20 @@
+-- Every change to an article's content is recorded as a revision.
+-- Only the fields changed by an edit are stored, the others are NULL:
+-- the first revision of each article carries its original content.
+CREATE TABLE article_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    article_id VARCHAR(255) NOT NULL,
+    editor_id UUID NOT NULL,
+    title VARCHAR(255),
+    description VARCHAR(1024),
+    body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (article_id) REFERENCES articles(slug) ON DELETE CASCADE,
+    FOREIGN KEY (editor_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX article_revisions_article_id_idx ON article_revisions (article_id, id);
+
+-- Existing articles start their history from their current content.
+INSERT INTO article_revisions (article_id, editor_id, title, description, body, created_at)
+SELECT slug, user_id, title, description, body, updated_at FROM articles;
@@ -0,0 +1,2 @@
+DROP INDEX articles_search_idx;
+DROP FUNCTION article_search_vector(TEXT, TEXT, TEXT);
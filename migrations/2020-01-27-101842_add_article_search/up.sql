@@ -0,0 +1,9 @@
+-- The document searched by `GET /api/articles?q=...`: matches in the title rank higher
+-- than matches in the description, which rank higher than matches in the body.
+CREATE FUNCTION article_search_vector(title TEXT, description TEXT, body TEXT) RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector('english', title), 'A')
+        || setweight(to_tsvector('english', description), 'B')
+        || setweight(to_tsvector('english', body), 'C');
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE INDEX articles_search_idx ON articles USING GIN (article_search_vector(title, description, body));
@@ -0,0 +1 @@
+DROP INDEX articles_created_at_slug_idx;
@@ -0,0 +1,2 @@
+-- Supports keyset pagination of articles listed by creation date (ties broken by slug).
+CREATE INDEX articles_created_at_slug_idx ON articles (created_at DESC, slug);
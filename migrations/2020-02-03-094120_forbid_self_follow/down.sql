@@ -0,0 +1,3 @@
+ALTER TABLE followers DROP CONSTRAINT followers_no_self_follow;
+
+INSERT INTO followers (followed_id, follower_id) SELECT id, id FROM users;
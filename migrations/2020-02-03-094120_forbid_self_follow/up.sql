@@ -0,0 +1,5 @@
+-- Users used to follow themselves, to see their own articles in their feed:
+-- the feed now includes them explicitly.
+DELETE FROM followers WHERE followed_id = follower_id;
+
+ALTER TABLE followers ADD CONSTRAINT followers_no_self_follow CHECK (followed_id <> follower_id);
@@ -0,0 +1,23 @@
+-- A block hides the blocker's articles and comments from the blocked user,
+-- and prevents follows and comments between the two users in both directions.
+CREATE TABLE blocks (
+   blocker_id UUID NOT NULL,
+   blocked_id UUID NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   FOREIGN KEY (blocker_id) REFERENCES users(id) ON DELETE CASCADE,
+   FOREIGN KEY (blocked_id) REFERENCES users(id) ON DELETE CASCADE,
+   PRIMARY KEY (blocker_id, blocked_id),
+   CHECK (blocker_id <> blocked_id)
+);
+CREATE INDEX blocks_blocked_id_idx ON blocks (blocked_id);
+
+-- A mute hides the muted user's content from the muter's feed and comment lists.
+CREATE TABLE mutes (
+   muter_id UUID NOT NULL,
+   muted_id UUID NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   FOREIGN KEY (muter_id) REFERENCES users(id) ON DELETE CASCADE,
+   FOREIGN KEY (muted_id) REFERENCES users(id) ON DELETE CASCADE,
+   PRIMARY KEY (muter_id, muted_id),
+   CHECK (muter_id <> muted_id)
+);
@@ -0,0 +1,5 @@
+-- Placeholder author for the articles and comments of anonymized accounts.
+-- Its password is not a valid hash: nobody can log in as the deleted user.
+INSERT INTO users (id, username, email, password)
+VALUES ('00000000-0000-0000-0000-000000000000', 'deleted-user', 'deleted-user', '!')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,13 @@
+-- Only a hash of each token is stored: the token itself is sent by email to the user.
+CREATE TABLE password_reset_tokens (
+   token_hash VARCHAR(64) PRIMARY KEY,
+   user_id UUID NOT NULL,
+   expires_at TIMESTAMPTZ NOT NULL,
+   used_at TIMESTAMPTZ,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX password_reset_tokens_user_id_idx ON password_reset_tokens (user_id);
+
+-- Authentication tokens issued before this point in time are rejected.
+ALTER TABLE users ADD COLUMN sessions_revoked_at TIMESTAMPTZ;
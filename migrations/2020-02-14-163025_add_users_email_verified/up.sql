@@ -0,0 +1,4 @@
+-- New accounts have to prove they own their email address before publishing or commenting.
+ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT false;
+-- Existing accounts are trusted.
+UPDATE users SET email_verified = true;
@@ -0,0 +1,13 @@
+-- The TOTP secret of the users who enrolled in two-factor authentication.
+-- It is only enforced once confirmed, i.e. once the user proved their authenticator app works.
+CREATE TABLE two_factor_credentials (
+   user_id UUID PRIMARY KEY,
+   secret VARCHAR NOT NULL,
+   confirmed_at TIMESTAMPTZ,
+   -- The time step of the last accepted code: codes cannot be replayed
+   last_used_step BIGINT,
+   -- Only the hashes of the unused recovery codes are stored
+   recovery_code_hashes TEXT[] NOT NULL DEFAULT '{}',
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
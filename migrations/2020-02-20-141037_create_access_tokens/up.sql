@@ -0,0 +1,13 @@
+-- Long-lived tokens users create for automation, limited to a set of scopes.
+-- Only a hash of each token is stored: the token itself is shown once, when it is created.
+CREATE TABLE access_tokens (
+   id UUID PRIMARY KEY,
+   user_id UUID NOT NULL,
+   name VARCHAR NOT NULL,
+   token_hash VARCHAR(64) NOT NULL UNIQUE,
+   scopes TEXT[] NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   last_used_at TIMESTAMPTZ,
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX access_tokens_user_id_idx ON access_tokens (user_id);
@@ -0,0 +1,22 @@
+-- The accounts of our users with external identity providers (OpenID Connect).
+CREATE TABLE user_identities (
+   provider VARCHAR NOT NULL,
+   -- The identifier of the user for the provider (the `sub` claim)
+   subject VARCHAR NOT NULL,
+   user_id UUID NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   PRIMARY KEY (provider, subject),
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX user_identities_user_id_idx ON user_identities (user_id);
+
+-- The logins started with an external identity provider, waiting for its callback.
+CREATE TABLE oidc_login_attempts (
+   state VARCHAR PRIMARY KEY,
+   provider VARCHAR NOT NULL,
+   nonce VARCHAR NOT NULL,
+   -- The PKCE verifier: only its hash is sent to the provider
+   code_verifier VARCHAR NOT NULL,
+   expires_at TIMESTAMPTZ NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
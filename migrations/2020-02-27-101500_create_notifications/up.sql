@@ -0,0 +1,21 @@
+-- What happened to a user's account or articles: someone followed them, commented on
+-- or favorited one of their articles.
+CREATE TABLE notifications (
+   id BIGSERIAL PRIMARY KEY,
+   recipient_id UUID NOT NULL,
+   -- The user who followed, commented or favorited
+   actor_id UUID NOT NULL,
+   kind VARCHAR NOT NULL CHECK (kind IN ('follow', 'comment', 'favorite')),
+   article_id VARCHAR(255),
+   comment_id BIGINT,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+   read_at TIMESTAMPTZ,
+   FOREIGN KEY (recipient_id) REFERENCES users(id) ON DELETE CASCADE,
+   FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE CASCADE,
+   FOREIGN KEY (article_id) REFERENCES articles(slug) ON DELETE CASCADE,
+   FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE,
+   CHECK ((kind = 'follow') = (article_id IS NULL)),
+   CHECK ((kind = 'comment') = (comment_id IS NOT NULL))
+);
+CREATE INDEX notifications_recipient_id_idx ON notifications (recipient_id, id);
+CREATE INDEX notifications_unread_idx ON notifications (recipient_id) WHERE read_at IS NULL;
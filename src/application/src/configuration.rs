@@ -1,12 +1,16 @@
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 use std::env;
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize)]
 pub struct Application {
     pub host: String,
     pub port: usize,
+    /// The reverse proxies in front of the application, whose `X-Forwarded-For` is trusted.
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +34,225 @@ impl Postgres {
 #[derive(Debug, Deserialize)]
 pub struct Authentication {
     pub secret: String,
+    pub password_hashing: PasswordHashingSettings,
+    pub login_throttling: LoginThrottlingSettings,
+    /// The OpenID Connect providers users can log in with.
+    #[serde(default)]
+    pub identity_providers: Vec<IdentityProviderSettings>,
+}
+
+/// The algorithm, and its parameters, used to hash new passwords.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum PasswordHashingSettings {
+    Bcrypt {
+        cost: u32,
+    },
+    Argon2id {
+        /// In KiB.
+        memory_cost: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl From<PasswordHashingSettings> for domain::PasswordHashing {
+    fn from(s: PasswordHashingSettings) -> Self {
+        match s {
+            PasswordHashingSettings::Bcrypt { cost } => domain::PasswordHashing::Bcrypt { cost },
+            PasswordHashingSettings::Argon2id {
+                memory_cost,
+                iterations,
+                parallelism,
+            } => domain::PasswordHashing::Argon2id {
+                memory_cost,
+                iterations,
+                parallelism,
+            },
+        }
+    }
+}
+
+/// How many failed logins are tolerated before locking out further attempts.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LoginThrottlingSettings {
+    pub max_attempts_per_ip: u32,
+    pub max_attempts_per_account: u32,
+    pub window_seconds: u64,
+    pub lockout_seconds: u64,
+}
+
+impl From<LoginThrottlingSettings> for web::throttle::LoginThrottling {
+    fn from(s: LoginThrottlingSettings) -> Self {
+        web::throttle::LoginThrottling {
+            max_attempts_per_ip: s.max_attempts_per_ip,
+            max_attempts_per_account: s.max_attempts_per_account,
+            window: Duration::from_secs(s.window_seconds),
+            lockout: Duration::from_secs(s.lockout_seconds),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitSettings {
+    pub requests: u32,
+    pub per_seconds: u64,
+}
+
+impl From<RateLimitSettings> for web::rate_limit::RateLimit {
+    fn from(s: RateLimitSettings) -> Self {
+        web::rate_limit::RateLimit {
+            requests: s.requests,
+            per: Duration::from_secs(s.per_seconds),
+        }
+    }
+}
+
+/// The rate limit applied to each group of endpoints, per user or per IP for anonymous callers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitsSettings {
+    pub articles: RateLimitSettings,
+    pub comments: RateLimitSettings,
+    pub favorites: RateLimitSettings,
+    pub default: RateLimitSettings,
+}
+
+impl From<RateLimitsSettings> for web::rate_limit::RateLimits {
+    fn from(s: RateLimitsSettings) -> Self {
+        web::rate_limit::RateLimits {
+            articles: s.articles.into(),
+            comments: s.comments.into(),
+            favorites: s.favorites.into(),
+            default: s.default.into(),
+        }
+    }
+}
+
+/// An OpenID Connect provider, registered with us as a confidential client.
+#[derive(Debug, Deserialize, Clone)]
+pub struct IdentityProviderSettings {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_endpoint: String,
+    /// Must be an `https` URL, like `jwks_uri`.
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    /// A PEM certificate to trust on top of the system ones, for private certificate authorities.
+    #[serde(default)]
+    pub ca_certificate: Option<String>,
+    pub redirect_uri: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+impl From<IdentityProviderSettings> for web::oidc::OidcProvider {
+    fn from(s: IdentityProviderSettings) -> Self {
+        web::oidc::OidcProvider {
+            name: s.name,
+            issuer: s.issuer,
+            client_id: s.client_id,
+            client_secret: s.client_secret,
+            authorization_endpoint: s.authorization_endpoint,
+            token_endpoint: s.token_endpoint,
+            jwks_uri: s.jwks_uri,
+            ca_certificate: s.ca_certificate,
+            redirect_uri: s.redirect_uri,
+            scopes: s.scopes,
+        }
+    }
+}
+
+/// Which websites may call the API from a browser.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorsSettings {
+    /// `*` allows any origin.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    #[serde(default)]
+    pub exposed_headers: Vec<String>,
+    pub max_age_seconds: u64,
+    pub allow_credentials: bool,
+}
+
+impl From<CorsSettings> for web::cors::CorsPolicy {
+    fn from(s: CorsSettings) -> Self {
+        let allowed_origins = if s.allowed_origins.iter().any(|o| o == "*") {
+            web::cors::AllowedOrigins::Any
+        } else {
+            web::cors::AllowedOrigins::List(s.allowed_origins)
+        };
+        web::cors::CorsPolicy {
+            allowed_origins,
+            allowed_methods: s.allowed_methods,
+            allowed_headers: s.allowed_headers,
+            exposed_headers: s.exposed_headers,
+            max_age: Duration::from_secs(s.max_age_seconds),
+            allow_credentials: s.allow_credentials,
+        }
+    }
+}
+
+/// How the notification stream of each connected client looks for new notifications.
+#[derive(Debug, Deserialize, Clone)]
+pub struct NotificationStreamSettings {
+    pub poll_interval_milliseconds: u64,
+    pub keep_alive_seconds: u64,
+}
+
+impl From<NotificationStreamSettings> for web::notifications::stream::NotificationStream {
+    fn from(s: NotificationStreamSettings) -> Self {
+        web::notifications::stream::NotificationStream {
+            poll_interval: Duration::from_millis(s.poll_interval_milliseconds),
+            keep_alive: Duration::from_secs(s.keep_alive_seconds),
+        }
+    }
+}
+
+/// Where the emails for our users end up.
+/// There is no default: each environment has to choose one explicitly.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum MailerSettings {
+    /// Emails are logged in full, secrets included: for local development only.
+    Log,
+    File {
+        directory: PathBuf,
+    },
+    /// Emails are delivered by an SMTP server, over TLS.
+    Smtp {
+        host: String,
+        /// `465` for implicit TLS, anything else (usually `587`) for `STARTTLS`.
+        port: u16,
+        username: String,
+        password: String,
+        /// The address our emails are sent from.
+        from: String,
+    },
+}
+
+/// Where the spans of distributed traces are exported.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "exporter", rename_all = "lowercase")]
+pub enum TracingSettings {
+    /// Traces are propagated, but not recorded.
+    None,
+    Stdout,
+    /// An OpenTelemetry collector, accepting OTLP over HTTP in JSON.
+    Otlp {
+        /// E.g. `http://localhost:4318/v1/traces`.
+        endpoint: String,
+        service_name: String,
+    },
+}
+
+/// Who may scrape the metrics of the service.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct MetricsSettings {
+    /// The bearer token Prometheus has to present: without one, metrics are not served.
+    pub token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,6 +260,13 @@ pub struct Settings {
     pub application: Application,
     pub database: Postgres,
     pub authentication: Authentication,
+    pub mailer: MailerSettings,
+    pub rate_limits: RateLimitsSettings,
+    pub tracing: TracingSettings,
+    pub cors: CorsSettings,
+    pub notification_stream: NotificationStreamSettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
 }
 
 impl Settings {
@@ -61,4 +291,25 @@ impl Settings {
         // Deserialize (and thus freeze) the entire configuration as
         s.try_into()
     }
+
+    /// The subset of the settings the web application cares about.
+    pub fn app_settings(&self) -> web::AppSettings {
+        web::AppSettings {
+            password_hashing: self.authentication.password_hashing.clone().into(),
+            login_throttling: self.authentication.login_throttling.clone().into(),
+            rate_limits: self.rate_limits.clone().into(),
+            oidc_providers: self
+                .authentication
+                .identity_providers
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+            cors: self.cors.clone().into(),
+            notification_stream: self.notification_stream.clone().into(),
+            trusted_proxies: self.application.trusted_proxies.clone(),
+            metrics_token: self.metrics.token.clone(),
+            token_secret: self.authentication.secret.clone(),
+        }
+    }
 }
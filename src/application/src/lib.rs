@@ -1 +1,4 @@
 pub mod configuration;
+pub mod logging;
+pub mod mailer;
+pub mod tracing;
@@ -0,0 +1,45 @@
+//! Logs are written to stderr as JSON lines, tagged with the IDs of the request being served
+//! and of its trace.
+use log::Record;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use web::request_log::{current_request_id, ACCESS_LOG_TARGET};
+
+/// The JSON object describing `record`.
+///
+/// The fields of access log records, already JSON objects, are inlined rather than nested
+/// in `message`.
+fn to_json(record: &Record, timestamp: String) -> Value {
+    let mut line = Map::new();
+    line.insert("timestamp".into(), timestamp.into());
+    line.insert("level".into(), record.level().to_string().into());
+    line.insert("target".into(), record.target().into());
+    let message = record.args().to_string();
+    match serde_json::from_str::<Map<String, Value>>(&message) {
+        Ok(fields) if record.target() == ACCESS_LOG_TARGET => line.extend(fields),
+        _ => {
+            line.insert("message".into(), message.into());
+        }
+    }
+    if !line.contains_key("request_id") {
+        if let Some(request_id) = current_request_id() {
+            line.insert("request_id".into(), request_id.into());
+        }
+    }
+    if !line.contains_key("trace_id") {
+        if let Some(trace_id) = telemetry::current_trace_id() {
+            line.insert("trace_id".into(), trace_id.to_string().into());
+        }
+    }
+    json!(line)
+}
+
+/// Install the logger, filtered by `RUST_LOG` as usual.
+pub fn init() {
+    env_logger::Builder::from_default_env()
+        .format(|buf, record| {
+            let timestamp = buf.timestamp().to_string();
+            writeln!(buf, "{}", to_json(record, timestamp))
+        })
+        .init();
+}
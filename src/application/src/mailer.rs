@@ -0,0 +1,153 @@
+use crate::configuration::MailerSettings;
+use chrono::Utc;
+use domain::{Email, Mailer, MailerError};
+use lettre::smtp::authentication::Credentials;
+use lettre::{
+    ClientSecurity, ClientTlsParameters, EmailAddress, Envelope, SendableEmail, SmtpClient,
+    Transport,
+};
+use log::info;
+use native_tls::TlsConnector;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Build the mailer specified in the configuration.
+pub fn from_settings(settings: &MailerSettings) -> Box<dyn Mailer + Send + Sync> {
+    match settings {
+        MailerSettings::Log => Box::new(LogMailer),
+        MailerSettings::File { directory } => Box::new(FileMailer {
+            directory: directory.to_owned(),
+        }),
+        MailerSettings::Smtp {
+            host,
+            port,
+            username,
+            password,
+            from,
+        } => Box::new(SmtpMailer {
+            host: host.to_owned(),
+            port: *port,
+            credentials: Credentials::new(username.to_owned(), password.to_owned()),
+            from: from.to_owned(),
+        }),
+    }
+}
+
+/// Emails are not sent: they are logged, which is handy for local development.
+///
+/// The password reset and email verification tokens end up in the logs:
+/// never use it in production.
+#[derive(Clone, Debug, Default)]
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, email: Email) -> Result<(), MailerError> {
+        info!("Email to {} - {}\n{}", email.to, email.subject, email.body);
+        Ok(())
+    }
+}
+
+/// Emails are not sent: each of them is written to a separate file in `directory`.
+#[derive(Clone, Debug)]
+pub struct FileMailer {
+    pub directory: PathBuf,
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: Email) -> Result<(), MailerError> {
+        let sent_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(anyhow::Error::from)?
+            .as_nanos();
+        let path = self.directory.join(format!("{}-{}.eml", sent_at, email.to));
+        let content = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            email.to, email.subject, email.body
+        );
+        fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(path, content))
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+}
+
+/// Emails are handed over to an SMTP server, over TLS.
+#[derive(Clone, Debug)]
+pub struct SmtpMailer {
+    pub host: String,
+    /// `465` for implicit TLS, anything else for `STARTTLS`.
+    pub port: u16,
+    pub credentials: Credentials,
+    pub from: String,
+}
+
+impl SmtpMailer {
+    /// A connection is opened for each email: emails are few and far between.
+    fn client(&self) -> Result<SmtpClient, anyhow::Error> {
+        let connector = TlsConnector::new()?;
+        let tls = ClientTlsParameters::new(self.host.to_owned(), connector);
+        let security = if self.port == 465 {
+            ClientSecurity::Wrapper(tls)
+        } else {
+            ClientSecurity::Required(tls)
+        };
+        let client = SmtpClient::new((self.host.as_str(), self.port), security)?
+            .credentials(self.credentials.clone());
+        Ok(client)
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: Email) -> Result<(), MailerError> {
+        let envelope = Envelope::new(
+            Some(EmailAddress::new(self.from.to_owned()).map_err(anyhow::Error::from)?),
+            vec![EmailAddress::new(email.to.to_owned()).map_err(anyhow::Error::from)?],
+        )
+        .map_err(anyhow::Error::from)?;
+        // Only used by lettre to identify the email in its logs
+        let message_id = format!("{}-{}", Utc::now().timestamp_nanos(), email.to);
+        let message = to_message(&self.from, &email);
+        self.client()?
+            .transport()
+            .send(SendableEmail::new(envelope, message_id, message))
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+}
+
+/// A plain text message, with the line endings SMTP expects.
+fn to_message(from: &str, email: &Email) -> Vec<u8> {
+    let headers = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        email.to,
+        email.subject,
+        Utc::now().to_rfc2822()
+    );
+    let body = email.body.replace("\r\n", "\n").replace('\n', "\r\n");
+    format!("{}{}\r\n", headers, body).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_use_crlf_line_endings() {
+        let email = Email {
+            to: "ferris@example.com".into(),
+            subject: "Reset your password".into(),
+            body: "Hi!\n\nUse this token: abc\r\n".into(),
+        };
+
+        let message = String::from_utf8(to_message("conduit@example.com", &email)).unwrap();
+        let (headers, body) = message.split_at(message.find("\r\n\r\n").unwrap());
+        assert!(headers.starts_with(
+            "From: conduit@example.com\r\nTo: ferris@example.com\r\nSubject: Reset your password\r\n"
+        ));
+        assert_eq!(body, "\r\n\r\nHi!\r\n\r\nUse this token: abc\r\n\r\n");
+        assert!(!message.replace("\r\n", "").contains('\n'));
+    }
+}
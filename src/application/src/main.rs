@@ -1,22 +1,31 @@
 use async_std::task::block_on;
 use db::{connection::Repo, Repository};
 use realworld_application::configuration::Settings;
+use realworld_application::{logging, mailer, tracing};
 use std::path::PathBuf;
+use web::client_address::serve;
 use web::get_app;
 
 fn main() -> Result<(), std::io::Error> {
     let settings = Settings::new(PathBuf::default()).expect("Failed to load configuration");
-    env_logger::init();
+    logging::init();
+    if let Some(exporter) = tracing::exporter_from_settings(&settings.tracing) {
+        telemetry::install(exporter);
+    }
 
     let state = Repository(Repo::new(&settings.database.connection_string()));
-    let app = get_app(state);
+    let app = get_app(
+        state,
+        mailer::from_settings(&settings.mailer),
+        settings.app_settings(),
+    );
     let address = format!(
         "{}:{}",
         settings.application.host, settings.application.port
     );
 
     block_on(async {
-        app.listen(address).await?;
+        serve(app.into_http_service(), address).await?;
         Ok(())
     })
 }
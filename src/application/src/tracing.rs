@@ -0,0 +1,15 @@
+use crate::configuration::TracingSettings;
+use std::sync::Arc;
+use telemetry::{OtlpExporter, SpanExporter, StdoutExporter};
+
+/// Build the span exporter specified in the configuration, if any.
+pub fn exporter_from_settings(settings: &TracingSettings) -> Option<Arc<dyn SpanExporter>> {
+    match settings {
+        TracingSettings::None => None,
+        TracingSettings::Stdout => Some(Arc::new(StdoutExporter::default())),
+        TracingSettings::Otlp {
+            endpoint,
+            service_name,
+        } => Some(Arc::new(OtlpExporter::new(endpoint, service_name))),
+    }
+}
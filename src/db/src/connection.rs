@@ -1,5 +1,6 @@
 use diesel::r2d2::ConnectionManager;
 use diesel::Connection;
+use metrics::{DB_POOL_CONNECTIONS, DB_POOL_WAIT_DURATION};
 use r2d2::{Pool, PooledConnection};
 
 /// A database "repository", for running database workloads.
@@ -34,6 +35,14 @@ where
     }
 
     pub fn conn(&self) -> PooledConnection<ConnectionManager<T>> {
-        self.connection_pool.get().unwrap()
+        let connection = {
+            let _timer = DB_POOL_WAIT_DURATION.start_timer(&[]);
+            self.connection_pool.get().unwrap()
+        };
+        let state = self.connection_pool.state();
+        let idle = f64::from(state.idle_connections);
+        DB_POOL_CONNECTIONS.set(&["idle"], idle);
+        DB_POOL_CONNECTIONS.set(&["in_use"], f64::from(state.connections) - idle);
+        connection
     }
 }
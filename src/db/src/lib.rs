@@ -7,8 +7,9 @@ pub mod queries;
 pub mod repository;
 pub mod schema;
 pub mod shims;
+pub mod tracing;
 
-use diesel::PgConnection;
 pub use repository::Repository;
+use tracing::TracedConnection;
 
-pub type Repo = connection::Repo<PgConnection>;
+pub type Repo = connection::Repo<TracedConnection>;
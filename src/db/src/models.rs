@@ -1,7 +1,16 @@
+use crate::schema::access_tokens;
+use crate::schema::article_revisions;
 use crate::schema::articles;
+use crate::schema::blocks;
 use crate::schema::comments;
 use crate::schema::favorites;
 use crate::schema::followers;
+use crate::schema::mutes;
+use crate::schema::notifications;
+use crate::schema::oidc_login_attempts;
+use crate::schema::password_reset_tokens;
+use crate::schema::two_factor_credentials;
+use crate::schema::user_identities;
 use crate::schema::users;
 use chrono::{DateTime, Utc};
 use diesel::{AsChangeset, Insertable, Queryable};
@@ -18,6 +27,8 @@ pub struct User {
     pub image: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub sessions_revoked_at: Option<DateTime<Utc>>,
+    pub email_verified: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, AsChangeset, Default, Clone)]
@@ -28,6 +39,7 @@ pub struct UpdateUser<'a> {
     pub password: Option<String>,
     pub image: Option<&'a str>,
     pub bio: Option<&'a str>,
+    pub email_verified: Option<bool>,
 }
 
 #[derive(Queryable, Serialize, Deserialize, Debug, PartialEq)]
@@ -40,6 +52,8 @@ pub struct Article {
     pub user_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub status: String,
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable, Deserialize, Debug, Clone)]
@@ -60,6 +74,8 @@ pub struct NewArticle<'a> {
     pub body: &'a str,
     pub tag_list: Vec<String>,
     pub user_id: Uuid,
+    pub status: &'a str,
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 #[derive(AsChangeset, Deserialize, Debug, Clone)]
@@ -68,6 +84,8 @@ pub struct UpdateArticle<'a> {
     pub title: Option<&'a str>,
     pub description: Option<&'a str>,
     pub body: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub publish_at: Option<Option<DateTime<Utc>>>,
 }
 
 #[derive(Insertable, Deserialize, Debug, Clone)]
@@ -84,6 +102,20 @@ pub struct NewFollower {
     pub follower_id: Uuid,
 }
 
+#[derive(Insertable, Deserialize, Debug, Clone)]
+#[table_name = "blocks"]
+pub struct NewBlock {
+    pub blocker_id: Uuid,
+    pub blocked_id: Uuid,
+}
+
+#[derive(Insertable, Deserialize, Debug, Clone)]
+#[table_name = "mutes"]
+pub struct NewMute {
+    pub muter_id: Uuid,
+    pub muted_id: Uuid,
+}
+
 #[derive(Insertable, Deserialize, Debug, Clone)]
 #[table_name = "comments"]
 pub struct NewComment<'a> {
@@ -101,3 +133,146 @@ pub struct Comment {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+#[derive(Insertable, Deserialize, Debug, Clone)]
+#[table_name = "article_revisions"]
+pub struct NewArticleRevision<'a> {
+    pub article_id: &'a str,
+    pub editor_id: Uuid,
+    pub title: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub body: Option<&'a str>,
+}
+
+impl<'a> NewArticleRevision<'a> {
+    /// The first revision of an article, carrying all its content.
+    pub fn initial(article: &'a Article) -> Self {
+        Self {
+            article_id: &article.slug,
+            editor_id: article.user_id,
+            title: Some(&article.title),
+            description: Some(&article.description),
+            body: Some(&article.body),
+        }
+    }
+
+    /// A revision carrying the fields that differ between `before` and `after`.
+    pub fn between(before: &Article, after: &'a Article, editor_id: Uuid) -> Self {
+        fn changed<'a>(before: &str, after: &'a str) -> Option<&'a str> {
+            if before != after {
+                Some(after)
+            } else {
+                None
+            }
+        }
+        Self {
+            article_id: &after.slug,
+            editor_id,
+            title: changed(&before.title, &after.title),
+            description: changed(&before.description, &after.description),
+            body: changed(&before.body, &after.body),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.body.is_none()
+    }
+}
+
+#[derive(Queryable, Deserialize, Debug, Clone)]
+pub struct ArticleRevision {
+    pub id: i64,
+    pub article_id: String,
+    pub editor_id: Uuid,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "password_reset_tokens"]
+pub struct NewPasswordResetToken<'a> {
+    pub token_hash: &'a str,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct TwoFactorCredentials {
+    pub user_id: Uuid,
+    /// Base32-encoded.
+    pub secret: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub recovery_code_hashes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "two_factor_credentials"]
+pub struct NewTwoFactorCredentials<'a> {
+    pub user_id: Uuid,
+    pub secret: &'a str,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "access_tokens"]
+pub struct NewAccessToken<'a> {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: Vec<&'static str>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "user_identities"]
+pub struct NewUserIdentity<'a> {
+    pub provider: &'a str,
+    pub subject: &'a str,
+    pub user_id: Uuid,
+}
+
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[table_name = "oidc_login_attempts"]
+pub struct OidcLoginAttempt {
+    pub state: String,
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct Notification {
+    pub id: i64,
+    pub recipient_id: Uuid,
+    pub actor_id: Uuid,
+    pub kind: String,
+    pub article_id: Option<String>,
+    pub comment_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "notifications"]
+pub struct NewNotification<'a> {
+    pub recipient_id: Uuid,
+    pub actor_id: Uuid,
+    pub kind: &'a str,
+    pub article_id: Option<&'a str>,
+    pub comment_id: Option<i64>,
+}
@@ -0,0 +1,55 @@
+use crate::models::{AccessToken, NewAccessToken};
+use crate::schema::access_tokens;
+use crate::Repo;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+pub fn insert(repo: &Repo, token: NewAccessToken) -> Result<AccessToken, Error> {
+    diesel::insert_into(access_tokens::table)
+        .values(&token)
+        .get_result(&repo.conn())
+}
+
+pub fn find_by_user(repo: &Repo, owner_id: Uuid) -> Result<Vec<AccessToken>, Error> {
+    use crate::schema::access_tokens::dsl::*;
+
+    access_tokens
+        .filter(user_id.eq(owner_id))
+        .order(created_at.desc())
+        .load(&repo.conn())
+}
+
+/// It fails with `Error::NotFound` if the user has no such token.
+pub fn delete(repo: &Repo, owner_id: Uuid, token_id: Uuid) -> Result<(), Error> {
+    use crate::schema::access_tokens::dsl::*;
+
+    let deleted = diesel::delete(
+        access_tokens
+            .filter(id.eq(token_id))
+            .filter(user_id.eq(owner_id)),
+    )
+    .execute(&repo.conn())?;
+    match deleted {
+        0 => Err(Error::NotFound),
+        _ => Ok(()),
+    }
+}
+
+pub fn find_by_hash(repo: &Repo, hash: &str) -> Result<AccessToken, Error> {
+    use crate::schema::access_tokens::dsl::*;
+
+    access_tokens
+        .filter(token_hash.eq(hash))
+        .first(&repo.conn())
+}
+
+/// Find a token by hash, recording that it has just been used.
+pub fn find_and_touch(repo: &Repo, hash: &str) -> Result<AccessToken, Error> {
+    use crate::schema::access_tokens::dsl::*;
+
+    diesel::update(access_tokens.filter(token_hash.eq(hash)))
+        .set(last_used_at.eq(Utc::now()))
+        .get_result(&repo.conn())
+}
@@ -1,32 +1,55 @@
-use crate::models::{Article, NewArticle, UpdateArticle, User};
+use crate::models::{Article, NewArticle, NewArticleRevision, UpdateArticle, User};
 use crate::queries::favorites::n_favorites;
+use crate::queries::revisions;
 use crate::schema::articles;
 use crate::shims::to_article;
 use crate::Repo;
+use chrono::Utc;
+use diesel::dsl::sql;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error;
 use diesel::sql_query;
-use domain::ArticleQuery;
-use std::collections::HashSet;
+use diesel::sql_types::{Array, BigInt, Bool, Float, Text, Timestamptz};
+use domain::{ArticleCursor, ArticleQuery, ArticleSort};
+use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 use uuid::Uuid;
 
+/// Insert a new article, recording its content as its first revision.
 pub fn insert(repo: &Repo, article: NewArticle) -> Result<Article, Error> {
-    diesel::insert_into(articles::table)
-        .values(&article)
-        .get_result(&repo.conn())
+    let conn = repo.conn();
+    conn.transaction(|| {
+        let article: Article = diesel::insert_into(articles::table)
+            .values(&article)
+            .get_result(&conn)?;
+        revisions::insert(&conn, NewArticleRevision::initial(&article))?;
+        Ok(article)
+    })
 }
 
+/// Update an article, recording the content changes (if any) as a new revision
+/// authored by `editor_id`.
 pub fn update(
     repo: &Repo,
     article_update: UpdateArticle,
     slug_value: &str,
+    editor_id: Uuid,
 ) -> Result<Article, Error> {
     use crate::schema::articles::dsl::{articles, slug};
 
-    diesel::update(articles.filter(slug.eq(slug_value)))
-        .set(&article_update)
-        .get_result(&repo.conn())
+    let conn = repo.conn();
+    conn.transaction(|| {
+        let before: Article = articles.find(slug_value).for_update().first(&conn)?;
+        let after: Article = diesel::update(articles.filter(slug.eq(slug_value)))
+            .set(&article_update)
+            .get_result(&conn)?;
+        let revision = NewArticleRevision::between(&before, &after, editor_id);
+        if !revision.is_empty() {
+            revisions::insert(&conn, revision)?;
+        }
+        Ok(after)
+    })
 }
 
 pub fn delete(repo: &Repo, slug_value: &str) -> Result<(), Error> {
@@ -38,13 +61,75 @@ pub fn delete(repo: &Repo, slug_value: &str) -> Result<(), Error> {
         .map(|_| ())
 }
 
-pub fn find(repo: &Repo, query: ArticleQuery) -> Result<Vec<(Article, User, u64)>, Error> {
+/// Values of the `status` column for articles that are public once `publish_at` has passed.
+const PUBLIC_STATUSES: [&str; 2] = ["published", "scheduled"];
+
+/// The condition selecting the articles in the page identified by `cursor`, for a listing
+/// sorted by creation date (ties broken by ascending slug).
+fn keyset_condition<QS>(
+    cursor: &ArticleCursor,
+    newest_first: bool,
+) -> Box<dyn BoxableExpression<QS, Pg, SqlType = Bool>> {
+    let (position, forwards) = match cursor {
+        ArticleCursor::After(position) => (position, true),
+        ArticleCursor::Before(position) => (position, false),
+    };
+    let created_at_operator = if forwards == newest_first { "<" } else { ">" };
+    let slug_operator = if forwards { ">" } else { "<" };
+    Box::new(
+        sql::<Bool>(&format!("(articles.created_at {} ", created_at_operator))
+            .bind::<Timestamptz, _>(position.created_at)
+            .sql(" OR (articles.created_at = ")
+            .bind::<Timestamptz, _>(position.created_at)
+            .sql(&format!(" AND articles.slug {} ", slug_operator))
+            .bind::<Text, _>(position.slug.to_owned())
+            .sql("))"),
+    )
+}
+
+/// Pages before a cursor are fetched in reverse order, closest article to the cursor first.
+fn is_backwards(cursor: &Option<ArticleCursor>) -> bool {
+    matches!(cursor, Some(ArticleCursor::Before(_)))
+}
+
+/// Put the rows of a page fetched in reverse order (see `is_backwards`) back in listing order.
+fn restore_order<T>(mut rows: Vec<T>, cursor: &Option<ArticleCursor>) -> Vec<T> {
+    if is_backwards(cursor) {
+        rows.reverse();
+    }
+    rows
+}
+
+/// Articles matching `query`.
+/// If `viewer_id` is specified, the articles of the users who blocked the viewer are left out.
+pub fn find(
+    repo: &Repo,
+    query: ArticleQuery,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<(Article, User, u64)>, Error> {
     use crate::schema::articles::dsl::*;
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
     use crate::schema::users::dsl::{username, users};
 
+    // Search results are sorted by relevance, unless a sort order is requested
+    let sort = match (query.sort, &query.q) {
+        (None, Some(_)) => None,
+        (sort, _) => Some(sort.unwrap_or(ArticleSort::Newest)),
+    };
+    // Cursors are only meaningful for listings sorted by creation date: callers are expected
+    // to reject the others, failing loudly beats returning a page from the wrong place
+    if query.cursor.is_some() && !query.is_sorted_by_creation_date() {
+        return Err(Error::QueryBuilderError(
+            "Cursors can only be used with listings sorted by creation date".into(),
+        ));
+    }
+    let cursor = query.cursor;
+
     let results: Vec<(Article, User)> = {
         let q = articles
             .inner_join(users)
+            .filter(status.eq_any(&PUBLIC_STATUSES[..]))
+            .filter(publish_at.le(Utc::now()))
             .select((articles::all_columns(), users::all_columns()))
             .into_boxed();
 
@@ -54,9 +139,94 @@ pub fn find(repo: &Repo, query: ArticleQuery) -> Result<Vec<(Article, User, u64)
             q
         };
 
+        let q = if let Some(v) = viewer_id {
+            q.filter(user_id.ne_all(blocks.filter(blocked_id.eq(v)).select(blocker_id)))
+        } else {
+            q
+        };
+
+        let q = if let Some(t) = query.tag {
+            q.filter(tag_list.contains(vec![t]))
+        } else {
+            q
+        };
+
+        let q = if let Some(text) = &query.q {
+            // The expression must match the one of `articles_search_idx` for the index to be used
+            q.filter(
+                sql::<Bool>(
+                    "article_search_vector(articles.title, articles.description, articles.body) @@ plainto_tsquery('english', ",
+                )
+                .bind::<Text, _>(text.to_owned())
+                .sql(")"),
+            )
+        } else {
+            q
+        };
+
+        let q = match &cursor {
+            Some(c) => q.filter(keyset_condition(c, sort == Some(ArticleSort::Newest))),
+            None => q,
+        };
+
+        let q = match (sort, query.q) {
+            (None, Some(text)) => q
+                .order(
+                    sql::<Float>(
+                        "ts_rank(article_search_vector(articles.title, articles.description, articles.body), plainto_tsquery('english', ",
+                    )
+                    .bind::<Text, _>(text)
+                    .sql("))")
+                    .desc(),
+                )
+                .then_order_by(created_at.desc())
+                .then_order_by(slug.asc()),
+            // Ties are broken by ascending slug, as in `keyset_condition`
+            (Some(ArticleSort::Newest), _) if is_backwards(&cursor) => {
+                q.order(created_at.asc()).then_order_by(slug.desc())
+            }
+            (Some(ArticleSort::Newest), _) => q.order(created_at.desc()).then_order_by(slug.asc()),
+            (Some(ArticleSort::Oldest), _) if is_backwards(&cursor) => {
+                q.order(created_at.desc()).then_order_by(slug.desc())
+            }
+            (Some(ArticleSort::Oldest), _) => q.order(created_at.asc()).then_order_by(slug.asc()),
+            (Some(ArticleSort::RecentlyUpdated), _) => {
+                q.order(updated_at.desc()).then_order_by(slug.asc())
+            }
+            (Some(ArticleSort::MostFavorited), _) => q
+                .order(
+                    sql::<BigInt>(
+                        "(SELECT COUNT(*) FROM favorites WHERE favorites.article_id = articles.slug)",
+                    )
+                    .desc(),
+                )
+                .then_order_by(slug.asc()),
+            (Some(ArticleSort::MostCommented), _) => q
+                .order(
+                    sql::<BigInt>(
+                        "(SELECT COUNT(*) FROM comments WHERE comments.article_id = articles.slug)",
+                    )
+                    .desc(),
+                )
+                .then_order_by(slug.asc()),
+            (None, None) => unreachable!("Listings without a search always have a sort order"),
+        };
+
+        let q = if let Some(l) = query.limit {
+            q.limit(l as i64)
+        } else {
+            q
+        };
+
+        let q = if let Some(o) = query.offset {
+            q.offset(o as i64)
+        } else {
+            q
+        };
+
         q.load(&repo.conn())
     }?;
-    results
+    restore_order(results, &cursor)
         .into_iter()
         .map(|(article, user)| {
             n_favorites(&repo, &article.slug).map(|n_fav| (article, user, n_fav as u64))
@@ -78,29 +248,114 @@ pub fn find_one(repo: &Repo, slug_value: &str) -> Result<domain::Article, Error>
     Ok(article)
 }
 
+/// For each of the specified articles, the fragments of its body matching the
+/// full-text search `query`, with the matching terms wrapped in `<mark>` tags.
+/// If the body does not match, the beginning of the body is returned.
+///
+/// The body is HTML-escaped before being highlighted, so that the `<mark>` tags
+/// are the only markup in the snippets.
+pub fn snippets(
+    repo: &Repo,
+    query: &str,
+    slugs: &[String],
+) -> Result<HashMap<String, String>, Error> {
+    #[derive(QueryableByName)]
+    struct Snippet {
+        #[sql_type = "Text"]
+        pub slug: String,
+        #[sql_type = "Text"]
+        pub snippet: String,
+    }
+
+    // `ts_headline` is expensive: it is only computed for the articles that are going
+    // to be returned, rather than for all the articles matching the query.
+    let snippets: Vec<Snippet> = sql_query(
+        "SELECT slug, ts_headline('english', \
+         replace(replace(replace(replace(replace(body, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), \
+         '\"', '&quot;'), '''', '&#39;'), plainto_tsquery('english', $1), \
+         'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10') AS snippet \
+         FROM articles WHERE slug = ANY($2)",
+    )
+    .bind::<Text, _>(query)
+    .bind::<Array<Text>, _>(slugs)
+    .load(&repo.conn())?;
+    Ok(snippets.into_iter().map(|s| (s.slug, s.snippet)).collect())
+}
+
+/// Articles authored by the specified user which are not public yet.
+pub fn drafts(repo: &Repo, author_id: Uuid) -> Result<Vec<(Article, User, u64)>, Error> {
+    use crate::schema::articles::dsl::{articles, created_at, publish_at, slug, status, user_id};
+    use crate::schema::users::dsl::users;
+
+    let not_public_yet = status
+        .eq("draft")
+        .or(status.eq("scheduled").and(publish_at.gt(Utc::now())));
+    let results: Vec<(Article, User)> = articles
+        .inner_join(users)
+        .filter(user_id.eq(author_id))
+        .filter(not_public_yet)
+        .select((articles::all_columns(), users::all_columns()))
+        .order(created_at.desc())
+        .then_order_by(slug.asc())
+        .load(&repo.conn())?;
+    results
+        .into_iter()
+        .map(|(article, user)| {
+            n_favorites(repo, &article.slug).map(|n_fav| (article, user, n_fav as u64))
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
+/// The articles written by the users followed by `user_id_value`, as well as its own.
+/// The articles of users who blocked it or it muted are left out.
 pub fn feed(
     repo: &Repo,
     user_id_value: Uuid,
     limit: u64,
     offset: u64,
+    cursor: Option<ArticleCursor>,
 ) -> Result<Vec<(Article, User, u64)>, Error> {
-    use crate::schema::articles::dsl::{articles, created_at, user_id};
+    use crate::schema::articles::dsl::{articles, created_at, publish_at, slug, status, user_id};
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
     use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+    use crate::schema::mutes::dsl::{muted_id, muter_id, mutes};
     use crate::schema::users::dsl::{id, users};
 
     let limit = limit as i64;
     let offset = offset as i64;
 
-    let results: Vec<(Article, User)> = followers
+    let followed = followers
         .filter(follower_id.eq(user_id_value))
-        .inner_join(users.on(id.eq(followed_id)))
-        .inner_join(articles.on(user_id.eq(id)))
+        .select(followed_id);
+    let q = articles
+        .inner_join(users.on(id.eq(user_id)))
+        .filter(user_id.eq(user_id_value).or(user_id.eq_any(followed)))
+        .filter(
+            user_id.ne_all(
+                blocks
+                    .filter(blocked_id.eq(user_id_value))
+                    .select(blocker_id),
+            ),
+        )
+        .filter(user_id.ne_all(mutes.filter(muter_id.eq(user_id_value)).select(muted_id)))
+        .filter(status.eq_any(&PUBLIC_STATUSES[..]))
+        .filter(publish_at.le(Utc::now()))
         .select((articles::all_columns(), users::all_columns()))
-        .order(created_at.desc())
-        .limit(limit)
-        .offset(offset)
-        .get_results(&repo.conn())?;
-    results
+        .into_boxed();
+
+    let q = match &cursor {
+        Some(c) => q.filter(keyset_condition(c, true)),
+        None => q,
+    };
+
+    let q = if is_backwards(&cursor) {
+        q.order(created_at.asc()).then_order_by(slug.desc())
+    } else {
+        q.order(created_at.desc()).then_order_by(slug.asc())
+    };
+
+    let results: Vec<(Article, User)> = q.limit(limit).offset(offset).get_results(&repo.conn())?;
+    restore_order(results, &cursor)
         .into_iter()
         .map(|(article, user)| {
             n_favorites(&repo, &article.slug).map(|n_fav| (article, user, n_fav as u64))
@@ -111,7 +366,7 @@ pub fn feed(
 /// Fetching ALL tags seems like a really bad idea in a proper application.
 pub fn tags(repo: &Repo) -> Result<HashSet<String>, Error> {
     use diesel::pg::types::sql_types::Array;
-    use diesel::sql_types::Text;
+    use diesel::sql_types::{Text, Timestamptz};
 
     #[derive(QueryableByName)]
     pub struct Tags {
@@ -119,7 +374,8 @@ pub fn tags(repo: &Repo) -> Result<HashSet<String>, Error> {
         pub tags: Vec<String>,
     }
 
-    let query = sql_query("SELECT array_agg(DISTINCT tag) as tags FROM (SELECT 1, unnest(tag_list) FROM articles) AS t(id, tag) GROUP BY id");
+    let query = sql_query("SELECT array_agg(DISTINCT tag) as tags FROM (SELECT 1, unnest(tag_list) FROM articles WHERE status IN ('published', 'scheduled') AND publish_at <= $1) AS t(id, tag) GROUP BY id")
+        .bind::<Timestamptz, _>(Utc::now());
     let mut result: Vec<Tags> = query.load(&repo.conn())?;
     // This is not actually an array: it's either a single element of empty
     let tags = match result.pop() {
@@ -0,0 +1,63 @@
+use crate::models::NewBlock;
+use crate::schema::blocks;
+use crate::Repo;
+use diesel::expression::dsl::count;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+/// Record the block and remove any follow between the two users, in both directions.
+pub fn block(repo: &Repo, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), Error> {
+    use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+
+    let row = NewBlock {
+        blocker_id,
+        blocked_id,
+    };
+    let conn = repo.conn();
+    conn.transaction(|| {
+        diesel::insert_into(blocks::table)
+            .values(&row)
+            // If it already exists, ignore it and don't return an error
+            .on_conflict_do_nothing()
+            .execute(&conn)?;
+        let follows = followers.filter(
+            (follower_id.eq(blocker_id).and(followed_id.eq(blocked_id)))
+                .or(follower_id.eq(blocked_id).and(followed_id.eq(blocker_id))),
+        );
+        diesel::delete(follows).execute(&conn)?;
+        Ok(())
+    })
+}
+
+pub fn unblock(repo: &Repo, blocker_id_value: Uuid, blocked_id_value: Uuid) -> Result<(), Error> {
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
+
+    let to_be_deleted = blocks.filter(
+        blocker_id
+            .eq(blocker_id_value)
+            .and(blocked_id.eq(blocked_id_value)),
+    );
+    diesel::delete(to_be_deleted)
+        .execute(&repo.conn())
+        // Discard the number of deleted rows
+        .map(|_| ())
+}
+
+pub fn has_blocked(
+    repo: &Repo,
+    blocker_id_value: Uuid,
+    blocked_id_value: Uuid,
+) -> Result<bool, Error> {
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
+
+    let n: i64 = blocks
+        .filter(
+            blocker_id
+                .eq(blocker_id_value)
+                .and(blocked_id.eq(blocked_id_value)),
+        )
+        .select(count(blocked_id))
+        .get_result(&repo.conn())?;
+    Ok(n == 1)
+}
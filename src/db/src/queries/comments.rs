@@ -4,6 +4,7 @@ use crate::Repo;
 use diesel::result::Error;
 use diesel::Table;
 use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
 
 pub fn create_comment(repo: &Repo, comment: NewComment) -> Result<Comment, Error> {
     diesel::insert_into(comments::table)
@@ -27,13 +28,41 @@ pub fn delete_comment(repo: &Repo, comment_id: u64) -> Result<(), Error> {
         .map(|_| ())
 }
 
-pub fn get_comments(repo: &Repo, article_slug: &str) -> Result<Vec<(Comment, User)>, Error> {
-    use crate::schema::comments::dsl::{article_id, comments};
+/// The comments to an article.
+/// If `viewer_id` is specified, the comments of the users who blocked the viewer
+/// or that the viewer muted are left out.
+pub fn get_comments(
+    repo: &Repo,
+    article_slug: &str,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<(Comment, User)>, Error> {
+    get_articles_comments(repo, &[article_slug.to_owned()], viewer_id)
+}
+
+/// The comments to several articles at once, oldest first: see `get_comments`.
+pub fn get_articles_comments(
+    repo: &Repo,
+    article_slugs: &[String],
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<(Comment, User)>, Error> {
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
+    use crate::schema::comments::dsl::{article_id, author_id, comments, id};
+    use crate::schema::mutes::dsl::{muted_id, muter_id, mutes};
     use crate::schema::users::dsl::users;
 
-    comments
-        .filter(article_id.eq(article_slug))
+    let q = comments
+        .filter(article_id.eq_any(article_slugs))
         .inner_join(users)
         .select((comments::all_columns(), users::all_columns()))
-        .load(&repo.conn())
+        .order(id)
+        .into_boxed();
+
+    let q = if let Some(v) = viewer_id {
+        q.filter(author_id.ne_all(blocks.filter(blocked_id.eq(v)).select(blocker_id)))
+            .filter(author_id.ne_all(mutes.filter(muter_id.eq(v)).select(muted_id)))
+    } else {
+        q
+    };
+
+    q.load(&repo.conn())
 }
@@ -1,23 +1,29 @@
-use crate::models::NewFollower;
+use crate::models::{NewFollower, User};
 use crate::schema::followers;
 use crate::Repo;
 use diesel::expression::dsl::count;
 use diesel::prelude::*;
 use diesel::result::Error;
+use domain::FollowOutcome;
+use std::collections::HashSet;
 use uuid::Uuid;
 
-pub fn follow(repo: &Repo, follower_id: Uuid, followed_id: Uuid) -> Result<(), Error> {
+pub fn follow(repo: &Repo, follower_id: Uuid, followed_id: Uuid) -> Result<FollowOutcome, Error> {
     let row = NewFollower {
         follower_id,
         followed_id,
     };
-    diesel::insert_into(followers::table)
+    let n_inserted: usize = diesel::insert_into(followers::table)
         .values(&row)
         // If it already exists, ignore it and don't return an error
         .on_conflict_do_nothing()
-        .execute(&repo.conn())
-        // Discard the number of inserted rows
-        .map(|_| ())
+        .execute(&repo.conn())?;
+    let outcome = if n_inserted == 0 {
+        FollowOutcome::AlreadyFollowing
+    } else {
+        FollowOutcome::NewFollow
+    };
+    Ok(outcome)
 }
 
 pub fn unfollow(
@@ -54,3 +60,77 @@ pub fn is_following(
         .get_result(&repo.conn())?;
     Ok(n == 1)
 }
+
+/// The users following `user_id`, sorted by username.
+pub fn followers_of(
+    repo: &Repo,
+    user_id: Uuid,
+    limit: u64,
+    offset: u64,
+) -> Result<Vec<User>, Error> {
+    use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+    use crate::schema::users::dsl::{id, username, users};
+
+    followers
+        .filter(followed_id.eq(user_id))
+        .inner_join(users.on(id.eq(follower_id)))
+        .select(users::all_columns())
+        .order(username.asc())
+        .limit(limit as i64)
+        .offset(offset as i64)
+        .load(&repo.conn())
+}
+
+/// The users followed by `user_id`, sorted by username.
+pub fn followed_by(
+    repo: &Repo,
+    user_id: Uuid,
+    limit: u64,
+    offset: u64,
+) -> Result<Vec<User>, Error> {
+    use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+    use crate::schema::users::dsl::{id, username, users};
+
+    followers
+        .filter(follower_id.eq(user_id))
+        .inner_join(users.on(id.eq(followed_id)))
+        .select(users::all_columns())
+        .order(username.asc())
+        .limit(limit as i64)
+        .offset(offset as i64)
+        .load(&repo.conn())
+}
+
+/// The number of followers of `user_id` and the number of users it follows.
+pub fn counts(repo: &Repo, user_id: Uuid) -> Result<(u64, u64), Error> {
+    use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+
+    let conn = repo.conn();
+    let n_followers: i64 = followers
+        .filter(followed_id.eq(user_id))
+        .select(count(follower_id))
+        .get_result(&conn)?;
+    let n_following: i64 = followers
+        .filter(follower_id.eq(user_id))
+        .select(count(followed_id))
+        .get_result(&conn)?;
+    Ok((n_followers as u64, n_following as u64))
+}
+
+/// Which of the specified users are followed by `follower_id_value`.
+pub fn followed_usernames(
+    repo: &Repo,
+    follower_id_value: Uuid,
+    usernames: &[String],
+) -> Result<HashSet<String>, Error> {
+    use crate::schema::followers::dsl::{followed_id, follower_id, followers};
+    use crate::schema::users::dsl::{id, username, users};
+
+    let followed: Vec<String> = followers
+        .filter(follower_id.eq(follower_id_value))
+        .inner_join(users.on(id.eq(followed_id)))
+        .filter(username.eq_any(usernames))
+        .select(username)
+        .load(&repo.conn())?;
+    Ok(followed.into_iter().collect())
+}
@@ -0,0 +1,44 @@
+use crate::models::{NewUserIdentity, OidcLoginAttempt, User};
+use crate::schema::{oidc_login_attempts, user_identities, users};
+use crate::Repo;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+pub fn find_user(repo: &Repo, provider: &str, subject: &str) -> Result<User, Error> {
+    user_identities::table
+        .find((provider, subject))
+        .inner_join(users::table)
+        .select(users::all_columns)
+        .first(&repo.conn())
+}
+
+pub fn insert(repo: &Repo, identity: NewUserIdentity) -> Result<(), Error> {
+    diesel::insert_into(user_identities::table)
+        .values(&identity)
+        .execute(&repo.conn())?;
+    Ok(())
+}
+
+pub fn insert_login_attempt(repo: &Repo, attempt: &OidcLoginAttempt) -> Result<(), Error> {
+    diesel::insert_into(oidc_login_attempts::table)
+        .values(attempt)
+        .execute(&repo.conn())?;
+    Ok(())
+}
+
+/// Delete the login attempt identified by `state_value`, returning it if it had not expired.
+///
+/// Expired attempts are cleaned up along the way.
+pub fn take_login_attempt(repo: &Repo, state_value: &str) -> Result<OidcLoginAttempt, Error> {
+    use crate::schema::oidc_login_attempts::dsl::*;
+
+    let conn = repo.conn();
+    conn.transaction(|| {
+        let now = Utc::now();
+        diesel::delete(oidc_login_attempts.filter(expires_at.le(now))).execute(&conn)?;
+        diesel::delete(oidc_login_attempts.find(state_value))
+            .returning((state, provider, nonce, code_verifier, expires_at))
+            .get_result(&conn)
+    })
+}
@@ -1,5 +1,13 @@
+pub mod access_tokens;
 pub mod articles;
+pub mod blocks;
 pub mod comments;
 pub mod favorites;
 pub mod followers;
+pub mod identities;
+pub mod mutes;
+pub mod notifications;
+pub mod password_resets;
+pub mod revisions;
+pub mod two_factor;
 pub mod users;
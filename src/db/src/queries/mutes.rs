@@ -0,0 +1,27 @@
+use crate::models::NewMute;
+use crate::schema::mutes;
+use crate::Repo;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+pub fn mute(repo: &Repo, muter_id: Uuid, muted_id: Uuid) -> Result<(), Error> {
+    let row = NewMute { muter_id, muted_id };
+    diesel::insert_into(mutes::table)
+        .values(&row)
+        // If it already exists, ignore it and don't return an error
+        .on_conflict_do_nothing()
+        .execute(&repo.conn())
+        // Discard the number of inserted rows
+        .map(|_| ())
+}
+
+pub fn unmute(repo: &Repo, muter_id_value: Uuid, muted_id_value: Uuid) -> Result<(), Error> {
+    use crate::schema::mutes::dsl::{muted_id, muter_id, mutes};
+
+    let to_be_deleted = mutes.filter(muter_id.eq(muter_id_value).and(muted_id.eq(muted_id_value)));
+    diesel::delete(to_be_deleted)
+        .execute(&repo.conn())
+        // Discard the number of deleted rows
+        .map(|_| ())
+}
@@ -0,0 +1,134 @@
+use crate::models::{NewNotification, Notification, User};
+use crate::Repo;
+use chrono::{DateTime, Utc};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::Table;
+use uuid::Uuid;
+
+/// Records a notification, unless the same one is already waiting unread
+/// (comments are never the same).
+pub fn insert(repo: &Repo, notification: NewNotification) -> Result<(), Error> {
+    use crate::schema::notifications::dsl::*;
+
+    let conn = repo.conn();
+    if notification.comment_id.is_none() {
+        let pending = notifications
+            .filter(recipient_id.eq(notification.recipient_id))
+            .filter(actor_id.eq(notification.actor_id))
+            .filter(kind.eq(notification.kind))
+            .filter(read_at.is_null())
+            .into_boxed();
+        let pending = match notification.article_id {
+            Some(slug) => pending.filter(article_id.eq(slug)),
+            None => pending.filter(article_id.is_null()),
+        };
+        let n_pending: i64 = pending.select(count_star()).get_result(&conn)?;
+        if n_pending > 0 {
+            return Ok(());
+        }
+    }
+    diesel::insert_into(notifications)
+        .values(&notification)
+        .execute(&conn)
+        // Discard the number of inserted rows
+        .map(|_| ())
+}
+
+/// The notifications of a user, most recent first, with their actor and the title
+/// of their article. The ones caused by users the recipient blocked or muted are left out.
+pub fn find(
+    repo: &Repo,
+    recipient: Uuid,
+    unread_only: bool,
+    after_id: Option<u64>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> Result<Vec<(Notification, User, Option<String>)>, Error> {
+    use crate::schema::articles;
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
+    use crate::schema::mutes::dsl::{muted_id, muter_id, mutes};
+    use crate::schema::notifications::dsl::*;
+    use crate::schema::users;
+
+    let mut q = notifications
+        .filter(recipient_id.eq(recipient))
+        .filter(actor_id.ne_all(blocks.filter(blocker_id.eq(recipient)).select(blocked_id)))
+        .filter(actor_id.ne_all(mutes.filter(muter_id.eq(recipient)).select(muted_id)))
+        .inner_join(users::table.on(users::id.eq(actor_id)))
+        .left_join(articles::table)
+        .select((
+            notifications::all_columns(),
+            users::table::all_columns(),
+            articles::title.nullable(),
+        ))
+        .order(id.desc())
+        .into_boxed();
+    if unread_only {
+        q = q.filter(read_at.is_null());
+    }
+    if let Some(after_id) = after_id {
+        q = q.filter(id.gt(after_id as i64));
+    }
+    if let Some(limit) = limit {
+        q = q.limit(limit as i64);
+    }
+    if let Some(offset) = offset {
+        q = q.offset(offset as i64);
+    }
+    q.load(&repo.conn())
+}
+
+pub fn count_unread(repo: &Repo, recipient: Uuid) -> Result<u64, Error> {
+    use crate::schema::blocks::dsl::{blocked_id, blocker_id, blocks};
+    use crate::schema::mutes::dsl::{muted_id, muter_id, mutes};
+    use crate::schema::notifications::dsl::*;
+
+    let n_unread: i64 = notifications
+        .filter(recipient_id.eq(recipient))
+        .filter(read_at.is_null())
+        .filter(actor_id.ne_all(blocks.filter(blocker_id.eq(recipient)).select(blocked_id)))
+        .filter(actor_id.ne_all(mutes.filter(muter_id.eq(recipient)).select(muted_id)))
+        .select(count_star())
+        .get_result(&repo.conn())?;
+    Ok(n_unread as u64)
+}
+
+/// Returns `false` if the recipient has no such notification.
+pub fn mark(repo: &Repo, recipient: Uuid, notification_id: u64, read: bool) -> Result<bool, Error> {
+    use crate::schema::notifications::dsl::*;
+
+    let conn = repo.conn();
+    let target = notifications
+        .filter(id.eq(notification_id as i64))
+        .filter(recipient_id.eq(recipient));
+    let n_found: i64 = target.select(count_star()).get_result(&conn)?;
+    if n_found == 0 {
+        return Ok(false);
+    }
+    if read {
+        // Marking a read notification as read again keeps the time it was first read
+        diesel::update(target.filter(read_at.is_null()))
+            .set(read_at.eq(Utc::now()))
+            .execute(&conn)?;
+    } else {
+        diesel::update(target)
+            .set(read_at.eq(None::<DateTime<Utc>>))
+            .execute(&conn)?;
+    }
+    Ok(true)
+}
+
+pub fn mark_all_read(repo: &Repo, recipient: Uuid) -> Result<(), Error> {
+    use crate::schema::notifications::dsl::*;
+
+    let unread = notifications
+        .filter(recipient_id.eq(recipient))
+        .filter(read_at.is_null());
+    diesel::update(unread)
+        .set(read_at.eq(Utc::now()))
+        .execute(&repo.conn())
+        // Discard the number of updated rows
+        .map(|_| ())
+}
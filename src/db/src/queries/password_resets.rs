@@ -0,0 +1,51 @@
+use crate::models::{NewPasswordResetToken, User};
+use crate::schema::{access_tokens, password_reset_tokens, users};
+use crate::Repo;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+pub fn insert(repo: &Repo, token: NewPasswordResetToken) -> Result<(), Error> {
+    diesel::insert_into(password_reset_tokens::table)
+        .values(&token)
+        .execute(&repo.conn())?;
+    Ok(())
+}
+
+/// Set a new password for the owner of a valid (known, unused and unexpired) reset token.
+///
+/// All the outstanding reset tokens of the user are marked as used,
+/// the sessions opened before the reset are revoked and the access tokens of the user
+/// are deleted: whoever got hold of the old password may have created some.
+/// It fails with `Error::NotFound` if the token is not valid.
+pub fn redeem(repo: &Repo, token_hash_value: &str, password_hash: &str) -> Result<User, Error> {
+    use crate::schema::password_reset_tokens::dsl::*;
+
+    let conn = repo.conn();
+    conn.transaction(|| {
+        let now = Utc::now();
+        let owner_id = password_reset_tokens
+            .filter(token_hash.eq(token_hash_value))
+            .filter(used_at.is_null())
+            .filter(expires_at.gt(now))
+            .select(user_id)
+            .for_update()
+            .first::<Uuid>(&conn)?;
+        diesel::update(
+            password_reset_tokens
+                .filter(user_id.eq(owner_id))
+                .filter(used_at.is_null()),
+        )
+        .set(used_at.eq(now))
+        .execute(&conn)?;
+        diesel::delete(access_tokens::table.filter(access_tokens::user_id.eq(owner_id)))
+            .execute(&conn)?;
+        diesel::update(users::table.find(owner_id))
+            .set((
+                users::password.eq(password_hash),
+                users::sessions_revoked_at.eq(now),
+            ))
+            .get_result(&conn)
+    })
+}
@@ -0,0 +1,46 @@
+use crate::models::{ArticleRevision, NewArticleRevision, User};
+use crate::schema::article_revisions;
+use crate::tracing::TracedConnection;
+use crate::Repo;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// Record a revision.
+/// It takes a connection, instead of a `Repo`, to be part of the transaction
+/// that changes the article.
+pub fn insert(conn: &TracedConnection, revision: NewArticleRevision) -> Result<(), Error> {
+    diesel::insert_into(article_revisions::table)
+        .values(&revision)
+        .execute(conn)
+        // Discard the number of inserted rows
+        .map(|_| ())
+}
+
+/// All the revisions of an article, oldest first, with their editors.
+pub fn list(repo: &Repo, article_slug: &str) -> Result<Vec<(ArticleRevision, User)>, Error> {
+    use crate::schema::article_revisions::dsl::{article_id, article_revisions, id};
+    use crate::schema::users::dsl::users;
+
+    article_revisions
+        .filter(article_id.eq(article_slug))
+        .inner_join(users)
+        .select((article_revisions::all_columns(), users::all_columns()))
+        .order(id.asc())
+        .load(&repo.conn())
+}
+
+pub fn find(
+    repo: &Repo,
+    article_slug: &str,
+    revision_id: u64,
+) -> Result<(ArticleRevision, User), Error> {
+    use crate::schema::article_revisions::dsl::{article_id, article_revisions, id};
+    use crate::schema::users::dsl::users;
+
+    article_revisions
+        .filter(article_id.eq(article_slug))
+        .filter(id.eq(revision_id as i64))
+        .inner_join(users)
+        .select((article_revisions::all_columns(), users::all_columns()))
+        .first(&repo.conn())
+}
@@ -0,0 +1,100 @@
+use crate::models::{NewTwoFactorCredentials, TwoFactorCredentials};
+use crate::schema::two_factor_credentials;
+use crate::Repo;
+use chrono::Utc;
+use diesel::pg::types::sql_types::Array;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::Text;
+use uuid::Uuid;
+
+sql_function!(fn array_remove(array: Array<Text>, element: Text) -> Array<Text>);
+
+pub fn find(repo: &Repo, owner_id: Uuid) -> Result<TwoFactorCredentials, Error> {
+    two_factor_credentials::table
+        .find(owner_id)
+        .first(&repo.conn())
+}
+
+/// Store an unconfirmed secret, replacing any previous unconfirmed one.
+///
+/// It returns `false`, leaving everything untouched, if the user already confirmed a secret.
+pub fn enroll(repo: &Repo, credentials: NewTwoFactorCredentials) -> Result<bool, Error> {
+    use crate::schema::two_factor_credentials::dsl::*;
+
+    let conn = repo.conn();
+    conn.transaction(|| {
+        diesel::delete(
+            two_factor_credentials
+                .filter(user_id.eq(credentials.user_id))
+                .filter(confirmed_at.is_null()),
+        )
+        .execute(&conn)?;
+        let inserted = diesel::insert_into(two_factor_credentials)
+            .values(&credentials)
+            .on_conflict_do_nothing()
+            .execute(&conn)?;
+        Ok(inserted == 1)
+    })
+}
+
+/// It fails with `Error::NotFound` if there is no unconfirmed secret for the user.
+pub fn confirm(
+    repo: &Repo,
+    owner_id: Uuid,
+    step: i64,
+    code_hashes: &[String],
+) -> Result<(), Error> {
+    use crate::schema::two_factor_credentials::dsl::*;
+
+    let updated = diesel::update(
+        two_factor_credentials
+            .filter(user_id.eq(owner_id))
+            .filter(confirmed_at.is_null()),
+    )
+    .set((
+        confirmed_at.eq(Utc::now()),
+        last_used_step.eq(step),
+        recovery_code_hashes.eq(code_hashes),
+    ))
+    .execute(&repo.conn())?;
+    match updated {
+        0 => Err(Error::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Record `step` as used, unless the same or a later one was used already.
+pub fn use_step(repo: &Repo, owner_id: Uuid, step: i64) -> Result<bool, Error> {
+    use crate::schema::two_factor_credentials::dsl::*;
+
+    let updated = diesel::update(
+        two_factor_credentials
+            .filter(user_id.eq(owner_id))
+            .filter(confirmed_at.is_not_null())
+            .filter(last_used_step.is_null().or(last_used_step.lt(step))),
+    )
+    .set(last_used_step.eq(step))
+    .execute(&repo.conn())?;
+    Ok(updated == 1)
+}
+
+/// Remove `code_hash` from the unused recovery codes, if it is there.
+pub fn use_recovery_code(repo: &Repo, owner_id: Uuid, code_hash: &str) -> Result<bool, Error> {
+    use crate::schema::two_factor_credentials::dsl::*;
+
+    let updated = diesel::update(
+        two_factor_credentials
+            .filter(user_id.eq(owner_id))
+            .filter(confirmed_at.is_not_null())
+            .filter(recovery_code_hashes.contains(vec![code_hash])),
+    )
+    .set(recovery_code_hashes.eq(array_remove(recovery_code_hashes, code_hash)))
+    .execute(&repo.conn())?;
+    Ok(updated == 1)
+}
+
+pub fn delete(repo: &Repo, owner_id: Uuid) -> Result<(), Error> {
+    diesel::delete(two_factor_credentials::table.find(owner_id)).execute(&repo.conn())?;
+    Ok(())
+}
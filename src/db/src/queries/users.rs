@@ -1,21 +1,21 @@
 use crate::models::{NewUser, UpdateUser, User};
-use crate::schema::users;
+use crate::schema::{article_revisions, articles, comments, users};
 use crate::Repo;
 
-use crate::queries::followers::follow;
 use diesel::prelude::*;
 use diesel::result::Error;
 use uuid::Uuid;
 
+/// The id of the placeholder author of the content left behind by anonymized accounts.
+/// It is created by a migration.
+pub fn deleted_user_id() -> Uuid {
+    Uuid::nil()
+}
+
 pub fn insert(repo: &Repo, user: NewUser) -> Result<User, Error> {
-    let new_user: User = diesel::insert_into(users::table)
+    diesel::insert_into(users::table)
         .values(&user)
-        .get_result(&repo.conn())?;
-
-    // Invariant: a user always follows themselves
-    follow(repo, new_user.id, new_user.id)?;
-
-    Ok(new_user)
+        .get_result(&repo.conn())
 }
 
 pub fn find(repo: &Repo, user_id: Uuid) -> Result<User, Error> {
@@ -30,9 +30,18 @@ pub fn find_by_username(repo: &Repo, username_value: &str) -> Result<User, Error
         .first(&repo.conn())
 }
 
+pub fn username_exists(repo: &Repo, username_value: &str) -> Result<bool, Error> {
+    use crate::schema::users::dsl::*;
+    use diesel::dsl::exists;
+    diesel::select(exists(users.filter(username.eq(username_value)))).get_result(&repo.conn())
+}
+
 pub fn find_by_email(repo: &Repo, user_email: &str) -> Result<User, Error> {
     use crate::schema::users::dsl::*;
-    users.filter(email.eq(user_email)).first(&repo.conn())
+    users
+        .filter(email.eq(user_email))
+        .filter(id.ne(deleted_user_id()))
+        .first(&repo.conn())
 }
 
 pub fn update(repo: &Repo, user_id: Uuid, details: UpdateUser) -> Result<User, Error> {
@@ -41,3 +50,39 @@ pub fn update(repo: &Repo, user_id: Uuid, details: UpdateUser) -> Result<User, E
         .set(&details)
         .get_result(&repo.conn())
 }
+
+/// Mark `email` as verified, if it is still the email address of the user.
+pub fn verify_email(repo: &Repo, user_id: Uuid, email_value: &str) -> Result<User, Error> {
+    use crate::schema::users::dsl::*;
+    diesel::update(users.find(user_id).filter(email.eq(email_value)))
+        .set(email_verified.eq(true))
+        .get_result(&repo.conn())
+}
+
+/// Delete a user together with their favorites, follows, blocks and mutes.
+///
+/// If `anonymize` is true, their articles, comments and revisions are kept
+/// and reassigned to the deleted user placeholder; otherwise they are deleted as well.
+pub fn delete(repo: &Repo, user_id: Uuid, anonymize: bool) -> Result<(), Error> {
+    let conn = repo.conn();
+    conn.transaction(|| {
+        if anonymize {
+            diesel::update(articles::table.filter(articles::user_id.eq(user_id)))
+                .set(articles::user_id.eq(deleted_user_id()))
+                .execute(&conn)?;
+            diesel::update(comments::table.filter(comments::author_id.eq(user_id)))
+                .set(comments::author_id.eq(deleted_user_id()))
+                .execute(&conn)?;
+            diesel::update(
+                article_revisions::table.filter(article_revisions::editor_id.eq(user_id)),
+            )
+            .set(article_revisions::editor_id.eq(deleted_user_id()))
+            .execute(&conn)?;
+        }
+        let deleted = diesel::delete(users::table.find(user_id)).execute(&conn)?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    })
+}
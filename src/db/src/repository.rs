@@ -1,11 +1,22 @@
-use crate::models::{Article, NewArticle, NewComment, NewUser, UpdateUser};
-use crate::queries::{articles, comments, favorites, followers, users};
-use crate::shims::{to_article, to_comment};
+use crate::models::{
+    Article, NewAccessToken, NewArticle, NewComment, NewNotification, NewPasswordResetToken,
+    NewTwoFactorCredentials, NewUser, NewUserIdentity, OidcLoginAttempt, UpdateUser,
+};
+use crate::queries::{
+    access_tokens, articles, blocks, comments, favorites, followers, identities, mutes,
+    notifications, password_resets, revisions, two_factor, users,
+};
+use crate::shims::{
+    to_access_token, to_article, to_comment, to_event_columns, to_notification, to_revision,
+};
 use crate::Repo;
 use anyhow::Error as OpaqueError;
+use chrono::{DateTime, Utc};
 use diesel::result::{DatabaseErrorKind, Error};
-use domain::{DatabaseError, DeleteCommentError, GetUserError};
-use std::collections::HashSet;
+use domain::{DatabaseError, DeleteCommentError, GetUserError, NotificationError};
+use metrics::{Timer, DB_QUERY_DURATION, FAVORITES, PUBLISHED_ARTICLES, SIGN_UPS};
+use std::collections::{HashMap, HashSet};
+use telemetry::{start_span, Span, SpanKind};
 use uuid::Uuid;
 
 /// Helper function to cast a diesel::Error into a domain Database Error.
@@ -14,33 +25,53 @@ pub fn to_db_error(e: Error) -> domain::DatabaseError {
     domain::DatabaseError::from(OpaqueError::from(e))
 }
 
+/// Time a repository method and trace it as a span, until the result is dropped.
+fn instrument(method: &'static str) -> (Timer, Span) {
+    let timer = DB_QUERY_DURATION.start_timer(&[method]);
+    let span = start_span(format!("Repository::{}", method), SpanKind::Internal);
+    (timer, span)
+}
+
 pub struct Repository(pub Repo);
 
 impl domain::repositories::Repository for Repository {
-    fn publish_article(
+    fn create_article(
         &self,
         draft: domain::ArticleContent,
+        status: domain::ArticleStatus,
         author: &domain::User,
     ) -> Result<domain::Article, domain::PublishArticleError> {
-        let result: Article = articles::insert(&self.0, NewArticle::from((&draft, author)))
-            .map_err(|e| match e {
-                Error::DatabaseError(kind, _) => match kind {
-                    DatabaseErrorKind::UniqueViolation => {
-                        domain::PublishArticleError::DuplicatedSlug {
-                            slug: draft.slug(),
-                            source: to_db_error(e),
-                        }
-                    }
-                    _ => to_db_error(e).into(),
+        let _instrument = instrument("create_article");
+        let result: Article = articles::insert(
+            &self.0,
+            NewArticle::from((&draft, &status, author)),
+        )
+        .map_err(|e| match e {
+            Error::DatabaseError(kind, _) => match kind {
+                DatabaseErrorKind::UniqueViolation => domain::PublishArticleError::DuplicatedSlug {
+                    slug: draft.slug(),
+                    source: to_db_error(e),
                 },
-                e => to_db_error(e).into(),
-            })?;
+                _ => to_db_error(e).into(),
+            },
+            e => to_db_error(e).into(),
+        })?;
+        if status == domain::ArticleStatus::Published {
+            PUBLISHED_ARTICLES.inc(&[]);
+        }
         let article = to_article(result, author.to_owned(), 0);
         Ok(article)
     }
 
     fn get_article_by_slug(&self, slug: &str) -> Result<domain::Article, domain::GetArticleError> {
-        Ok(articles::find_one(&self.0, &slug).map_err(to_db_error)?)
+        let _instrument = instrument("get_article_by_slug");
+        articles::find_one(&self.0, &slug).map_err(|e| match e {
+            e @ Error::NotFound => domain::GetArticleError::ArticleNotFound {
+                slug: slug.to_owned(),
+                source: to_db_error(e),
+            },
+            e => to_db_error(e).into(),
+        })
     }
 
     fn get_article_view(
@@ -48,6 +79,7 @@ impl domain::repositories::Repository for Repository {
         viewer: &domain::User,
         article: domain::Article,
     ) -> Result<domain::ArticleView, domain::GetArticleError> {
+        let _instrument = instrument("get_article_view");
         let author_view = self
             .get_profile_view(viewer, &article.author.username)
             .unwrap();
@@ -60,6 +92,7 @@ impl domain::repositories::Repository for Repository {
             metadata: article.metadata,
             favorited: is_favorite,
             favorites_count: article.favorites_count,
+            status: article.status,
             viewer: viewer.id,
         };
         Ok(article_view)
@@ -70,6 +103,7 @@ impl domain::repositories::Repository for Repository {
         viewer: &domain::User,
         articles: Vec<domain::Article>,
     ) -> Result<Vec<domain::ArticleView>, DatabaseError> {
+        let _instrument = instrument("get_articles_views");
         let slugs: Vec<String> = articles.iter().map(|a| a.slug.to_owned()).collect();
         let slugs: Vec<&str> = slugs.iter().map(|slug| slug.as_str()).collect();
 
@@ -86,6 +120,7 @@ impl domain::repositories::Repository for Repository {
                     metadata: a.metadata,
                     favorited,
                     favorites_count: a.favorites_count,
+                    status: a.status,
                     viewer: viewer.id,
                 };
                 Ok(article_view)
@@ -96,8 +131,33 @@ impl domain::repositories::Repository for Repository {
     fn find_articles(
         &self,
         query: domain::ArticleQuery,
+        viewer: Option<&domain::User>,
     ) -> Result<Vec<domain::Article>, DatabaseError> {
-        let result: Vec<domain::Article> = articles::find(&self.0, query)
+        let _instrument = instrument("find_articles");
+        let result: Vec<domain::Article> = articles::find(&self.0, query, viewer.map(|v| v.id))
+            .map_err(to_db_error)?
+            .into_iter()
+            .map(|(a, u, n_fav)| {
+                let u: domain::User = u.into();
+                to_article(a, u, n_fav)
+            })
+            .collect();
+        Ok(result)
+    }
+
+    fn search_snippets(
+        &self,
+        query: &str,
+        articles: &[domain::Article],
+    ) -> Result<HashMap<String, String>, DatabaseError> {
+        let _instrument = instrument("search_snippets");
+        let slugs: Vec<String> = articles.iter().map(|a| a.slug.to_owned()).collect();
+        articles::snippets(&self.0, query, &slugs).map_err(to_db_error)
+    }
+
+    fn find_drafts(&self, author: &domain::User) -> Result<Vec<domain::Article>, DatabaseError> {
+        let _instrument = instrument("find_drafts");
+        let result: Vec<domain::Article> = articles::drafts(&self.0, author.id)
             .map_err(to_db_error)?
             .into_iter()
             .map(|(a, u, n_fav)| {
@@ -113,8 +173,9 @@ impl domain::repositories::Repository for Repository {
         user: &domain::User,
         query: domain::FeedQuery,
     ) -> Result<Vec<domain::ArticleView>, DatabaseError> {
+        let _instrument = instrument("feed");
         let articles: Vec<domain::Article> =
-            articles::feed(&self.0, user.id, query.limit, query.offset)
+            articles::feed(&self.0, user.id, query.limit, query.offset, query.cursor)
                 .map_err(to_db_error)?
                 .into_iter()
                 .map(|(a, u, n_fav)| {
@@ -126,6 +187,7 @@ impl domain::repositories::Repository for Repository {
     }
 
     fn delete_article(&self, article: &domain::Article) -> Result<(), DatabaseError> {
+        let _instrument = instrument("delete_article");
         Ok(articles::delete(&self.0, &article.slug).map_err(to_db_error)?)
     }
 
@@ -135,6 +197,7 @@ impl domain::repositories::Repository for Repository {
         article: &domain::Article,
         comment: domain::CommentContent,
     ) -> Result<domain::Comment, DatabaseError> {
+        let _instrument = instrument("comment_article");
         let new_comment = NewComment {
             body: &comment.0,
             article_id: &article.slug,
@@ -152,6 +215,7 @@ impl domain::repositories::Repository for Repository {
     }
 
     fn get_comment(&self, comment_id: u64) -> Result<domain::Comment, DeleteCommentError> {
+        let _instrument = instrument("get_comment");
         let comment = comments::get_comment(&self.0, comment_id).map_err(|e| match e {
             Error::NotFound => DeleteCommentError::CommentNotFound {
                 comment_id,
@@ -166,8 +230,10 @@ impl domain::repositories::Repository for Repository {
     fn get_comments(
         &self,
         article: &domain::Article,
+        viewer: Option<&domain::User>,
     ) -> Result<Vec<domain::Comment>, DatabaseError> {
-        let comments: Vec<_> = comments::get_comments(&self.0, &article.slug)
+        let _instrument = instrument("get_comments");
+        let comments: Vec<_> = comments::get_comments(&self.0, &article.slug, viewer.map(|v| v.id))
             .map_err(to_db_error)?
             .into_iter()
             .map(|(c, u)| to_comment(c, u))
@@ -175,7 +241,26 @@ impl domain::repositories::Repository for Repository {
         Ok(comments)
     }
 
+    fn get_articles_comments(
+        &self,
+        slugs: &[String],
+        viewer: Option<&domain::User>,
+    ) -> Result<HashMap<String, Vec<domain::Comment>>, DatabaseError> {
+        let _instrument = instrument("get_articles_comments");
+        let mut comments_by_slug: HashMap<String, Vec<domain::Comment>> = HashMap::new();
+        for (c, u) in comments::get_articles_comments(&self.0, slugs, viewer.map(|v| v.id))
+            .map_err(to_db_error)?
+        {
+            comments_by_slug
+                .entry(c.article_id.to_owned())
+                .or_default()
+                .push(to_comment(c, u));
+        }
+        Ok(comments_by_slug)
+    }
+
     fn delete_comment(&self, comment_id: u64) -> Result<(), DeleteCommentError> {
+        let _instrument = instrument("delete_comment");
         Ok(comments::delete_comment(&self.0, comment_id).map_err(to_db_error)?)
     }
 
@@ -183,18 +268,61 @@ impl domain::repositories::Repository for Repository {
         &self,
         article: domain::Article,
         update: domain::ArticleUpdate,
+        editor: &domain::User,
     ) -> Result<domain::Article, DatabaseError> {
-        articles::update(&self.0, (&update).into(), &article.slug).map_err(to_db_error)?;
+        let _instrument = instrument("update_article");
+        let changes = (&update, &article.status).into();
+        articles::update(&self.0, changes, &article.slug, editor.id).map_err(to_db_error)?;
+        if article.status != domain::ArticleStatus::Published
+            && update.status == Some(domain::ArticleStatus::Published)
+        {
+            PUBLISHED_ARTICLES.inc(&[]);
+        }
         let article = self.get_article_by_slug(&article.slug)?;
         Ok(article)
     }
 
+    fn get_revisions(
+        &self,
+        article: &domain::Article,
+    ) -> Result<Vec<domain::ArticleRevision>, DatabaseError> {
+        let _instrument = instrument("get_revisions");
+        let revisions = revisions::list(&self.0, &article.slug)
+            .map_err(to_db_error)?
+            .into_iter()
+            .map(|(r, u)| to_revision(r, u))
+            .collect();
+        Ok(revisions)
+    }
+
+    fn get_revision(
+        &self,
+        article: &domain::Article,
+        revision_id: u64,
+    ) -> Result<domain::ArticleRevision, domain::ChangeArticleError> {
+        let _instrument = instrument("get_revision");
+        let (revision, editor) =
+            revisions::find(&self.0, &article.slug, revision_id).map_err(|e| match e {
+                Error::NotFound => domain::ChangeArticleError::RevisionNotFound {
+                    slug: article.slug.to_owned(),
+                    revision_id,
+                },
+                e => to_db_error(e).into(),
+            })?;
+        Ok(to_revision(revision, editor))
+    }
+
     fn favorite(
         &self,
         article: &domain::Article,
         user: &domain::User,
     ) -> Result<domain::FavoriteOutcome, domain::DatabaseError> {
-        Ok(favorites::favorite(&self.0, user.id, &article.slug).map_err(to_db_error)?)
+        let _instrument = instrument("favorite");
+        let outcome = favorites::favorite(&self.0, user.id, &article.slug).map_err(to_db_error)?;
+        if let domain::FavoriteOutcome::NewFavorite = outcome {
+            FAVORITES.inc(&[]);
+        }
+        Ok(outcome)
     }
 
     fn unfavorite(
@@ -202,19 +330,21 @@ impl domain::repositories::Repository for Repository {
         article: &domain::Article,
         user: &domain::User,
     ) -> Result<domain::UnfavoriteOutcome, domain::DatabaseError> {
+        let _instrument = instrument("unfavorite");
         Ok(favorites::unfavorite(&self.0, user.id, &article.slug).map_err(to_db_error)?)
     }
 
     fn sign_up(&self, sign_up: domain::SignUp) -> Result<domain::User, domain::SignUpError> {
+        let _instrument = instrument("sign_up");
         let new_user = NewUser {
             username: &sign_up.username,
             email: &sign_up.email,
             password: sign_up.password.hash(),
             id: Uuid::new_v4(),
         };
-        Ok(users::insert(&self.0, new_user)
-            .map_err(to_db_error)?
-            .into())
+        let user = users::insert(&self.0, new_user).map_err(to_db_error)?;
+        SIGN_UPS.inc(&[]);
+        Ok(user.into())
     }
 
     fn update_user(
@@ -222,12 +352,28 @@ impl domain::repositories::Repository for Repository {
         user: domain::User,
         update: domain::UserUpdate,
     ) -> Result<domain::User, DatabaseError> {
-        let update = UpdateUser::from(&update);
+        let _instrument = instrument("update_user");
+        let mut update = UpdateUser::from(&update);
+        // A new email address has to be verified again
+        if matches!(update.email, Some(email) if email != user.email) {
+            update.email_verified = Some(false);
+        }
         let updated = users::update(&self.0, user.id, update).map_err(to_db_error)?;
         Ok(domain::User::from(updated))
     }
 
+    fn delete_user(
+        &self,
+        user: &domain::User,
+        mode: domain::AccountDeletion,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("delete_user");
+        let anonymize = mode == domain::AccountDeletion::Anonymize;
+        users::delete(&self.0, user.id, anonymize).map_err(to_db_error)
+    }
+
     fn get_user_by_id(&self, user_id: Uuid) -> Result<domain::User, GetUserError> {
+        let _instrument = instrument("get_user_by_id");
         let result = users::find(&self.0, user_id);
         let user = result.map_err(|e| match e {
             e @ Error::NotFound => domain::GetUserError::NotFound {
@@ -239,27 +385,316 @@ impl domain::repositories::Repository for Repository {
         Ok(domain::User::from(user))
     }
 
-    fn get_user_by_email_and_password(
+    fn verify_email(
         &self,
+        user_id: Uuid,
         email: &str,
-        password: &str,
-    ) -> Result<domain::User, domain::LoginError> {
-        let result = users::find_by_email(&self.0, email);
+    ) -> Result<domain::User, domain::VerifyEmailError> {
+        let _instrument = instrument("verify_email");
+        let user = users::verify_email(&self.0, user_id, email).map_err(|e| match e {
+            Error::NotFound => domain::VerifyEmailError::InvalidToken,
+            e => to_db_error(e).into(),
+        })?;
+        Ok(domain::User::from(user))
+    }
+
+    fn find_user_by_email(&self, email: &str) -> Result<Option<domain::User>, DatabaseError> {
+        let _instrument = instrument("find_user_by_email");
+        match users::find_by_email(&self.0, email) {
+            Ok(user) => Ok(Some(domain::User::from(user))),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(to_db_error(e)),
+        }
+    }
+
+    fn username_exists(&self, username: &str) -> Result<bool, DatabaseError> {
+        let _instrument = instrument("username_exists");
+        users::username_exists(&self.0, username).map_err(to_db_error)
+    }
+
+    fn find_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<domain::User>, DatabaseError> {
+        let _instrument = instrument("find_user_by_identity");
+        match identities::find_user(&self.0, provider, subject) {
+            Ok(user) => Ok(Some(domain::User::from(user))),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(to_db_error(e)),
+        }
+    }
+
+    fn link_identity(
+        &self,
+        user: &domain::User,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("link_identity");
+        let identity = NewUserIdentity {
+            provider,
+            subject,
+            user_id: user.id,
+        };
+        identities::insert(&self.0, identity).map_err(to_db_error)
+    }
+
+    fn create_oidc_login_attempt(
+        &self,
+        attempt: &domain::OidcLoginAttempt,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("create_oidc_login_attempt");
+        let attempt = OidcLoginAttempt {
+            state: attempt.state.to_owned(),
+            provider: attempt.provider.to_owned(),
+            nonce: attempt.nonce.to_owned(),
+            code_verifier: attempt.code_verifier.to_owned(),
+            expires_at: attempt.expires_at,
+        };
+        identities::insert_login_attempt(&self.0, &attempt).map_err(to_db_error)
+    }
+
+    fn take_oidc_login_attempt(
+        &self,
+        state: &str,
+    ) -> Result<Option<domain::OidcLoginAttempt>, DatabaseError> {
+        let _instrument = instrument("take_oidc_login_attempt");
+        match identities::take_login_attempt(&self.0, state) {
+            Ok(a) => Ok(Some(domain::OidcLoginAttempt {
+                state: a.state,
+                provider: a.provider,
+                nonce: a.nonce,
+                code_verifier: a.code_verifier,
+                expires_at: a.expires_at,
+            })),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(to_db_error(e)),
+        }
+    }
+
+    fn get_sessions_revoked_at(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, GetUserError> {
+        let _instrument = instrument("get_sessions_revoked_at");
+        let result = users::find(&self.0, user_id);
         let user = result.map_err(|e| match e {
-            Error::NotFound => domain::LoginError::NotFound,
+            e @ Error::NotFound => domain::GetUserError::NotFound {
+                user_id,
+                source: to_db_error(e),
+            },
             e => to_db_error(e).into(),
         })?;
+        Ok(user.sessions_revoked_at)
+    }
+
+    fn create_password_reset(
+        &self,
+        user: &domain::User,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("create_password_reset");
+        let token = NewPasswordResetToken {
+            token_hash,
+            user_id: user.id,
+            expires_at,
+        };
+        password_resets::insert(&self.0, token).map_err(to_db_error)
+    }
+
+    fn reset_password(
+        &self,
+        token_hash: &str,
+        new_password: &domain::Password,
+    ) -> Result<domain::User, domain::PasswordResetError> {
+        let _instrument = instrument("reset_password");
+        let user = password_resets::redeem(&self.0, token_hash, new_password.hash()).map_err(
+            |e| match e {
+                Error::NotFound => domain::PasswordResetError::InvalidToken,
+                e => to_db_error(e).into(),
+            },
+        )?;
+        Ok(domain::User::from(user))
+    }
+
+    fn get_two_factor(
+        &self,
+        user: &domain::User,
+    ) -> Result<Option<domain::TwoFactor>, DatabaseError> {
+        let _instrument = instrument("get_two_factor");
+        let credentials = match two_factor::find(&self.0, user.id) {
+            Ok(c) => c,
+            Err(Error::NotFound) => return Ok(None),
+            Err(e) => return Err(to_db_error(e)),
+        };
+        let secret = domain::TotpSecret::from_base32(&credentials.secret).ok_or_else(|| {
+            DatabaseError::from(OpaqueError::msg(
+                "Malformed two-factor authentication secret",
+            ))
+        })?;
+        Ok(Some(domain::TwoFactor {
+            secret,
+            confirmed: credentials.confirmed_at.is_some(),
+        }))
+    }
+
+    fn enroll_two_factor(
+        &self,
+        user: &domain::User,
+        secret: &domain::TotpSecret,
+    ) -> Result<(), domain::TwoFactorError> {
+        let _instrument = instrument("enroll_two_factor");
+        let credentials = NewTwoFactorCredentials {
+            user_id: user.id,
+            secret: &secret.to_base32(),
+        };
+        match two_factor::enroll(&self.0, credentials).map_err(to_db_error)? {
+            true => Ok(()),
+            false => Err(domain::TwoFactorError::AlreadyEnabled),
+        }
+    }
+
+    fn confirm_two_factor(
+        &self,
+        user: &domain::User,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("confirm_two_factor");
+        two_factor::confirm(&self.0, user.id, step, recovery_code_hashes).map_err(to_db_error)
+    }
+
+    fn use_two_factor_step(&self, user: &domain::User, step: i64) -> Result<bool, DatabaseError> {
+        let _instrument = instrument("use_two_factor_step");
+        two_factor::use_step(&self.0, user.id, step).map_err(to_db_error)
+    }
+
+    fn use_recovery_code(
+        &self,
+        user: &domain::User,
+        code_hash: &str,
+    ) -> Result<bool, DatabaseError> {
+        let _instrument = instrument("use_recovery_code");
+        two_factor::use_recovery_code(&self.0, user.id, code_hash).map_err(to_db_error)
+    }
+
+    fn disable_two_factor(&self, user: &domain::User) -> Result<(), DatabaseError> {
+        let _instrument = instrument("disable_two_factor");
+        two_factor::delete(&self.0, user.id).map_err(to_db_error)
+    }
+
+    fn create_access_token(
+        &self,
+        user: &domain::User,
+        name: &str,
+        scopes: &[domain::Scope],
+        token_hash: &str,
+    ) -> Result<domain::AccessToken, DatabaseError> {
+        let _instrument = instrument("create_access_token");
+        let token = NewAccessToken {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            name,
+            token_hash,
+            scopes: scopes.iter().map(|s| s.as_str()).collect(),
+        };
+        let token = access_tokens::insert(&self.0, token).map_err(to_db_error)?;
+        Ok(to_access_token(token))
+    }
+
+    fn get_access_tokens(
+        &self,
+        user: &domain::User,
+    ) -> Result<Vec<domain::AccessToken>, DatabaseError> {
+        let _instrument = instrument("get_access_tokens");
+        let tokens = access_tokens::find_by_user(&self.0, user.id).map_err(to_db_error)?;
+        Ok(tokens.into_iter().map(to_access_token).collect())
+    }
+
+    fn delete_access_token(
+        &self,
+        user: &domain::User,
+        token_id: Uuid,
+    ) -> Result<(), domain::AccessTokenError> {
+        let _instrument = instrument("delete_access_token");
+        access_tokens::delete(&self.0, user.id, token_id).map_err(|e| match e {
+            Error::NotFound => domain::AccessTokenError::NotFound { token_id },
+            e => to_db_error(e).into(),
+        })
+    }
+
+    fn use_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<domain::AccessToken>, DatabaseError> {
+        let _instrument = instrument("use_access_token");
+        match access_tokens::find_and_touch(&self.0, token_hash) {
+            Ok(token) => Ok(Some(to_access_token(token))),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(to_db_error(e)),
+        }
+    }
+
+    fn find_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<domain::AccessToken>, DatabaseError> {
+        let _instrument = instrument("find_access_token");
+        match access_tokens::find_by_hash(&self.0, token_hash) {
+            Ok(token) => Ok(Some(to_access_token(token))),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(to_db_error(e)),
+        }
+    }
+
+    fn get_user_by_email_and_password(
+        &self,
+        email: &str,
+        password: &str,
+        hashing: &domain::PasswordHashing,
+    ) -> Result<domain::User, domain::LoginError> {
+        let _instrument = instrument("get_user_by_email_and_password");
+        let user = match users::find_by_email(&self.0, email) {
+            Ok(user) => user,
+            Err(Error::NotFound) => {
+                // Hash the password anyway: responding faster for unknown emails
+                // would reveal which ones are registered.
+                domain::Password::from_clear_text(password.to_owned(), hashing)?;
+                return Err(domain::LoginError::NotFound);
+            }
+            Err(e) => return Err(to_db_error(e).into()),
+        };
 
         // Check if the provided password is valid
         let stored_password = domain::Password::from_hash(user.password.to_owned());
-        if !stored_password.verify(&password)? {
-            return Err(domain::LoginError::NotFound);
+        match stored_password.verify(&password) {
+            Ok(true) => {}
+            Ok(false) => return Err(domain::LoginError::NotFound),
+            // Hashes which cannot be parsed (e.g. the one of the deleted user placeholder)
+            // match no password: hash it anyway, as for unknown emails
+            Err(_) => {
+                domain::Password::from_clear_text(password.to_owned(), hashing)?;
+                return Err(domain::LoginError::NotFound);
+            }
+        }
+
+        // The password is in clear text only now: it is the time to upgrade its hash
+        if stored_password.needs_rehash(hashing) {
+            let rehashed = domain::Password::from_clear_text(password.to_owned(), hashing)?;
+            let update = UpdateUser {
+                password: Some(rehashed.hash().to_owned()),
+                ..Default::default()
+            };
+            users::update(&self.0, user.id, update).map_err(to_db_error)?;
         }
 
         Ok(domain::User::from(user))
     }
 
     fn get_profile(&self, username: &str) -> Result<domain::Profile, GetUserError> {
+        let _instrument = instrument("get_profile");
         let user = users::find_by_username(&self.0, username).map_err(to_db_error)?;
         Ok(domain::Profile::from(user))
     }
@@ -269,6 +704,7 @@ impl domain::repositories::Repository for Repository {
         viewer: &domain::User,
         username: &str,
     ) -> Result<domain::ProfileView, GetUserError> {
+        let _instrument = instrument("get_profile_view");
         let viewed_user = users::find_by_username(&self.0, username).map_err(to_db_error)?;
         let following =
             followers::is_following(&self.0, viewer.id, viewed_user.id).map_err(to_db_error)?;
@@ -284,7 +720,8 @@ impl domain::repositories::Repository for Repository {
         &self,
         follower: &domain::User,
         to_be_followed: &domain::Profile,
-    ) -> Result<(), DatabaseError> {
+    ) -> Result<domain::FollowOutcome, DatabaseError> {
+        let _instrument = instrument("follow");
         let followed_user =
             users::find_by_username(&self.0, &to_be_followed.username).map_err(to_db_error)?;
         Ok(followers::follow(&self.0, follower.id, followed_user.id).map_err(to_db_error)?)
@@ -295,6 +732,7 @@ impl domain::repositories::Repository for Repository {
         follower: &domain::User,
         to_be_unfollowed: &domain::Profile,
     ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("unfollow");
         let unfollowed_user =
             users::find_by_username(&self.0, &to_be_unfollowed.username).map_err(to_db_error)?;
         Ok(
@@ -303,7 +741,186 @@ impl domain::repositories::Repository for Repository {
         )
     }
 
+    fn block(
+        &self,
+        blocker: &domain::User,
+        to_be_blocked: &domain::Profile,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("block");
+        let blocked_user =
+            users::find_by_username(&self.0, &to_be_blocked.username).map_err(to_db_error)?;
+        blocks::block(&self.0, blocker.id, blocked_user.id).map_err(to_db_error)
+    }
+
+    fn unblock(
+        &self,
+        blocker: &domain::User,
+        to_be_unblocked: &domain::Profile,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("unblock");
+        let blocked_user =
+            users::find_by_username(&self.0, &to_be_unblocked.username).map_err(to_db_error)?;
+        blocks::unblock(&self.0, blocker.id, blocked_user.id).map_err(to_db_error)
+    }
+
+    fn has_blocked(
+        &self,
+        blocker: &domain::Profile,
+        blocked: &domain::Profile,
+    ) -> Result<bool, DatabaseError> {
+        let _instrument = instrument("has_blocked");
+        let blocker = users::find_by_username(&self.0, &blocker.username).map_err(to_db_error)?;
+        let blocked = users::find_by_username(&self.0, &blocked.username).map_err(to_db_error)?;
+        blocks::has_blocked(&self.0, blocker.id, blocked.id).map_err(to_db_error)
+    }
+
+    fn mute(
+        &self,
+        muter: &domain::User,
+        to_be_muted: &domain::Profile,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("mute");
+        let muted_user =
+            users::find_by_username(&self.0, &to_be_muted.username).map_err(to_db_error)?;
+        mutes::mute(&self.0, muter.id, muted_user.id).map_err(to_db_error)
+    }
+
+    fn unmute(
+        &self,
+        muter: &domain::User,
+        to_be_unmuted: &domain::Profile,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("unmute");
+        let muted_user =
+            users::find_by_username(&self.0, &to_be_unmuted.username).map_err(to_db_error)?;
+        mutes::unmute(&self.0, muter.id, muted_user.id).map_err(to_db_error)
+    }
+
+    fn get_followers(
+        &self,
+        profile: &domain::Profile,
+        query: domain::ProfilesQuery,
+    ) -> Result<Vec<domain::Profile>, DatabaseError> {
+        let _instrument = instrument("get_followers");
+        let user = users::find_by_username(&self.0, &profile.username).map_err(to_db_error)?;
+        let followers = followers::followers_of(&self.0, user.id, query.limit, query.offset)
+            .map_err(to_db_error)?;
+        Ok(followers.into_iter().map(domain::Profile::from).collect())
+    }
+
+    fn get_followed(
+        &self,
+        profile: &domain::Profile,
+        query: domain::ProfilesQuery,
+    ) -> Result<Vec<domain::Profile>, DatabaseError> {
+        let _instrument = instrument("get_followed");
+        let user = users::find_by_username(&self.0, &profile.username).map_err(to_db_error)?;
+        let followed = followers::followed_by(&self.0, user.id, query.limit, query.offset)
+            .map_err(to_db_error)?;
+        Ok(followed.into_iter().map(domain::Profile::from).collect())
+    }
+
+    fn get_follow_counts(
+        &self,
+        profile: &domain::Profile,
+    ) -> Result<domain::FollowCounts, DatabaseError> {
+        let _instrument = instrument("get_follow_counts");
+        let user = users::find_by_username(&self.0, &profile.username).map_err(to_db_error)?;
+        let (followers, following) = followers::counts(&self.0, user.id).map_err(to_db_error)?;
+        Ok(domain::FollowCounts {
+            followers,
+            following,
+        })
+    }
+
+    fn get_profiles_views(
+        &self,
+        viewer: &domain::User,
+        profiles: Vec<domain::Profile>,
+    ) -> Result<Vec<domain::ProfileView>, DatabaseError> {
+        let _instrument = instrument("get_profiles_views");
+        let usernames: Vec<String> = profiles.iter().map(|p| p.username.to_owned()).collect();
+        let followed =
+            followers::followed_usernames(&self.0, viewer.id, &usernames).map_err(to_db_error)?;
+        let views = profiles
+            .into_iter()
+            .map(|profile| domain::ProfileView {
+                following: followed.contains(&profile.username),
+                profile,
+                viewer: viewer.id,
+            })
+            .collect();
+        Ok(views)
+    }
+
     fn get_tags(&self) -> Result<HashSet<String>, DatabaseError> {
+        let _instrument = instrument("get_tags");
         Ok(articles::tags(&self.0).map_err(OpaqueError::from)?)
     }
+
+    fn record_event(
+        &self,
+        actor: &domain::User,
+        recipient: &domain::Profile,
+        event: domain::Event,
+    ) -> Result<(), DatabaseError> {
+        let _instrument = instrument("record_event");
+        let recipient =
+            users::find_by_username(&self.0, &recipient.username).map_err(to_db_error)?;
+        let (kind, article_id, comment_id) = to_event_columns(&event);
+        let notification = NewNotification {
+            recipient_id: recipient.id,
+            actor_id: actor.id,
+            kind,
+            article_id,
+            comment_id,
+        };
+        notifications::insert(&self.0, notification).map_err(to_db_error)
+    }
+
+    fn get_notifications(
+        &self,
+        recipient: &domain::User,
+        query: domain::NotificationsQuery,
+    ) -> Result<Vec<domain::Notification>, DatabaseError> {
+        let _instrument = instrument("get_notifications");
+        let notifications = notifications::find(
+            &self.0,
+            recipient.id,
+            query.unread_only,
+            query.after_id,
+            query.limit,
+            query.offset,
+        )
+        .map_err(to_db_error)?
+        .into_iter()
+        .filter_map(|(n, actor, article_title)| to_notification(n, actor, article_title))
+        .collect();
+        Ok(notifications)
+    }
+
+    fn count_unread_notifications(&self, recipient: &domain::User) -> Result<u64, DatabaseError> {
+        let _instrument = instrument("count_unread_notifications");
+        notifications::count_unread(&self.0, recipient.id).map_err(to_db_error)
+    }
+
+    fn mark_notification(
+        &self,
+        recipient: &domain::User,
+        notification_id: u64,
+        read: bool,
+    ) -> Result<(), NotificationError> {
+        let _instrument = instrument("mark_notification");
+        let found = notifications::mark(&self.0, recipient.id, notification_id, read)
+            .map_err(to_db_error)?;
+        if !found {
+            return Err(NotificationError::NotFound { notification_id });
+        }
+        Ok(())
+    }
+
+    fn mark_all_notifications_read(&self, recipient: &domain::User) -> Result<(), DatabaseError> {
+        let _instrument = instrument("mark_all_notifications_read");
+        notifications::mark_all_read(&self.0, recipient.id).map_err(to_db_error)
+    }
 }
@@ -1,3 +1,27 @@
+table! {
+    access_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        name -> Varchar,
+        token_hash -> Varchar,
+        scopes -> Array<Text>,
+        created_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    article_revisions (id) {
+        id -> Int8,
+        article_id -> Varchar,
+        editor_id -> Uuid,
+        title -> Nullable<Varchar>,
+        description -> Nullable<Varchar>,
+        body -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     articles (slug) {
         title -> Varchar,
@@ -8,6 +32,16 @@ table! {
         user_id -> Uuid,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        status -> Varchar,
+        publish_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    blocks (blocker_id, blocked_id) {
+        blocker_id -> Uuid,
+        blocked_id -> Uuid,
+        created_at -> Timestamptz,
     }
 }
 
@@ -36,6 +70,68 @@ table! {
     }
 }
 
+table! {
+    mutes (muter_id, muted_id) {
+        muter_id -> Uuid,
+        muted_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> Int8,
+        recipient_id -> Uuid,
+        actor_id -> Uuid,
+        kind -> Varchar,
+        article_id -> Nullable<Varchar>,
+        comment_id -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        read_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    oidc_login_attempts (state) {
+        state -> Varchar,
+        provider -> Varchar,
+        nonce -> Varchar,
+        code_verifier -> Varchar,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    password_reset_tokens (token_hash) {
+        token_hash -> Varchar,
+        user_id -> Uuid,
+        expires_at -> Timestamptz,
+        used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    two_factor_credentials (user_id) {
+        user_id -> Uuid,
+        secret -> Varchar,
+        confirmed_at -> Nullable<Timestamptz>,
+        last_used_step -> Nullable<Int8>,
+        recovery_code_hashes -> Array<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    user_identities (provider, subject) {
+        provider -> Varchar,
+        subject -> Varchar,
+        user_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     users (id) {
         id -> Uuid,
@@ -46,13 +142,38 @@ table! {
         image -> Nullable<Varchar>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        sessions_revoked_at -> Nullable<Timestamptz>,
+        email_verified -> Bool,
     }
 }
 
+joinable!(access_tokens -> users (user_id));
+joinable!(article_revisions -> articles (article_id));
+joinable!(article_revisions -> users (editor_id));
 joinable!(articles -> users (user_id));
 joinable!(comments -> articles (article_id));
 joinable!(comments -> users (author_id));
 joinable!(favorites -> articles (article_id));
 joinable!(favorites -> users (user_id));
+joinable!(notifications -> articles (article_id));
+joinable!(notifications -> comments (comment_id));
+joinable!(password_reset_tokens -> users (user_id));
+joinable!(two_factor_credentials -> users (user_id));
+joinable!(user_identities -> users (user_id));
 
-allow_tables_to_appear_in_same_query!(articles, comments, favorites, followers, users,);
+allow_tables_to_appear_in_same_query!(
+    access_tokens,
+    article_revisions,
+    articles,
+    blocks,
+    comments,
+    favorites,
+    followers,
+    mutes,
+    notifications,
+    oidc_login_attempts,
+    password_reset_tokens,
+    two_factor_credentials,
+    user_identities,
+    users,
+);
@@ -1,4 +1,30 @@
-use crate::models::{Article, Comment, NewArticle, UpdateArticle, UpdateUser, User};
+use crate::models::{
+    AccessToken, Article, ArticleRevision, Comment, NewArticle, Notification, UpdateArticle,
+    UpdateUser, User,
+};
+use chrono::{DateTime, Utc};
+
+/// Map an article status onto the values of the `status` and `publish_at` columns.
+pub fn to_status_columns(status: &domain::ArticleStatus) -> (&'static str, Option<DateTime<Utc>>) {
+    match status {
+        domain::ArticleStatus::Draft => ("draft", None),
+        domain::ArticleStatus::Scheduled { publish_at } => ("scheduled", Some(*publish_at)),
+        domain::ArticleStatus::Published => ("published", Some(Utc::now())),
+        domain::ArticleStatus::Archived => ("archived", None),
+    }
+}
+
+pub fn to_status(status: &str, publish_at: Option<DateTime<Utc>>) -> domain::ArticleStatus {
+    match (status, publish_at) {
+        ("draft", _) => domain::ArticleStatus::Draft,
+        ("scheduled", Some(publish_at)) if publish_at > Utc::now() => {
+            domain::ArticleStatus::Scheduled { publish_at }
+        }
+        ("archived", _) => domain::ArticleStatus::Archived,
+        // Scheduled articles whose publication time has passed are published.
+        _ => domain::ArticleStatus::Published,
+    }
+}
 
 pub fn to_article(a: Article, u: domain::User, n_fav: u64) -> domain::Article {
     let metadata = domain::ArticleMetadata {
@@ -17,6 +43,7 @@ pub fn to_article(a: Article, u: domain::User, n_fav: u64) -> domain::Article {
         author: u.profile,
         metadata,
         favorites_count: n_fav,
+        status: to_status(&a.status, a.publish_at),
     }
 }
 
@@ -25,6 +52,7 @@ impl From<User> for domain::User {
         domain::User {
             id: u.id,
             email: u.email,
+            email_verified: u.email_verified,
             profile: domain::Profile {
                 username: u.username,
                 bio: u.bio,
@@ -54,9 +82,40 @@ pub fn to_comment(c: Comment, u: User) -> domain::Comment {
     }
 }
 
+pub fn to_revision(r: ArticleRevision, editor: User) -> domain::ArticleRevision {
+    domain::ArticleRevision {
+        id: r.id as u64,
+        editor: domain::Profile::from(editor),
+        title: r.title,
+        description: r.description,
+        body: r.body,
+        created_at: r.created_at,
+    }
+}
+
 impl<'a> From<(&'a domain::ArticleContent, &'a domain::User)> for NewArticle<'a> {
     fn from(x: (&'a domain::ArticleContent, &'a domain::User)) -> Self {
         let (draft, author) = x;
+        Self::from((draft, &domain::ArticleStatus::Published, author))
+    }
+}
+
+impl<'a>
+    From<(
+        &'a domain::ArticleContent,
+        &'a domain::ArticleStatus,
+        &'a domain::User,
+    )> for NewArticle<'a>
+{
+    fn from(
+        x: (
+            &'a domain::ArticleContent,
+            &'a domain::ArticleStatus,
+            &'a domain::User,
+        ),
+    ) -> Self {
+        let (draft, status, author) = x;
+        let (status, publish_at) = to_status_columns(status);
         Self {
             title: &draft.title,
             slug: draft.slug(),
@@ -64,16 +123,36 @@ impl<'a> From<(&'a domain::ArticleContent, &'a domain::User)> for NewArticle<'a>
             body: &draft.body,
             tag_list: draft.tag_list.to_owned(),
             user_id: author.id.to_owned(),
+            status,
+            publish_at,
         }
     }
 }
 
-impl<'a> From<&'a domain::ArticleUpdate> for UpdateArticle<'a> {
-    fn from(update: &'a domain::ArticleUpdate) -> Self {
+/// The update of an article, given its current status.
+///
+/// Articles that are already published keep their original publication time.
+impl<'a> From<(&'a domain::ArticleUpdate, &'a domain::ArticleStatus)> for UpdateArticle<'a> {
+    fn from(x: (&'a domain::ArticleUpdate, &'a domain::ArticleStatus)) -> Self {
+        let (update, current) = x;
+        let (status, publish_at) = match &update.status {
+            Some(domain::ArticleStatus::Published)
+                if current == &domain::ArticleStatus::Published =>
+            {
+                (Some("published"), None)
+            }
+            Some(status) => {
+                let (status, publish_at) = to_status_columns(status);
+                (Some(status), Some(publish_at))
+            }
+            None => (None, None),
+        };
         Self {
             title: update.title.as_deref(),
             description: update.description.as_deref(),
             body: update.body.as_deref(),
+            status,
+            publish_at,
         }
     }
 }
@@ -86,6 +165,60 @@ impl<'a> From<&'a domain::UserUpdate> for UpdateUser<'a> {
             password: u.password.as_ref().map(|p| p.hash().to_owned()),
             image: u.image.as_deref(),
             bio: u.bio.as_deref(),
+            email_verified: None,
         }
     }
 }
+
+/// Scopes we do not know about (e.g. dropped by a later version) are ignored.
+pub fn to_access_token(t: AccessToken) -> domain::AccessToken {
+    domain::AccessToken {
+        id: t.id,
+        user_id: t.user_id,
+        name: t.name,
+        scopes: t
+            .scopes
+            .iter()
+            .filter_map(|s| domain::Scope::parse(s))
+            .collect(),
+        created_at: t.created_at,
+        last_used_at: t.last_used_at,
+    }
+}
+
+/// Map an event onto the values of the `kind`, `article_id` and `comment_id` columns.
+pub fn to_event_columns(event: &domain::Event) -> (&'static str, Option<&str>, Option<i64>) {
+    match event {
+        domain::Event::Followed => ("follow", None, None),
+        domain::Event::Commented {
+            article_slug,
+            comment_id,
+        } => ("comment", Some(article_slug), Some(*comment_id as i64)),
+        domain::Event::Favorited { article_slug } => ("favorite", Some(article_slug), None),
+    }
+}
+
+/// Notifications of a kind we do not know about (e.g. added by a later version) are ignored.
+pub fn to_notification(
+    n: Notification,
+    actor: User,
+    article_title: Option<String>,
+) -> Option<domain::Notification> {
+    let event = match (n.kind.as_str(), n.article_id, n.comment_id) {
+        ("follow", _, _) => domain::Event::Followed,
+        ("comment", Some(article_slug), Some(comment_id)) => domain::Event::Commented {
+            article_slug,
+            comment_id: comment_id as u64,
+        },
+        ("favorite", Some(article_slug), _) => domain::Event::Favorited { article_slug },
+        _ => return None,
+    };
+    Some(domain::Notification {
+        id: n.id as u64,
+        actor: domain::Profile::from(actor),
+        event,
+        article_title,
+        created_at: n.created_at,
+        read_at: n.read_at,
+    })
+}
@@ -0,0 +1,93 @@
+//! Each query sent to Postgres is traced as a span, carrying its SQL.
+use diesel::connection::{AnsiTransactionManager, SimpleConnection};
+use diesel::deserialize::{Queryable, QueryableByName};
+use diesel::pg::{Pg, PgQueryBuilder};
+use diesel::query_builder::{AsQuery, QueryBuilder, QueryFragment, QueryId};
+use diesel::sql_types::HasSqlType;
+use diesel::{Connection, ConnectionResult, PgConnection, QueryResult};
+use telemetry::{start_span, Span, SpanKind};
+
+/// A `PgConnection` tracing the queries it runs.
+///
+/// Statements are recorded without their bind parameters, which may hold personal data.
+pub struct TracedConnection(PgConnection);
+
+/// A span named after the operation and the first table of `sql`, e.g. `SELECT articles`.
+fn query_span(sql: &str) -> Span {
+    let operation = sql.split_whitespace().next().unwrap_or_default();
+    let table = sql.split('"').nth(1);
+    let name = match table {
+        Some(table) => format!("{} {}", operation, table),
+        None => operation.to_owned(),
+    };
+    let mut span = start_span(name, SpanKind::Client);
+    span.set_attribute("db.system", "postgresql");
+    span.set_attribute("db.statement", sql);
+    span
+}
+
+fn sql_of<T: QueryFragment<Pg>>(query: &T) -> String {
+    let mut builder = PgQueryBuilder::default();
+    match query.to_sql(&mut builder) {
+        Ok(()) => builder.finish(),
+        Err(_) => "unknown".into(),
+    }
+}
+
+fn traced<T>(sql: &str, run: impl FnOnce() -> QueryResult<T>) -> QueryResult<T> {
+    let mut span = query_span(sql);
+    let result = run();
+    if result.is_err() {
+        span.set_error();
+    }
+    result
+}
+
+impl SimpleConnection for TracedConnection {
+    fn batch_execute(&self, query: &str) -> QueryResult<()> {
+        traced(query, || self.0.batch_execute(query))
+    }
+}
+
+impl Connection for TracedConnection {
+    type Backend = Pg;
+    type TransactionManager = AnsiTransactionManager;
+
+    fn establish(database_url: &str) -> ConnectionResult<Self> {
+        PgConnection::establish(database_url).map(TracedConnection)
+    }
+
+    fn execute(&self, query: &str) -> QueryResult<usize> {
+        traced(query, || self.0.execute(query))
+    }
+
+    fn query_by_index<T, U>(&self, source: T) -> QueryResult<Vec<U>>
+    where
+        T: AsQuery,
+        T::Query: QueryFragment<Pg> + QueryId,
+        Pg: HasSqlType<T::SqlType>,
+        U: Queryable<T::SqlType, Pg>,
+    {
+        let query = source.as_query();
+        traced(&sql_of(&query), || self.0.query_by_index(query))
+    }
+
+    fn query_by_name<T, U>(&self, source: &T) -> QueryResult<Vec<U>>
+    where
+        T: QueryFragment<Pg> + QueryId,
+        U: QueryableByName<Pg>,
+    {
+        traced(&sql_of(source), || self.0.query_by_name(source))
+    }
+
+    fn execute_returning_count<T>(&self, source: &T) -> QueryResult<usize>
+    where
+        T: QueryFragment<Pg> + QueryId,
+    {
+        traced(&sql_of(source), || self.0.execute_returning_count(source))
+    }
+
+    fn transaction_manager(&self) -> &Self::TransactionManager {
+        self.0.transaction_manager()
+    }
+}
@@ -1,10 +1,15 @@
 mod helpers;
 
+use helpers::generate;
 use helpers::test_db::get_test_repo;
 use helpers::{create_articles, create_user, create_users};
 
-use realworld_db::models::{NewArticle, User};
-use realworld_db::queries::articles;
+use chrono::{Duration, Utc};
+use domain::{
+    ArticleCursor, ArticlePosition, ArticleQuery, ArticleSort, ArticleStatus, ArticleUpdate,
+};
+use realworld_db::models::{NewArticle, UpdateArticle, User};
+use realworld_db::queries::{articles, followers};
 use std::collections::HashSet;
 
 #[test]
@@ -13,11 +18,282 @@ fn list_articles() {
 
     let users: Vec<User> = create_users(&repo, 5).into_iter().map(|(u, _)| u).collect();
     let _articles = create_articles(&repo, users);
-    let results = articles::find(&repo, Default::default()).expect("Failed to get articles");
+    let results = articles::find(&repo, Default::default(), None).expect("Failed to get articles");
 
     assert_eq!(results.len(), 5);
 }
 
+#[test]
+fn articles_which_are_not_public_are_not_listed() {
+    let repo = get_test_repo();
+    let author = create_user(&repo).0;
+
+    let tomorrow = Utc::now() + Duration::days(1);
+    let statuses = vec![
+        ArticleStatus::Draft,
+        ArticleStatus::Scheduled {
+            publish_at: tomorrow,
+        },
+        ArticleStatus::Archived,
+    ];
+    for status in &statuses {
+        let draft = generate::article_content();
+        articles::insert(
+            &repo,
+            NewArticle::from((&draft, status, &author.clone().into())),
+        )
+        .expect("Failed to create article");
+    }
+
+    let results = articles::find(&repo, Default::default(), None).expect("Failed to get articles");
+    assert!(results.is_empty());
+    assert!(articles::tags(&repo).unwrap().is_empty());
+
+    // Drafts and scheduled articles are listed as drafts, archived ones are not
+    let drafts = articles::drafts(&repo, author.id).expect("Failed to get drafts");
+    assert_eq!(drafts.len(), 2);
+}
+
+#[test]
+fn scheduled_articles_are_listed_once_their_publication_time_has_passed() {
+    let repo = get_test_repo();
+    let author = create_user(&repo).0;
+
+    let draft = generate::article_content();
+    let status = ArticleStatus::Scheduled {
+        publish_at: Utc::now() - Duration::minutes(1),
+    };
+    articles::insert(
+        &repo,
+        NewArticle::from((&draft, &status, &author.clone().into())),
+    )
+    .expect("Failed to create article");
+
+    let results = articles::find(&repo, Default::default(), None).expect("Failed to get articles");
+    assert_eq!(results.len(), 1);
+    assert!(articles::drafts(&repo, author.id).unwrap().is_empty());
+}
+
+#[test]
+fn republishing_an_article_keeps_its_publication_time() {
+    let repo = get_test_repo();
+    let author = create_user(&repo).0;
+
+    let draft = generate::article_content();
+    let yesterday = Utc::now() - Duration::days(1);
+    let status = ArticleStatus::Scheduled {
+        publish_at: yesterday,
+    };
+    let article = articles::insert(
+        &repo,
+        NewArticle::from((&draft, &status, &author.clone().into())),
+    )
+    .expect("Failed to create article");
+
+    let update = ArticleUpdate {
+        title: Some("Still published".into()),
+        description: None,
+        body: None,
+        status: Some(ArticleStatus::Published),
+    };
+    let changes = UpdateArticle::from((&update, &ArticleStatus::Published));
+    let updated = articles::update(&repo, changes, &article.slug, author.id)
+        .expect("Failed to update article");
+    assert_eq!(updated.status, "published");
+    assert_eq!(updated.publish_at, article.publish_at);
+
+    // Drafts are published as of now
+    let changes = UpdateArticle::from((&update, &ArticleStatus::Draft));
+    let updated = articles::update(&repo, changes, &article.slug, author.id)
+        .expect("Failed to update article");
+    assert!(updated.publish_at.unwrap() > yesterday + Duration::hours(1));
+}
+
+#[test]
+fn paginate_articles_with_cursors() {
+    paginate_articles_with_cursors_sorted_by(ArticleSort::Newest);
+}
+
+#[test]
+fn paginate_articles_oldest_first_with_cursors() {
+    paginate_articles_with_cursors_sorted_by(ArticleSort::Oldest);
+}
+
+fn paginate_articles_with_cursors_sorted_by(sort: ArticleSort) {
+    let repo = get_test_repo();
+    let users: Vec<User> = create_users(&repo, 5).into_iter().map(|(u, _)| u).collect();
+    create_articles(&repo, users);
+
+    let query = ArticleQuery {
+        sort: Some(sort),
+        ..Default::default()
+    };
+    let all: Vec<String> = articles::find(&repo, query, None)
+        .expect("Failed to get articles")
+        .into_iter()
+        .map(|(a, _, _)| a.slug)
+        .collect();
+    // Articles created in the same transaction share their creation date: ties are
+    // broken by ascending slug, whatever the sort order
+    let mut by_slug = all.clone();
+    by_slug.sort();
+    assert_eq!(all, by_slug);
+
+    let page = |cursor: Option<ArticleCursor>| -> Vec<(String, ArticlePosition)> {
+        let query = ArticleQuery {
+            sort: Some(sort),
+            limit: Some(2),
+            cursor,
+            ..Default::default()
+        };
+        articles::find(&repo, query, None)
+            .expect("Failed to get articles")
+            .into_iter()
+            .map(|(a, _, _)| {
+                let position = ArticlePosition {
+                    created_at: a.created_at,
+                    slug: a.slug.clone(),
+                };
+                (a.slug, position)
+            })
+            .collect()
+    };
+
+    let first_page = page(None);
+    let second_page = page(Some(ArticleCursor::After(first_page[1].1.clone())));
+    let slugs: Vec<String> = second_page.iter().map(|(s, _)| s.to_owned()).collect();
+    assert_eq!(slugs, all[2..4].to_vec());
+
+    // Going backwards from the second page brings us back to the first one
+    let previous_page = page(Some(ArticleCursor::Before(second_page[0].1.clone())));
+    assert_eq!(previous_page, first_page);
+
+    let last_page = page(Some(ArticleCursor::After(second_page[1].1.clone())));
+    let slugs: Vec<String> = last_page.iter().map(|(s, _)| s.to_owned()).collect();
+    assert_eq!(slugs, all[4..].to_vec());
+}
+
+#[test]
+fn cursors_are_rejected_with_other_sort_orders() {
+    let repo = get_test_repo();
+    let position = ArticlePosition {
+        created_at: Utc::now(),
+        slug: "a-slug".into(),
+    };
+    let query = ArticleQuery {
+        sort: Some(ArticleSort::MostFavorited),
+        cursor: Some(ArticleCursor::After(position)),
+        ..Default::default()
+    };
+    assert!(articles::find(&repo, query, None).is_err());
+}
+
+#[test]
+fn search_articles() {
+    let repo = get_test_repo();
+    let author = create_user(&repo).0;
+
+    let new_article = |slug: &str, title: &'static str, body: &'static str, tag: &str| NewArticle {
+        title,
+        slug: slug.to_string(),
+        description: "An article about crustaceans",
+        body,
+        tag_list: vec![tag.to_string()],
+        user_id: author.id,
+        status: "published",
+        publish_at: Some(Utc::now()),
+    };
+    let new_articles = vec![
+        new_article("in-title", "Ferris", "Lorem ipsum", "rust"),
+        new_article(
+            "in-body",
+            "Crabs",
+            "Lorem ipsum dolor sit amet, Ferris consectetur",
+            "crab",
+        ),
+        new_article("no-match", "Lobsters", "Lorem ipsum", "crab"),
+    ];
+    for article in new_articles {
+        articles::insert(&repo, article).expect("Failed to create article");
+    }
+
+    let query = ArticleQuery {
+        q: Some("ferris".into()),
+        ..Default::default()
+    };
+    let results = articles::find(&repo, query, None).expect("Failed to search articles");
+    let slugs: Vec<String> = results.into_iter().map(|(a, _, _)| a.slug).collect();
+    // Matches in the title rank higher than matches in the body
+    assert_eq!(slugs, vec!["in-title", "in-body"]);
+
+    // Search can be combined with the other filters
+    let query = ArticleQuery {
+        q: Some("ferris".into()),
+        tag: Some("crab".to_string()),
+        ..Default::default()
+    };
+    let results = articles::find(&repo, query, None).expect("Failed to search articles");
+    let slugs: Vec<String> = results.into_iter().map(|(a, _, _)| a.slug).collect();
+    assert_eq!(slugs, vec!["in-body"]);
+
+    let snippets = articles::snippets(&repo, "ferris", &slugs).expect("Failed to get snippets");
+    assert!(snippets["in-body"].contains("<mark>Ferris</mark>"));
+}
+
+#[test]
+fn search_snippets_are_html_escaped() {
+    let repo = get_test_repo();
+    let author = create_user(&repo).0;
+
+    let article = NewArticle {
+        title: "Crabs",
+        slug: "markup".to_string(),
+        description: "An article about crustaceans",
+        body: "Say <script>alert('Ferris')</script> & \"Ferris\"",
+        tag_list: vec![],
+        user_id: author.id,
+        status: "published",
+        publish_at: Some(Utc::now()),
+    };
+    articles::insert(&repo, article).expect("Failed to create article");
+
+    let slugs = vec!["markup".to_string()];
+    let snippets = articles::snippets(&repo, "ferris", &slugs).expect("Failed to get snippets");
+    let snippet = &snippets["markup"];
+    assert!(!snippet.contains("script>"), "{}", snippet);
+    assert!(
+        snippet.contains("&lt;/script&gt; &amp; &quot;"),
+        "{}",
+        snippet
+    );
+    assert!(
+        snippet.contains("&#39;<mark>Ferris</mark>&#39;"),
+        "{}",
+        snippet
+    );
+}
+
+#[test]
+fn feed_includes_own_articles_and_the_ones_of_followed_users() {
+    let repo = get_test_repo();
+    let users: Vec<User> = create_users(&repo, 3).into_iter().map(|(u, _)| u).collect();
+    let (reader, followed, stranger) = (&users[0], &users[1], &users[2]);
+    let written = create_articles(&repo, users.clone());
+    followers::follow(&repo, reader.id, followed.id).expect("Failed to follow");
+
+    let feed: HashSet<String> = articles::feed(&repo, reader.id, 10, 0, None)
+        .expect("Failed to get feed")
+        .into_iter()
+        .map(|(a, _, _)| a.slug)
+        .collect();
+    let expected: HashSet<String> = written
+        .into_iter()
+        .filter(|a| a.user_id != stranger.id)
+        .map(|a| a.slug)
+        .collect();
+    assert_eq!(feed, expected);
+}
+
 #[test]
 fn delete_article() {
     let repo = get_test_repo();
@@ -31,7 +307,7 @@ fn delete_article() {
     let slug = articles[0].slug.clone();
     articles::delete(&repo, &slug).expect("Failed to delete article");
 
-    let results = articles::find(&repo, Default::default()).expect("Failed to get articles");
+    let results = articles::find(&repo, Default::default(), None).expect("Failed to get articles");
     assert_eq!(results.len() as i32, n_articles - 1);
 
     // The deleted article can't be fetched anymore
@@ -60,6 +336,8 @@ fn no_tags_if_there_are_only_articles_without_tags() {
         body: "ohoh".into(),
         tag_list: vec![],
         user_id: user.id,
+        status: "published",
+        publish_at: Some(Utc::now()),
     };
     articles::insert(&repo, article).unwrap();
 
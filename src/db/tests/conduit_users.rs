@@ -1,10 +1,13 @@
 mod helpers;
 
+use domain::repositories::Repository as _;
+use domain::{LoginError, PasswordHashing};
 use fake::fake;
-use helpers::create_user;
 use helpers::test_db::get_test_repo;
+use helpers::{create_article, create_user};
 use realworld_db::models::UpdateUser;
-use realworld_db::queries::users;
+use realworld_db::queries::{articles, users};
+use realworld_db::Repository;
 
 #[test]
 fn test_create_user() {
@@ -26,6 +29,21 @@ fn test_authenticate_user() {
     assert!(results.is_ok());
 }
 
+#[test]
+fn unparsable_password_hashes_match_no_password() {
+    let repository = Repository(get_test_repo());
+    let (user, _) = create_user(&repository.0);
+    let placeholder = UpdateUser {
+        password: Some("!".into()),
+        ..Default::default()
+    };
+    users::update(&repository.0, user.id, placeholder).expect("Failed to update user");
+
+    let result =
+        repository.get_user_by_email_and_password(&user.email, "!", &PasswordHashing::default());
+    assert!(matches!(result, Err(LoginError::NotFound)));
+}
+
 #[test]
 fn test_update_user() {
     let repo = get_test_repo();
@@ -52,3 +70,28 @@ fn test_update_user() {
     assert_eq!(updated_user.image, Some(image));
     assert_eq!(updated_user.email, email);
 }
+
+#[test]
+fn anonymized_users_leave_their_articles_behind() {
+    let repo = get_test_repo();
+    let (user, _) = create_user(&repo);
+    let article = create_article(&repo, &user);
+
+    users::delete(&repo, user.id, true).expect("Failed to delete user");
+
+    assert!(users::find(&repo, user.id).is_err());
+    let kept = articles::find_one(&repo, &article.slug).expect("The article is gone");
+    assert_eq!(kept.author.username, "deleted-user");
+}
+
+#[test]
+fn deleted_users_take_their_articles_with_them() {
+    let repo = get_test_repo();
+    let (user, _) = create_user(&repo);
+    let article = create_article(&repo, &user);
+
+    users::delete(&repo, user.id, false).expect("Failed to delete user");
+
+    assert!(users::find(&repo, user.id).is_err());
+    assert!(articles::find_one(&repo, &article.slug).is_err());
+}
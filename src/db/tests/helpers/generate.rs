@@ -20,8 +20,12 @@ pub fn new_user() -> (domain::SignUp, String) {
     let sign_up = domain::SignUp {
         username: fake!(Internet.user_name).to_string(),
         email: fake!(Internet.free_email).to_string(),
-        password: domain::Password::from_clear_text(password.clone())
-            .expect("Failed to hash password"),
+        password: domain::Password::from_clear_text(
+            password.clone(),
+            // The lowest cost allowed by bcrypt, to keep tests fast
+            &domain::PasswordHashing::Bcrypt { cost: 4 },
+        )
+        .expect("Failed to hash password"),
     };
     (sign_up, password)
 }
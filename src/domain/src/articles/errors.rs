@@ -9,6 +9,9 @@ pub enum GetArticleError {
         #[source]
         source: DatabaseError,
     },
+    /// The article exists, but the viewer is not allowed to know about it.
+    #[error("There is no article with {slug:?} as slug.")]
+    Hidden { slug: String },
     #[error("Something went wrong.")]
     DatabaseError(#[from] DatabaseError),
 }
@@ -23,6 +26,12 @@ pub enum ChangeArticleError {
     },
     #[error("User {user_id:?} is not the author of the article (slug: {slug:?}).")]
     Forbidden { user_id: Uuid, slug: String },
+    #[error("There is no revision with id {revision_id:?} for the article with {slug:?} as slug.")]
+    RevisionNotFound { slug: String, revision_id: u64 },
+    #[error("User {user_id:?} cannot comment the article (slug: {slug:?}): one of the two users blocked the other.")]
+    Blocked { user_id: Uuid, slug: String },
+    #[error("User {user_id:?} has to verify their email address before publishing or commenting the article (slug: {slug:?}).")]
+    EmailNotVerified { user_id: Uuid, slug: String },
     #[error("Something went wrong.")]
     DatabaseError(#[from] DatabaseError),
 }
@@ -41,6 +50,8 @@ pub enum PublishArticleError {
         #[source]
         source: DatabaseError,
     },
+    #[error("User {user_id:?} has to verify their email address before publishing.")]
+    EmailNotVerified { user_id: Uuid },
     #[error("Something went wrong.")]
     DatabaseError(#[from] DatabaseError),
 }
@@ -1,5 +1,5 @@
 use crate::repositories::Repository;
-use crate::{Comment, DatabaseError, Profile, ProfileView};
+use crate::{Comment, DatabaseError, Profile, ProfileView, User};
 use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -23,6 +23,38 @@ impl ArticleContent {
     }
 }
 
+/// Where an article is in its lifecycle.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArticleStatus {
+    /// Only visible to its author.
+    Draft,
+    /// Only visible to its author until `publish_at`, public afterwards.
+    Scheduled { publish_at: DateTime<Utc> },
+    /// Public.
+    Published,
+    /// Withdrawn by its author: only visible to them.
+    Archived,
+}
+
+impl ArticleStatus {
+    /// Returns `true` if an article with this status can be seen by anybody.
+    pub fn is_public(&self) -> bool {
+        match self {
+            ArticleStatus::Published => true,
+            ArticleStatus::Scheduled { publish_at } => publish_at <= &Utc::now(),
+            ArticleStatus::Draft | ArticleStatus::Archived => false,
+        }
+    }
+
+    /// Returns `true` if an article with this status is, or will be, public.
+    pub fn is_publication(&self) -> bool {
+        match self {
+            ArticleStatus::Published | ArticleStatus::Scheduled { .. } => true,
+            ArticleStatus::Draft | ArticleStatus::Archived => false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Article {
     pub content: ArticleContent,
@@ -30,11 +62,24 @@ pub struct Article {
     pub author: Profile,
     pub metadata: ArticleMetadata,
     pub favorites_count: u64,
+    pub status: ArticleStatus,
 }
 
 impl Article {
-    pub fn comments(&self, repository: &impl Repository) -> Result<Vec<Comment>, DatabaseError> {
-        repository.get_comments(&self)
+    pub fn comments(
+        &self,
+        viewer: Option<&User>,
+        repository: &impl Repository,
+    ) -> Result<Vec<Comment>, DatabaseError> {
+        repository.get_comments(&self, viewer)
+    }
+
+    /// Public articles can be seen by everybody, the others only by their author.
+    pub fn is_visible_to(&self, viewer: Option<&User>) -> bool {
+        self.status.is_public()
+            || viewer
+                .iter()
+                .any(|v| v.profile.username == self.author.username)
     }
 }
 
@@ -52,6 +97,7 @@ pub struct ArticleView {
     pub metadata: ArticleMetadata,
     pub favorited: bool,
     pub favorites_count: u64,
+    pub status: ArticleStatus,
     // The user owning this view of an article
     pub viewer: Uuid,
 }
@@ -61,6 +107,46 @@ pub struct ArticleUpdate {
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub status: Option<ArticleStatus>,
+}
+
+/// A recorded change to the content of an article.
+/// Fields that were not changed by the edit are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArticleRevision {
+    pub id: u64,
+    pub editor: Profile,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The content of an article as it was right after the specified revision,
+/// given its history (oldest revision first).
+/// It returns `None` if there is no revision with that id.
+pub fn content_at_revision(history: &[ArticleRevision], revision_id: u64) -> Option<ArticleUpdate> {
+    if !history.iter().any(|r| r.id == revision_id) {
+        return None;
+    }
+    let mut content = ArticleUpdate {
+        title: None,
+        description: None,
+        body: None,
+        status: None,
+    };
+    for revision in history.iter().take_while(|r| r.id <= revision_id) {
+        if let Some(title) = &revision.title {
+            content.title = Some(title.to_owned());
+        }
+        if let Some(description) = &revision.description {
+            content.description = Some(description.to_owned());
+        }
+        if let Some(body) = &revision.body {
+            content.body = Some(body.to_owned());
+        }
+    }
+    Some(content)
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -68,4 +154,54 @@ pub struct ArticleQuery {
     pub author: Option<String>,
     pub favorited: Option<String>,
     pub tag: Option<String>,
+    /// Full-text search: if set, only matching articles are returned,
+    /// most relevant first unless `sort` is specified.
+    pub q: Option<String>,
+    /// Defaults to `ArticleSort::Newest` (or relevance, for full-text searches).
+    pub sort: Option<ArticleSort>,
+    /// If not set, all matching articles are returned.
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    /// Only honoured when sorting by creation date (`ArticleSort::Newest` or `ArticleSort::Oldest`).
+    #[serde(skip)]
+    pub cursor: Option<ArticleCursor>,
+}
+
+/// The position of an article in a listing ordered by creation date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticlePosition {
+    pub created_at: DateTime<Utc>,
+    pub slug: String,
+}
+
+/// Keyset pagination: a page is made of the articles right after (or right before)
+/// a given position, rather than of the articles after a given number of rows.
+/// Pages are therefore not affected by articles published in the meantime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArticleCursor {
+    After(ArticlePosition),
+    Before(ArticlePosition),
+}
+
+impl ArticleQuery {
+    /// Returns `true` if the matching articles are listed by creation date,
+    /// hence they can be paginated using a cursor.
+    pub fn is_sorted_by_creation_date(&self) -> bool {
+        matches!(
+            (self.sort, &self.q),
+            (Some(ArticleSort::Newest), _) | (Some(ArticleSort::Oldest), _) | (None, None)
+        )
+    }
+}
+
+/// The order of the articles returned by a query.
+/// Ties are always broken by slug, to guarantee a stable ordering.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArticleSort {
+    Newest,
+    Oldest,
+    MostFavorited,
+    MostCommented,
+    RecentlyUpdated,
 }
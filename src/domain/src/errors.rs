@@ -21,6 +21,7 @@ impl From<GetArticleError> for DatabaseError {
         match e {
             GetArticleError::ArticleNotFound { source, .. } => source,
             GetArticleError::DatabaseError(e) => e,
+            e @ GetArticleError::Hidden { .. } => anyhow::Error::from(e).into(),
         }
     }
 }
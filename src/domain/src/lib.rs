@@ -1,10 +1,14 @@
 pub mod articles;
 pub mod comments;
 pub mod errors;
+pub mod mailer;
+pub mod notifications;
 pub mod repositories;
 pub mod users;
 
 pub use articles::*;
 pub use comments::*;
 pub use errors::*;
+pub use mailer::*;
+pub use notifications::*;
 pub use users::*;
@@ -0,0 +1,20 @@
+/// An email to be delivered to one of our users.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Failed to send email.")]
+pub struct MailerError {
+    #[from]
+    source: anyhow::Error,
+}
+
+/// How emails reach our users: implementations range from logging them,
+/// for local development, to handing them over to an actual mail server.
+pub trait Mailer {
+    fn send(&self, email: Email) -> Result<(), MailerError>;
+}
@@ -0,0 +1,10 @@
+use crate::DatabaseError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum NotificationError {
+    /// Users cannot tell apart missing notifications from the ones of other users.
+    #[error("There is no notification with {notification_id:?} as id.")]
+    NotFound { notification_id: u64 },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
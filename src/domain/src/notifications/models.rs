@@ -0,0 +1,95 @@
+use crate::repositories::Repository;
+use crate::{DatabaseError, NotificationError, Profile, User};
+use chrono::{DateTime, Utc};
+
+/// Something a user did which concerns another user, who gets notified about it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The actor started following the recipient.
+    Followed,
+    /// The actor commented on an article of the recipient.
+    Commented {
+        article_slug: String,
+        comment_id: u64,
+    },
+    /// The actor favorited an article of the recipient.
+    Favorited { article_slug: String },
+}
+
+/// An event, as recorded for its recipient.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub id: u64,
+    /// Who caused the event.
+    pub actor: Profile,
+    pub event: Event,
+    /// The title of the article the event is about, if any.
+    pub article_title: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NotificationsQuery {
+    pub unread_only: bool,
+    /// Only the notifications recorded after this one, e.g. to catch up with a stream.
+    pub after_id: Option<u64>,
+    /// If not set, all matching notifications are returned.
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl User {
+    /// Records `event` for `recipient`, unless they are the one who caused it.
+    pub(crate) fn notify(
+        &self,
+        recipient: &Profile,
+        event: Event,
+        repository: &impl Repository,
+    ) -> Result<(), DatabaseError> {
+        if recipient.username == self.profile.username {
+            return Ok(());
+        }
+        repository.record_event(self, recipient, event)
+    }
+
+    /// The notifications of the user, most recent first. The ones caused by users they
+    /// blocked or muted are left out.
+    pub fn notifications(
+        &self,
+        query: NotificationsQuery,
+        repository: &impl Repository,
+    ) -> Result<Vec<Notification>, DatabaseError> {
+        repository.get_notifications(self, query)
+    }
+
+    pub fn unread_notifications_count(
+        &self,
+        repository: &impl Repository,
+    ) -> Result<u64, DatabaseError> {
+        repository.count_unread_notifications(self)
+    }
+
+    /// Marks one of the user's notifications as read, or as unread.
+    pub fn mark_notification(
+        &self,
+        notification_id: u64,
+        read: bool,
+        repository: &impl Repository,
+    ) -> Result<(), NotificationError> {
+        repository.mark_notification(self, notification_id, read)
+    }
+
+    pub fn mark_all_notifications_read(
+        &self,
+        repository: &impl Repository,
+    ) -> Result<(), DatabaseError> {
+        repository.mark_all_notifications_read(self)
+    }
+}
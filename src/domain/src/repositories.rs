@@ -1,16 +1,22 @@
 use crate::{
-    Article, ArticleContent, ArticleQuery, ArticleUpdate, ArticleView, Comment, CommentContent,
-    DatabaseError, DeleteCommentError, FavoriteOutcome, FeedQuery, GetArticleError, GetUserError,
-    LoginError, Profile, ProfileView, PublishArticleError, SignUp, SignUpError, UnfavoriteOutcome,
-    User, UserUpdate,
+    AccessToken, AccessTokenError, AccountDeletion, Article, ArticleContent, ArticleQuery,
+    ArticleRevision, ArticleStatus, ArticleUpdate, ArticleView, ChangeArticleError, Comment,
+    CommentContent, DatabaseError, DeleteCommentError, Event, FavoriteOutcome, FeedQuery,
+    FollowCounts, FollowOutcome, GetArticleError, GetUserError, LoginError, Notification,
+    NotificationError, NotificationsQuery, OidcLoginAttempt, Password, PasswordHashing,
+    PasswordResetError, Profile, ProfileView, ProfilesQuery, PublishArticleError, Scope, SignUp,
+    SignUpError, TotpSecret, TwoFactor, TwoFactorError, UnfavoriteOutcome, User, UserUpdate,
+    VerifyEmailError,
 };
-use std::collections::HashSet;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 pub trait Repository {
-    fn publish_article(
+    fn create_article(
         &self,
         draft: ArticleContent,
+        status: ArticleStatus,
         author: &User,
     ) -> Result<Article, PublishArticleError>;
     fn get_article_by_slug(&self, slug: &str) -> Result<Article, GetArticleError>;
@@ -24,7 +30,21 @@ pub trait Repository {
         viewer: &User,
         articles: Vec<Article>,
     ) -> Result<Vec<ArticleView>, DatabaseError>;
-    fn find_articles(&self, query: ArticleQuery) -> Result<Vec<Article>, DatabaseError>;
+    /// Articles matching `query`: the articles of the users who blocked `viewer` are left out.
+    fn find_articles(
+        &self,
+        query: ArticleQuery,
+        viewer: Option<&User>,
+    ) -> Result<Vec<Article>, DatabaseError>;
+    /// Extracts from each article the fragments matching the full-text search `query`,
+    /// with the matching terms highlighted.
+    /// The returned map is keyed by article slug.
+    fn search_snippets(
+        &self,
+        query: &str,
+        articles: &[Article],
+    ) -> Result<HashMap<String, String>, DatabaseError>;
+    fn find_drafts(&self, author: &User) -> Result<Vec<Article>, DatabaseError>;
     fn feed(&self, user: &User, query: FeedQuery) -> Result<Vec<ArticleView>, DatabaseError>;
     fn delete_article(&self, article: &Article) -> Result<(), DatabaseError>;
     fn comment_article(
@@ -34,13 +54,33 @@ pub trait Repository {
         comment: CommentContent,
     ) -> Result<Comment, DatabaseError>;
     fn get_comment(&self, comment_id: u64) -> Result<Comment, DeleteCommentError>;
-    fn get_comments(&self, article: &Article) -> Result<Vec<Comment>, DatabaseError>;
+    /// The comments to `article`: the comments of the users who blocked `viewer`,
+    /// or that `viewer` muted, are left out.
+    fn get_comments(
+        &self,
+        article: &Article,
+        viewer: Option<&User>,
+    ) -> Result<Vec<Comment>, DatabaseError>;
+    /// The comments to each of the articles identified by `slugs`, fetched at once and keyed
+    /// by slug: see `get_comments`. Articles without comments have no entry.
+    fn get_articles_comments(
+        &self,
+        slugs: &[String],
+        viewer: Option<&User>,
+    ) -> Result<HashMap<String, Vec<Comment>>, DatabaseError>;
     fn delete_comment(&self, comment_id: u64) -> Result<(), DeleteCommentError>;
     fn update_article(
         &self,
         article: Article,
         update: ArticleUpdate,
+        editor: &User,
     ) -> Result<Article, DatabaseError>;
+    fn get_revisions(&self, article: &Article) -> Result<Vec<ArticleRevision>, DatabaseError>;
+    fn get_revision(
+        &self,
+        article: &Article,
+        revision_id: u64,
+    ) -> Result<ArticleRevision, ChangeArticleError>;
     fn favorite(&self, article: &Article, user: &User) -> Result<FavoriteOutcome, DatabaseError>;
     fn unfavorite(
         &self,
@@ -49,15 +89,137 @@ pub trait Repository {
     ) -> Result<UnfavoriteOutcome, DatabaseError>;
     fn sign_up(&self, sign_up: SignUp) -> Result<User, SignUpError>;
     fn update_user(&self, user: User, update: UserUpdate) -> Result<User, DatabaseError>;
+    /// Delete `user` and, depending on `mode`, their content, as a single transaction.
+    fn delete_user(&self, user: &User, mode: AccountDeletion) -> Result<(), DatabaseError>;
     fn get_user_by_id(&self, user_id: Uuid) -> Result<User, GetUserError>;
+    /// Mark `email` as verified for the specified user, provided it is still their email address.
+    fn verify_email(&self, user_id: Uuid, email: &str) -> Result<User, VerifyEmailError>;
+    fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DatabaseError>;
+    fn username_exists(&self, username: &str) -> Result<bool, DatabaseError>;
+    /// The user linked to `subject` at the external identity provider `provider`, if any.
+    fn find_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<User>, DatabaseError>;
+    fn link_identity(
+        &self,
+        user: &User,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), DatabaseError>;
+    fn create_oidc_login_attempt(&self, attempt: &OidcLoginAttempt) -> Result<(), DatabaseError>;
+    /// Remove the unexpired login attempt identified by `state`, returning it.
+    fn take_oidc_login_attempt(
+        &self,
+        state: &str,
+    ) -> Result<Option<OidcLoginAttempt>, DatabaseError>;
+    /// Authentication tokens issued before the returned point in time must be rejected.
+    fn get_sessions_revoked_at(&self, user_id: Uuid)
+        -> Result<Option<DateTime<Utc>>, GetUserError>;
+    fn create_password_reset(
+        &self,
+        user: &User,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), DatabaseError>;
+    /// Redeem a password reset token, setting `new_password` for its owner.
+    fn reset_password(
+        &self,
+        token_hash: &str,
+        new_password: &Password,
+    ) -> Result<User, PasswordResetError>;
+    fn get_two_factor(&self, user: &User) -> Result<Option<TwoFactor>, DatabaseError>;
+    /// Store an unconfirmed secret for `user`, replacing any previous unconfirmed one.
+    /// It fails if two-factor authentication is already confirmed.
+    fn enroll_two_factor(&self, user: &User, secret: &TotpSecret) -> Result<(), TwoFactorError>;
+    /// Enforce two-factor authentication, recording `step` as used.
+    fn confirm_two_factor(
+        &self,
+        user: &User,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), DatabaseError>;
+    /// Record that a code was accepted for `step`: it returns `false`
+    /// if a code for the same or a later step was accepted already.
+    fn use_two_factor_step(&self, user: &User, step: i64) -> Result<bool, DatabaseError>;
+    /// Consume a recovery code: it returns `false` if it is unknown or already used.
+    fn use_recovery_code(&self, user: &User, code_hash: &str) -> Result<bool, DatabaseError>;
+    fn disable_two_factor(&self, user: &User) -> Result<(), DatabaseError>;
+    fn create_access_token(
+        &self,
+        user: &User,
+        name: &str,
+        scopes: &[Scope],
+        token_hash: &str,
+    ) -> Result<AccessToken, DatabaseError>;
+    /// The access tokens of `user`, the most recent first.
+    fn get_access_tokens(&self, user: &User) -> Result<Vec<AccessToken>, DatabaseError>;
+    /// It fails with `NotFound` if `user` has no token with id `token_id`.
+    fn delete_access_token(&self, user: &User, token_id: Uuid) -> Result<(), AccessTokenError>;
+    /// The token whose secret hashes to `token_hash`, if any: it is recorded as used.
+    fn use_access_token(&self, token_hash: &str) -> Result<Option<AccessToken>, DatabaseError>;
+    /// Like `use_access_token`, without recording the use.
+    fn find_access_token(&self, token_hash: &str) -> Result<Option<AccessToken>, DatabaseError>;
+    /// Passwords hashed with an outdated algorithm or cost are upgraded to `hashing` on success.
     fn get_user_by_email_and_password(
         &self,
         email: &str,
         password: &str,
+        hashing: &PasswordHashing,
     ) -> Result<User, LoginError>;
     fn get_profile(&self, username: &str) -> Result<Profile, GetUserError>;
     fn get_profile_view(&self, viewer: &User, username: &str) -> Result<ProfileView, GetUserError>;
-    fn follow(&self, follower: &User, to_be_followed: &Profile) -> Result<(), DatabaseError>;
+    fn follow(
+        &self,
+        follower: &User,
+        to_be_followed: &Profile,
+    ) -> Result<FollowOutcome, DatabaseError>;
     fn unfollow(&self, follower: &User, to_be_unfollowed: &Profile) -> Result<(), DatabaseError>;
+    fn block(&self, blocker: &User, to_be_blocked: &Profile) -> Result<(), DatabaseError>;
+    fn unblock(&self, blocker: &User, to_be_unblocked: &Profile) -> Result<(), DatabaseError>;
+    fn has_blocked(&self, blocker: &Profile, blocked: &Profile) -> Result<bool, DatabaseError>;
+    fn mute(&self, muter: &User, to_be_muted: &Profile) -> Result<(), DatabaseError>;
+    fn unmute(&self, muter: &User, to_be_unmuted: &Profile) -> Result<(), DatabaseError>;
+    /// The profiles following `profile`, sorted by username.
+    fn get_followers(
+        &self,
+        profile: &Profile,
+        query: ProfilesQuery,
+    ) -> Result<Vec<Profile>, DatabaseError>;
+    /// The profiles followed by `profile`, sorted by username.
+    fn get_followed(
+        &self,
+        profile: &Profile,
+        query: ProfilesQuery,
+    ) -> Result<Vec<Profile>, DatabaseError>;
+    fn get_follow_counts(&self, profile: &Profile) -> Result<FollowCounts, DatabaseError>;
+    fn get_profiles_views(
+        &self,
+        viewer: &User,
+        profiles: Vec<Profile>,
+    ) -> Result<Vec<ProfileView>, DatabaseError>;
     fn get_tags(&self) -> Result<HashSet<String>, DatabaseError>;
+    /// Records that `actor` caused `event`, for `recipient`.
+    /// Events repeated while the previous one is still unread (e.g. following, unfollowing
+    /// and following again) are only recorded once; every comment is recorded.
+    fn record_event(
+        &self,
+        actor: &User,
+        recipient: &Profile,
+        event: Event,
+    ) -> Result<(), DatabaseError>;
+    fn get_notifications(
+        &self,
+        recipient: &User,
+        query: NotificationsQuery,
+    ) -> Result<Vec<Notification>, DatabaseError>;
+    fn count_unread_notifications(&self, recipient: &User) -> Result<u64, DatabaseError>;
+    fn mark_notification(
+        &self,
+        recipient: &User,
+        notification_id: u64,
+        read: bool,
+    ) -> Result<(), NotificationError>;
+    fn mark_all_notifications_read(&self, recipient: &User) -> Result<(), DatabaseError>;
 }
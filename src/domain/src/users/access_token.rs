@@ -0,0 +1,112 @@
+use crate::repositories::Repository;
+use crate::{AccessTokenError, User};
+use chrono::{DateTime, Utc};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use uuid::Uuid;
+
+/// What an access token allows its bearer to do, on top of reading what its owner can read
+/// (their notifications aside).
+///
+/// Managing the account itself (e.g. changing the password) is never allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Publish, edit and delete articles.
+    ArticlesWrite,
+    /// Post and delete comments.
+    CommentsWrite,
+    /// Favorite and unfavorite articles.
+    FavoritesWrite,
+    /// Follow, block and mute other users.
+    ProfilesWrite,
+    /// Read notifications, and mark them as read or unread.
+    NotificationsRead,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 5] = [
+        Scope::ArticlesWrite,
+        Scope::CommentsWrite,
+        Scope::FavoritesWrite,
+        Scope::ProfilesWrite,
+        Scope::NotificationsRead,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::ArticlesWrite => "articles:write",
+            Scope::CommentsWrite => "comments:write",
+            Scope::FavoritesWrite => "favorites:write",
+            Scope::ProfilesWrite => "profiles:write",
+            Scope::NotificationsRead => "notifications:read",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Scope> {
+        Scope::ALL.iter().copied().find(|scope| scope.as_str() == s)
+    }
+}
+
+/// The secret part of an access token, which its bearer presents to authenticate.
+///
+/// Only its hash is stored: it is shown to the user once, when the token is created.
+pub struct AccessTokenSecret(String);
+
+/// Makes leaked tokens easy to spot, e.g. by secret scanners.
+const SECRET_PREFIX: &str = "cdt_";
+
+impl AccessTokenSecret {
+    pub fn generate() -> Result<Self, AccessTokenError> {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|e| AccessTokenError::SecretGeneration(e.into()))?;
+        Ok(Self(format!(
+            "{}{}",
+            SECRET_PREFIX,
+            base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)
+        )))
+    }
+
+    pub fn from_clear_text(secret: String) -> Self {
+        Self(secret)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The hex-encoded SHA-256 of the secret.
+    pub fn hash(&self) -> String {
+        digest(&SHA256, self.0.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Chosen by the user, to remember what the token is for.
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl User {
+    /// Create a new access token, returning it along with its secret.
+    pub fn create_access_token(
+        &self,
+        name: &str,
+        scopes: &[Scope],
+        repository: &impl Repository,
+    ) -> Result<(AccessToken, AccessTokenSecret), AccessTokenError> {
+        let secret = AccessTokenSecret::generate()?;
+        let token = repository.create_access_token(self, name, scopes, &secret.hash())?;
+        Ok((token, secret))
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{Email, Mailer, MailerError, User};
+
+impl User {
+    /// Email the user the token proving they own their email address.
+    ///
+    /// The token is opaque to the domain: it is up to the caller to issue it
+    /// and to check it when it comes back.
+    pub fn send_email_verification(
+        &self,
+        token: &str,
+        mailer: &dyn Mailer,
+    ) -> Result<(), MailerError> {
+        let email = Email {
+            to: self.email.to_owned(),
+            subject: "Verify your email address".into(),
+            body: format!(
+                "Hi {},\n\nuse the following token to verify your email address: {}\n\
+                 You will be able to publish articles and to comment once it is verified.",
+                self.profile.username, token
+            ),
+        };
+        mailer.send(email)
+    }
+}
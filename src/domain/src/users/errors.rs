@@ -1,11 +1,23 @@
-use crate::DatabaseError;
+use crate::{DatabaseError, MailerError};
 use uuid::Uuid;
 
 #[derive(thiserror::Error, Debug)]
 #[error("Failed to process password.")]
 pub struct PasswordError {
     #[from]
-    source: bcrypt::BcryptError,
+    source: anyhow::Error,
+}
+
+impl From<bcrypt::BcryptError> for PasswordError {
+    fn from(e: bcrypt::BcryptError) -> Self {
+        anyhow::Error::from(e).into()
+    }
+}
+
+impl From<argon2::Error> for PasswordError {
+    fn from(e: argon2::Error) -> Self {
+        anyhow::Error::from(e).into()
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -29,6 +41,108 @@ pub enum LoginError {
     DatabaseError(#[from] DatabaseError),
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum FollowError {
+    #[error("You cannot follow yourself.")]
+    SelfFollow,
+    #[error("You cannot follow {username:?}: one of you blocked the other.")]
+    Blocked { username: String },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BlockError {
+    #[error("You cannot block or mute yourself.")]
+    SelfTarget,
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DeleteAccountError {
+    #[error("The password you specified is wrong.")]
+    WrongPassword,
+    #[error("Failed to process password")]
+    PasswordError(#[from] PasswordError),
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PasswordResetError {
+    #[error("The password reset token is not valid: it might have expired or it might have been used already.")]
+    InvalidToken,
+    #[error("Failed to generate a password reset token.")]
+    TokenGeneration(#[source] anyhow::Error),
+    #[error("Failed to send the password reset email.")]
+    MailerError(#[from] MailerError),
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyEmailError {
+    #[error("The email verification token is not valid: it might have expired or the email address might have changed.")]
+    InvalidToken,
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TwoFactorError {
+    #[error("Two-factor authentication is already enabled.")]
+    AlreadyEnabled,
+    #[error("Two-factor authentication is not enabled.")]
+    NotEnabled,
+    #[error("Two-factor authentication has to be enrolled in before being confirmed.")]
+    NotEnrolled,
+    #[error("The two-factor authentication code is not valid.")]
+    InvalidCode,
+    #[error("Failed to generate a two-factor authentication secret.")]
+    SecretGeneration(#[source] anyhow::Error),
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AccessTokenError {
+    #[error("There is no access token with id {token_id:?}.")]
+    NotFound { token_id: Uuid },
+    #[error("Failed to generate an access token.")]
+    SecretGeneration(#[source] anyhow::Error),
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExternalLoginError {
+    #[error("The identity provider {provider:?} is not configured.")]
+    UnknownProvider { provider: String },
+    #[error("The login attempt is not valid: it might have expired or it might have been completed already.")]
+    InvalidState,
+    #[error("The identity provider did not share an email address.")]
+    MissingEmail,
+    #[error("An account already uses {email:?}: log in with your password instead.")]
+    EmailTaken { email: String },
+    #[error("The identity provider could not authenticate the user.")]
+    Provider(#[source] anyhow::Error),
+    #[error("Failed to generate the parameters of the login attempt.")]
+    RandomGeneration(#[source] anyhow::Error),
+    #[error("Failed to process password")]
+    PasswordError(#[from] PasswordError),
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+impl From<SignUpError> for ExternalLoginError {
+    fn from(e: SignUpError) -> Self {
+        match e {
+            SignUpError::DatabaseError(e) => e.into(),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SignUpError {
     #[error("Something went wrong.")]
@@ -0,0 +1,153 @@
+use crate::repositories::Repository;
+use crate::{ExternalLoginError, Password, PasswordHashing, SignUp, User, VerifyEmailError};
+use chrono::{DateTime, Duration, Utc};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// How long users have to log in with their identity provider once they start, in minutes.
+const LOGIN_ATTEMPT_VALIDITY_MINUTES: i64 = 10;
+
+/// Who a user is according to an external identity provider.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalIdentity {
+    /// The name of the provider in our configuration.
+    pub provider: String,
+    /// The stable identifier of the user for the provider.
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider checked that the user owns `email`.
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+}
+
+/// A login started with an external identity provider, waiting for its callback.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OidcLoginAttempt {
+    /// Ties the callback to the login attempt.
+    pub state: String,
+    pub provider: String,
+    /// Ties the ID token returned by the provider to the login attempt.
+    pub nonce: String,
+    /// The PKCE verifier: only its hash is sent in the authorization request.
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl OidcLoginAttempt {
+    pub fn generate(provider: &str) -> Result<Self, ExternalLoginError> {
+        Ok(Self {
+            state: random_string()?,
+            provider: provider.to_owned(),
+            nonce: random_string()?,
+            code_verifier: random_string()?,
+            expires_at: Utc::now() + Duration::minutes(LOGIN_ATTEMPT_VALIDITY_MINUTES),
+        })
+    }
+
+    /// The PKCE challenge derived from the verifier, using the `S256` method of RFC 7636.
+    pub fn code_challenge(&self) -> String {
+        let hash = digest(&SHA256, self.code_verifier.as_bytes());
+        base64::encode_config(hash.as_ref(), base64::URL_SAFE_NO_PAD)
+    }
+}
+
+/// 43 characters out of the unreserved ones allowed by RFC 7636.
+fn random_string() -> Result<String, ExternalLoginError> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|e| ExternalLoginError::RandomGeneration(e.into()))?;
+    Ok(base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD))
+}
+
+/// The user behind `identity`.
+///
+/// The first time around, the identity is linked to the account using the same email address,
+/// provided both the identity provider and the account verified it; if there is none, a new
+/// account is created. Accounts with an unverified address are not linked: whoever signed up
+/// with it may not own it, and would keep access to the account with their password.
+/// Callers still have to ask for the second factor of users who enabled it.
+pub fn sign_in_with(
+    identity: &ExternalIdentity,
+    hashing: &PasswordHashing,
+    repository: &impl Repository,
+) -> Result<User, ExternalLoginError> {
+    if let Some(user) = repository.find_user_by_identity(&identity.provider, &identity.subject)? {
+        return Ok(user);
+    }
+    let email = identity
+        .email
+        .as_ref()
+        .ok_or(ExternalLoginError::MissingEmail)?;
+
+    let user = match repository.find_user_by_email(email)? {
+        Some(user) if identity.email_verified && user.email_verified => user,
+        Some(_) => {
+            return Err(ExternalLoginError::EmailTaken {
+                email: email.to_owned(),
+            })
+        }
+        None => {
+            // Nobody knows this password: users can choose one with a password reset
+            let password = Password::from_clear_text(random_string()?, hashing)?;
+            let sign_up = SignUp {
+                username: available_username(identity, email, repository)?,
+                email: email.to_owned(),
+                password,
+            };
+            let user = repository.sign_up(sign_up)?;
+            if !identity.email_verified {
+                user
+            } else {
+                match repository.verify_email(user.id, email) {
+                    Ok(verified_user) => verified_user,
+                    // The email address changed in the meantime: it stays unverified
+                    Err(VerifyEmailError::InvalidToken) => user,
+                    Err(VerifyEmailError::DatabaseError(e)) => return Err(e.into()),
+                }
+            }
+        }
+    };
+    repository.link_identity(&user, &identity.provider, &identity.subject)?;
+    Ok(user)
+}
+
+/// The preferred username of the user, or the local part of their email address,
+/// with a numeric suffix if it is taken already.
+fn available_username(
+    identity: &ExternalIdentity,
+    email: &str,
+    repository: &impl Repository,
+) -> Result<String, ExternalLoginError> {
+    let base = identity
+        .preferred_username
+        .clone()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_owned());
+    for suffix in 0.. {
+        let candidate = match suffix {
+            0 => base.clone(),
+            n => format!("{}{}", base, n),
+        };
+        if !repository.username_exists(&candidate)? {
+            return Ok(candidate);
+        }
+    }
+    unreachable!("There is always an available username")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_challenge_matches_the_rfc_7636_example() {
+        let attempt = OidcLoginAttempt {
+            code_verifier: "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".into(),
+            ..OidcLoginAttempt::generate("a-provider").unwrap()
+        };
+        assert_eq!(
+            attempt.code_challenge(),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
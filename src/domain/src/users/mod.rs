@@ -1,5 +1,16 @@
+pub mod access_token;
+pub mod email_verification;
 pub mod errors;
+pub mod identity;
 pub mod models;
+pub mod password;
+pub mod password_reset;
+pub mod two_factor;
 
+pub use access_token::*;
 pub use errors::*;
+pub use identity::*;
 pub use models::*;
+pub use password::*;
+pub use password_reset::*;
+pub use two_factor::*;
@@ -1,45 +1,50 @@
 use crate::repositories::Repository;
 use crate::{
-    Article, ArticleContent, ArticleUpdate, ArticleView, ChangeArticleError, Comment,
-    CommentContent, CommentView, DatabaseError, DeleteCommentError, PasswordError,
-    PublishArticleError,
+    content_at_revision, Article, ArticleContent, ArticleCursor, ArticleRevision, ArticleStatus,
+    ArticleUpdate, ArticleView, BlockError, ChangeArticleError, Comment, CommentContent,
+    CommentView, DatabaseError, DeleteAccountError, DeleteCommentError, Event, FollowError,
+    LoginError, Password, PasswordHashing, PublishArticleError,
 };
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct Password(String);
-
-impl Password {
-    /// Given a clear-text password, it returns a `Password` instance
-    /// containing the password's hash.
-    pub fn from_clear_text(clear_text_password: String) -> Result<Password, PasswordError> {
-        // TODO: Read hash cost from configuration - we are using 4 for now.
-        let hash = bcrypt::hash(clear_text_password, 4)?;
-        Ok(Password(hash))
-    }
+pub struct Profile {
+    pub username: String,
+    pub bio: Option<String>,
+    pub image: Option<String>,
+}
 
-    /// Given an already hashed password, it returns a `Password` instance
-    /// containing that very same hash.
-    pub fn from_hash(hashed_password: String) -> Password {
-        Password(hashed_password)
+impl Profile {
+    /// Who is following this profile.
+    pub fn followers(
+        &self,
+        query: ProfilesQuery,
+        repository: &impl Repository,
+    ) -> Result<Vec<Profile>, DatabaseError> {
+        repository.get_followers(self, query)
     }
 
-    /// Returns the hashed password.
-    pub fn hash(&self) -> &str {
-        &self.0
+    /// Who this profile is following.
+    pub fn following(
+        &self,
+        query: ProfilesQuery,
+        repository: &impl Repository,
+    ) -> Result<Vec<Profile>, DatabaseError> {
+        repository.get_followed(self, query)
     }
 
-    /// Check that a password matches `self` when hashed.
-    pub fn verify(&self, clear_text_password: &str) -> Result<bool, PasswordError> {
-        Ok(bcrypt::verify(clear_text_password, &self.0)?)
+    pub fn follow_counts(
+        &self,
+        repository: &impl Repository,
+    ) -> Result<FollowCounts, DatabaseError> {
+        repository.get_follow_counts(self)
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct Profile {
-    pub username: String,
-    pub bio: Option<String>,
-    pub image: Option<String>,
+pub struct FollowCounts {
+    pub followers: u64,
+    pub following: u64,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -53,6 +58,8 @@ pub struct SignUp {
 pub struct User {
     pub id: Uuid,
     pub email: String,
+    /// Whether the user proved they own `email`: it is required to publish and to comment.
+    pub email_verified: bool,
     pub profile: Profile,
 }
 
@@ -71,7 +78,26 @@ impl User {
         draft: ArticleContent,
         repository: &impl Repository,
     ) -> Result<Article, PublishArticleError> {
-        repository.publish_article(draft, &self)
+        self.write(draft, ArticleStatus::Published, repository)
+    }
+
+    /// Save a new article: depending on `status` it will be public right away,
+    /// at a later point in time or only when explicitly published by its author.
+    pub fn write(
+        &self,
+        draft: ArticleContent,
+        status: ArticleStatus,
+        repository: &impl Repository,
+    ) -> Result<Article, PublishArticleError> {
+        if status.is_publication() && !self.email_verified {
+            return Err(PublishArticleError::EmailNotVerified { user_id: self.id });
+        }
+        repository.create_article(draft, status, self)
+    }
+
+    /// All the articles authored by this user which are not public yet.
+    pub fn drafts(&self, repository: &impl Repository) -> Result<Vec<Article>, DatabaseError> {
+        repository.find_drafts(self)
     }
 
     pub fn update_article(
@@ -80,14 +106,66 @@ impl User {
         update: ArticleUpdate,
         repository: &impl Repository,
     ) -> Result<Article, ChangeArticleError> {
+        self.ensure_author_of(&article)?;
+        let publishes = matches!(&update.status, Some(status) if status.is_publication());
+        if publishes && !self.email_verified {
+            return Err(ChangeArticleError::EmailNotVerified {
+                user_id: self.id,
+                slug: article.slug,
+            });
+        }
+        let updated_article = repository.update_article(article, update, self)?;
+        Ok(updated_article)
+    }
+
+    /// The edit history of one of your articles, oldest revision first.
+    pub fn article_revisions(
+        &self,
+        article: &Article,
+        repository: &impl Repository,
+    ) -> Result<Vec<ArticleRevision>, ChangeArticleError> {
+        self.ensure_author_of(article)?;
+        Ok(repository.get_revisions(article)?)
+    }
+
+    pub fn article_revision(
+        &self,
+        article: &Article,
+        revision_id: u64,
+        repository: &impl Repository,
+    ) -> Result<ArticleRevision, ChangeArticleError> {
+        self.ensure_author_of(article)?;
+        repository.get_revision(article, revision_id)
+    }
+
+    /// Bring the content of one of your articles back to what it was right after
+    /// the specified revision.
+    /// The restore is an edit like any other: it is recorded as a new revision.
+    pub fn restore_article_revision(
+        &self,
+        article: Article,
+        revision_id: u64,
+        repository: &impl Repository,
+    ) -> Result<Article, ChangeArticleError> {
+        let history = self.article_revisions(&article, repository)?;
+        let content = content_at_revision(&history, revision_id).ok_or_else(|| {
+            ChangeArticleError::RevisionNotFound {
+                slug: article.slug.to_owned(),
+                revision_id,
+            }
+        })?;
+        Ok(repository.update_article(article, content, self)?)
+    }
+
+    fn ensure_author_of(&self, article: &Article) -> Result<(), ChangeArticleError> {
+        // You can only change your own articles
         if article.author.username != self.profile.username {
             return Err(ChangeArticleError::Forbidden {
-                slug: article.slug,
+                slug: article.slug.to_owned(),
                 user_id: self.id,
             });
         }
-        let updated_article = repository.update_article(article, update)?;
-        Ok(updated_article)
+        Ok(())
     }
 
     pub fn update(
@@ -103,13 +181,7 @@ impl User {
         article: Article,
         repository: &impl Repository,
     ) -> Result<(), ChangeArticleError> {
-        // You can only delete your own articles
-        if article.author.username != self.profile.username {
-            return Err(ChangeArticleError::Forbidden {
-                slug: article.slug,
-                user_id: self.id,
-            });
-        }
+        self.ensure_author_of(&article)?;
         Ok(repository.delete_article(&article)?)
     }
 
@@ -119,13 +191,30 @@ impl User {
         comment: CommentContent,
         repository: &impl Repository,
     ) -> Result<CommentView, ChangeArticleError> {
+        if !self.email_verified {
+            return Err(ChangeArticleError::EmailNotVerified {
+                user_id: self.id,
+                slug: article.slug.to_owned(),
+            });
+        }
+        if self.is_blocked_with(&article.author, repository)? {
+            return Err(ChangeArticleError::Blocked {
+                user_id: self.id,
+                slug: article.slug.to_owned(),
+            });
+        }
         let posted_comment = repository.comment_article(&self, &article, comment)?;
+        let event = Event::Commented {
+            article_slug: article.slug.to_owned(),
+            comment_id: posted_comment.id,
+        };
+        self.notify(&article.author, event, repository)?;
         let view = CommentView {
             id: posted_comment.id,
             author: ProfileView {
                 profile: posted_comment.author,
-                // Users always self-follow
-                following: true,
+                // Users don't follow themselves
+                following: false,
                 viewer: self.id,
             },
             body: posted_comment.body,
@@ -157,7 +246,13 @@ impl User {
         repository: &(impl Repository + Repository),
     ) -> Result<ArticleView, DatabaseError> {
         let n_favorites = match repository.favorite(&article, self)? {
-            FavoriteOutcome::NewFavorite => article.favorites_count + 1,
+            FavoriteOutcome::NewFavorite => {
+                let event = Event::Favorited {
+                    article_slug: article.slug.to_owned(),
+                };
+                self.notify(&article.author, event, repository)?;
+                article.favorites_count + 1
+            }
             FavoriteOutcome::AlreadyAFavorite => article.favorites_count,
         };
         let article_view = ArticleView {
@@ -167,6 +262,7 @@ impl User {
             metadata: article.metadata,
             favorited: true,
             favorites_count: n_favorites,
+            status: article.status,
             viewer: self.id.to_owned(),
         };
         Ok(article_view)
@@ -188,6 +284,7 @@ impl User {
             metadata: article.metadata,
             favorited: false,
             favorites_count: n_favorites,
+            status: article.status,
             viewer: self.id.to_owned(),
         };
         Ok(article_view)
@@ -197,8 +294,19 @@ impl User {
         &self,
         p: Profile,
         repository: &impl Repository,
-    ) -> Result<ProfileView, DatabaseError> {
-        repository.follow(self, &p)?;
+    ) -> Result<ProfileView, FollowError> {
+        if p.username == self.profile.username {
+            return Err(FollowError::SelfFollow);
+        }
+        if self.is_blocked_with(&p, repository)? {
+            return Err(FollowError::Blocked {
+                username: p.username,
+            });
+        }
+        // Following someone again does not notify them again
+        if let FollowOutcome::NewFollow = repository.follow(self, &p)? {
+            self.notify(&p, Event::Followed, repository)?;
+        }
         let view = ProfileView {
             profile: p,
             following: true,
@@ -221,6 +329,59 @@ impl User {
         Ok(view)
     }
 
+    /// Blocking a user removes any follow between the two of you.
+    pub fn block(&self, p: &Profile, repository: &impl Repository) -> Result<(), BlockError> {
+        if p.username == self.profile.username {
+            return Err(BlockError::SelfTarget);
+        }
+        Ok(repository.block(self, p)?)
+    }
+
+    pub fn unblock(&self, p: &Profile, repository: &impl Repository) -> Result<(), DatabaseError> {
+        repository.unblock(self, p)
+    }
+
+    /// Muting a user hides their articles from your feed and their comments from you.
+    pub fn mute(&self, p: &Profile, repository: &impl Repository) -> Result<(), BlockError> {
+        if p.username == self.profile.username {
+            return Err(BlockError::SelfTarget);
+        }
+        Ok(repository.mute(self, p)?)
+    }
+
+    pub fn unmute(&self, p: &Profile, repository: &impl Repository) -> Result<(), DatabaseError> {
+        repository.unmute(self, p)
+    }
+
+    /// Returns `true` if either you blocked `p` or `p` blocked you.
+    pub fn is_blocked_with(
+        &self,
+        p: &Profile,
+        repository: &impl Repository,
+    ) -> Result<bool, DatabaseError> {
+        Ok(
+            repository.has_blocked(&self.profile, p)?
+                || repository.has_blocked(p, &self.profile)?,
+        )
+    }
+
+    /// Close your account: the password is asked again to confirm it is really you.
+    pub fn delete_account(
+        self,
+        password: &str,
+        mode: AccountDeletion,
+        hashing: &PasswordHashing,
+        repository: &impl Repository,
+    ) -> Result<(), DeleteAccountError> {
+        match repository.get_user_by_email_and_password(&self.email, password, hashing) {
+            Ok(_) => {}
+            Err(LoginError::NotFound) => return Err(DeleteAccountError::WrongPassword),
+            Err(LoginError::PasswordError(e)) => return Err(e.into()),
+            Err(LoginError::DatabaseError(e)) => return Err(e.into()),
+        }
+        Ok(repository.delete_user(&self, mode)?)
+    }
+
     pub fn feed(
         &self,
         query: FeedQuery,
@@ -230,11 +391,25 @@ impl User {
     }
 }
 
+/// What happens to the articles and comments of a deleted account.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountDeletion {
+    /// They are deleted together with the account.
+    Delete,
+    /// They are kept, but attributed to a "deleted user" placeholder.
+    Anonymize,
+}
+
 pub enum FavoriteOutcome {
     NewFavorite,
     AlreadyAFavorite,
 }
 
+pub enum FollowOutcome {
+    NewFollow,
+    AlreadyFollowing,
+}
+
 pub enum UnfavoriteOutcome {
     WasAFavorite,
     WasNotAFavorite,
@@ -248,8 +423,15 @@ pub struct ProfileView {
     pub viewer: Uuid,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilesQuery {
+    pub limit: u64,
+    pub offset: u64,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FeedQuery {
     pub limit: u64,
     pub offset: u64,
+    pub cursor: Option<ArticleCursor>,
 }
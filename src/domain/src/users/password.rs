@@ -0,0 +1,144 @@
+use crate::PasswordError;
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// How new passwords get hashed.
+///
+/// Hashes produced with a different algorithm or with weaker parameters can still be verified:
+/// the algorithm is detected from the prefix of the hash.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PasswordHashing {
+    Bcrypt {
+        cost: u32,
+    },
+    Argon2id {
+        /// In KiB.
+        memory_cost: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl Default for PasswordHashing {
+    fn default() -> Self {
+        PasswordHashing::Bcrypt {
+            cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Password(String);
+
+impl Password {
+    /// Given a clear-text password, it returns a `Password` instance
+    /// containing the password's hash.
+    pub fn from_clear_text(
+        clear_text_password: String,
+        hashing: &PasswordHashing,
+    ) -> Result<Password, PasswordError> {
+        let hash = match hashing {
+            PasswordHashing::Bcrypt { cost } => bcrypt::hash(clear_text_password, *cost)?,
+            PasswordHashing::Argon2id {
+                memory_cost,
+                iterations,
+                parallelism,
+            } => {
+                let mut salt = [0u8; 16];
+                SystemRandom::new()
+                    .fill(&mut salt)
+                    .map_err(|e| PasswordError::from(anyhow::Error::from(e)))?;
+                let config = argon2::Config {
+                    variant: argon2::Variant::Argon2id,
+                    mem_cost: *memory_cost,
+                    time_cost: *iterations,
+                    lanes: *parallelism,
+                    ..argon2::Config::default()
+                };
+                argon2::hash_encoded(clear_text_password.as_bytes(), &salt, &config)?
+            }
+        };
+        Ok(Password(hash))
+    }
+
+    /// Given an already hashed password, it returns a `Password` instance
+    /// containing that very same hash.
+    pub fn from_hash(hashed_password: String) -> Password {
+        Password(hashed_password)
+    }
+
+    /// Returns the hashed password.
+    pub fn hash(&self) -> &str {
+        &self.0
+    }
+
+    /// Check that a password matches `self` when hashed.
+    pub fn verify(&self, clear_text_password: &str) -> Result<bool, PasswordError> {
+        if self.is_argon2id() {
+            Ok(argon2::verify_encoded(
+                &self.0,
+                clear_text_password.as_bytes(),
+            )?)
+        } else {
+            Ok(bcrypt::verify(clear_text_password, &self.0)?)
+        }
+    }
+
+    /// Returns `true` if the hash was not produced using `hashing`:
+    /// either the algorithm or its parameters are different.
+    pub fn needs_rehash(&self, hashing: &PasswordHashing) -> bool {
+        match hashing {
+            // Bcrypt hashes look like `$2b$<cost>$<salt and hash>`
+            PasswordHashing::Bcrypt { cost } => {
+                self.is_argon2id()
+                    || self.0.split('$').nth(2) != Some(format!("{:02}", cost).as_str())
+            }
+            // Argon2 hashes look like `$argon2id$v=19$m=<memory>,t=<iterations>,p=<parallelism>$<salt>$<hash>`
+            PasswordHashing::Argon2id {
+                memory_cost,
+                iterations,
+                parallelism,
+            } => {
+                let parameters = format!("m={},t={},p={}", memory_cost, iterations, parallelism);
+                !self.is_argon2id() || self.0.split('$').nth(3) != Some(parameters.as_str())
+            }
+        }
+    }
+
+    fn is_argon2id(&self) -> bool {
+        self.0.starts_with("$argon2id$")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_are_verified_whatever_their_algorithm() {
+        let bcrypt = PasswordHashing::Bcrypt { cost: 4 };
+        let argon2id = PasswordHashing::Argon2id {
+            memory_cost: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        for hashing in &[bcrypt, argon2id] {
+            let password = Password::from_clear_text("a-password".into(), hashing).unwrap();
+            assert!(password.verify("a-password").unwrap());
+            assert!(!password.verify("another-password").unwrap());
+            assert!(!password.needs_rehash(hashing));
+        }
+    }
+
+    #[test]
+    fn hashes_with_different_parameters_need_a_rehash() {
+        let password =
+            Password::from_clear_text("a-password".into(), &PasswordHashing::Bcrypt { cost: 4 })
+                .unwrap();
+        assert!(password.needs_rehash(&PasswordHashing::Bcrypt { cost: 5 }));
+        assert!(password.needs_rehash(&PasswordHashing::Argon2id {
+            memory_cost: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }));
+    }
+}
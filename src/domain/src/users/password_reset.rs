@@ -0,0 +1,77 @@
+use crate::repositories::Repository;
+use crate::{Email, Mailer, Password, PasswordResetError, User};
+use chrono::{Duration, Utc};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// How long a password reset token can be used for, in minutes.
+const TOKEN_VALIDITY_MINUTES: i64 = 60;
+
+/// A random secret proving that whoever holds it can read the user's emails.
+///
+/// Only its hash is stored: a leak of the database does not allow anybody
+/// to reset passwords.
+pub struct PasswordResetToken(String);
+
+impl PasswordResetToken {
+    pub fn generate() -> Result<Self, PasswordResetError> {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|e| PasswordResetError::TokenGeneration(e.into()))?;
+        Ok(Self(base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)))
+    }
+
+    pub fn from_clear_text(token: String) -> Self {
+        Self(token)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The hex-encoded SHA-256 of the token.
+    pub fn hash(&self) -> String {
+        digest(&SHA256, self.0.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+impl User {
+    /// Email the user a single-use token they can use to choose a new password.
+    pub fn request_password_reset(
+        &self,
+        repository: &impl Repository,
+        mailer: &dyn Mailer,
+    ) -> Result<(), PasswordResetError> {
+        let token = PasswordResetToken::generate()?;
+        let expires_at = Utc::now() + Duration::minutes(TOKEN_VALIDITY_MINUTES);
+        repository.create_password_reset(self, &token.hash(), expires_at)?;
+        let email = Email {
+            to: self.email.to_owned(),
+            subject: "Reset your password".into(),
+            body: format!(
+                "Hi {},\n\nuse the following token to choose a new password: {}\n\
+                 It expires in {} minutes and it can only be used once.\n\n\
+                 If you did not ask for a password reset, you can safely ignore this email.",
+                self.profile.username,
+                token.as_str(),
+                TOKEN_VALIDITY_MINUTES
+            ),
+        };
+        Ok(mailer.send(email)?)
+    }
+}
+
+/// Set a new password for the user who received `token`.
+/// It logs the user out of all their existing sessions and deletes their access tokens.
+pub fn reset_password(
+    token: &PasswordResetToken,
+    new_password: Password,
+    repository: &impl Repository,
+) -> Result<User, PasswordResetError> {
+    repository.reset_password(&token.hash(), &new_password)
+}
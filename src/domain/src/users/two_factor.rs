@@ -0,0 +1,270 @@
+use crate::repositories::Repository;
+use crate::{DatabaseError, TwoFactorError, User};
+use chrono::{DateTime, Utc};
+use data_encoding::BASE32_NOPAD;
+use ring::digest::{digest, SHA1, SHA256};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// The name authenticator apps display next to the codes of our users.
+const ISSUER: &str = "Conduit";
+/// Codes change every 30 seconds.
+const STEP_SECONDS: i64 = 30;
+/// Codes of the previous and of the next time step are accepted too, to tolerate clock drift.
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const RECOVERY_CODES: usize = 10;
+
+/// The secret shared with the authenticator app of a user, as described by RFC 6238:
+/// it produces 6-digit codes, using HMAC-SHA1 over 30-second time steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TotpSecret(Vec<u8>);
+
+impl TotpSecret {
+    pub fn generate() -> Result<Self, TwoFactorError> {
+        let mut bytes = vec![0u8; 20];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|e| TwoFactorError::SecretGeneration(e.into()))?;
+        Ok(Self(bytes))
+    }
+
+    /// Returns `None` if `encoded` is not valid base32.
+    pub fn from_base32(encoded: &str) -> Option<Self> {
+        BASE32_NOPAD.decode(encoded.as_bytes()).ok().map(Self)
+    }
+
+    /// The format authenticator apps expect when the secret is typed in.
+    pub fn to_base32(&self) -> String {
+        BASE32_NOPAD.encode(&self.0)
+    }
+
+    /// The URI to share with authenticator apps, usually as a QR code.
+    pub fn otpauth_uri(&self, account: &str) -> String {
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits=6&period={period}",
+            issuer = ISSUER,
+            account = percent_encode(account),
+            secret = self.to_base32(),
+            period = STEP_SECONDS,
+        )
+    }
+
+    /// The code an authenticator app displays at `now`.
+    pub fn code(&self, now: DateTime<Utc>) -> String {
+        self.code_at(now.timestamp() / STEP_SECONDS)
+    }
+
+    fn code_at(&self, step: i64) -> String {
+        let key = hmac::SigningKey::new(&SHA1, &self.0);
+        let signature = hmac::sign(&key, &step.to_be_bytes());
+        let mac = signature.as_ref();
+        // Dynamic truncation, as described by RFC 4226
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            mac[offset],
+            mac[offset + 1],
+            mac[offset + 2],
+            mac[offset + 3],
+        ]) & 0x7fff_ffff;
+        format!("{:06}", binary % 1_000_000)
+    }
+
+    /// The time step `code` was generated for, if it is valid at `now`.
+    pub fn matching_step(&self, code: &str, now: DateTime<Utc>) -> Option<i64> {
+        let current_step = now.timestamp() / STEP_SECONDS;
+        (current_step - ALLOWED_DRIFT_STEPS..=current_step + ALLOWED_DRIFT_STEPS)
+            .find(|step| constant_time_eq(self.code_at(*step).as_bytes(), code.trim().as_bytes()))
+    }
+}
+
+/// A single-use code allowing users to log in when they lose access to their authenticator app.
+///
+/// Only its hash is stored: they are handed out once, when two-factor authentication is enabled.
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    pub fn generate() -> Result<Self, TwoFactorError> {
+        let mut bytes = [0u8; 10];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|e| TwoFactorError::SecretGeneration(e.into()))?;
+        let encoded = BASE32_NOPAD.encode(&bytes).to_lowercase();
+        // Groups of four characters are easier to copy down
+        let groups: Vec<&str> = (0..encoded.len())
+            .step_by(4)
+            .map(|i| &encoded[i..i + 4])
+            .collect();
+        Ok(Self(groups.join("-")))
+    }
+
+    pub fn from_clear_text(code: String) -> Self {
+        Self(code)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The hex-encoded SHA-256 of the code, ignoring dashes, whitespace and case.
+    pub fn hash(&self) -> String {
+        let normalized: String = self
+            .0
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+        digest(&SHA256, normalized.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// The two-factor authentication settings of a user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TwoFactor {
+    pub secret: TotpSecret,
+    /// Two-factor authentication is only enforced once confirmed.
+    pub confirmed: bool,
+}
+
+impl User {
+    /// Generate a new secret for the user to add to their authenticator app.
+    ///
+    /// Two-factor authentication is not enforced until `confirm_two_factor` succeeds:
+    /// enrolling again before that replaces the secret.
+    pub fn enroll_two_factor(
+        &self,
+        repository: &impl Repository,
+    ) -> Result<TotpSecret, TwoFactorError> {
+        let secret = TotpSecret::generate()?;
+        repository.enroll_two_factor(self, &secret)?;
+        Ok(secret)
+    }
+
+    /// Enforce two-factor authentication, provided `code` shows the authenticator app is set up.
+    ///
+    /// It returns the recovery codes of the user: they cannot be retrieved later on.
+    pub fn confirm_two_factor(
+        &self,
+        code: &str,
+        repository: &impl Repository,
+    ) -> Result<Vec<RecoveryCode>, TwoFactorError> {
+        let two_factor = match repository.get_two_factor(self)? {
+            Some(t) if !t.confirmed => t,
+            Some(_) => return Err(TwoFactorError::AlreadyEnabled),
+            None => return Err(TwoFactorError::NotEnrolled),
+        };
+        let step = two_factor
+            .secret
+            .matching_step(code, Utc::now())
+            .ok_or(TwoFactorError::InvalidCode)?;
+        let recovery_codes = (0..RECOVERY_CODES)
+            .map(|_| RecoveryCode::generate())
+            .collect::<Result<Vec<_>, _>>()?;
+        let hashes: Vec<String> = recovery_codes.iter().map(RecoveryCode::hash).collect();
+        repository.confirm_two_factor(self, step, &hashes)?;
+        Ok(recovery_codes)
+    }
+
+    /// Turn two-factor authentication off, provided `code` is a valid second factor.
+    pub fn disable_two_factor(
+        &self,
+        code: &str,
+        repository: &impl Repository,
+    ) -> Result<(), TwoFactorError> {
+        self.verify_second_factor(code, repository)?;
+        Ok(repository.disable_two_factor(self)?)
+    }
+
+    pub fn requires_two_factor(&self, repository: &impl Repository) -> Result<bool, DatabaseError> {
+        Ok(matches!(repository.get_two_factor(self)?, Some(t) if t.confirmed))
+    }
+
+    /// Check `code`, either from the authenticator app or one of the recovery codes.
+    ///
+    /// Each of them can be used only once.
+    pub fn verify_second_factor(
+        &self,
+        code: &str,
+        repository: &impl Repository,
+    ) -> Result<(), TwoFactorError> {
+        let two_factor = match repository.get_two_factor(self)? {
+            Some(t) if t.confirmed => t,
+            _ => return Err(TwoFactorError::NotEnabled),
+        };
+        let accepted = match two_factor.secret.matching_step(code, Utc::now()) {
+            Some(step) => repository.use_two_factor_step(self, step)?,
+            None => {
+                let recovery_code = RecoveryCode::from_clear_text(code.to_owned());
+                repository.use_recovery_code(self, &recovery_code.hash())?
+            }
+        };
+        if accepted {
+            Ok(())
+        } else {
+            Err(TwoFactorError::InvalidCode)
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Escape everything but the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn codes_match_the_rfc_6238_test_vectors() {
+        let secret = TotpSecret(b"12345678901234567890".to_vec());
+        // The RFC lists 8-digit codes: ours are their last 6 digits
+        for (time, code) in &[
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            let now = Utc.timestamp(*time, 0);
+            assert_eq!(secret.matching_step(code, now), Some(*time / STEP_SECONDS));
+        }
+    }
+
+    #[test]
+    fn codes_are_accepted_within_the_allowed_drift() {
+        let secret = TotpSecret::generate().unwrap();
+        let now = Utc.timestamp(1_581_000_000, 0);
+        let step = now.timestamp() / STEP_SECONDS;
+        assert_eq!(
+            secret.matching_step(&secret.code_at(step - 1), now),
+            Some(step - 1)
+        );
+        assert_eq!(
+            secret.matching_step(&secret.code_at(step + 1), now),
+            Some(step + 1)
+        );
+        assert_eq!(secret.matching_step(&secret.code_at(step - 2), now), None);
+    }
+
+    #[test]
+    fn recovery_codes_are_hashed_regardless_of_formatting() {
+        let code = RecoveryCode::generate().unwrap();
+        let retyped = RecoveryCode::from_clear_text(code.as_str().replace('-', " ").to_uppercase());
+        assert_eq!(code.hash(), retyped.hash());
+    }
+}
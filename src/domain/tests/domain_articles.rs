@@ -7,7 +7,7 @@ use fake::fake;
 use helpers::generate;
 use helpers::test_db::get_test_repo;
 use realworld_domain::repositories::Repository as RepositoryTrait;
-use realworld_domain::{ArticleUpdate, PublishArticleError};
+use realworld_domain::{ArticleUpdate, ChangeArticleError, PublishArticleError};
 
 #[test]
 fn slugs_must_be_unique() {
@@ -64,6 +64,7 @@ fn update_and_retrieve_article() {
         title: Some(fake!(Lorem.sentence(4, 10)).to_string()),
         description: Some(fake!(Lorem.paragraph(3, 10)).to_string()),
         body: Some(fake!(Lorem.paragraph(10, 5)).to_string()),
+        status: None,
     };
     let updated_article = author
         .update_article(article, update.clone(), &repository)
@@ -76,3 +77,42 @@ fn update_and_retrieve_article() {
     );
     assert_eq!(update.body, updated_article.content.body.into());
 }
+
+#[test]
+fn restoring_a_revision_brings_back_its_content() {
+    let repo = get_test_repo();
+    let repository = Repository(repo);
+
+    let author = create_user2(&repository).0;
+    let article = create_article2(&repository, With::Value(&author));
+    let original_content = article.content.clone();
+
+    let update = ArticleUpdate {
+        title: Some(fake!(Lorem.sentence(4, 10)).to_string()),
+        description: None,
+        body: Some(fake!(Lorem.paragraph(10, 5)).to_string()),
+        status: None,
+    };
+    let updated_article = author.update_article(article, update, &repository).unwrap();
+
+    let revisions = author
+        .article_revisions(&updated_article, &repository)
+        .unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[1].description, None);
+
+    let restored_article = author
+        .restore_article_revision(updated_article, revisions[0].id, &repository)
+        .unwrap();
+    assert_eq!(restored_article.content, original_content);
+
+    let result = author.article_revision(&restored_article, revisions[0].id + 1000, &repository);
+    match result {
+        Err(ChangeArticleError::RevisionNotFound { .. }) => {}
+        _ => panic!("Expected a missing revision"),
+    }
+    let revisions = author
+        .article_revisions(&restored_article, &repository)
+        .unwrap();
+    assert_eq!(revisions.len(), 3);
+}
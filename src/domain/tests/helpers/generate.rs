@@ -20,8 +20,12 @@ pub fn new_user() -> (realworld_domain::SignUp, String) {
     let sign_up = realworld_domain::SignUp {
         username: fake!(Internet.user_name).to_string(),
         email: fake!(Internet.free_email).to_string(),
-        password: realworld_domain::Password::from_clear_text(password.clone())
-            .expect("Failed to hash password"),
+        password: realworld_domain::Password::from_clear_text(
+            password.clone(),
+            // The lowest cost allowed by bcrypt, to keep tests fast
+            &realworld_domain::PasswordHashing::Bcrypt { cost: 4 },
+        )
+        .expect("Failed to hash password"),
     };
     (sign_up, password)
 }
@@ -0,0 +1,95 @@
+//! The metrics of the application, exposed to Prometheus in its text format.
+//!
+//! They live in a process-wide registry: the crates of the application update them
+//! wherever the events they count happen, and `render` reports all of them.
+mod registry;
+
+pub use registry::{Counter, Gauge, Histogram, Metric, Timer};
+
+use once_cell::sync::Lazy;
+
+/// Bucket bounds, in seconds, fit for request and query latencies.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub static HTTP_REQUESTS: Lazy<Counter> = Lazy::new(|| {
+    Counter::new(
+        "http_requests_total",
+        "HTTP requests served, by route and status.",
+        &["method", "route", "status"],
+    )
+});
+
+pub static HTTP_REQUEST_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "http_request_duration_seconds",
+        "Time spent serving HTTP requests, by route and status.",
+        &["method", "route", "status"],
+        LATENCY_BUCKETS,
+    )
+});
+
+pub static DB_POOL_CONNECTIONS: Lazy<Gauge> = Lazy::new(|| {
+    Gauge::new(
+        "db_pool_connections",
+        "Connections of the database pool, by state, as of the last checkout.",
+        &["state"],
+    )
+});
+
+pub static DB_POOL_WAIT_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "db_pool_wait_seconds",
+        "Time spent waiting for a connection from the database pool.",
+        &[],
+        LATENCY_BUCKETS,
+    )
+});
+
+pub static DB_QUERY_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "db_query_duration_seconds",
+        "Time spent in each method of the repository.",
+        &["method"],
+        LATENCY_BUCKETS,
+    )
+});
+
+pub static SIGN_UPS: Lazy<Counter> =
+    Lazy::new(|| Counter::new("sign_ups_total", "Users who signed up.", &[]));
+
+pub static PUBLISHED_ARTICLES: Lazy<Counter> = Lazy::new(|| {
+    Counter::new(
+        "articles_published_total",
+        "Articles published, on creation or from a draft.",
+        &[],
+    )
+});
+
+pub static FAVORITES: Lazy<Counter> = Lazy::new(|| {
+    Counter::new(
+        "favorites_total",
+        "Articles added to the favorites of a user.",
+        &[],
+    )
+});
+
+/// All the metrics, in the text exposition format of Prometheus.
+pub fn render() -> String {
+    let metrics: [&dyn Metric; 8] = [
+        &*HTTP_REQUESTS,
+        &*HTTP_REQUEST_DURATION,
+        &*DB_POOL_CONNECTIONS,
+        &*DB_POOL_WAIT_DURATION,
+        &*DB_QUERY_DURATION,
+        &*SIGN_UPS,
+        &*PUBLISHED_ARTICLES,
+        &*FAVORITES,
+    ];
+    let mut output = String::new();
+    for metric in metrics.iter() {
+        metric.render(&mut output);
+    }
+    output
+}
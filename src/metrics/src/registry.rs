@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// A metric that can be reported to Prometheus.
+pub trait Metric: Send + Sync {
+    /// Append the metric to `output`, in the text exposition format.
+    fn render(&self, output: &mut String);
+}
+
+type LabelValues = Vec<String>;
+
+fn label_values(label_names: &[&str], values: &[&str]) -> LabelValues {
+    assert_eq!(
+        label_names.len(),
+        values.len(),
+        "Expected values for the labels {:?}",
+        label_names
+    );
+    values.iter().map(|v| (*v).to_owned()).collect()
+}
+
+/// `{name="value",...}`, or nothing without labels.
+fn format_labels(names: &[&str], values: &[String], extra: Option<(&str, &str)>) -> String {
+    let pairs: Vec<String> = names
+        .iter()
+        .copied()
+        .zip(values.iter().map(String::as_str))
+        .chain(extra)
+        .map(|(name, value)| {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, escaped)
+        })
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn write_header(output: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(output, "# HELP {} {}", name, help).unwrap();
+    writeln!(output, "# TYPE {} {}", name, kind).unwrap();
+}
+
+/// A value that only goes up.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    values: Mutex<BTreeMap<LabelValues, u64>>,
+}
+
+impl Counter {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add one to the series identified by `labels`, given in the order of the label names.
+    pub fn inc(&self, labels: &[&str]) {
+        let key = label_values(self.label_names, labels);
+        *self.values.lock().unwrap().entry(key).or_insert(0) += 1;
+    }
+}
+
+impl Metric for Counter {
+    fn render(&self, output: &mut String) {
+        write_header(output, self.name, self.help, "counter");
+        let values = self.values.lock().unwrap();
+        if values.is_empty() && self.label_names.is_empty() {
+            writeln!(output, "{} 0", self.name).unwrap();
+        }
+        for (labels, value) in values.iter() {
+            let labels = format_labels(self.label_names, labels, None);
+            writeln!(output, "{}{} {}", self.name, labels, value).unwrap();
+        }
+    }
+}
+
+/// A value that goes up and down.
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    values: Mutex<BTreeMap<LabelValues, f64>>,
+}
+
+impl Gauge {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn set(&self, labels: &[&str], value: f64) {
+        let key = label_values(self.label_names, labels);
+        self.values.lock().unwrap().insert(key, value);
+    }
+}
+
+impl Metric for Gauge {
+    fn render(&self, output: &mut String) {
+        write_header(output, self.name, self.help, "gauge");
+        for (labels, value) in self.values.lock().unwrap().iter() {
+            let labels = format_labels(self.label_names, labels, None);
+            writeln!(output, "{}{} {}", self.name, labels, value).unwrap();
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Observations {
+    /// The number of observations in each bucket, not cumulated.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// The distribution of observed values, e.g. durations in seconds.
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    label_names: &'static [&'static str],
+    /// The upper bounds of the buckets, in increasing order.
+    bounds: &'static [f64],
+    values: Mutex<BTreeMap<LabelValues, Observations>>,
+}
+
+impl Histogram {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        label_names: &'static [&'static str],
+        bounds: &'static [f64],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            label_names,
+            bounds,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        let key = label_values(self.label_names, labels);
+        let mut values = self.values.lock().unwrap();
+        let observations = values.entry(key).or_insert_with(|| Observations {
+            buckets: vec![0; self.bounds.len()],
+            ..Observations::default()
+        });
+        if let Some(bucket) = self.bounds.iter().position(|bound| value <= *bound) {
+            observations.buckets[bucket] += 1;
+        }
+        observations.sum += value;
+        observations.count += 1;
+    }
+
+    /// Observe the time elapsed until the returned timer is dropped, in seconds.
+    pub fn start_timer(&'static self, labels: &[&str]) -> Timer {
+        Timer {
+            histogram: self,
+            labels: label_values(self.label_names, labels),
+            started_at: Instant::now(),
+        }
+    }
+}
+
+impl Metric for Histogram {
+    fn render(&self, output: &mut String) {
+        write_header(output, self.name, self.help, "histogram");
+        for (labels, observations) in self.values.lock().unwrap().iter() {
+            let mut cumulated = 0;
+            for (bound, count) in self.bounds.iter().zip(&observations.buckets) {
+                cumulated += count;
+                let le = bound.to_string();
+                let bucket_labels = format_labels(self.label_names, labels, Some(("le", &le)));
+                writeln!(
+                    output,
+                    "{}_bucket{} {}",
+                    self.name, bucket_labels, cumulated
+                )
+                .unwrap();
+            }
+            let inf_labels = format_labels(self.label_names, labels, Some(("le", "+Inf")));
+            writeln!(
+                output,
+                "{}_bucket{} {}",
+                self.name, inf_labels, observations.count
+            )
+            .unwrap();
+            let labels = format_labels(self.label_names, labels, None);
+            writeln!(output, "{}_sum{} {}", self.name, labels, observations.sum).unwrap();
+            writeln!(
+                output,
+                "{}_count{} {}",
+                self.name, labels, observations.count
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Records its lifetime into a histogram when dropped.
+pub struct Timer {
+    histogram: &'static Histogram,
+    labels: LabelValues,
+    started_at: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+        self.histogram
+            .observe(&labels, self.started_at.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_are_rendered_per_series() {
+        let counter = Counter::new("requests_total", "Requests.", &["route"]);
+        counter.inc(&["/api/articles"]);
+        counter.inc(&["/api/articles"]);
+        counter.inc(&["/api/\"quoted\""]);
+
+        let mut output = String::new();
+        counter.render(&mut output);
+        assert_eq!(
+            output,
+            "# HELP requests_total Requests.\n\
+             # TYPE requests_total counter\n\
+             requests_total{route=\"/api/\\\"quoted\\\"\"} 1\n\
+             requests_total{route=\"/api/articles\"} 2\n"
+        );
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histogram = Histogram::new("latency_seconds", "Latency.", &[], &[0.1, 1.0]);
+        histogram.observe(&[], 0.05);
+        histogram.observe(&[], 0.5);
+        histogram.observe(&[], 5.0);
+
+        let mut output = String::new();
+        histogram.render(&mut output);
+        assert_eq!(
+            output,
+            "# HELP latency_seconds Latency.\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{le=\"0.1\"} 1\n\
+             latency_seconds_bucket{le=\"1\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 3\n\
+             latency_seconds_sum 5.55\n\
+             latency_seconds_count 3\n"
+        );
+    }
+}
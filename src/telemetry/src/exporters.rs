@@ -0,0 +1,244 @@
+use crate::{SpanData, SpanKind, TraceId, Value};
+use log::warn;
+use serde_json::{json, Value as Json};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Where ended spans go.
+pub trait SpanExporter: Send + Sync {
+    /// Called on the thread which ended the span: it should not block.
+    fn export(&self, span: SpanData);
+}
+
+/// Prints spans to stdout, one JSON object per line.
+#[derive(Debug, Default)]
+pub struct StdoutExporter {}
+
+impl SpanExporter for StdoutExporter {
+    fn export(&self, span: SpanData) {
+        println!("{}", span_json(&span));
+    }
+}
+
+/// Keeps spans in memory, for tests to inspect.
+#[derive(Clone, Default)]
+pub struct InMemoryExporter {
+    spans: Arc<Mutex<Vec<SpanData>>>,
+}
+
+impl InMemoryExporter {
+    /// The spans exported so far, in the order they ended.
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.spans.lock().unwrap().clone()
+    }
+
+    /// The spans of one trace: handy when several tests share the exporter.
+    pub fn spans_of(&self, trace_id: TraceId) -> Vec<SpanData> {
+        self.spans()
+            .into_iter()
+            .filter(|s| s.context.trace_id == trace_id)
+            .collect()
+    }
+}
+
+impl SpanExporter for InMemoryExporter {
+    fn export(&self, span: SpanData) {
+        self.spans.lock().unwrap().push(span);
+    }
+}
+
+const BATCH_SIZE: usize = 512;
+const BATCH_DELAY: Duration = Duration::from_secs(5);
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends spans in batches to an OpenTelemetry collector, using OTLP over HTTP with the JSON
+/// encoding (e.g. to `http://localhost:4318/v1/traces`).
+///
+/// Batches are sent from a background thread. Only plain HTTP is spoken: collectors usually
+/// run next to the application.
+pub struct OtlpExporter {
+    sender: Mutex<Sender<SpanData>>,
+}
+
+impl OtlpExporter {
+    pub fn new(endpoint: &str, service_name: &str) -> Self {
+        let (sender, receiver) = channel::<SpanData>();
+        let endpoint = endpoint.to_owned();
+        let service_name = service_name.to_owned();
+        thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut deadline = Instant::now() + BATCH_DELAY;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let disconnected = match receiver.recv_timeout(timeout) {
+                    Ok(span) => {
+                        batch.push(span);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+                if batch.len() >= BATCH_SIZE || Instant::now() >= deadline || disconnected {
+                    if !batch.is_empty() {
+                        let body = otlp_json(&service_name, &batch).to_string();
+                        if let Err(e) = post_json(&endpoint, &body) {
+                            warn!("Failed to export {} spans: {}", batch.len(), e);
+                        }
+                        batch.clear();
+                    }
+                    deadline = Instant::now() + BATCH_DELAY;
+                }
+                if disconnected {
+                    return;
+                }
+            }
+        });
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+}
+
+impl SpanExporter for OtlpExporter {
+    fn export(&self, span: SpanData) {
+        // The background thread only stops once the exporter is dropped
+        let _ = self.sender.lock().unwrap().send(span);
+    }
+}
+
+fn unix_nanos(time: std::time::SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+fn attribute_json(key: &str, value: &Value) -> Json {
+    let value = match value {
+        Value::String(s) => json!({ "stringValue": s }),
+        // 64-bit integers are encoded as strings
+        Value::Int(i) => json!({ "intValue": i.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn span_json(span: &SpanData) -> Json {
+    let mut json = json!({
+        "traceId": span.context.trace_id.to_string(),
+        "spanId": span.context.span_id.to_string(),
+        "name": span.name,
+        "kind": match span.kind {
+            SpanKind::Internal => 1,
+            SpanKind::Server => 2,
+            SpanKind::Client => 3,
+        },
+        "startTimeUnixNano": unix_nanos(span.start),
+        "endTimeUnixNano": unix_nanos(span.end),
+        "attributes": span
+            .attributes
+            .iter()
+            .map(|(key, value)| attribute_json(key, value))
+            .collect::<Vec<_>>(),
+        "status": { "code": if span.error { 2 } else { 0 } },
+    });
+    if let Some(parent) = span.parent_span_id {
+        json["parentSpanId"] = parent.to_string().into();
+    }
+    json
+}
+
+/// The OTLP `ExportTraceServiceRequest` carrying `spans`, in its JSON encoding.
+pub fn otlp_json(service_name: &str, spans: &[SpanData]) -> Json {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute_json("service.name", &Value::from(service_name))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME") },
+                "spans": spans.iter().map(span_json).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn post_json(endpoint: &str, body: &str) -> std::io::Result<()> {
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidInput, endpoint.to_owned());
+    let rest = endpoint.strip_prefix("http://").ok_or_else(invalid)?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rfind(':') {
+        Some(i) => (
+            &authority[..i],
+            authority[i + 1..].parse().map_err(|_| invalid())?,
+        ),
+        None => (authority, 80),
+    };
+
+    let mut stream = TcpStream::connect((host, port))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\n\r\n{}",
+        path,
+        authority,
+        body.len(),
+        body
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status = response.split_whitespace().nth(1).unwrap_or_default();
+    if status.starts_with('2') {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "The collector responded with {}",
+            status
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SpanContext, SpanId};
+    use std::time::SystemTime;
+
+    #[test]
+    fn spans_are_encoded_as_otlp_json() {
+        let context = SpanContext {
+            trace_id: TraceId([1; 16]),
+            span_id: SpanId([2; 8]),
+            sampled: true,
+        };
+        let span = SpanData {
+            name: "GET /api/articles".into(),
+            kind: SpanKind::Server,
+            context,
+            parent_span_id: Some(SpanId([3; 8])),
+            start: SystemTime::UNIX_EPOCH,
+            end: SystemTime::UNIX_EPOCH + Duration::from_millis(5),
+            attributes: vec![("http.status_code", Value::Int(200))],
+            error: false,
+        };
+
+        let json = otlp_json("conduit", &[span]);
+        let span = &json["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "01".repeat(16));
+        assert_eq!(span["parentSpanId"], "03".repeat(8));
+        assert_eq!(span["kind"], 2);
+        assert_eq!(span["endTimeUnixNano"], "5000000");
+        assert_eq!(
+            span["attributes"][0],
+            json!({ "key": "http.status_code", "value": { "intValue": "200" } })
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Distributed tracing, following the data model of OpenTelemetry.
+//!
+//! Spans are started with `start_span`: the new span becomes the current one of the async task
+//! (or thread, outside of tasks) until it is dropped, so that the spans started in the meantime
+//! are its children. Once ended, recorded spans are handed over to the exporter installed with
+//! `install`: nothing is recorded until then.
+mod context;
+mod exporters;
+
+pub use context::{SpanContext, SpanId, TraceId};
+pub use exporters::{otlp_json, InMemoryExporter, OtlpExporter, SpanExporter, StdoutExporter};
+
+use once_cell::sync::Lazy;
+use std::cell::Cell;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpanKind {
+    /// Serving a request from a remote caller.
+    Server,
+    /// Work within the process.
+    Internal,
+    /// Calling a remote service, e.g. the database.
+    Client,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Int(i)
+    }
+}
+
+/// An ended span, as exported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanData {
+    pub name: String,
+    pub kind: SpanKind,
+    pub context: SpanContext,
+    pub parent_span_id: Option<SpanId>,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(&'static str, Value)>,
+    pub error: bool,
+}
+
+static EXPORTER: Lazy<RwLock<Option<Arc<dyn SpanExporter>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Record spans from now on, sending them to `exporter`, which replaces any previous one.
+pub fn install(exporter: Arc<dyn SpanExporter>) {
+    *EXPORTER.write().unwrap() = Some(exporter);
+}
+
+fn exporter() -> Option<Arc<dyn SpanExporter>> {
+    EXPORTER.read().unwrap().clone()
+}
+
+async_std::task_local! {
+    static TASK_CURRENT: Cell<Option<SpanContext>> = Cell::new(None);
+}
+
+thread_local! {
+    static THREAD_CURRENT: Cell<Option<SpanContext>> = const { Cell::new(None) };
+}
+
+/// The context of the current span, if any.
+pub fn current() -> Option<SpanContext> {
+    TASK_CURRENT
+        .try_with(Cell::get)
+        .unwrap_or_else(|_| THREAD_CURRENT.with(Cell::get))
+}
+
+/// Make `context` the current one, returning the previous one.
+fn replace_current(context: Option<SpanContext>) -> Option<SpanContext> {
+    TASK_CURRENT
+        .try_with(|c| c.replace(context))
+        .unwrap_or_else(|_| THREAD_CURRENT.with(|c| c.replace(context)))
+}
+
+/// The trace the current span belongs to, if any: handy to correlate logs with traces.
+pub fn current_trace_id() -> Option<TraceId> {
+    current().map(|c| c.trace_id)
+}
+
+/// A span in progress: it ends when dropped.
+pub struct Span {
+    data: SpanData,
+    /// `None` when the span is not recorded: it is still propagated.
+    exporter: Option<Arc<dyn SpanExporter>>,
+    previous: Option<SpanContext>,
+}
+
+/// Start a child of the current span, or a new trace if there is none.
+pub fn start_span(name: impl Into<String>, kind: SpanKind) -> Span {
+    start_span_with_parent(name, kind, current())
+}
+
+/// Start a child of `parent`, typically propagated by a remote caller,
+/// or a new trace if there is none.
+pub fn start_span_with_parent(
+    name: impl Into<String>,
+    kind: SpanKind,
+    parent: Option<SpanContext>,
+) -> Span {
+    let context = SpanContext {
+        trace_id: parent.map_or_else(TraceId::random, |p| p.trace_id),
+        span_id: SpanId::random(),
+        // Callers may have decided against recording the trace
+        sampled: !matches!(parent, Some(SpanContext { sampled: false, .. })),
+    };
+    let exporter = if context.sampled { exporter() } else { None };
+    let now = SystemTime::now();
+    Span {
+        data: SpanData {
+            name: name.into(),
+            kind,
+            context,
+            parent_span_id: parent.map(|p| p.span_id),
+            start: now,
+            end: now,
+            attributes: Vec::new(),
+            error: false,
+        },
+        exporter,
+        previous: replace_current(Some(context)),
+    }
+}
+
+impl Span {
+    pub fn context(&self) -> SpanContext {
+        self.data.context
+    }
+
+    pub fn set_attribute(&mut self, key: &'static str, value: impl Into<Value>) {
+        if self.exporter.is_some() {
+            self.data.attributes.push((key, value.into()));
+        }
+    }
+
+    /// Mark the operation as failed.
+    pub fn set_error(&mut self) {
+        self.data.error = true;
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        replace_current(self.previous);
+        if let Some(exporter) = self.exporter.take() {
+            let mut data = self.data.clone();
+            data.end = SystemTime::now();
+            exporter.export(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The exporter is global: tests running in parallel share one, and filter spans by trace.
+    fn exporter() -> InMemoryExporter {
+        static EXPORTER: Lazy<InMemoryExporter> = Lazy::new(|| {
+            let exporter = InMemoryExporter::default();
+            install(Arc::new(exporter.clone()));
+            exporter
+        });
+        EXPORTER.clone()
+    }
+
+    #[test]
+    fn nested_spans_are_children_of_the_current_span() {
+        let exporter = exporter();
+        let remote_parent = SpanContext::from_traceparent(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        );
+
+        let root = start_span_with_parent("request", SpanKind::Server, remote_parent);
+        let child = start_span("query", SpanKind::Client);
+        let (root_context, child_context) = (root.context(), child.context());
+        drop(child);
+        assert_eq!(current(), Some(root_context));
+        drop(root);
+        assert_eq!(current(), None);
+
+        let spans = exporter.spans_of(root_context.trace_id);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].name, "query");
+        assert_eq!(spans[0].context, child_context);
+        assert_eq!(spans[0].parent_span_id, Some(root_context.span_id));
+        assert_eq!(spans[1].context.trace_id, remote_parent.unwrap().trace_id);
+        assert_eq!(
+            spans[1].parent_span_id,
+            Some(remote_parent.unwrap().span_id)
+        );
+    }
+
+    #[test]
+    fn unsampled_traces_are_propagated_but_not_recorded() {
+        let exporter = exporter();
+        let remote_parent = SpanContext::from_traceparent(
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00",
+        );
+
+        let root = start_span_with_parent("request", SpanKind::Server, remote_parent);
+        let child = start_span("query", SpanKind::Client);
+        assert!(!child.context().sampled);
+        drop(child);
+        drop(root);
+
+        assert!(exporter
+            .spans_of(remote_parent.unwrap().trace_id)
+            .is_empty());
+    }
+}
@@ -1,7 +1,15 @@
-use crate::Context;
+use crate::client_address::ClientAddressMiddleware;
+use crate::cors::{CorsMiddleware, CorsPolicy};
+use crate::monitoring::MetricsMiddleware;
+use crate::rate_limit::{InMemoryStore, RateLimitMiddleware, RateLimits};
+use crate::request_log::RequestLogMiddleware;
+use crate::throttle::LoginThrottle;
+use crate::tracing::TracingMiddleware;
+use crate::{AppSettings, Context};
 use domain::repositories::Repository;
-use http::HeaderValue;
-use tide::middleware::{Cors, Origin};
+use domain::Mailer;
+use std::net::IpAddr;
+use std::sync::Arc;
 use tide::{IntoResponse, Response, Server};
 
 pub fn result_to_response<T: IntoResponse, E: IntoResponse>(r: Result<T, E>) -> Response {
@@ -20,27 +28,135 @@ pub fn result_to_response<T: IntoResponse, E: IntoResponse>(r: Result<T, E>) ->
     }
 }
 
-pub fn get_app<R: Repository + Send + Sync>(repository: R) -> Server<Context<R>> {
-    let context = Context { repository };
+pub fn get_app<R: Repository + Send + Sync>(
+    repository: R,
+    mailer: Box<dyn Mailer + Send + Sync>,
+    settings: AppSettings,
+) -> Server<Context<R>> {
+    let context = Context {
+        repository,
+        mailer: Arc::from(mailer),
+        password_hashing: settings.password_hashing,
+        login_throttle: LoginThrottle::new(settings.login_throttling),
+        oidc_providers: settings.oidc_providers,
+        notification_stream: settings.notification_stream,
+        metrics_token: settings.metrics_token,
+        token_secret: settings.token_secret,
+    };
     let mut app = Server::with_state(context);
-    app = add_middleware(app);
+    app = add_middleware(
+        app,
+        settings.rate_limits,
+        settings.cors,
+        settings.trusted_proxies,
+    );
     app = add_routes(app);
     app
 }
 
+/// The templates of the routes registered by `add_routes`.
+pub const ROUTES: &[&str] = &[
+    "/api/user",
+    "/api/user/verification-email",
+    "/api/user/two-factor",
+    "/api/user/two-factor/confirm",
+    "/api/user/tokens",
+    "/api/user/tokens/:id",
+    "/api/user/drafts",
+    "/api/users",
+    "/api/users/login",
+    "/api/users/login/two-factor",
+    "/api/users/verify-email",
+    "/api/users/password-reset",
+    "/api/users/password-reset/confirm",
+    "/api/auth/:provider/authorize",
+    "/api/auth/:provider/callback",
+    "/api/profiles/:username",
+    "/api/profiles/:username/followers",
+    "/api/profiles/:username/following",
+    "/api/profiles/:username/follow",
+    "/api/profiles/:username/block",
+    "/api/profiles/:username/mute",
+    "/api/tags",
+    "/api/articles",
+    "/api/articles/feed",
+    "/api/articles/:slug",
+    "/api/articles/:slug/revisions",
+    "/api/articles/:slug/revisions/:id",
+    "/api/articles/:slug/revisions/:id/restore",
+    "/api/articles/:slug/comments",
+    "/api/articles/:slug/comments/:id",
+    "/api/articles/:slug/favorite",
+    "/api/notifications",
+    "/api/notifications/read",
+    "/api/notifications/stream",
+    "/api/notifications/:id/read",
+    "/metrics",
+    crate::openapi::OPENAPI_ROUTE,
+    crate::graphql::GRAPHQL_ROUTE,
+];
+
 pub fn add_routes<R: Repository + Send + Sync>(mut api: Server<Context<R>>) -> Server<Context<R>> {
     api.at("/api/user")
         .get(|req| async move { result_to_response(crate::users::get_current_user(req).await) })
-        .put(|req| async move { result_to_response(crate::users::update_user(req).await) });
+        .put(|req| async move { result_to_response(crate::users::update_user(req).await) })
+        .delete(|req| async move { result_to_response(crate::users::delete_user(req).await) });
+    api.at("/api/user/verification-email")
+        .post(|req| async move {
+            result_to_response(crate::users::resend_verification_email(req).await)
+        });
+    api.at("/api/user/two-factor")
+        .post(|req| async move { result_to_response(crate::users::enroll_two_factor(req).await) })
+        .delete(
+            |req| async move { result_to_response(crate::users::disable_two_factor(req).await) },
+        );
+    api.at("/api/user/two-factor/confirm")
+        .post(|req| async move { result_to_response(crate::users::confirm_two_factor(req).await) });
+    api.at("/api/user/tokens")
+        .get(|req| async move { result_to_response(crate::users::list_access_tokens(req).await) })
+        .post(
+            |req| async move { result_to_response(crate::users::create_access_token(req).await) },
+        );
+    api.at("/api/user/tokens/:id").delete(|req| async move {
+        result_to_response(crate::users::revoke_access_token(req).await)
+    });
+    api.at("/api/user/drafts")
+        .get(|req| async move { result_to_response(crate::articles::drafts(req).await) });
     api.at("/api/users")
         .post(|req| async move { result_to_response(crate::users::register(req).await) });
     api.at("/api/users/login")
         .post(|req| async move { result_to_response(crate::users::login(req).await) });
+    api.at("/api/users/login/two-factor")
+        .post(
+            |req| async move { result_to_response(crate::users::login_second_factor(req).await) },
+        );
+    api.at("/api/users/verify-email")
+        .post(|req| async move { result_to_response(crate::users::verify_email(req).await) });
+    api.at("/api/users/password-reset").post(|req| async move {
+        result_to_response(crate::users::request_password_reset(req).await)
+    });
+    api.at("/api/users/password-reset/confirm").post(|req| async move {
+        result_to_response(crate::users::confirm_password_reset(req).await)
+    });
+    api.at("/api/auth/:provider/authorize")
+        .get(|req| async move { result_to_response(crate::oidc::authorize(req).await) });
+    api.at("/api/auth/:provider/callback")
+        .get(|req| async move { result_to_response(crate::oidc::callback(req).await) });
     api.at("/api/profiles/:username")
         .get(|req| async move { result_to_response(crate::profiles::get_profile(req).await) });
+    api.at("/api/profiles/:username/followers")
+        .get(|req| async move { result_to_response(crate::profiles::followers(req).await) });
+    api.at("/api/profiles/:username/following")
+        .get(|req| async move { result_to_response(crate::profiles::following(req).await) });
     api.at("/api/profiles/:username/follow")
         .post(|req| async move { result_to_response(crate::profiles::follow(req).await) })
         .delete(|req| async move { result_to_response(crate::profiles::unfollow(req).await) });
+    api.at("/api/profiles/:username/block")
+        .post(|req| async move { result_to_response(crate::profiles::block(req).await) })
+        .delete(|req| async move { result_to_response(crate::profiles::unblock(req).await) });
+    api.at("/api/profiles/:username/mute")
+        .post(|req| async move { result_to_response(crate::profiles::mute(req).await) })
+        .delete(|req| async move { result_to_response(crate::profiles::unmute(req).await) });
     api.at("/api/tags")
         .get(|req| async move { result_to_response(crate::articles::tags(req).await) });
     api.at("/api/articles")
@@ -54,6 +170,14 @@ pub fn add_routes<R: Repository + Send + Sync>(mut api: Server<Context<R>>) -> S
         .delete(
             |req| async move { result_to_response(crate::articles::delete_article(req).await) },
         );
+    api.at("/api/articles/:slug/revisions")
+        .get(|req| async move { result_to_response(crate::articles::list_revisions(req).await) });
+    api.at("/api/articles/:slug/revisions/:id")
+        .get(|req| async move { result_to_response(crate::articles::get_revision(req).await) });
+    api.at("/api/articles/:slug/revisions/:id/restore")
+        .post(
+            |req| async move { result_to_response(crate::articles::restore_revision(req).await) },
+        );
     api.at("/api/articles/:slug/comments")
         .get(|req| async move { result_to_response(crate::comments::get(req).await) })
         .post(|req| async move { result_to_response(crate::comments::create(req).await) });
@@ -62,16 +186,43 @@ pub fn add_routes<R: Repository + Send + Sync>(mut api: Server<Context<R>>) -> S
     api.at("/api/articles/:slug/favorite")
         .post(|req| async move { result_to_response(crate::articles::favorite(req).await) })
         .delete(|req| async move { result_to_response(crate::articles::unfavorite(req).await) });
+    api.at("/api/notifications")
+        .get(|req| async move { result_to_response(crate::notifications::list(req).await) });
+    api.at("/api/notifications/read").post(|req| async move {
+        result_to_response(crate::notifications::mark_all_read(req).await)
+    });
+    api.at("/api/notifications/stream")
+        .get(|req| async move { result_to_response(crate::notifications::stream(req).await) });
+    api.at("/api/notifications/:id/read")
+        .post(|req| async move { result_to_response(crate::notifications::mark_read(req).await) })
+        .delete(
+            |req| async move { result_to_response(crate::notifications::mark_unread(req).await) },
+        );
+    api.at("/metrics").get(crate::monitoring::metrics::<R>);
+    api.at(crate::openapi::OPENAPI_ROUTE)
+        .get(|req| async move { result_to_response(crate::openapi::openapi(req).await) });
+    api.at(crate::graphql::GRAPHQL_ROUTE)
+        .post(|req| async move { result_to_response(crate::graphql::graphql(req).await) });
     api
 }
 
-pub fn add_middleware<State: 'static + Sync + Send>(mut app: Server<State>) -> Server<State> {
-    let rules = Cors::new()
-        .allow_methods(HeaderValue::from_static("GET, POST, PUT, DELETE, OPTIONS"))
-        .allow_origin(Origin::from("*"))
-        .allow_credentials(false);
-    app.middleware(tide::middleware::RequestLogger::new());
-    app.middleware(rules);
+pub fn add_middleware<R: Repository + Send + Sync>(
+    mut app: Server<Context<R>>,
+    rate_limits: RateLimits,
+    cors: CorsPolicy,
+    trusted_proxies: Vec<IpAddr>,
+) -> Server<Context<R>> {
+    app.middleware(ClientAddressMiddleware::new(trusted_proxies));
+    app.middleware(TracingMiddleware::new());
+    app.middleware(RequestLogMiddleware::new());
+    app.middleware(MetricsMiddleware::new());
+    // Before authentication and rate limiting: preflight requests carry no credentials
+    app.middleware(CorsMiddleware::new(cors));
     app.middleware(crate::middleware::JwtMiddleware::new());
+    // After `JwtMiddleware`: authenticated callers are limited by user rather than by IP
+    app.middleware(RateLimitMiddleware::new(
+        rate_limits,
+        Box::new(InMemoryStore::default()),
+    ));
     app
 }
@@ -1,6 +1,8 @@
+use crate::articles::find::ensure_visible;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
+use domain::Scope;
 use tide::Response;
 
 pub async fn delete_article<R: 'static + Repository + Sync + Send>(
@@ -10,10 +12,11 @@ pub async fn delete_article<R: 'static + Repository + Sync + Send>(
     let repository = &cx.state().repository;
 
     // They have to be authenticated to perform deletions
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let user_id = cx.get_claims_with_scope(Scope::ArticlesWrite)?.user_id();
 
     let user = repository.get_user_by_id(user_id)?;
     let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&user), repository)?;
     user.delete(article, repository)?;
 
     Ok(Response::new(200))
@@ -0,0 +1,19 @@
+use crate::articles::responses::ArticlesResponse;
+use crate::middleware::ContextExt;
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use tide::{Request, Response};
+
+pub async fn drafts<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims()?.user_id();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let drafts = user.drafts(repository)?;
+    let views = repository.get_articles_views(&user, drafts)?;
+
+    let response = ArticlesResponse::from(views);
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
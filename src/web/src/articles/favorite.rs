@@ -1,7 +1,9 @@
+use crate::articles::find::ensure_visible;
 use crate::articles::responses::ArticleResponse;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
+use domain::Scope;
 use tide::{Request, Response};
 
 pub async fn favorite<R: 'static + Repository + Sync + Send>(
@@ -25,12 +27,13 @@ pub async fn _favorite<R: 'static + Repository + Sync + Send>(
     cx: Request<Context<R>>,
     action: Action,
 ) -> Result<Response, ErrorResponse> {
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let user_id = cx.get_claims_with_scope(Scope::FavoritesWrite)?.user_id();
     let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
     let user = repository.get_user_by_id(user_id)?;
     let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&user), repository)?;
     let article_view = match action {
         Action::Favorite => user.favorite(article, repository),
         Action::Unfavorite => user.unfavorite(article, repository),
@@ -1,34 +1,51 @@
+use crate::articles::pagination::{decode_cursor, MAX_LIMIT};
 use crate::articles::responses::ArticlesResponse;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use tide::{Request, Response};
 
 #[derive(Serialize, Deserialize)]
 pub struct FeedQuery {
-    #[serde(default)]
+    #[serde(default = "default_limit")]
     pub limit: u64,
 
     #[serde(default)]
     pub offset: u64,
+
+    /// An opaque cursor, as returned in `nextCursor` or `prevCursor`.
+    pub cursor: Option<String>,
+}
+
+fn default_limit() -> u64 {
+    20
 }
 
 impl Default for FeedQuery {
     fn default() -> Self {
         Self {
-            limit: 20,
+            limit: default_limit(),
             offset: 0,
+            cursor: None,
         }
     }
 }
 
-impl From<FeedQuery> for domain::FeedQuery {
-    fn from(f: FeedQuery) -> Self {
-        Self {
-            limit: f.limit,
+impl TryFrom<FeedQuery> for domain::FeedQuery {
+    type Error = String;
+
+    fn try_from(f: FeedQuery) -> Result<Self, Self::Error> {
+        let cursor = match f.cursor {
+            Some(cursor) => Some(decode_cursor(&cursor)?),
+            None => None,
+        };
+        Ok(Self {
+            limit: f.limit.min(MAX_LIMIT),
             offset: f.offset,
-        }
+            cursor,
+        })
     }
 }
 
@@ -39,10 +56,13 @@ pub async fn feed<R: 'static + Repository + Sync + Send>(
     let query = cx.query::<FeedQuery>().unwrap_or_default();
     let repository = &cx.state().repository;
 
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let user_id = cx.get_claims()?.user_id();
     let user = repository.get_user_by_id(user_id)?;
 
-    let articles = user.feed(query.into(), repository)?;
-    let response = ArticlesResponse::from(articles);
+    let query =
+        domain::FeedQuery::try_from(query).map_err(|e| Response::new(400).body_string(e))?;
+    let (cursor, offset, limit) = (query.cursor.clone(), query.offset, query.limit);
+    let articles = user.feed(query, repository)?;
+    let response = ArticlesResponse::from(articles).with_cursors(cursor.as_ref(), offset, limit);
     Ok(Response::new(200).body_json(&response).unwrap())
 }
@@ -2,6 +2,7 @@ use crate::articles::responses::ArticleResponse;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
+use domain::{Article, GetArticleError, User};
 use tide::{Request, Response};
 use uuid::Uuid;
 
@@ -16,10 +17,33 @@ pub async fn get_article<R: 'static + Repository + Sync + Send>(
     let response: ArticleResponse = match user_id {
         Some(user_id) => {
             let user = repository.get_user_by_id(user_id).unwrap();
+            ensure_visible(&article, Some(&user), repository)?;
             let article_view = repository.get_article_view(&user, article).unwrap();
             article_view.into()
         }
-        None => article.into(),
+        None => {
+            ensure_visible(&article, None, repository)?;
+            article.into()
+        }
     };
     Ok(Response::new(200).body_json(&response).unwrap())
 }
+
+/// Articles that are not public yet (or anymore) do not exist for anybody but their author,
+/// and the articles of a user do not exist for the users they blocked.
+pub fn ensure_visible<R: Repository>(
+    article: &Article,
+    viewer: Option<&User>,
+    repository: &R,
+) -> Result<(), GetArticleError> {
+    let is_blocked = match viewer {
+        Some(viewer) => repository.has_blocked(&article.author, &viewer.profile)?,
+        None => false,
+    };
+    if !article.is_visible_to(viewer) || is_blocked {
+        return Err(GetArticleError::Hidden {
+            slug: article.slug.to_owned(),
+        });
+    }
+    Ok(())
+}
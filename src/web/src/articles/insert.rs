@@ -1,8 +1,13 @@
 use crate::articles::responses::ArticleResponse;
+use crate::articles::status::{to_article_status, Status};
 use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
+use chrono::{DateTime, Utc};
 use domain::repositories::Repository;
+use domain::Scope;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tide::Response;
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -11,6 +16,16 @@ pub struct Request {
     pub article: NewArticleRequest,
 }
 
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("NewArticleRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<NewArticleRequest>("article")
+            .build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct NewArticleRequest {
@@ -18,6 +33,24 @@ pub struct NewArticleRequest {
     pub description: String,
     pub body: String,
     pub tag_list: Option<Vec<String>>,
+    /// Articles are published right away, unless specified otherwise.
+    pub status: Option<Status>,
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+impl Schema for NewArticleRequest {
+    const NAME: Option<&'static str> = Some("NewArticle");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("title")
+            .field::<String>("description")
+            .field::<String>("body")
+            .optional::<Option<Vec<String>>>("tagList")
+            .optional::<Option<Status>>("status")
+            .optional::<Option<DateTime<Utc>>>("publishAt")
+            .build()
+    }
 }
 
 impl From<NewArticleRequest> for domain::ArticleContent {
@@ -34,17 +67,20 @@ impl From<NewArticleRequest> for domain::ArticleContent {
 pub async fn insert_article<R: 'static + Repository + Sync + Send>(
     mut cx: tide::Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
+    let author_id = cx.get_claims_with_scope(Scope::ArticlesWrite)?.user_id();
     let request: Request = cx
         .body_json()
         .await
         .map_err(|e| Response::new(400).body_string(e.to_string()))?;
-    let author_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let status = to_article_status(request.article.status, request.article.publish_at)
+        .map_err(|e| Response::new(400).body_string(e))?
+        .unwrap_or(domain::ArticleStatus::Published);
     let repository = &cx.state().repository;
 
     let author = repository.get_user_by_id(author_id)?;
-    let published_article = author.publish(request.article.into(), repository)?;
+    let article = author.write(request.article.into(), status, repository)?;
 
     Ok(Response::new(200)
-        .body_json(&ArticleResponse::from(published_article))
+        .body_json(&ArticleResponse::from(article))
         .unwrap())
 }
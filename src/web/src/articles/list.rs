@@ -1,8 +1,11 @@
+use crate::articles::pagination::{decode_cursor, MAX_LIMIT};
 use crate::articles::responses::ArticlesResponse;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::str::FromStr;
 use tide::{IntoResponse, Request, Response};
 use uuid::Uuid;
@@ -12,15 +15,40 @@ pub struct ArticleQuery {
     pub author: Option<String>,
     pub favorited: Option<String>,
     pub tag: Option<String>,
+    pub q: Option<String>,
+    /// Unknown sort orders are rejected when the query is deserialized.
+    pub sort: Option<domain::ArticleSort>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    pub cursor: Option<String>,
 }
 
-impl From<ArticleQuery> for domain::ArticleQuery {
-    fn from(q: ArticleQuery) -> Self {
-        Self {
+/// As per the spec, 20 articles are returned by default.
+const DEFAULT_LIMIT: u64 = 20;
+
+impl TryFrom<ArticleQuery> for domain::ArticleQuery {
+    type Error = String;
+
+    fn try_from(q: ArticleQuery) -> Result<Self, Self::Error> {
+        let cursor = match q.cursor {
+            Some(cursor) => Some(decode_cursor(&cursor)?),
+            None => None,
+        };
+        let query = Self {
             author: q.author,
             favorited: q.favorited,
             tag: q.tag,
+            // A blank search is no search at all
+            q: q.q.filter(|text| !text.trim().is_empty()),
+            sort: q.sort,
+            limit: Some(q.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)),
+            offset: q.offset,
+            cursor,
+        };
+        if query.cursor.is_some() && !query.is_sorted_by_creation_date() {
+            return Err("Cursors can only be used with `newest` and `oldest` sort orders.".into());
         }
+        Ok(query)
     }
 }
 
@@ -38,14 +66,35 @@ pub async fn list_articles<R: 'static + Repository + Sync + Send>(
     let repository = &cx.state().repository;
 
     let user_id: Option<Uuid> = cx.get_claims().map(|c| c.user_id()).ok();
-    let articles = repository.find_articles(query.into())?;
-    let response: ArticlesResponse = match user_id {
-        Some(user_id) => {
-            let user = repository.get_user_by_id(user_id)?;
+    let query =
+        domain::ArticleQuery::try_from(query).map_err(|e| Response::new(400).body_string(e))?;
+    let search = query.q.clone();
+    let paginate = query.is_sorted_by_creation_date();
+    let (cursor, offset, limit) = (
+        query.cursor.clone(),
+        query.offset.unwrap_or(0),
+        query.limit.unwrap_or(DEFAULT_LIMIT),
+    );
+    let user = user_id
+        .map(|user_id| repository.get_user_by_id(user_id))
+        .transpose()?;
+    let articles = repository.find_articles(query, user.as_ref())?;
+    let mut snippets = match &search {
+        Some(text) => repository.search_snippets(text, &articles)?,
+        None => HashMap::new(),
+    };
+    let mut response: ArticlesResponse = match user {
+        Some(user) => {
             let views = repository.get_articles_views(&user, articles)?;
             ArticlesResponse::from(views)
         }
         None => ArticlesResponse::from(articles),
     };
+    for article in response.articles.iter_mut() {
+        article.snippet = snippets.remove(&article.slug);
+    }
+    if paginate {
+        response = response.with_cursors(cursor.as_ref(), offset, limit);
+    }
     Ok(Response::new(200).body_json(&response).unwrap())
 }
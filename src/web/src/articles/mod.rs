@@ -1,18 +1,24 @@
 pub mod delete;
+pub mod drafts;
 pub mod favorite;
 pub mod feed;
 pub mod find;
 pub mod insert;
 pub mod list;
+pub mod pagination;
 pub mod responses;
+pub mod revisions;
+pub mod status;
 pub mod tags;
 pub mod update;
 
 pub use delete::delete_article;
+pub use drafts::drafts;
 pub use favorite::{favorite, unfavorite};
 pub use feed::feed;
 pub use find::get_article;
 pub use insert::insert_article;
 pub use list::list_articles;
+pub use revisions::{get_revision, list_revisions, restore_revision};
 pub use tags::tags;
 pub use update::update_article;
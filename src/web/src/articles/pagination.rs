@@ -0,0 +1,83 @@
+use crate::articles::responses::ArticlesResponse;
+use chrono::{DateTime, SecondsFormat, Utc};
+use domain::{ArticleCursor, ArticlePosition};
+
+/// Larger pages are cut down to this many articles.
+pub const MAX_LIMIT: u64 = 100;
+
+/// Cursors are opaque to clients: they are the base64 encoding of
+/// `<direction>|<creation date of the article>|<slug of the article>`.
+pub fn encode_cursor(cursor: &ArticleCursor) -> String {
+    let (direction, position) = match cursor {
+        ArticleCursor::After(position) => ("after", position),
+        ArticleCursor::Before(position) => ("before", position),
+    };
+    let raw = format!(
+        "{}|{}|{}",
+        direction,
+        position
+            .created_at
+            .to_rfc3339_opts(SecondsFormat::Micros, true),
+        position.slug
+    );
+    base64::encode_config(&raw, base64::URL_SAFE_NO_PAD)
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<ArticleCursor, String> {
+    let invalid = || format!("`{}` is not a valid cursor.", cursor);
+    let raw = base64::decode_config(cursor, base64::URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+    let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+    let mut parts = raw.splitn(3, '|');
+    let (direction, created_at, slug) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(direction), Some(created_at), Some(slug)) => (direction, created_at, slug),
+        _ => return Err(invalid()),
+    };
+    let position = ArticlePosition {
+        created_at: DateTime::parse_from_rfc3339(created_at)
+            .map_err(|_| invalid())?
+            .with_timezone(&Utc),
+        slug: slug.to_owned(),
+    };
+    match direction {
+        "after" => Ok(ArticleCursor::After(position)),
+        "before" => Ok(ArticleCursor::Before(position)),
+        _ => Err(invalid()),
+    }
+}
+
+impl ArticlesResponse {
+    /// Set the cursors pointing to the pages surrounding this one, which was requested
+    /// using `requested` (or `offset`, for the first page) and `limit`.
+    ///
+    /// A full page is assumed to be followed by another one: the last page can be empty.
+    pub fn with_cursors(
+        mut self,
+        requested: Option<&ArticleCursor>,
+        offset: u64,
+        limit: u64,
+    ) -> Self {
+        let (first, last) = match (self.articles.first(), self.articles.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return self,
+        };
+        let is_full = self.articles.len() as u64 >= limit;
+        let (has_previous, has_next) = match requested {
+            Some(ArticleCursor::Before(_)) => (is_full, true),
+            Some(ArticleCursor::After(_)) => (true, is_full),
+            None => (offset > 0, is_full),
+        };
+        if has_previous {
+            self.prev_cursor = Some(encode_cursor(&ArticleCursor::Before(ArticlePosition {
+                created_at: first.created_at,
+                slug: first.slug.to_owned(),
+            })));
+        }
+        if has_next {
+            self.next_cursor = Some(encode_cursor(&ArticleCursor::After(ArticlePosition {
+                created_at: last.created_at,
+                slug: last.slug.to_owned(),
+            })));
+        }
+        self
+    }
+}
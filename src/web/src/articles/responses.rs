@@ -1,12 +1,33 @@
+use crate::articles::status::Status;
+use crate::openapi::{Components, Object, Schema};
 use chrono::{DateTime, Utc};
 use domain::Profile;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ArticlesResponse {
     pub articles: Vec<Article>,
     pub articles_count: u64,
+    /// Opaque cursors to the adjacent pages, if any: see `ArticlesResponse::with_cursors`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+
+impl Schema for ArticlesResponse {
+    const NAME: Option<&'static str> = Some("ArticlesResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<Article>>("articles")
+            .field::<u64>("articlesCount")
+            .optional::<String>("nextCursor")
+            .optional::<String>("prevCursor")
+            .build()
+    }
 }
 
 impl<T: Into<Article>> From<Vec<T>> for ArticlesResponse {
@@ -16,23 +37,33 @@ impl<T: Into<Article>> From<Vec<T>> for ArticlesResponse {
         Self {
             articles,
             articles_count,
+            next_cursor: None,
+            prev_cursor: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ArticleResponse {
     pub article: Article,
 }
 
+impl Schema for ArticleResponse {
+    const NAME: Option<&'static str> = Some("ArticleResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<Article>("article").build()
+    }
+}
+
 impl<T: Into<Article>> From<T> for ArticleResponse {
     fn from(a: T) -> Self {
         Self { article: a.into() }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Article {
     pub title: String,
@@ -45,6 +76,42 @@ pub struct Article {
     pub updated_at: DateTime<Utc>,
     pub author: Author,
     pub tag_list: Vec<String>,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Only set for full-text search results: the fragments of the body matching
+    /// the search, with the matching terms wrapped in `<mark>` tags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl Schema for Article {
+    const NAME: Option<&'static str> = Some("Article");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("title")
+            .field::<String>("slug")
+            .field::<String>("description")
+            .field::<String>("body")
+            .field::<bool>("favorited")
+            .field::<u64>("favoritesCount")
+            .field::<DateTime<Utc>>("createdAt")
+            .field::<DateTime<Utc>>("updatedAt")
+            .field::<Author>("author")
+            .field::<Vec<String>>("tagList")
+            .field::<Status>("status")
+            .optional::<DateTime<Utc>>("publishAt")
+            .optional::<String>("snippet")
+            .build()
+    }
+}
+
+fn publish_at(status: &domain::ArticleStatus) -> Option<DateTime<Utc>> {
+    match status {
+        domain::ArticleStatus::Scheduled { publish_at } => Some(*publish_at),
+        _ => None,
+    }
 }
 
 impl From<domain::Article> for Article {
@@ -60,6 +127,9 @@ impl From<domain::Article> for Article {
             created_at: a.metadata.created_at,
             updated_at: a.metadata.updated_at,
             author: a.author.into(),
+            status: (&a.status).into(),
+            publish_at: publish_at(&a.status),
+            snippet: None,
         }
     }
 }
@@ -77,6 +147,9 @@ impl From<domain::ArticleView> for Article {
             created_at: a.metadata.created_at,
             updated_at: a.metadata.updated_at,
             author: a.author.into(),
+            status: (&a.status).into(),
+            publish_at: publish_at(&a.status),
+            snippet: None,
         }
     }
 }
@@ -90,6 +163,19 @@ pub struct Author {
     pub following: bool,
 }
 
+impl Schema for Author {
+    const NAME: Option<&'static str> = Some("Author");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("username")
+            .field::<Option<String>>("bio")
+            .field::<Option<String>>("image")
+            .field::<bool>("following")
+            .build()
+    }
+}
+
 impl From<domain::Profile> for Author {
     fn from(p: Profile) -> Self {
         Self {
@@ -111,3 +197,84 @@ impl From<domain::ProfileView> for Author {
         }
     }
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionsResponse {
+    pub revisions: Vec<Revision>,
+}
+
+impl Schema for RevisionsResponse {
+    const NAME: Option<&'static str> = Some("RevisionsResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<Revision>>("revisions")
+            .build()
+    }
+}
+
+impl From<Vec<domain::ArticleRevision>> for RevisionsResponse {
+    fn from(revisions: Vec<domain::ArticleRevision>) -> Self {
+        Self {
+            revisions: revisions.into_iter().map(|r| r.into()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionResponse {
+    pub revision: Revision,
+}
+
+impl Schema for RevisionResponse {
+    const NAME: Option<&'static str> = Some("RevisionResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Revision>("revision")
+            .build()
+    }
+}
+
+/// Only the fields changed by the revision are set.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: u64,
+    pub editor: Author,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Schema for Revision {
+    const NAME: Option<&'static str> = Some("Revision");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .description("Only the fields changed by the revision are set.")
+            .field::<u64>("id")
+            .field::<Author>("editor")
+            .field::<Option<String>>("title")
+            .field::<Option<String>>("description")
+            .field::<Option<String>>("body")
+            .field::<DateTime<Utc>>("createdAt")
+            .build()
+    }
+}
+
+impl From<domain::ArticleRevision> for Revision {
+    fn from(r: domain::ArticleRevision) -> Self {
+        Self {
+            id: r.id,
+            editor: r.editor.into(),
+            title: r.title,
+            description: r.description,
+            body: r.body,
+            created_at: r.created_at,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::articles::find::ensure_visible;
+use crate::articles::responses::{ArticleResponse, RevisionResponse, RevisionsResponse};
+use crate::middleware::ContextExt;
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use domain::Scope;
+use tide::{Request, Response};
+
+pub async fn list_revisions<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims()?.user_id();
+    let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&user), repository)?;
+    let revisions = user.article_revisions(&article, repository)?;
+
+    let response = RevisionsResponse::from(revisions);
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+pub async fn get_revision<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims()?.user_id();
+    let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
+    let revision_id: u64 = cx.param("id").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&user), repository)?;
+    let revision = user.article_revision(&article, revision_id, repository)?;
+
+    let response = RevisionResponse {
+        revision: revision.into(),
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+pub async fn restore_revision<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims_with_scope(Scope::ArticlesWrite)?.user_id();
+    let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
+    let revision_id: u64 = cx.param("id").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&user), repository)?;
+    let restored_article = user.restore_article_revision(article, revision_id, repository)?;
+
+    let response: ArticleResponse = repository.get_article_view(&user, restored_article)?.into();
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
@@ -0,0 +1,59 @@
+use crate::openapi::{string_enum, Components, Schema};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The lifecycle state of an article, as exposed in the API.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Draft,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+impl Schema for Status {
+    const NAME: Option<&'static str> = Some("ArticleStatus");
+
+    fn schema(_: &mut Components) -> Value {
+        string_enum(&["draft", "scheduled", "published", "archived"])
+    }
+}
+
+impl From<&domain::ArticleStatus> for Status {
+    fn from(s: &domain::ArticleStatus) -> Self {
+        match s {
+            domain::ArticleStatus::Draft => Status::Draft,
+            domain::ArticleStatus::Scheduled { .. } => Status::Scheduled,
+            domain::ArticleStatus::Published => Status::Published,
+            domain::ArticleStatus::Archived => Status::Archived,
+        }
+    }
+}
+
+/// Combine the `status` and `publishAt` fields of a request into a domain status.
+///
+/// `publishAt` on its own implies `scheduled`; it is required for scheduled articles
+/// and rejected for every other status.
+pub fn to_article_status(
+    status: Option<Status>,
+    publish_at: Option<DateTime<Utc>>,
+) -> Result<Option<domain::ArticleStatus>, String> {
+    let status = match (status, publish_at) {
+        (None, None) => None,
+        (None, Some(publish_at)) | (Some(Status::Scheduled), Some(publish_at)) => {
+            Some(domain::ArticleStatus::Scheduled { publish_at })
+        }
+        (Some(Status::Scheduled), None) => {
+            return Err("`publishAt` is required to schedule an article.".into())
+        }
+        (Some(_), Some(_)) => {
+            return Err("`publishAt` can only be set for scheduled articles.".into())
+        }
+        (Some(Status::Draft), None) => Some(domain::ArticleStatus::Draft),
+        (Some(Status::Published), None) => Some(domain::ArticleStatus::Published),
+        (Some(Status::Archived), None) => Some(domain::ArticleStatus::Archived),
+    };
+    Ok(status)
+}
@@ -1,6 +1,8 @@
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tide::{Request, Response};
 
 #[derive(Serialize, Deserialize)]
@@ -8,6 +10,14 @@ pub struct TagsResponse {
     pub tags: Vec<String>,
 }
 
+impl Schema for TagsResponse {
+    const NAME: Option<&'static str> = Some("TagsResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<Vec<String>>("tags").build()
+    }
+}
+
 pub async fn tags<R: 'static + Repository + Sync + Send>(
     cx: Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
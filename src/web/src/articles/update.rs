@@ -1,9 +1,15 @@
+use crate::articles::find::ensure_visible;
 use crate::articles::responses::ArticleResponse;
+use crate::articles::status::{to_article_status, Status};
 use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
+use chrono::{DateTime, Utc};
 use domain::repositories::Repository;
-use domain::ArticleUpdate;
+use domain::{ArticleUpdate, Scope};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
 use tide::Response;
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -12,38 +18,71 @@ pub struct Request {
     pub article: UpdateArticleRequest,
 }
 
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("UpdateArticleRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<UpdateArticleRequest>("article")
+            .build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateArticleRequest {
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub status: Option<Status>,
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
-impl From<Request> for ArticleUpdate {
-    fn from(r: Request) -> ArticleUpdate {
-        ArticleUpdate {
+impl Schema for UpdateArticleRequest {
+    const NAME: Option<&'static str> = Some("ArticleUpdate");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .description("Only the fields which are set get updated.")
+            .optional::<Option<String>>("title")
+            .optional::<Option<String>>("description")
+            .optional::<Option<String>>("body")
+            .optional::<Option<Status>>("status")
+            .optional::<Option<DateTime<Utc>>>("publishAt")
+            .build()
+    }
+}
+
+impl TryFrom<Request> for ArticleUpdate {
+    type Error = String;
+
+    fn try_from(r: Request) -> Result<ArticleUpdate, Self::Error> {
+        let update = ArticleUpdate {
             title: r.article.title,
             body: r.article.body,
             description: r.article.description,
-        }
+            status: to_article_status(r.article.status, r.article.publish_at)?,
+        };
+        Ok(update)
     }
 }
 
 pub async fn update_article<R: 'static + Repository + Sync + Send>(
     mut cx: tide::Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims_with_scope(Scope::ArticlesWrite)?.user_id();
     let request: Request = cx
         .body_json()
         .await
         .map_err(|e| Response::new(400).body_string(e.to_string()))?;
-    let slug: String = cx.param("slug").map_err(|_| Response::new(401))?;
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let update = ArticleUpdate::try_from(request).map_err(|e| Response::new(400).body_string(e))?;
+    let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
     let article = repository.get_article_by_slug(&slug)?;
     let user = repository.get_user_by_id(user_id)?;
-    let updated_article = user.update_article(article, request.into(), repository)?;
+    ensure_visible(&article, Some(&user), repository)?;
+    let updated_article = user.update_article(article, update, repository)?;
 
     let response: ArticleResponse = repository.get_article_view(&user, updated_article)?.into();
     Ok(Response::new(200).body_json(&response).unwrap())
@@ -1,3 +1,4 @@
+use domain::{AccessToken, Scope};
 use http::HeaderMap;
 use jsonwebtoken::{decode, encode, Header, Validation};
 use log::debug;
@@ -13,12 +14,38 @@ const SECRET: &str = "secret";
 pub struct Claims {
     sub: Uuid,
     exp: u64,
+    // Tokens issued before this field was introduced are treated as issued at the epoch.
+    #[serde(default)]
+    iat: u64,
+    // Only set on special-purpose tokens, which must not be accepted as authentication tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+    // Only set for callers authenticated with an access token rather than a session
+    #[serde(skip)]
+    scopes: Option<Vec<Scope>>,
 }
 
 impl Claims {
     pub fn user_id(&self) -> Uuid {
         self.sub
     }
+
+    /// When the token was issued, as seconds since the epoch.
+    pub fn issued_at(&self) -> u64 {
+        self.iat
+    }
+
+    /// Sessions can do whatever their user can, access tokens only what their scopes allow.
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match &self.scopes {
+            Some(scopes) => scopes.contains(&scope),
+            None => true,
+        }
+    }
+
+    pub fn is_session(&self) -> bool {
+        self.scopes.is_none()
+    }
 }
 
 fn validation() -> Validation {
@@ -33,7 +60,113 @@ pub fn claims_for(user_id: Uuid, expire_in: u64) -> Claims {
     Claims {
         sub: user_id,
         exp: seconds_from_now(expire_in),
+        iat: seconds_from_now(0),
+        purpose: None,
+        scopes: None,
+    }
+}
+
+/// The claims of a caller authenticated with `token`: they are not encoded in a JWT.
+pub fn claims_for_access_token(token: &AccessToken) -> Claims {
+    Claims {
+        sub: token.user_id,
+        exp: 0,
+        iat: 0,
+        purpose: None,
+        scopes: Some(token.scopes.clone()),
+    }
+}
+
+/// The claims of the tokens proving that a user owns an email address.
+///
+/// They are bound to the address: changing it invalidates the tokens issued for the old one.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EmailVerificationClaims {
+    sub: Uuid,
+    email: String,
+    exp: u64,
+    // Prevents authentication tokens from being used as verification tokens and vice versa
+    purpose: String,
+}
+
+const EMAIL_VERIFICATION_PURPOSE: &str = "email-verification";
+
+impl EmailVerificationClaims {
+    pub fn user_id(&self) -> Uuid {
+        self.sub
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+}
+
+/// Verification tokens are valid for a day.
+pub fn encode_email_verification_token(user_id: Uuid, email: &str, secret: &str) -> String {
+    let claims = EmailVerificationClaims {
+        sub: user_id,
+        email: email.to_owned(),
+        exp: seconds_from_now(24 * 3600),
+        purpose: EMAIL_VERIFICATION_PURPOSE.to_owned(),
+    };
+    encode(&Header::default(), &claims, secret.as_ref()).unwrap()
+}
+
+pub fn decode_email_verification_token(
+    token: &str,
+    secret: &str,
+) -> Option<EmailVerificationClaims> {
+    let decoded = decode::<EmailVerificationClaims>(token, secret.as_ref(), &validation());
+    if let Err(e) = &decoded {
+        debug!("Failed to decode email verification token {}", e);
+    }
+    decoded
+        .map(|token_data| token_data.claims)
+        .ok()
+        .filter(|claims| claims.purpose == EMAIL_VERIFICATION_PURPOSE)
+}
+
+/// The claims of the tokens proving that a user got their password right,
+/// to be exchanged for an authentication token along with their second factor.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TwoFactorChallengeClaims {
+    sub: Uuid,
+    exp: u64,
+    purpose: String,
+}
+
+const TWO_FACTOR_CHALLENGE_PURPOSE: &str = "two-factor-challenge";
+
+/// Users have five minutes to provide their second factor.
+pub const TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS: u64 = 5 * 60;
+
+impl TwoFactorChallengeClaims {
+    pub fn user_id(&self) -> Uuid {
+        self.sub
+    }
+}
+
+pub fn encode_two_factor_challenge_token(user_id: Uuid, secret: &str) -> String {
+    let claims = TwoFactorChallengeClaims {
+        sub: user_id,
+        exp: seconds_from_now(TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS),
+        purpose: TWO_FACTOR_CHALLENGE_PURPOSE.to_owned(),
+    };
+    encode(&Header::default(), &claims, secret.as_ref()).unwrap()
+}
+
+pub fn decode_two_factor_challenge_token(
+    token: &str,
+    secret: &str,
+) -> Option<TwoFactorChallengeClaims> {
+    let decoded = decode::<TwoFactorChallengeClaims>(token, secret.as_ref(), &validation());
+    if let Err(e) = &decoded {
+        debug!("Failed to decode two-factor challenge token {}", e);
     }
+    decoded
+        .map(|token_data| token_data.claims)
+        .ok()
+        .filter(|claims| claims.purpose == TWO_FACTOR_CHALLENGE_PURPOSE)
 }
 
 fn seconds_from_now(secs: u64) -> u64 {
@@ -42,6 +175,9 @@ fn seconds_from_now(secs: u64) -> u64 {
     expiry_time.as_secs()
 }
 
+/// The authentication scheme of access tokens: any other scheme is taken to carry a JWT.
+pub const ACCESS_TOKEN_SCHEME: &str = "Bearer";
+
 pub fn extract_token(headers: &HeaderMap) -> Option<&str> {
     match headers.get("Authorization") {
         Some(h) => match h.to_str() {
@@ -52,13 +188,37 @@ pub fn extract_token(headers: &HeaderMap) -> Option<&str> {
     }
 }
 
+fn is_access_token_scheme(headers: &HeaderMap) -> bool {
+    headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(' ').next())
+        .map(|scheme| scheme.eq_ignore_ascii_case(ACCESS_TOKEN_SCHEME))
+        .unwrap_or(false)
+}
+
+/// The secret of the access token in the `Authorization` header, if any.
+pub fn extract_access_token(headers: &HeaderMap) -> Option<&str> {
+    if is_access_token_scheme(headers) {
+        extract_token(headers)
+    } else {
+        None
+    }
+}
+
 pub fn extract_claims(headers: &HeaderMap) -> Option<Claims> {
+    if is_access_token_scheme(headers) {
+        return None;
+    }
     extract_token(headers).and_then(|token| {
         let decoded = decode::<Claims>(&token, SECRET.as_ref(), &validation());
         if let Err(e) = &decoded {
             debug!("Failed to decode token {}", e);
         }
-        decoded.map(|token_data| token_data.claims).ok()
+        decoded
+            .map(|token_data| token_data.claims)
+            .ok()
+            .filter(|claims| claims.purpose.is_none())
     })
 }
 
@@ -78,4 +238,27 @@ mod tests {
 
         assert!(decoded.is_ok());
     }
+
+    #[test]
+    fn special_purpose_tokens_are_not_authentication_tokens() {
+        let sub = Uuid::new_v4();
+        for token in &[
+            encode_two_factor_challenge_token(sub, SECRET),
+            encode_email_verification_token(sub, "someone@example.com", SECRET),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert("Authorization", format!("Token {}", token).parse().unwrap());
+            assert!(extract_claims(&headers).is_none());
+        }
+        assert!(decode_two_factor_challenge_token(&encode_token(sub), SECRET).is_none());
+    }
+
+    #[test]
+    fn special_purpose_tokens_are_signed_with_the_configured_secret() {
+        let sub = Uuid::new_v4();
+        let token = encode_two_factor_challenge_token(sub, "a-secret");
+        assert!(decode_two_factor_challenge_token(&token, "another-secret").is_none());
+        let claims = decode_two_factor_challenge_token(&token, "a-secret").unwrap();
+        assert_eq!(claims.user_id(), sub);
+    }
 }
@@ -0,0 +1,187 @@
+//! Where requests come from: the address of the client, as used to throttle logins and
+//! to rate limit anonymous callers.
+//!
+//! It is the address of the peer of the connection, unless that peer is one of our trusted
+//! reverse proxies: the client is then the one they report in `X-Forwarded-For`.
+use async_std::net::{TcpListener, ToSocketAddrs};
+use async_std::task;
+use futures::future::{BoxFuture, FutureObj};
+use futures::stream::{self, StreamExt};
+use futures::task::{Spawn, SpawnError};
+use http_service::HttpService;
+use log::{error, info};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tide::{Middleware, Next, Request, Response};
+
+/// The address of the peer of the connection a request came through.
+///
+/// Set on every request by `serve`: Tide's own `listen` does not pass it along.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerAddr(pub SocketAddr);
+
+/// Sets the `PeerAddr` of all the requests handled by `service`.
+pub struct WithPeerAddr<S> {
+    service: Arc<S>,
+    peer: SocketAddr,
+}
+
+impl<S> WithPeerAddr<S> {
+    pub fn new(service: Arc<S>, peer: SocketAddr) -> Self {
+        Self { service, peer }
+    }
+}
+
+impl<S: HttpService> HttpService for WithPeerAddr<S> {
+    type Connection = S::Connection;
+    type ConnectionFuture = S::ConnectionFuture;
+    type ResponseFuture = S::ResponseFuture;
+
+    fn connect(&self) -> Self::ConnectionFuture {
+        self.service.connect()
+    }
+
+    fn respond(
+        &self,
+        conn: &mut Self::Connection,
+        mut req: http_service::Request,
+    ) -> Self::ResponseFuture {
+        req.extensions_mut().insert(PeerAddr(self.peer));
+        self.service.respond(conn, req)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Spawner;
+
+impl Spawn for &Spawner {
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        task::spawn(future);
+        Ok(())
+    }
+}
+
+/// Serve `service` at `address`, like Tide's `listen`, setting the `PeerAddr` of each request.
+///
+/// Each connection gets its own server, which knows who is on the other end.
+pub async fn serve<S: HttpService>(service: S, address: impl ToSocketAddrs) -> io::Result<()> {
+    let listener = TcpListener::bind(address).await?;
+    info!("Server is listening on: http://{}", listener.local_addr()?);
+    let service = Arc::new(service);
+    let mut incoming = listener.incoming();
+    while let Some(connection) = incoming.next().await {
+        let (connection, peer) = match connection.and_then(|c| c.peer_addr().map(|p| (c, p))) {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                error!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let server =
+            http_service_hyper::Server::builder(stream::iter(vec![Ok::<_, io::Error>(connection)]))
+                .with_spawner(Spawner)
+                .serve(WithPeerAddr::new(service.clone(), peer));
+        task::spawn(async move {
+            if let Err(e) = server.await {
+                error!("Failed to serve a connection from {}: {}", peer, e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// The address of the client, stored in the request locals by `ClientAddressMiddleware`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClientIp(pub IpAddr);
+
+/// The address of the client, if known.
+///
+/// It is only unknown for requests that did not go through `serve`, which have no `PeerAddr`:
+/// they are not throttled or rate limited by address, rather than all sharing the same limits.
+pub fn client_ip<S>(cx: &Request<S>) -> Option<IpAddr> {
+    cx.local::<ClientIp>().map(|ip| ip.0)
+}
+
+/// Works out the address of the client of each request, see `client_ip`.
+#[derive(Clone, Debug, Default)]
+pub struct ClientAddressMiddleware {
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl ClientAddressMiddleware {
+    /// `X-Forwarded-For` is only taken into account for requests coming from `trusted_proxies`.
+    pub fn new(trusted_proxies: Vec<IpAddr>) -> Self {
+        Self { trusted_proxies }
+    }
+
+    /// The rightmost address of `X-Forwarded-For` which is not one of our proxies:
+    /// the ones before it are supplied by the client itself.
+    fn resolve(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let mut client = peer;
+        if let Some(forwarded_for) = forwarded_for {
+            for hop in forwarded_for.rsplit(',') {
+                if !self.trusted_proxies.contains(&client) {
+                    break;
+                }
+                match hop.trim().parse() {
+                    Ok(ip) => client = ip,
+                    Err(_) => break,
+                }
+            }
+        }
+        client
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for ClientAddressMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let peer = match cx.local::<PeerAddr>() {
+                Some(peer) => peer.0.ip(),
+                None => return next.run(cx).await,
+            };
+            let forwarded_for = cx
+                .headers()
+                .get("X-Forwarded-For")
+                .and_then(|value| value.to_str().ok());
+            let client = self.resolve(peer, forwarded_for);
+            next.run(cx.set_local(ClientIp(client))).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_addresses_are_ignored_without_trusted_proxies() {
+        let middleware = ClientAddressMiddleware::default();
+        let peer = ip("203.0.113.7");
+        assert_eq!(middleware.resolve(peer, None), peer);
+        assert_eq!(middleware.resolve(peer, Some("198.51.100.1")), peer);
+    }
+
+    #[test]
+    fn trusted_proxies_report_the_client() {
+        let proxy = ip("10.0.0.2");
+        let middleware = ClientAddressMiddleware::new(vec![proxy, ip("10.0.0.3")]);
+
+        assert_eq!(middleware.resolve(proxy, None), proxy);
+        assert_eq!(
+            middleware.resolve(proxy, Some("198.51.100.1")),
+            ip("198.51.100.1")
+        );
+        // Only the entries added by our proxies can be trusted
+        assert_eq!(
+            middleware.resolve(proxy, Some("192.0.2.1, 198.51.100.1, 10.0.0.3")),
+            ip("198.51.100.1")
+        );
+        assert_eq!(middleware.resolve(proxy, Some("nonsense")), proxy);
+    }
+}
@@ -1,9 +1,12 @@
+use crate::articles::find::ensure_visible;
 use crate::comments::responses::CommentResponse;
 use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
-use domain::CommentContent;
+use domain::{CommentContent, Scope};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tide::Response;
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -12,25 +15,44 @@ pub struct Request {
     pub comment: NewCommentRequest,
 }
 
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("NewCommentRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<NewCommentRequest>("comment")
+            .build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct NewCommentRequest {
     pub body: String,
 }
 
+impl Schema for NewCommentRequest {
+    const NAME: Option<&'static str> = Some("NewComment");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<String>("body").build()
+    }
+}
+
 pub async fn create<R: 'static + Repository + Sync + Send>(
     mut cx: tide::Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
+    let author_id = cx.get_claims_with_scope(Scope::CommentsWrite)?.user_id();
     let new_comment: Request = cx
         .body_json()
         .await
         .map_err(|e| Response::new(400).body_string(e.to_string()))?;
-    let author_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
     let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
     let author = repository.get_user_by_id(author_id)?;
     let article = repository.get_article_by_slug(&slug)?;
+    ensure_visible(&article, Some(&author), repository)?;
     let posted_comment = author.comment(
         &article,
         CommentContent(new_comment.comment.body),
@@ -1,12 +1,13 @@
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
+use domain::Scope;
 use tide::Response;
 
 pub async fn delete<R: 'static + Repository + Sync + Send>(
     cx: tide::Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
-    let author_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let author_id = cx.get_claims_with_scope(Scope::CommentsWrite)?.user_id();
     let comment_id: u64 = cx.param("id").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
@@ -1,3 +1,4 @@
+use crate::articles::find::ensure_visible;
 use crate::comments::responses::CommentsResponse;
 use crate::middleware::ContextExt;
 use crate::{Context, ErrorResponse};
@@ -12,12 +13,15 @@ pub async fn get<R: 'static + Repository + Sync + Send>(
     let slug: String = cx.param("slug").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
+    let user = user_id
+        .map(|user_id| repository.get_user_by_id(user_id))
+        .transpose()?;
     let article = repository.get_article_by_slug(&slug)?;
-    let comments = article.comments(repository)?;
+    ensure_visible(&article, user.as_ref(), repository)?;
+    let comments = article.comments(user.as_ref(), repository)?;
 
-    let response: CommentsResponse = match user_id {
-        Some(user_id) => {
-            let user = repository.get_user_by_id(user_id)?;
+    let response: CommentsResponse = match user {
+        Some(user) => {
             let result: Result<Vec<_>, _> = comments
                 .into_iter()
                 .map(|c| c.view(&user, repository))
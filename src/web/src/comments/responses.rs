@@ -1,19 +1,39 @@
 use crate::articles::responses::Author;
+use crate::openapi::{Components, Object, Schema};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CommentsResponse {
     pub comments: Vec<Comment>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+impl Schema for CommentsResponse {
+    const NAME: Option<&'static str> = Some("CommentsResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<Comment>>("comments")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CommentResponse {
     pub comment: Comment,
 }
 
+impl Schema for CommentResponse {
+    const NAME: Option<&'static str> = Some("CommentResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<Comment>("comment").build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Comment {
@@ -24,6 +44,20 @@ pub struct Comment {
     pub author: Author,
 }
 
+impl Schema for Comment {
+    const NAME: Option<&'static str> = Some("Comment");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<u64>("id")
+            .field::<DateTime<Utc>>("createdAt")
+            .field::<DateTime<Utc>>("updatedAt")
+            .field::<String>("body")
+            .field::<Author>("author")
+            .build()
+    }
+}
+
 impl From<domain::Comment> for Comment {
     fn from(c: domain::Comment) -> Self {
         Self {
@@ -0,0 +1,231 @@
+//! Cross-Origin Resource Sharing: which websites may call the API from a browser.
+use futures::future::BoxFuture;
+use http::Method;
+use std::time::Duration;
+use tide::{Middleware, Next, Request, Response};
+
+/// The websites allowed to call the API.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllowedOrigins {
+    Any,
+    /// Origins as sent by browsers, e.g. `https://conduit.example.com`.
+    List(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorsPolicy {
+    pub allowed_origins: AllowedOrigins,
+    pub allowed_methods: Vec<String>,
+    /// `*` allows any header.
+    pub allowed_headers: Vec<String>,
+    /// Response headers browsers let scripts read, besides the basic ones.
+    pub exposed_headers: Vec<String>,
+    /// How long browsers may cache the answer to a preflight request.
+    pub max_age: Duration,
+    /// Whether browsers may send cookies and `Authorization` headers.
+    ///
+    /// It requires a list of origins: allowing credentials from any website would let all of
+    /// them act on behalf of our users.
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_origins: AllowedOrigins::Any,
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|m| (*m).to_owned())
+                .collect(),
+            allowed_headers: vec!["*".into()],
+            exposed_headers: Vec::new(),
+            max_age: Duration::from_secs(86400),
+            allow_credentials: false,
+        }
+    }
+}
+
+impl CorsPolicy {
+    fn allows(&self, origin: &str) -> bool {
+        match &self.allowed_origins {
+            AllowedOrigins::Any => true,
+            AllowedOrigins::List(origins) => origins.iter().any(|o| o == origin),
+        }
+    }
+
+    fn credentials_allowed(&self) -> bool {
+        self.allow_credentials && matches!(self.allowed_origins, AllowedOrigins::List(_))
+    }
+
+    /// The value of `Access-Control-Allow-Origin` for an allowed `origin`.
+    fn allow_origin_header(&self, origin: &str) -> String {
+        match self.allowed_origins {
+            AllowedOrigins::Any => "*".into(),
+            AllowedOrigins::List(_) => origin.into(),
+        }
+    }
+}
+
+/// Applies a `CorsPolicy`, answering preflight requests for every route.
+///
+/// Requests without an `Origin` header (e.g. from other servers or mobile apps) are not subject
+/// to CORS: they go through untouched. Requests from origins which are not allowed go through
+/// too, without CORS headers: browsers will not let the calling website see the response.
+pub struct CorsMiddleware {
+    policy: CorsPolicy,
+}
+
+impl CorsMiddleware {
+    pub fn new(policy: CorsPolicy) -> Self {
+        if policy.allow_credentials && !policy.credentials_allowed() {
+            log::warn!("CORS credentials are ignored: they require a list of allowed origins");
+        }
+        Self { policy }
+    }
+
+    fn preflight_response(&self, origin: &str, requested_headers: Option<&str>) -> Response {
+        let policy = &self.policy;
+        if !policy.allows(origin) {
+            return Response::new(403).body_string("Origin not allowed.".into());
+        }
+        let allowed_headers = match requested_headers {
+            // The wildcard is taken literally for credentialed requests: echo the request instead
+            Some(requested)
+                if policy.credentials_allowed()
+                    && policy.allowed_headers.iter().any(|h| h == "*") =>
+            {
+                requested.to_owned()
+            }
+            _ => policy.allowed_headers.join(", "),
+        };
+        let response = Response::new(204)
+            .set_header(
+                "Access-Control-Allow-Origin",
+                policy.allow_origin_header(origin),
+            )
+            .set_header(
+                "Access-Control-Allow-Methods",
+                policy.allowed_methods.join(", "),
+            )
+            .set_header("Access-Control-Allow-Headers", allowed_headers)
+            .set_header(
+                "Access-Control-Max-Age",
+                policy.max_age.as_secs().to_string(),
+            )
+            .set_header(
+                "Vary",
+                "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+            );
+        if policy.credentials_allowed() {
+            response.set_header("Access-Control-Allow-Credentials", "true")
+        } else {
+            response
+        }
+    }
+
+    fn add_headers(&self, origin: &str, mut response: Response) -> Response {
+        let policy = &self.policy;
+        if !policy.allows(origin) {
+            return response;
+        }
+        response = response.set_header(
+            "Access-Control-Allow-Origin",
+            policy.allow_origin_header(origin),
+        );
+        if let AllowedOrigins::List(_) = policy.allowed_origins {
+            response = response.append_header("Vary", "Origin");
+        }
+        if policy.credentials_allowed() {
+            response = response.set_header("Access-Control-Allow-Credentials", "true");
+        }
+        if !policy.exposed_headers.is_empty() {
+            response = response.set_header(
+                "Access-Control-Expose-Headers",
+                policy.exposed_headers.join(", "),
+            );
+        }
+        response
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for CorsMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let origin = match cx.header("Origin") {
+                Some(origin) => origin.to_owned(),
+                None => return next.run(cx).await,
+            };
+            let is_preflight = cx.method() == Method::OPTIONS
+                && cx.header("Access-Control-Request-Method").is_some();
+            if is_preflight {
+                let requested_headers = cx.header("Access-Control-Request-Headers");
+                return self.preflight_response(&origin, requested_headers);
+            }
+            let response = next.run(cx).await;
+            self.add_headers(&origin, response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors_headers(response: Response) -> (u16, Vec<(String, String)>) {
+        let response: http_service::Response = response.into();
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| name.as_str().starts_with("access-control-"))
+            .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_owned()))
+            .collect();
+        (response.status().as_u16(), headers)
+    }
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn credentials_require_a_list_of_origins() {
+        let any = CorsMiddleware::new(CorsPolicy {
+            allow_credentials: true,
+            ..CorsPolicy::default()
+        });
+        let (status, headers) =
+            cors_headers(any.preflight_response("https://a.example.com", Some("authorization")));
+        assert_eq!(status, 204);
+        assert_eq!(header(&headers, "access-control-allow-origin"), Some("*"));
+        assert_eq!(header(&headers, "access-control-allow-headers"), Some("*"));
+        assert_eq!(header(&headers, "access-control-allow-credentials"), None);
+
+        let listed = CorsMiddleware::new(CorsPolicy {
+            allowed_origins: AllowedOrigins::List(vec!["https://a.example.com".into()]),
+            allow_credentials: true,
+            ..CorsPolicy::default()
+        });
+        let (_, headers) =
+            cors_headers(listed.preflight_response("https://a.example.com", Some("authorization")));
+        assert_eq!(
+            header(&headers, "access-control-allow-origin"),
+            Some("https://a.example.com")
+        );
+        assert_eq!(
+            header(&headers, "access-control-allow-headers"),
+            Some("authorization")
+        );
+        assert_eq!(
+            header(&headers, "access-control-allow-credentials"),
+            Some("true")
+        );
+
+        let (status, _) = cors_headers(listed.preflight_response("https://b.example.com", None));
+        assert_eq!(status, 403);
+        let (_, headers) =
+            cors_headers(listed.add_headers("https://b.example.com", Response::new(200)));
+        assert!(headers.is_empty());
+    }
+}
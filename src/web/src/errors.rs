@@ -1,24 +1,34 @@
 //! A sub-module to prescribe how each domain error gets converted to an HTTP response.
 use crate::ErrorResponse;
 use domain::{
-    ChangeArticleError, DatabaseError, DeleteCommentError, GetArticleError, GetUserError,
-    LoginError, PasswordError, PublishArticleError, SignUpError,
+    AccessTokenError, BlockError, ChangeArticleError, DatabaseError, DeleteAccountError,
+    DeleteCommentError, ExternalLoginError, FollowError, GetArticleError, GetUserError, LoginError,
+    NotificationError, PasswordError, PasswordResetError, PublishArticleError, SignUpError,
+    TwoFactorError, VerifyEmailError,
 };
+use log::error;
+use std::fmt::Debug;
 use tide::Response;
 
+/// Internal errors are logged, with their causes, but not disclosed to callers.
+fn internal_error(e: &impl Debug) -> Response {
+    error!("Internal error: {:?}", e);
+    Response::new(500)
+}
+
 impl From<GetUserError> for ErrorResponse {
     fn from(e: GetUserError) -> ErrorResponse {
         let r = match &e {
             GetUserError::NotFound { .. } => Response::new(404).body_string(e.to_string()),
-            GetUserError::DatabaseError(_) => Response::new(500),
+            GetUserError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
 }
 
 impl From<PasswordError> for ErrorResponse {
-    fn from(_: PasswordError) -> ErrorResponse {
-        ErrorResponse(Response::new(500))
+    fn from(e: PasswordError) -> ErrorResponse {
+        ErrorResponse(internal_error(&e))
     }
 }
 
@@ -26,8 +36,108 @@ impl From<LoginError> for ErrorResponse {
     fn from(e: LoginError) -> ErrorResponse {
         let r = match &e {
             LoginError::NotFound => Response::new(401),
-            LoginError::PasswordError(_) => Response::new(500),
-            LoginError::DatabaseError(_) => Response::new(500),
+            LoginError::PasswordError(_) => internal_error(&e),
+            LoginError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<FollowError> for ErrorResponse {
+    fn from(e: FollowError) -> ErrorResponse {
+        let r = match &e {
+            FollowError::SelfFollow => Response::new(422).body_string(e.to_string()),
+            FollowError::Blocked { .. } => Response::new(403).body_string(e.to_string()),
+            FollowError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<BlockError> for ErrorResponse {
+    fn from(e: BlockError) -> ErrorResponse {
+        let r = match &e {
+            BlockError::SelfTarget => Response::new(422).body_string(e.to_string()),
+            BlockError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<DeleteAccountError> for ErrorResponse {
+    fn from(e: DeleteAccountError) -> ErrorResponse {
+        let r = match &e {
+            DeleteAccountError::WrongPassword => Response::new(403).body_string(e.to_string()),
+            DeleteAccountError::PasswordError(_) => internal_error(&e),
+            DeleteAccountError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<PasswordResetError> for ErrorResponse {
+    fn from(e: PasswordResetError) -> ErrorResponse {
+        let r = match &e {
+            PasswordResetError::InvalidToken => Response::new(400).body_string(e.to_string()),
+            PasswordResetError::TokenGeneration(_) => internal_error(&e),
+            PasswordResetError::MailerError(_) => internal_error(&e),
+            PasswordResetError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<VerifyEmailError> for ErrorResponse {
+    fn from(e: VerifyEmailError) -> ErrorResponse {
+        let r = match &e {
+            VerifyEmailError::InvalidToken => Response::new(400).body_string(e.to_string()),
+            VerifyEmailError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<TwoFactorError> for ErrorResponse {
+    fn from(e: TwoFactorError) -> ErrorResponse {
+        let r = match &e {
+            TwoFactorError::AlreadyEnabled
+            | TwoFactorError::NotEnabled
+            | TwoFactorError::NotEnrolled => Response::new(409).body_string(e.to_string()),
+            TwoFactorError::InvalidCode => Response::new(422).body_string(e.to_string()),
+            TwoFactorError::SecretGeneration(_) => internal_error(&e),
+            TwoFactorError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<AccessTokenError> for ErrorResponse {
+    fn from(e: AccessTokenError) -> ErrorResponse {
+        let r = match &e {
+            AccessTokenError::NotFound { .. } => Response::new(404).body_string(e.to_string()),
+            AccessTokenError::SecretGeneration(_) => internal_error(&e),
+            AccessTokenError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<ExternalLoginError> for ErrorResponse {
+    fn from(e: ExternalLoginError) -> ErrorResponse {
+        let r = match &e {
+            ExternalLoginError::UnknownProvider { .. } => {
+                Response::new(404).body_string(e.to_string())
+            }
+            ExternalLoginError::InvalidState => Response::new(400).body_string(e.to_string()),
+            ExternalLoginError::MissingEmail => Response::new(422).body_string(e.to_string()),
+            ExternalLoginError::EmailTaken { .. } => Response::new(409).body_string(e.to_string()),
+            ExternalLoginError::Provider(source) => {
+                log::warn!("Login with an identity provider failed: {:#}", source);
+                Response::new(401).body_string(e.to_string())
+            }
+            ExternalLoginError::RandomGeneration(_) => internal_error(&e),
+            ExternalLoginError::PasswordError(_) => internal_error(&e),
+            ExternalLoginError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
@@ -36,7 +146,7 @@ impl From<LoginError> for ErrorResponse {
 impl From<SignUpError> for ErrorResponse {
     fn from(e: SignUpError) -> ErrorResponse {
         let r = match &e {
-            SignUpError::DatabaseError(_) => Response::new(500),
+            SignUpError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
@@ -45,18 +155,18 @@ impl From<SignUpError> for ErrorResponse {
 impl From<GetArticleError> for ErrorResponse {
     fn from(e: GetArticleError) -> ErrorResponse {
         let r = match &e {
-            GetArticleError::ArticleNotFound { .. } => {
+            GetArticleError::ArticleNotFound { .. } | GetArticleError::Hidden { .. } => {
                 Response::new(404).body_string(e.to_string())
             }
-            GetArticleError::DatabaseError(_) => Response::new(500),
+            GetArticleError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
 }
 
 impl From<DatabaseError> for ErrorResponse {
-    fn from(_: DatabaseError) -> ErrorResponse {
-        ErrorResponse(Response::new(500))
+    fn from(e: DatabaseError) -> ErrorResponse {
+        ErrorResponse(internal_error(&e))
     }
 }
 
@@ -69,7 +179,10 @@ impl From<PublishArticleError> for ErrorResponse {
             PublishArticleError::DuplicatedSlug { .. } => {
                 Response::new(400).body_string(e.to_string())
             }
-            PublishArticleError::DatabaseError(_) => Response::new(500),
+            PublishArticleError::EmailNotVerified { .. } => {
+                Response::new(403).body_string(e.to_string())
+            }
+            PublishArticleError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
@@ -81,8 +194,15 @@ impl From<ChangeArticleError> for ErrorResponse {
             ChangeArticleError::ArticleNotFound { .. } => {
                 Response::new(404).body_string(e.to_string())
             }
-            ChangeArticleError::Forbidden { .. } => Response::new(401).body_string(e.to_string()),
-            ChangeArticleError::DatabaseError(_) => Response::new(500),
+            ChangeArticleError::Forbidden { .. } => Response::new(403).body_string(e.to_string()),
+            ChangeArticleError::RevisionNotFound { .. } => {
+                Response::new(404).body_string(e.to_string())
+            }
+            ChangeArticleError::Blocked { .. } => Response::new(403).body_string(e.to_string()),
+            ChangeArticleError::EmailNotVerified { .. } => {
+                Response::new(403).body_string(e.to_string())
+            }
+            ChangeArticleError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
@@ -94,8 +214,18 @@ impl From<DeleteCommentError> for ErrorResponse {
             DeleteCommentError::CommentNotFound { .. } => {
                 Response::new(404).body_string(e.to_string())
             }
-            DeleteCommentError::Forbidden { .. } => Response::new(401).body_string(e.to_string()),
-            DeleteCommentError::DatabaseError(_) => Response::new(500),
+            DeleteCommentError::Forbidden { .. } => Response::new(403).body_string(e.to_string()),
+            DeleteCommentError::DatabaseError(_) => internal_error(&e),
+        };
+        ErrorResponse(r)
+    }
+}
+
+impl From<NotificationError> for ErrorResponse {
+    fn from(e: NotificationError) -> ErrorResponse {
+        let r = match &e {
+            NotificationError::NotFound { .. } => Response::new(404).body_string(e.to_string()),
+            NotificationError::DatabaseError(_) => internal_error(&e),
         };
         ErrorResponse(r)
     }
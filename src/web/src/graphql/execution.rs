@@ -0,0 +1,820 @@
+//! Executes GraphQL operations against the repository.
+//!
+//! Fields are resolved breadth-first: a field is resolved for all the objects of a list at
+//! once, so that nested fields (e.g. the comments of every article of a list) are loaded with
+//! one repository call rather than one per object.
+use crate::articles::find::ensure_visible;
+use crate::articles::pagination::MAX_LIMIT;
+use crate::articles::status::Status;
+use crate::auth::Claims;
+use crate::graphql::parser::{Document, Field, Input, Operation, OperationKind, Selection};
+use crate::rate_limit::{CallerBuckets, RouteGroup};
+use crate::ErrorResponse;
+use domain::repositories::Repository;
+use domain::{
+    Article, ArticleContent, ArticleQuery, ArticleStatus, ArticleView, CommentContent, CommentView,
+    FeedQuery, Profile, ProfileView, Scope, User,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// As for the REST API, 20 articles are returned by default.
+const DEFAULT_LIMIT: u64 = 20;
+
+/// The number of articles to return, capped as for the REST API.
+fn limit(arguments: &HashMap<String, Value>) -> Result<u64, FieldError> {
+    Ok(optional_u64(arguments, "limit")?
+        .unwrap_or(DEFAULT_LIMIT)
+        .min(MAX_LIMIT))
+}
+
+/// An error raised while resolving a field: the field is set to `null`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub message: String,
+    /// The status code the REST API would have answered with.
+    pub status: u16,
+}
+
+impl FieldError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status,
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(400, message)
+    }
+
+    fn to_json(&self, path: &[String]) -> Value {
+        json!({
+            "message": self.message,
+            "path": path,
+            "extensions": { "status": self.status },
+        })
+    }
+}
+
+/// Domain errors are reported with the status and message of their REST counterpart.
+/// Internal errors are logged, as usual, but not disclosed.
+fn domain_error<E: Display>(e: E) -> FieldError
+where
+    ErrorResponse: From<E>,
+{
+    let message = e.to_string();
+    let status = ErrorResponse::from(e).0.status().as_u16();
+    if status >= 500 {
+        FieldError::new(status, "Internal server error.")
+    } else {
+        FieldError::new(status, message)
+    }
+}
+
+/// Anonymous callers get views owned by the nil UUID, following and favoriting nothing.
+fn anonymous_profile(profile: Profile) -> ProfileView {
+    ProfileView {
+        profile,
+        following: false,
+        viewer: Uuid::nil(),
+    }
+}
+
+fn anonymous_article(article: Article) -> ArticleView {
+    ArticleView {
+        content: article.content,
+        slug: article.slug,
+        author: anonymous_profile(article.author),
+        metadata: article.metadata,
+        favorited: false,
+        favorites_count: article.favorites_count,
+        status: article.status,
+        viewer: Uuid::nil(),
+    }
+}
+
+/// The objects of the same type a selection set applies to.
+enum Objects {
+    Articles(Vec<ArticleView>),
+    Profiles(Vec<ProfileView>),
+    Comments(Vec<CommentView>),
+}
+
+impl Objects {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Objects::Articles(_) => "Article",
+            Objects::Profiles(_) => "Profile",
+            Objects::Comments(_) => "Comment",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Objects::Articles(a) => a.len(),
+            Objects::Profiles(p) => p.len(),
+            Objects::Comments(c) => c.len(),
+        }
+    }
+}
+
+/// What a root field resolves to.
+enum Resolved {
+    One(Objects),
+    List(Objects),
+}
+
+/// The fields of a selection set, grouped by response key, in order.
+type CollectedFields<'d> = Vec<(String, Vec<&'d Field>)>;
+
+pub struct Executor<'a, R: Repository> {
+    repository: &'a R,
+    claims: Option<&'a Claims>,
+    viewer: Option<User>,
+    document: &'a Document,
+    buckets: Option<CallerBuckets>,
+    variables: Map<String, Value>,
+    errors: Vec<Value>,
+}
+
+impl<'a, R: Repository> Executor<'a, R> {
+    /// `viewer` is the user `claims` belong to, if any.
+    pub fn new(
+        repository: &'a R,
+        claims: Option<&'a Claims>,
+        viewer: Option<User>,
+        document: &'a Document,
+    ) -> Self {
+        Self {
+            repository,
+            claims,
+            viewer,
+            document,
+            buckets: None,
+            variables: Map::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Charges each mutation against the rate limit of its REST counterpart.
+    pub fn rate_limited(mut self, buckets: Option<CallerBuckets>) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Takes a token from the bucket of `group`, failing the field if it is empty.
+    fn charge(&self, group: RouteGroup) -> Result<(), FieldError> {
+        match &self.buckets {
+            Some(buckets) if !buckets.take(group).allowed => {
+                Err(FieldError::new(429, "Too many requests, try again later."))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Executes `operation`, returning its `data` and `errors`.
+    ///
+    /// Mutations are executed one after the other, in the order they were requested.
+    pub fn execute(
+        mut self,
+        operation: &'a Operation,
+        mut variables: Map<String, Value>,
+    ) -> (Value, Vec<Value>) {
+        for (name, default) in &operation.variables {
+            if !variables.contains_key(name) {
+                // Defaults are constants: they cannot refer to other variables
+                let value = default
+                    .as_ref()
+                    .and_then(|d| self.input(d).ok())
+                    .unwrap_or(Value::Null);
+                variables.insert(name.to_owned(), value);
+            }
+        }
+        self.variables = variables;
+
+        let root_type = match operation.kind {
+            OperationKind::Query => "Query",
+            OperationKind::Mutation => "Mutation",
+        };
+        let mut data = Map::new();
+        for (key, fields) in self.collect_fields(root_type, &operation.selection_set, &[]) {
+            let path = vec![key.clone()];
+            let field = fields[0];
+            let selection_set = merged_selection_sets(&fields);
+            let value = match self.resolve_root(operation.kind, field) {
+                Ok(None) => Value::String(root_type.into()),
+                Ok(Some(resolved)) => self.complete_resolved(resolved, &selection_set, &path),
+                Err(e) => {
+                    self.errors.push(e.to_json(&path));
+                    Value::Null
+                }
+            };
+            data.insert(key, value);
+        }
+        (Value::Object(data), self.errors)
+    }
+
+    /// The value of an argument, with variables substituted.
+    fn input(&self, input: &Input) -> Result<Value, FieldError> {
+        let value = match input {
+            Input::Variable(name) => self.variables.get(name).cloned().ok_or_else(|| {
+                FieldError::bad_request(format!("The variable `${}` is not defined.", name))
+            })?,
+            Input::Int(i) => (*i).into(),
+            Input::Float(x) => (*x).into(),
+            Input::String(s) | Input::Enum(s) => s.as_str().into(),
+            Input::Boolean(b) => (*b).into(),
+            Input::Null => Value::Null,
+            Input::List(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|i| self.input(i))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Input::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, i)| Ok((name.to_owned(), self.input(i)?)))
+                    .collect::<Result<_, FieldError>>()?,
+            ),
+        };
+        Ok(value)
+    }
+
+    /// The arguments of `field`, which only accepts `allowed` ones.
+    fn arguments(
+        &self,
+        field: &Field,
+        allowed: &[&str],
+    ) -> Result<HashMap<String, Value>, FieldError> {
+        let mut arguments = HashMap::new();
+        for (name, input) in &field.arguments {
+            if !allowed.contains(&name.as_str()) {
+                return Err(FieldError::bad_request(format!(
+                    "Unknown argument `{}` on field `{}`.",
+                    name, field.name
+                )));
+            }
+            let value = self.input(input)?;
+            if !value.is_null() {
+                arguments.insert(name.to_owned(), value);
+            }
+        }
+        Ok(arguments)
+    }
+
+    /// Whether `@skip` and `@include` let a selection through.
+    fn is_included(&self, directives: &[crate::graphql::parser::Directive]) -> bool {
+        directives.iter().all(|directive| {
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .and_then(|(_, input)| self.input(input).ok())
+                .and_then(|value| value.as_bool());
+            match (directive.name.as_str(), condition) {
+                ("skip", Some(condition)) => !condition,
+                ("include", Some(condition)) => condition,
+                _ => true,
+            }
+        })
+    }
+
+    /// Flattens the fragments of a selection set applying to `type_name`.
+    fn collect_fields<'d>(
+        &mut self,
+        type_name: &str,
+        selection_set: &'d [Selection],
+        path: &[String],
+    ) -> CollectedFields<'d>
+    where
+        'a: 'd,
+    {
+        let mut fields = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_fields_into(type_name, selection_set, path, &mut visited, &mut fields);
+        fields
+    }
+
+    fn collect_fields_into<'d>(
+        &mut self,
+        type_name: &str,
+        selection_set: &'d [Selection],
+        path: &[String],
+        visited: &mut HashSet<&'d str>,
+        fields: &mut CollectedFields<'d>,
+    ) where
+        'a: 'd,
+    {
+        for selection in selection_set {
+            match selection {
+                Selection::Field(field) => {
+                    if !self.is_included(&field.directives) {
+                        continue;
+                    }
+                    let key = field.response_key();
+                    match fields.iter_mut().find(|(k, _)| k == key) {
+                        Some((_, same_key)) => same_key.push(field),
+                        None => fields.push((key.to_owned(), vec![field])),
+                    }
+                }
+                Selection::FragmentSpread { name, directives } => {
+                    if !self.is_included(directives) || !visited.insert(name) {
+                        continue;
+                    }
+                    let fragment = match self.document.fragments.get(name) {
+                        Some(fragment) => fragment,
+                        None => {
+                            let e =
+                                FieldError::bad_request(format!("Unknown fragment `{}`.", name));
+                            self.errors.push(e.to_json(path));
+                            continue;
+                        }
+                    };
+                    if fragment.type_condition == type_name {
+                        let selection_set = &fragment.selection_set;
+                        self.collect_fields_into(type_name, selection_set, path, visited, fields);
+                    }
+                }
+                Selection::InlineFragment {
+                    type_condition,
+                    directives,
+                    selection_set,
+                } => {
+                    let applies = type_condition.iter().all(|t| t == type_name);
+                    if applies && self.is_included(directives) {
+                        self.collect_fields_into(type_name, selection_set, path, visited, fields);
+                    }
+                }
+            }
+        }
+    }
+
+    fn user_with_scope(&self, scope: Scope) -> Result<&User, FieldError> {
+        let (claims, user) = match (self.claims, &self.viewer) {
+            (Some(claims), Some(user)) => (claims, user),
+            _ => return Err(FieldError::new(401, "Authentication required.")),
+        };
+        if !claims.has_scope(scope) {
+            return Err(FieldError::new(
+                403,
+                format!("The access token lacks the `{}` scope.", scope.as_str()),
+            ));
+        }
+        Ok(user)
+    }
+
+    fn visible_article(&self, slug: &str) -> Result<Article, FieldError> {
+        let article = self
+            .repository
+            .get_article_by_slug(slug)
+            .map_err(domain_error)?;
+        ensure_visible(&article, self.viewer.as_ref(), self.repository).map_err(domain_error)?;
+        Ok(article)
+    }
+
+    fn article_views(&self, articles: Vec<Article>) -> Result<Vec<ArticleView>, FieldError> {
+        match &self.viewer {
+            Some(viewer) => self
+                .repository
+                .get_articles_views(viewer, articles)
+                .map_err(domain_error),
+            None => Ok(articles.into_iter().map(anonymous_article).collect()),
+        }
+    }
+
+    /// Resolves a field of `Query` or `Mutation`: `None` stands for `__typename`.
+    fn resolve_root(
+        &mut self,
+        kind: OperationKind,
+        field: &Field,
+    ) -> Result<Option<Resolved>, FieldError> {
+        if field.name == "__typename" {
+            self.arguments(field, &[])?;
+            return Ok(None);
+        }
+        let resolved = match (kind, field.name.as_str()) {
+            (OperationKind::Query, "article") => {
+                let arguments = self.arguments(field, &["slug"])?;
+                let article = self.visible_article(&required_string(&arguments, "slug")?)?;
+                Resolved::One(Objects::Articles(self.article_views(vec![article])?))
+            }
+            (OperationKind::Query, "articles") => {
+                let arguments =
+                    self.arguments(field, &["tag", "author", "favorited", "limit", "offset"])?;
+                let query = ArticleQuery {
+                    tag: optional_string(&arguments, "tag")?,
+                    author: optional_string(&arguments, "author")?,
+                    favorited: optional_string(&arguments, "favorited")?,
+                    limit: Some(limit(&arguments)?),
+                    offset: optional_u64(&arguments, "offset")?,
+                    ..ArticleQuery::default()
+                };
+                let articles = self
+                    .repository
+                    .find_articles(query, self.viewer.as_ref())
+                    .map_err(domain_error)?;
+                Resolved::List(Objects::Articles(self.article_views(articles)?))
+            }
+            (OperationKind::Query, "feed") => {
+                let arguments = self.arguments(field, &["limit", "offset"])?;
+                let query = FeedQuery {
+                    limit: limit(&arguments)?,
+                    offset: optional_u64(&arguments, "offset")?.unwrap_or(0),
+                    cursor: None,
+                };
+                let user = self
+                    .viewer
+                    .as_ref()
+                    .ok_or_else(|| FieldError::new(401, "Authentication required."))?;
+                let articles = user.feed(query, self.repository).map_err(domain_error)?;
+                Resolved::List(Objects::Articles(articles))
+            }
+            (OperationKind::Query, "profile") => {
+                let arguments = self.arguments(field, &["username"])?;
+                let username = required_string(&arguments, "username")?;
+                let view = match &self.viewer {
+                    Some(viewer) => self.repository.get_profile_view(viewer, &username),
+                    None => self
+                        .repository
+                        .get_profile(&username)
+                        .map(anonymous_profile),
+                }
+                .map_err(domain_error)?;
+                Resolved::One(Objects::Profiles(vec![view]))
+            }
+            (OperationKind::Mutation, "publishArticle") => {
+                self.charge(RouteGroup::Articles)?;
+                let arguments = self.arguments(field, &["article"])?;
+                let content = article_content(arguments.get("article"))?;
+                let author = self.user_with_scope(Scope::ArticlesWrite)?;
+                let article = author
+                    .write(content, ArticleStatus::Published, self.repository)
+                    .map_err(domain_error)?;
+                Resolved::One(Objects::Articles(self.article_views(vec![article])?))
+            }
+            (OperationKind::Mutation, "comment") => {
+                self.charge(RouteGroup::Comments)?;
+                let arguments = self.arguments(field, &["slug", "body"])?;
+                let slug = required_string(&arguments, "slug")?;
+                let body = required_string(&arguments, "body")?;
+                let author = self.user_with_scope(Scope::CommentsWrite)?;
+                let article = self.visible_article(&slug)?;
+                let comment = author
+                    .comment(&article, CommentContent(body), self.repository)
+                    .map_err(domain_error)?;
+                Resolved::One(Objects::Comments(vec![comment]))
+            }
+            (OperationKind::Mutation, name @ "favorite")
+            | (OperationKind::Mutation, name @ "unfavorite") => {
+                self.charge(RouteGroup::Favorites)?;
+                let arguments = self.arguments(field, &["slug"])?;
+                let slug = required_string(&arguments, "slug")?;
+                let user = self.user_with_scope(Scope::FavoritesWrite)?;
+                let article = self.visible_article(&slug)?;
+                let view = if name == "favorite" {
+                    user.favorite(article, self.repository)
+                } else {
+                    user.unfavorite(article, self.repository)
+                }
+                .map_err(domain_error)?;
+                Resolved::One(Objects::Articles(vec![view]))
+            }
+            (OperationKind::Mutation, "follow") => {
+                self.charge(RouteGroup::Default)?;
+                let arguments = self.arguments(field, &["username"])?;
+                let username = required_string(&arguments, "username")?;
+                let user = self.user_with_scope(Scope::ProfilesWrite)?;
+                let profile = self
+                    .repository
+                    .get_profile(&username)
+                    .map_err(domain_error)?;
+                let view = user
+                    .follow(profile, self.repository)
+                    .map_err(domain_error)?;
+                Resolved::One(Objects::Profiles(vec![view]))
+            }
+            (OperationKind::Mutation, "unfollow") => {
+                self.charge(RouteGroup::Default)?;
+                let arguments = self.arguments(field, &["username"])?;
+                let username = required_string(&arguments, "username")?;
+                let user = self.user_with_scope(Scope::ProfilesWrite)?;
+                let profile = self
+                    .repository
+                    .get_profile(&username)
+                    .map_err(domain_error)?;
+                let view = user
+                    .unfollow(profile, self.repository)
+                    .map_err(domain_error)?;
+                Resolved::One(Objects::Profiles(vec![view]))
+            }
+            (kind, name) => {
+                let type_name = match kind {
+                    OperationKind::Query => "Query",
+                    OperationKind::Mutation => "Mutation",
+                };
+                return Err(unknown_field(type_name, name));
+            }
+        };
+        Ok(Some(resolved))
+    }
+
+    fn complete_resolved(
+        &mut self,
+        resolved: Resolved,
+        selection_set: &[Selection],
+        path: &[String],
+    ) -> Value {
+        match resolved {
+            Resolved::One(objects) => self
+                .complete(objects, selection_set, path)
+                .into_iter()
+                .next()
+                .unwrap_or(Value::Null),
+            Resolved::List(objects) => Value::Array(self.complete(objects, selection_set, path)),
+        }
+    }
+
+    /// Resolves `selection_set` for each of `objects`.
+    fn complete(
+        &mut self,
+        objects: Objects,
+        selection_set: &[Selection],
+        path: &[String],
+    ) -> Vec<Value> {
+        let count = objects.len();
+        let mut rows = vec![Map::new(); count];
+        if selection_set.is_empty() {
+            let e = FieldError::bad_request(format!(
+                "A selection of subfields is required for `{}`.",
+                objects.type_name()
+            ));
+            self.errors.push(e.to_json(path));
+            return vec![Value::Null; count];
+        }
+        for (key, fields) in self.collect_fields(objects.type_name(), selection_set, path) {
+            let mut field_path = path.to_vec();
+            field_path.push(key.clone());
+            let column = match self.resolve_field(&objects, &fields, &field_path) {
+                Ok(column) => column,
+                Err(e) => {
+                    self.errors.push(e.to_json(&field_path));
+                    vec![Value::Null; count]
+                }
+            };
+            for (row, value) in rows.iter_mut().zip(column) {
+                row.insert(key.clone(), value);
+            }
+        }
+        rows.into_iter().map(Value::Object).collect()
+    }
+
+    /// Resolves a field for all `objects` at once.
+    fn resolve_field(
+        &mut self,
+        objects: &Objects,
+        fields: &[&Field],
+        path: &[String],
+    ) -> Result<Vec<Value>, FieldError> {
+        let field = fields[0];
+        self.arguments(field, &[])?;
+        let name = field.name.as_str();
+        let column: Vec<Value> = match objects {
+            Objects::Articles(articles) => match name {
+                "author" => {
+                    let authors = articles.iter().map(|a| a.author.clone()).collect();
+                    let selection_set = merged_selection_sets(fields);
+                    return Ok(self.complete(Objects::Profiles(authors), &selection_set, path));
+                }
+                "comments" => {
+                    let selection_set = merged_selection_sets(fields);
+                    return self.complete_comments(articles, &selection_set, path);
+                }
+                _ => articles
+                    .iter()
+                    .map(|a| article_scalar(a, name))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| unknown_field("Article", name))?,
+            },
+            Objects::Profiles(profiles) => profiles
+                .iter()
+                .map(|p| profile_scalar(p, name))
+                .collect::<Option<_>>()
+                .ok_or_else(|| unknown_field("Profile", name))?,
+            Objects::Comments(comments) => match name {
+                "author" => {
+                    let authors = comments.iter().map(|c| c.author.clone()).collect();
+                    let selection_set = merged_selection_sets(fields);
+                    return Ok(self.complete(Objects::Profiles(authors), &selection_set, path));
+                }
+                _ => comments
+                    .iter()
+                    .map(|c| comment_scalar(c, name))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| unknown_field("Comment", name))?,
+            },
+        };
+        if fields.iter().any(|f| !f.selection_set.is_empty()) {
+            return Err(FieldError::bad_request(format!(
+                "`{}` is a scalar: it cannot have a selection of subfields.",
+                name
+            )));
+        }
+        Ok(column)
+    }
+
+    /// The comments of all `articles`, loaded with two repository calls: one for the comments,
+    /// one for the views of their authors.
+    fn complete_comments(
+        &mut self,
+        articles: &[ArticleView],
+        selection_set: &[Selection],
+        path: &[String],
+    ) -> Result<Vec<Value>, FieldError> {
+        let slugs: Vec<String> = articles.iter().map(|a| a.slug.to_owned()).collect();
+        let mut comments_by_slug = self
+            .repository
+            .get_articles_comments(&slugs, self.viewer.as_ref())
+            .map_err(domain_error)?;
+
+        let mut authors: Vec<Profile> = Vec::new();
+        for comment in comments_by_slug.values().flatten() {
+            if !authors
+                .iter()
+                .any(|a| a.username == comment.author.username)
+            {
+                authors.push(comment.author.clone());
+            }
+        }
+        let author_views: HashMap<String, ProfileView> = match &self.viewer {
+            Some(viewer) => self
+                .repository
+                .get_profiles_views(viewer, authors)
+                .map_err(domain_error)?,
+            None => authors.into_iter().map(anonymous_profile).collect(),
+        }
+        .into_iter()
+        .map(|view| (view.profile.username.to_owned(), view))
+        .collect();
+
+        let mut counts = Vec::with_capacity(slugs.len());
+        let mut views = Vec::new();
+        for slug in &slugs {
+            let comments = comments_by_slug.remove(slug).unwrap_or_default();
+            counts.push(comments.len());
+            for comment in comments {
+                let author = author_views[&comment.author.username].clone();
+                views.push(CommentView {
+                    id: comment.id,
+                    author,
+                    body: comment.body,
+                    created_at: comment.created_at,
+                    updated_at: comment.updated_at,
+                });
+            }
+        }
+
+        let mut completed = self
+            .complete(Objects::Comments(views), selection_set, path)
+            .into_iter();
+        let column = counts
+            .into_iter()
+            .map(|count| Value::Array(completed.by_ref().take(count).collect()))
+            .collect();
+        Ok(column)
+    }
+}
+
+fn unknown_field(type_name: &str, name: &str) -> FieldError {
+    FieldError::bad_request(format!(
+        "Cannot query field `{}` on type `{}`.",
+        name, type_name
+    ))
+}
+
+fn merged_selection_sets(fields: &[&Field]) -> Vec<Selection> {
+    fields
+        .iter()
+        .flat_map(|f| f.selection_set.iter().cloned())
+        .collect()
+}
+
+fn article_scalar(article: &ArticleView, name: &str) -> Option<Value> {
+    let value = match name {
+        "__typename" => "Article".into(),
+        "slug" => article.slug.as_str().into(),
+        "title" => article.content.title.as_str().into(),
+        "description" => article.content.description.as_str().into(),
+        "body" => article.content.body.as_str().into(),
+        "tagList" => json!(article.content.tag_list),
+        "createdAt" => json!(article.metadata.created_at),
+        "updatedAt" => json!(article.metadata.updated_at),
+        "status" => json!(Status::from(&article.status)),
+        "publishAt" => match &article.status {
+            ArticleStatus::Scheduled { publish_at } => json!(publish_at),
+            _ => Value::Null,
+        },
+        "favorited" => article.favorited.into(),
+        "favoritesCount" => article.favorites_count.into(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn profile_scalar(profile: &ProfileView, name: &str) -> Option<Value> {
+    let value = match name {
+        "__typename" => "Profile".into(),
+        "username" => profile.profile.username.as_str().into(),
+        "bio" => json!(profile.profile.bio),
+        "image" => json!(profile.profile.image),
+        "following" => profile.following.into(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn comment_scalar(comment: &CommentView, name: &str) -> Option<Value> {
+    let value = match name {
+        "__typename" => "Comment".into(),
+        "id" => comment.id.to_string().into(),
+        "body" => comment.body.as_str().into(),
+        "createdAt" => json!(comment.created_at),
+        "updatedAt" => json!(comment.updated_at),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn optional_string(
+    arguments: &HashMap<String, Value>,
+    name: &str,
+) -> Result<Option<String>, FieldError> {
+    match arguments.get(name) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.to_owned())),
+        Some(_) => Err(FieldError::bad_request(format!(
+            "`{}` must be a string.",
+            name
+        ))),
+    }
+}
+
+fn required_string(arguments: &HashMap<String, Value>, name: &str) -> Result<String, FieldError> {
+    optional_string(arguments, name)?
+        .ok_or_else(|| FieldError::bad_request(format!("`{}` is required.", name)))
+}
+
+fn optional_u64(arguments: &HashMap<String, Value>, name: &str) -> Result<Option<u64>, FieldError> {
+    match arguments.get(name) {
+        None => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            FieldError::bad_request(format!("`{}` must be a non-negative integer.", name))
+        }),
+    }
+}
+
+/// The `ArticleInput` of `publishArticle`.
+fn article_content(input: Option<&Value>) -> Result<ArticleContent, FieldError> {
+    let fields = match input {
+        Some(Value::Object(fields)) => fields
+            .iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k.to_owned(), v.clone()))
+            .collect::<HashMap<_, _>>(),
+        Some(_) => return Err(FieldError::bad_request("`article` must be an object.")),
+        None => return Err(FieldError::bad_request("`article` is required.")),
+    };
+    if let Some(unknown) = fields
+        .keys()
+        .find(|k| !["title", "description", "body", "tagList"].contains(&k.as_str()))
+    {
+        return Err(FieldError::bad_request(format!(
+            "Unknown field `{}` in `ArticleInput`.",
+            unknown
+        )));
+    }
+    let tag_list = match fields.get("tagList") {
+        None => Vec::new(),
+        Some(Value::Array(tags)) => tags
+            .iter()
+            .map(|t| t.as_str().map(str::to_owned))
+            .collect::<Option<_>>()
+            .ok_or_else(|| FieldError::bad_request("`tagList` must be a list of strings."))?,
+        // Input coercion: a single value stands for a list of one
+        Some(Value::String(tag)) => vec![tag.to_owned()],
+        Some(_) => {
+            return Err(FieldError::bad_request(
+                "`tagList` must be a list of strings.",
+            ))
+        }
+    };
+    Ok(ArticleContent {
+        title: required_string(&fields, "title")?,
+        description: required_string(&fields, "description")?,
+        body: required_string(&fields, "body")?,
+        tag_list,
+    })
+}
@@ -0,0 +1,182 @@
+//! A GraphQL endpoint, served at `/graphql`, for clients that want an article, its author,
+//! its comments and the state of the viewer in one round trip.
+//!
+//! Resolvers go through the same `Repository` and domain methods as the REST handlers, with
+//! the same authentication: optional for queries, with the usual scopes for mutations.
+//! Each mutation also takes a token from the rate limit bucket of its REST counterpart, on
+//! top of the one taken for the request itself, so aliases do not get around the limits.
+//! Lists are resolved breadth-first (see `execution`), so nested fields are batched.
+//!
+//! Introspection is not supported. The schema is:
+//!
+//! ```graphql
+//! type Query {
+//!   article(slug: String!): Article
+//!   # At most 100 articles, as for the REST API
+//!   articles(tag: String, author: String, favorited: String, limit: Int = 20, offset: Int): [Article!]
+//!   # Requires authentication
+//!   feed(limit: Int = 20, offset: Int = 0): [Article!]
+//!   profile(username: String!): Profile
+//! }
+//!
+//! type Mutation {
+//!   publishArticle(article: ArticleInput!): Article  # `articles:write`
+//!   comment(slug: String!, body: String!): Comment   # `comments:write`
+//!   favorite(slug: String!): Article                 # `favorites:write`
+//!   unfavorite(slug: String!): Article               # `favorites:write`
+//!   follow(username: String!): Profile               # `profiles:write`
+//!   unfollow(username: String!): Profile             # `profiles:write`
+//! }
+//!
+//! input ArticleInput { title: String!, description: String!, body: String!, tagList: [String!] }
+//!
+//! type Article {
+//!   slug: String!
+//!   title: String!
+//!   description: String!
+//!   body: String!
+//!   tagList: [String!]!
+//!   createdAt: String!
+//!   updatedAt: String!
+//!   status: String!          # draft, scheduled, published or archived
+//!   publishAt: String
+//!   favorited: Boolean!
+//!   favoritesCount: Int!
+//!   author: Profile!
+//!   comments: [Comment!]!
+//! }
+//!
+//! type Profile { username: String!, bio: String, image: String, following: Boolean! }
+//!
+//! type Comment { id: ID!, body: String!, createdAt: String!, updatedAt: String!, author: Profile! }
+//! ```
+mod execution;
+mod parser;
+
+pub use execution::{Executor, FieldError};
+pub use parser::{parse, Document, ParseError};
+
+use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
+use crate::rate_limit::CallerBuckets;
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tide::{Request, Response};
+
+pub const GRAPHQL_ROUTE: &str = "/graphql";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    pub query: String,
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+    /// Required when `query` contains several operations.
+    #[serde(default)]
+    pub operation_name: Option<String>,
+}
+
+impl Schema for GraphQLRequest {
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("query")
+            .optional_schema("variables", json!({ "type": "object", "nullable": true }))
+            .optional::<Option<String>>("operationName")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GraphQLResponse {
+    /// `None` when the request could not be executed at all.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<Value>,
+}
+
+impl Schema for GraphQLResponse {
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .description(
+                "Field errors carry the `path` of the field and, in `extensions.status`, \
+                 the status code the REST API would have answered with.",
+            )
+            .optional_schema("data", json!({ "type": "object" }))
+            .optional_schema(
+                "errors",
+                json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "message": { "type": "string" } },
+                        "required": ["message"],
+                    },
+                }),
+            )
+            .build()
+    }
+}
+
+/// A request which cannot be executed: it is answered with a 400 and no `data`.
+fn request_error(error: Value) -> ErrorResponse {
+    let response = GraphQLResponse {
+        data: None,
+        errors: vec![error],
+    };
+    ErrorResponse(Response::new(400).body_json(&response).unwrap())
+}
+
+pub async fn graphql<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let request: GraphQLRequest = cx
+        .body_json()
+        .await
+        .map_err(|e| request_error(json!({ "message": e.to_string() })))?;
+    let document = parse(&request.query).map_err(|e| {
+        request_error(json!({
+            "message": e.to_string(),
+            "locations": [{ "line": e.line, "column": e.column }],
+        }))
+    })?;
+    let operation = match &request.operation_name {
+        Some(name) => document
+            .operations
+            .iter()
+            .find(|o| o.name.as_ref() == Some(name))
+            .ok_or_else(|| {
+                request_error(json!({ "message": format!("Unknown operation `{}`.", name) }))
+            })?,
+        None => match document.operations.as_slice() {
+            [operation] => operation,
+            [] => {
+                return Err(request_error(
+                    json!({ "message": "No operation to execute." }),
+                ))
+            }
+            _ => {
+                return Err(request_error(json!({
+                    "message": "`operationName` is required when the document contains several operations."
+                })))
+            }
+        },
+    };
+
+    let claims = cx.get_claims().ok();
+    let repository = &cx.state().repository;
+    let viewer = claims
+        .map(|claims| repository.get_user_by_id(claims.user_id()))
+        .transpose()?;
+    let (data, errors) = Executor::new(repository, claims, viewer, &document)
+        .rate_limited(cx.local::<CallerBuckets>().cloned())
+        .execute(operation, request.variables.unwrap_or_default());
+
+    let response = GraphQLResponse {
+        data: Some(data),
+        errors,
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
@@ -0,0 +1,749 @@
+//! Parses the executable subset of the GraphQL query language: operations and fragments.
+//!
+//! Type system definitions (`type`, `schema`, ...), subscriptions and block strings
+//! are rejected.
+use std::collections::HashMap;
+use std::fmt;
+
+/// Selection sets and input values nested deeper than this are rejected,
+/// so that a malicious query cannot exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    pub fragments: HashMap<String, Fragment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub name: Option<String>,
+    /// The declared variables, with their default value.
+    pub variables: Vec<(String, Option<Input>)>,
+    pub selection_set: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub type_condition: String,
+    pub selection_set: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Field(Field),
+    FragmentSpread {
+        name: String,
+        directives: Vec<Directive>,
+    },
+    InlineFragment {
+        type_condition: Option<String>,
+        directives: Vec<Directive>,
+        selection_set: Vec<Selection>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Input)>,
+    pub directives: Vec<Directive>,
+    pub selection_set: Vec<Selection>,
+}
+
+impl Field {
+    /// The key of the field in the response.
+    pub fn response_key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    pub name: String,
+    pub arguments: Vec<(String, Input)>,
+}
+
+/// An input value, as written in a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Input>),
+    Object(Vec<(String, Input)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// 1-based, as in GraphQL error locations.
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Syntax error at {}:{}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punctuator(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Punctuator(c) => write!(f, "`{}`", c),
+            Token::Spread => write!(f, "`...`"),
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Int(i) => write!(f, "`{}`", i),
+            Token::Float(x) => write!(f, "`{}`", x),
+            Token::String(s) => write!(f, "{:?}", s),
+            Token::End => write!(f, "the end of the document"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Skips whitespace, commas and comments.
+    fn skip_ignored(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => {
+                    self.bump();
+                }
+                '#' => {
+                    while let Some(&c) = self.chars.peek() {
+                        if c == '\n' || c == '\r' {
+                            break;
+                        }
+                        self.bump();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// The next token, with its position.
+    fn next_token(&mut self) -> Result<(Token, usize, usize), ParseError> {
+        self.skip_ignored();
+        let (line, column) = (self.line, self.column);
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok((Token::End, line, column)),
+        };
+        let token = match c {
+            '!' | '$' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => {
+                self.bump();
+                Token::Punctuator(c)
+            }
+            '.' => {
+                for _ in 0..3 {
+                    if self.bump() != Some('.') {
+                        return Err(self.error("Expected `...`"));
+                    }
+                }
+                Token::Spread
+            }
+            '"' => self.string()?,
+            '-' | '0'..='9' => self.number()?,
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c == '_' || c.is_ascii_alphanumeric() {
+                        name.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Name(name)
+            }
+            c => return Err(self.error(format!("Unexpected character {:?}", c))),
+        };
+        Ok((token, line, column))
+    }
+
+    fn string(&mut self) -> Result<Token, ParseError> {
+        self.bump();
+        if self.chars.peek() == Some(&'"') {
+            self.bump();
+            if self.chars.peek() == Some(&'"') {
+                return Err(self.error("Block strings are not supported"));
+            }
+            return Ok(Token::String(String::new()));
+        }
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') | Some('\r') => return Err(self.error("Unterminated string")),
+                Some('"') => return Ok(Token::String(value)),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = String::new();
+                            for _ in 0..4 {
+                                code.extend(self.bump());
+                            }
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .and_then(std::char::from_u32)
+                                .ok_or_else(|| {
+                                    self.error(format!("Invalid unicode escape `\\u{}`", code))
+                                })?
+                        }
+                        c => return Err(self.error(format!("Invalid escape {:?}", c))),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Token, ParseError> {
+        let mut number = String::new();
+        let mut is_float = false;
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '0'..='9' | '-' | '+' => {}
+                '.' | 'e' | 'E' => is_float = true,
+                _ => break,
+            }
+            number.push(c);
+            self.bump();
+        }
+        let invalid = || format!("Invalid number `{}`", number);
+        if is_float {
+            number
+                .parse()
+                .map(Token::Float)
+                .map_err(|_| self.error(invalid()))
+        } else {
+            number
+                .parse()
+                .map(Token::Int)
+                .map_err(|_| self.error(invalid()))
+        }
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    token: Token,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(source);
+        let (token, line, column) = lexer.next_token()?;
+        Ok(Self {
+            lexer,
+            token,
+            line,
+            column,
+            depth: 0,
+        })
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        self.error(format!("Unexpected {}", self.token))
+    }
+
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let (token, line, column) = self.lexer.next_token()?;
+        self.line = line;
+        self.column = column;
+        Ok(std::mem::replace(&mut self.token, token))
+    }
+
+    fn peek(&self, c: char) -> bool {
+        self.token == Token::Punctuator(c)
+    }
+
+    fn peek_name(&self, name: &str) -> bool {
+        matches!(&self.token, Token::Name(n) if n == name)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.peek(c) {
+            self.advance()?;
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected `{}`, found {}", c, self.token)))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        match &self.token {
+            Token::Name(_) => match self.advance()? {
+                Token::Name(name) => Ok(name),
+                _ => unreachable!(),
+            },
+            _ => Err(self.error(format!("Expected a name, found {}", self.token))),
+        }
+    }
+
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("The document is nested too deeply"));
+        }
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn document(&mut self) -> Result<Document, ParseError> {
+        let mut document = Document {
+            operations: Vec::new(),
+            fragments: HashMap::new(),
+        };
+        while self.token != Token::End {
+            if self.peek_name("fragment") {
+                self.advance()?;
+                let name = self.name()?;
+                if name == "on" {
+                    return Err(self.error("A fragment cannot be named `on`"));
+                }
+                let fragment = self.fragment()?;
+                if document.fragments.insert(name.clone(), fragment).is_some() {
+                    return Err(self.error(format!("The fragment `{}` is defined twice", name)));
+                }
+            } else {
+                let operation = self.operation()?;
+                document.operations.push(operation);
+            }
+        }
+        Ok(document)
+    }
+
+    fn operation(&mut self) -> Result<Operation, ParseError> {
+        if self.peek('{') {
+            return Ok(Operation {
+                kind: OperationKind::Query,
+                name: None,
+                variables: Vec::new(),
+                selection_set: self.selection_set()?,
+            });
+        }
+        let kind = match &self.token {
+            Token::Name(n) if n == "query" => OperationKind::Query,
+            Token::Name(n) if n == "mutation" => OperationKind::Mutation,
+            Token::Name(n) if n == "subscription" => {
+                return Err(self.error("Subscriptions are not supported"))
+            }
+            _ => return Err(self.unexpected()),
+        };
+        self.advance()?;
+        let name = match self.token {
+            Token::Name(_) => Some(self.name()?),
+            _ => None,
+        };
+        let variables = self.variable_definitions()?;
+        // Operation directives have no effect here
+        self.directives()?;
+        Ok(Operation {
+            kind,
+            name,
+            variables,
+            selection_set: self.selection_set()?,
+        })
+    }
+
+    fn variable_definitions(&mut self) -> Result<Vec<(String, Option<Input>)>, ParseError> {
+        let mut variables = Vec::new();
+        if !self.peek('(') {
+            return Ok(variables);
+        }
+        self.advance()?;
+        while !self.peek(')') {
+            self.expect('$')?;
+            let name = self.name()?;
+            self.expect(':')?;
+            // Types are not checked: arguments are converted by the resolvers
+            self.skip_type()?;
+            let default = if self.peek('=') {
+                self.advance()?;
+                Some(self.value(true)?)
+            } else {
+                None
+            };
+            self.directives()?;
+            variables.push((name, default));
+        }
+        self.advance()?;
+        Ok(variables)
+    }
+
+    fn skip_type(&mut self) -> Result<(), ParseError> {
+        if self.peek('[') {
+            self.advance()?;
+            self.nested(Self::skip_type)?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        if self.peek('!') {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    fn fragment(&mut self) -> Result<Fragment, ParseError> {
+        if !self.peek_name("on") {
+            return Err(self.error(format!("Expected `on`, found {}", self.token)));
+        }
+        self.advance()?;
+        let type_condition = self.name()?;
+        self.directives()?;
+        Ok(Fragment {
+            type_condition,
+            selection_set: self.selection_set()?,
+        })
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>, ParseError> {
+        self.nested(|parser| {
+            parser.expect('{')?;
+            let mut selections = Vec::new();
+            while !parser.peek('}') {
+                selections.push(parser.selection()?);
+            }
+            parser.advance()?;
+            if selections.is_empty() {
+                return Err(parser.error("Selection sets cannot be empty"));
+            }
+            Ok(selections)
+        })
+    }
+
+    fn selection(&mut self) -> Result<Selection, ParseError> {
+        if self.token != Token::Spread {
+            return Ok(Selection::Field(self.field()?));
+        }
+        self.advance()?;
+        match &self.token {
+            Token::Name(n) if n != "on" => {
+                let name = self.name()?;
+                Ok(Selection::FragmentSpread {
+                    name,
+                    directives: self.directives()?,
+                })
+            }
+            _ => {
+                let type_condition = if self.peek_name("on") {
+                    self.advance()?;
+                    Some(self.name()?)
+                } else {
+                    None
+                };
+                Ok(Selection::InlineFragment {
+                    type_condition,
+                    directives: self.directives()?,
+                    selection_set: self.selection_set()?,
+                })
+            }
+        }
+    }
+
+    fn field(&mut self) -> Result<Field, ParseError> {
+        let name = self.name()?;
+        let (alias, name) = if self.peek(':') {
+            self.advance()?;
+            (Some(name), self.name()?)
+        } else {
+            (None, name)
+        };
+        let arguments = self.arguments(false)?;
+        let directives = self.directives()?;
+        let selection_set = if self.peek('{') {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+        Ok(Field {
+            alias,
+            name,
+            arguments,
+            directives,
+            selection_set,
+        })
+    }
+
+    fn arguments(&mut self, is_const: bool) -> Result<Vec<(String, Input)>, ParseError> {
+        let mut arguments = Vec::new();
+        if !self.peek('(') {
+            return Ok(arguments);
+        }
+        self.advance()?;
+        while !self.peek(')') {
+            let name = self.name()?;
+            self.expect(':')?;
+            arguments.push((name, self.value(is_const)?));
+        }
+        self.advance()?;
+        Ok(arguments)
+    }
+
+    fn directives(&mut self) -> Result<Vec<Directive>, ParseError> {
+        let mut directives = Vec::new();
+        while self.peek('@') {
+            self.advance()?;
+            let name = self.name()?;
+            directives.push(Directive {
+                name,
+                arguments: self.arguments(false)?,
+            });
+        }
+        Ok(directives)
+    }
+
+    /// Variables are not allowed in constant values, e.g. the defaults of variables.
+    fn value(&mut self, is_const: bool) -> Result<Input, ParseError> {
+        let value = match &self.token {
+            Token::Punctuator('$') if !is_const => {
+                self.advance()?;
+                Input::Variable(self.name()?)
+            }
+            Token::Punctuator('[') => {
+                self.advance()?;
+                let items = self.nested(|parser| {
+                    let mut items = Vec::new();
+                    while !parser.peek(']') {
+                        items.push(parser.value(is_const)?);
+                    }
+                    Ok(items)
+                })?;
+                self.advance()?;
+                Input::List(items)
+            }
+            Token::Punctuator('{') => {
+                self.advance()?;
+                let fields = self.nested(|parser| {
+                    let mut fields = Vec::new();
+                    while !parser.peek('}') {
+                        let name = parser.name()?;
+                        parser.expect(':')?;
+                        fields.push((name, parser.value(is_const)?));
+                    }
+                    Ok(fields)
+                })?;
+                self.advance()?;
+                Input::Object(fields)
+            }
+            Token::Int(_) | Token::Float(_) | Token::String(_) => match self.advance()? {
+                Token::Int(i) => Input::Int(i),
+                Token::Float(x) => Input::Float(x),
+                Token::String(s) => Input::String(s),
+                _ => unreachable!(),
+            },
+            Token::Name(_) => match self.name()?.as_str() {
+                "true" => Input::Boolean(true),
+                "false" => Input::Boolean(false),
+                "null" => Input::Null,
+                name => Input::Enum(name.to_owned()),
+            },
+            _ => return Err(self.unexpected()),
+        };
+        Ok(value)
+    }
+}
+
+/// Parses a GraphQL document.
+pub fn parse(source: &str) -> Result<Document, ParseError> {
+    Parser::new(source)?.document()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(selection: &Selection) -> &Field {
+        match selection {
+            Selection::Field(field) => field,
+            other => panic!("Expected a field, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn operations_are_parsed() {
+        let document = parse(
+            r#"
+            # The article page
+            query Article($slug: String!, $withComments: Boolean = true) {
+              article(slug: $slug) {
+                title, tagList
+                writer: author { ...ProfileFields }
+                comments @include(if: $withComments) {
+                  ... on Comment { body }
+                }
+              }
+            }
+
+            fragment ProfileFields on Profile { username following }
+
+            mutation { publishArticle(article: {title: "A \"title\"\n", tagList: ["a", "b"]}) { slug } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(document.operations.len(), 2);
+        let query = &document.operations[0];
+        assert_eq!(query.kind, OperationKind::Query);
+        assert_eq!(query.name.as_deref(), Some("Article"));
+        assert_eq!(
+            query.variables,
+            vec![
+                ("slug".to_owned(), None),
+                ("withComments".to_owned(), Some(Input::Boolean(true)))
+            ]
+        );
+        let article = field(&query.selection_set[0]);
+        assert_eq!(
+            article.arguments,
+            vec![("slug".to_owned(), Input::Variable("slug".into()))]
+        );
+        let author = field(&article.selection_set[2]);
+        assert_eq!(author.response_key(), "writer");
+        assert_eq!(author.name, "author");
+        assert_eq!(
+            author.selection_set,
+            vec![Selection::FragmentSpread {
+                name: "ProfileFields".into(),
+                directives: vec![]
+            }]
+        );
+        let comments = field(&article.selection_set[3]);
+        assert_eq!(comments.directives[0].name, "include");
+        assert!(matches!(
+            &comments.selection_set[0],
+            Selection::InlineFragment { type_condition: Some(t), .. } if t == "Comment"
+        ));
+        assert_eq!(
+            document.fragments["ProfileFields"].type_condition,
+            "Profile"
+        );
+
+        let mutation = &document.operations[1];
+        assert_eq!(mutation.kind, OperationKind::Mutation);
+        let publish = field(&mutation.selection_set[0]);
+        assert_eq!(
+            publish.arguments,
+            vec![(
+                "article".to_owned(),
+                Input::Object(vec![
+                    ("title".to_owned(), Input::String("A \"title\"\n".into())),
+                    (
+                        "tagList".to_owned(),
+                        Input::List(vec![Input::String("a".into()), Input::String("b".into())])
+                    ),
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let error = parse("{\n  article(slug: ) { title }\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 17));
+        assert_eq!(error.message, "Unexpected `)`");
+
+        assert!(parse("subscription { comments }").is_err());
+        assert!(parse("{ article { } }").is_err());
+        assert!(parse("query ($a: Int = $b) { a }").is_err());
+        assert!(parse(r#"{ a(b: """c""") }"#).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let query = format!("{}{}", "{ a ".repeat(100), "}".repeat(100));
+        assert_eq!(
+            parse(&query).unwrap_err().message,
+            "The document is nested too deeply"
+        );
+        let value = format!("{{ a(b: {}{}) }}", "[".repeat(100), "]".repeat(100));
+        assert!(parse(&value).is_err());
+    }
+}
@@ -1,13 +1,32 @@
 pub mod app;
 pub mod articles;
 pub mod auth;
+pub mod client_address;
 pub mod comments;
+pub mod cors;
 pub mod errors;
+pub mod graphql;
 pub mod middleware;
+pub mod monitoring;
+pub mod notifications;
+pub mod oidc;
+pub mod openapi;
 pub mod profiles;
+pub mod rate_limit;
+pub mod request_log;
+pub mod throttle;
+pub mod tracing;
 pub mod users;
 
+use crate::cors::CorsPolicy;
+use crate::notifications::stream::NotificationStream;
+use crate::oidc::OidcProvider;
+use crate::rate_limit::RateLimits;
+use crate::throttle::{LoginThrottle, LoginThrottling};
 use domain::repositories::Repository;
+use domain::{Mailer, PasswordHashing};
+use std::net::IpAddr;
+use std::sync::Arc;
 use tide::{IntoResponse, Response};
 
 pub use app::get_app;
@@ -18,6 +37,32 @@ pub use app::get_app;
 /// or ease of testing (mocks and stubs).
 pub struct Context<R: 'static + Repository + Sync + Send> {
     pub repository: R,
+    pub mailer: Arc<dyn Mailer + Sync + Send>,
+    /// How new passwords get hashed.
+    pub password_hashing: PasswordHashing,
+    pub login_throttle: LoginThrottle,
+    pub oidc_providers: Vec<OidcProvider>,
+    pub notification_stream: NotificationStream,
+    pub metrics_token: Option<String>,
+    /// Signs email verification and two-factor challenge tokens.
+    pub token_secret: String,
+}
+
+/// The tunable behaviour of the application, usually populated from the configuration files.
+#[derive(Clone, Debug, Default)]
+pub struct AppSettings {
+    pub password_hashing: PasswordHashing,
+    pub login_throttling: LoginThrottling,
+    pub rate_limits: RateLimits,
+    pub oidc_providers: Vec<OidcProvider>,
+    pub cors: CorsPolicy,
+    pub notification_stream: NotificationStream,
+    /// The reverse proxies whose `X-Forwarded-For` headers are trusted.
+    pub trusted_proxies: Vec<IpAddr>,
+    /// The bearer token Prometheus scrapes `/metrics` with: they are not served without one.
+    pub metrics_token: Option<String>,
+    /// Signs email verification and two-factor challenge tokens.
+    pub token_secret: String,
 }
 
 /// A wrapper around Tide's Response type.
@@ -32,6 +77,13 @@ impl From<Response> for ErrorResponse {
     }
 }
 
+/// Allows handlers to bubble up errors produced by Tide helpers (e.g. `ContextExt::get_claims`).
+impl From<tide::Error> for ErrorResponse {
+    fn from(e: tide::Error) -> Self {
+        Self(e.into_response())
+    }
+}
+
 /// Required to have a Tide-compatible signature for the handler function of each endpoint.
 impl IntoResponse for ErrorResponse {
     fn into_response(self) -> Response {
@@ -1,9 +1,19 @@
 use futures::future::BoxFuture;
-use http::status::StatusCode;
-use log::info;
+use http::HeaderMap;
+use log::error;
 use tide::{Error, Middleware, Next, Request, Response};
 
-use crate::auth::{extract_claims, Claims};
+use crate::auth::{
+    claims_for_access_token, extract_access_token, extract_claims, extract_token, Claims,
+    ACCESS_TOKEN_SCHEME,
+};
+use crate::request_log::set_current_user_id;
+use crate::Context;
+use domain::repositories::Repository;
+use domain::{AccessToken, AccessTokenSecret, DatabaseError, GetUserError, Scope};
+
+/// The authentication scheme advertised in `WWW-Authenticate` challenges.
+const AUTHENTICATION_SCHEME: &str = "Token";
 
 #[derive(Clone, Default, Debug)]
 pub struct JwtMiddleware {}
@@ -14,25 +24,142 @@ impl JwtMiddleware {
     }
 }
 
+/// Marker stored in the request locals when the caller sent an `Authorization` header
+/// that we could not decode (malformed, badly signed or expired).
+#[derive(Clone, Copy, Debug)]
+pub struct InvalidToken;
+
 pub trait ContextExt {
+    /// Returns the claims of the authenticated caller.
+    ///
+    /// If there are none, it fails with a 401 carrying a `WWW-Authenticate` challenge:
+    /// `error="invalid_token"` is added when a token was provided but rejected.
     fn get_claims(&self) -> Result<&Claims, Error>;
+
+    /// Like `get_claims`, but callers using an access token need to have been granted `scope`.
+    ///
+    /// If they were not, it fails with a 403 carrying an `insufficient_scope` challenge.
+    fn get_claims_with_scope(&self, scope: Scope) -> Result<&Claims, Error>;
+
+    /// Like `get_claims`, but callers using an access token are rejected with a 403:
+    /// managing the account requires a session.
+    fn get_session_claims(&self) -> Result<&Claims, Error>;
 }
 
 impl<State> ContextExt for Request<State> {
     fn get_claims(&self) -> Result<&Claims, Error> {
-        self.local::<Claims>()
-            .ok_or_else(|| Error::from(StatusCode::UNAUTHORIZED))
+        self.local::<Claims>().ok_or_else(|| {
+            let challenge = if self.local::<InvalidToken>().is_some() {
+                format!("{} error=\"invalid_token\"", AUTHENTICATION_SCHEME)
+            } else {
+                AUTHENTICATION_SCHEME.to_string()
+            };
+            Error::from(Response::new(401).set_header("WWW-Authenticate", challenge))
+        })
+    }
+
+    fn get_claims_with_scope(&self, scope: Scope) -> Result<&Claims, Error> {
+        let claims = self.get_claims()?;
+        if claims.has_scope(scope) {
+            Ok(claims)
+        } else {
+            let challenge = format!(
+                "{} error=\"insufficient_scope\", scope=\"{}\"",
+                ACCESS_TOKEN_SCHEME,
+                scope.as_str()
+            );
+            Err(Error::from(
+                Response::new(403)
+                    .set_header("WWW-Authenticate", challenge)
+                    .body_string(format!(
+                        "The access token lacks the `{}` scope.",
+                        scope.as_str()
+                    )),
+            ))
+        }
+    }
+
+    fn get_session_claims(&self) -> Result<&Claims, Error> {
+        let claims = self.get_claims()?;
+        if claims.is_session() {
+            Ok(claims)
+        } else {
+            Err(Error::from(Response::new(403).body_string(
+                "Access tokens cannot be used to manage the account.".into(),
+            )))
+        }
     }
 }
 
-impl<State: Send + Sync + 'static> Middleware<State> for JwtMiddleware {
-    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+/// Tokens are rejected if their user is gone or if they were issued before the user's sessions
+/// got revoked (e.g. by a password reset).
+fn is_revoked<R: Repository>(claims: &Claims, repository: &R) -> Result<bool, DatabaseError> {
+    match repository.get_sessions_revoked_at(claims.user_id()) {
+        Ok(Some(revoked_at)) => Ok((claims.issued_at() as i64) < revoked_at.timestamp()),
+        Ok(None) => Ok(false),
+        Err(GetUserError::NotFound { .. }) => Ok(true),
+        Err(GetUserError::DatabaseError(e)) => Err(e),
+    }
+}
+
+/// The claims of the caller, if the credentials in `headers` are valid.
+///
+/// Access tokens are looked up, which records their use; sessions are checked for revocation.
+pub fn authenticate<R: Repository>(
+    headers: &HeaderMap,
+    repository: &R,
+) -> Result<Option<Claims>, DatabaseError> {
+    authenticate_with(headers, repository, |hash| {
+        repository.use_access_token(hash)
+    })
+}
+
+/// Like `authenticate`, without recording the use of access tokens: for checking again
+/// the credentials of a request which is still being served.
+pub fn reauthenticate<R: Repository>(
+    headers: &HeaderMap,
+    repository: &R,
+) -> Result<Option<Claims>, DatabaseError> {
+    authenticate_with(headers, repository, |hash| {
+        repository.find_access_token(hash)
+    })
+}
+
+fn authenticate_with<R: Repository>(
+    headers: &HeaderMap,
+    repository: &R,
+    find_access_token: impl FnOnce(&str) -> Result<Option<AccessToken>, DatabaseError>,
+) -> Result<Option<Claims>, DatabaseError> {
+    if let Some(secret) = extract_access_token(headers) {
+        let secret = AccessTokenSecret::from_clear_text(secret.to_owned());
+        let token = find_access_token(&secret.hash())?;
+        return Ok(token.as_ref().map(claims_for_access_token));
+    }
+    match extract_claims(headers) {
+        Some(claims) if !is_revoked(&claims, repository)? => Ok(Some(claims)),
+        _ => Ok(None),
+    }
+}
+
+impl<R: 'static + Repository + Send + Sync> Middleware<Context<R>> for JwtMiddleware {
+    fn handle<'a>(
+        &'a self,
+        cx: Request<Context<R>>,
+        next: Next<'a, Context<R>>,
+    ) -> BoxFuture<'a, Response> {
         Box::pin(async move {
-            info!("Headers: {:?}", cx.headers());
-            let claims = extract_claims(cx.headers());
-            info!("Claims: {:?}", claims);
+            let claims = match authenticate(cx.headers(), &cx.state().repository) {
+                Ok(claims) => claims,
+                Err(e) => {
+                    error!("Failed to authenticate the caller: {}", e);
+                    return Response::new(500);
+                }
+            };
             return if let Some(c) = claims {
+                set_current_user_id(c.user_id());
                 next.run(cx.set_local(c)).await
+            } else if extract_token(cx.headers()).is_some() {
+                next.run(cx.set_local(InvalidToken)).await
             } else {
                 next.run(cx).await
             };
@@ -0,0 +1,151 @@
+//! Prometheus metrics of the HTTP API, and the endpoint exposing all the metrics of the service.
+use domain::repositories::Repository;
+use futures::future::BoxFuture;
+use http::Method;
+use metrics::{HTTP_REQUESTS, HTTP_REQUEST_DURATION};
+use std::time::Instant;
+use tide::{Middleware, Next, Request, Response};
+
+use crate::app::ROUTES;
+use crate::Context;
+
+/// Reported instead of the path of requests matching no route, to bound the number of series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Reported instead of non-standard methods, which clients can make up at will.
+const OTHER_METHOD: &str = "other";
+
+/// The name of `method`, if it is one of the standard ones.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => OTHER_METHOD,
+    }
+}
+
+/// The template of the route matching `path`, e.g. `/api/articles/:slug` for
+/// `/api/articles/how-to-train-your-dragon`.
+///
+/// Literal segments take precedence over parameters, as they do for the router.
+pub fn route_of(path: &str) -> &'static str {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    ROUTES
+        .iter()
+        .filter_map(|route| {
+            let route_segments: Vec<&str> = route.split('/').collect();
+            if route_segments.len() != segments.len() {
+                return None;
+            }
+            let mut parameters = 0;
+            for (expected, actual) in route_segments.iter().zip(&segments) {
+                if expected.starts_with(':') && !actual.is_empty() {
+                    parameters += 1;
+                } else if expected != actual {
+                    return None;
+                }
+            }
+            Some((parameters, *route))
+        })
+        .min()
+        .map_or(UNMATCHED_ROUTE, |(_, route)| route)
+}
+
+/// Counts and times requests, by method, route and status.
+#[derive(Clone, Default, Debug)]
+pub struct MetricsMiddleware {}
+
+impl MetricsMiddleware {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for MetricsMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let method = method_label(cx.method());
+            let route = route_of(cx.uri().path());
+
+            let response = next.run(cx).await;
+
+            let status = response.status().as_u16().to_string();
+            let labels = [method, route, status.as_str()];
+            HTTP_REQUESTS.inc(&labels);
+            HTTP_REQUEST_DURATION.observe(&labels, started_at.elapsed().as_secs_f64());
+            response
+        })
+    }
+}
+
+/// Serve the metrics in the text exposition format of Prometheus.
+///
+/// Only to callers presenting the configured bearer token: without one, they are not served at all.
+pub async fn metrics<R: 'static + Repository + Sync + Send>(cx: Request<Context<R>>) -> Response {
+    let token = match &cx.state().metrics_token {
+        Some(token) => token,
+        None => return Response::new(404),
+    };
+    let presented = cx
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !presented.is_some_and(|presented| same_token(presented, token)) {
+        return Response::new(401).set_header("WWW-Authenticate", "Bearer");
+    }
+    Response::new(200)
+        .set_header("Content-Type", "text/plain; version=0.0.4")
+        .body_string(metrics::render())
+}
+
+/// Compares tokens in constant time, so that response times do not give them away.
+fn same_token(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_standard_methods_are_reported_as_other() {
+        assert_eq!(method_label(&Method::PATCH), "PATCH");
+        let made_up = Method::from_bytes(b"BREW").unwrap();
+        assert_eq!(method_label(&made_up), "other");
+    }
+
+    #[test]
+    fn paths_are_reported_by_route() {
+        assert_eq!(route_of("/api/articles"), "/api/articles");
+        assert_eq!(route_of("/api/articles/"), "/api/articles");
+        assert_eq!(route_of("/api/articles/feed"), "/api/articles/feed");
+        assert_eq!(route_of("/api/articles/a-slug"), "/api/articles/:slug");
+        assert_eq!(
+            route_of("/api/articles/a-slug/comments/12"),
+            "/api/articles/:slug/comments/:id"
+        );
+        assert_eq!(route_of("/api/nothing/here"), UNMATCHED_ROUTE);
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(same_token("a-metrics-token", "a-metrics-token"));
+        assert!(!same_token("a-metrics-tokem", "a-metrics-token"));
+        assert!(!same_token("a-metrics-toke", "a-metrics-token"));
+        assert!(!same_token("", "a-metrics-token"));
+    }
+}
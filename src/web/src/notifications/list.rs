@@ -0,0 +1,64 @@
+use crate::middleware::ContextExt;
+use crate::notifications::responses::{Notification, NotificationsResponse};
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use domain::Scope;
+use serde::{Deserialize, Serialize};
+use tide::{Request, Response};
+
+#[derive(Serialize, Deserialize)]
+pub struct NotificationsQuery {
+    /// Leave out the notifications which were already read.
+    #[serde(default)]
+    pub unread: bool,
+
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+
+    #[serde(default)]
+    pub offset: u64,
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+impl Default for NotificationsQuery {
+    fn default() -> Self {
+        Self {
+            unread: false,
+            limit: default_limit(),
+            offset: 0,
+        }
+    }
+}
+
+impl From<NotificationsQuery> for domain::NotificationsQuery {
+    fn from(q: NotificationsQuery) -> Self {
+        Self {
+            unread_only: q.unread,
+            after_id: None,
+            limit: Some(q.limit),
+            offset: Some(q.offset),
+        }
+    }
+}
+
+pub async fn list<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx
+        .get_claims_with_scope(Scope::NotificationsRead)?
+        .user_id();
+    // This can be avoided once https://github.com/http-rs/tide/pull/384 gets merged
+    let query = cx.query::<NotificationsQuery>().unwrap_or_default();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let notifications = user.notifications(query.into(), repository)?;
+    let response = NotificationsResponse {
+        notifications: Notification::for_recipient(&user, notifications, repository)?,
+        unread_count: user.unread_notifications_count(repository)?,
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
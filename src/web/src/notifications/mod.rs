@@ -0,0 +1,8 @@
+pub mod list;
+pub mod read;
+pub mod responses;
+pub mod stream;
+
+pub use list::list;
+pub use read::{mark_all_read, mark_read, mark_unread};
+pub use stream::stream;
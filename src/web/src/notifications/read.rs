@@ -0,0 +1,45 @@
+use crate::middleware::ContextExt;
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use domain::Scope;
+use tide::{Request, Response};
+
+pub async fn mark_read<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _mark(cx, true).await
+}
+
+pub async fn mark_unread<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _mark(cx, false).await
+}
+
+async fn _mark<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+    read: bool,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx
+        .get_claims_with_scope(Scope::NotificationsRead)?
+        .user_id();
+    let notification_id: u64 = cx.param("id").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    user.mark_notification(notification_id, read, repository)?;
+    Ok(Response::new(200))
+}
+
+pub async fn mark_all_read<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx
+        .get_claims_with_scope(Scope::NotificationsRead)?
+        .user_id();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    user.mark_all_notifications_read(repository)?;
+    Ok(Response::new(200))
+}
@@ -0,0 +1,114 @@
+use crate::articles::responses::Author;
+use crate::openapi::{string_enum, Components, Object, Schema};
+use chrono::{DateTime, Utc};
+use domain::repositories::Repository;
+use domain::{DatabaseError, Event, User};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationsResponse {
+    pub notifications: Vec<Notification>,
+    /// Across all the notifications of the user, not only the ones in this page.
+    pub unread_count: u64,
+}
+
+impl Schema for NotificationsResponse {
+    const NAME: Option<&'static str> = Some("NotificationsResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<Notification>>("notifications")
+            .field::<u64>("unreadCount")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub id: u64,
+    /// `follow`, `comment` or `favorite`.
+    pub kind: String,
+    pub actor: Author,
+    /// `None` for follows.
+    pub article: Option<NotificationArticle>,
+    /// Only set for comments.
+    pub comment_id: Option<u64>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Schema for Notification {
+    const NAME: Option<&'static str> = Some("Notification");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<u64>("id")
+            .field_schema("kind", string_enum(&["follow", "comment", "favorite"]))
+            .field::<Author>("actor")
+            .field::<Option<NotificationArticle>>("article")
+            .field::<Option<u64>>("commentId")
+            .field::<bool>("read")
+            .field::<DateTime<Utc>>("createdAt")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NotificationArticle {
+    pub slug: String,
+    pub title: String,
+}
+
+impl Schema for NotificationArticle {
+    const NAME: Option<&'static str> = Some("NotificationArticle");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("slug")
+            .field::<String>("title")
+            .build()
+    }
+}
+
+impl Notification {
+    /// The notifications of `recipient`, with whether they follow each of the actors.
+    pub fn for_recipient(
+        recipient: &User,
+        notifications: Vec<domain::Notification>,
+        repository: &impl Repository,
+    ) -> Result<Vec<Self>, DatabaseError> {
+        let actors = notifications.iter().map(|n| n.actor.clone()).collect();
+        let actors = repository.get_profiles_views(recipient, actors)?;
+        Ok(notifications
+            .into_iter()
+            .zip(actors)
+            .map(|(n, actor)| Notification::new(n, actor.into()))
+            .collect())
+    }
+
+    fn new(n: domain::Notification, actor: Author) -> Self {
+        let read = n.is_read();
+        let title = n.article_title.unwrap_or_default();
+        let (kind, article_slug, comment_id) = match n.event {
+            Event::Followed => ("follow", None, None),
+            Event::Commented {
+                article_slug,
+                comment_id,
+            } => ("comment", Some(article_slug), Some(comment_id)),
+            Event::Favorited { article_slug } => ("favorite", Some(article_slug), None),
+        };
+        let article = article_slug.map(|slug| NotificationArticle { slug, title });
+        Self {
+            id: n.id,
+            kind: kind.to_owned(),
+            actor,
+            article,
+            comment_id,
+            read,
+            created_at: n.created_at,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Pushes the notifications of the caller as they happen, as server-sent events.
+//!
+//! Each notification is sent as an event of type `notification`, with its id as event id:
+//! clients reconnecting with `Last-Event-ID` get the ones they missed in the meantime.
+//! Without it, only the notifications recorded after the client connected are sent.
+//!
+//! New notifications are found by polling the repository, so that they reach their recipient
+//! whichever instance of the application they are connected to.
+//!
+//! The credentials of the client are checked again on every poll: the stream ends as soon as
+//! they stop being valid, e.g. when the session expires or is revoked by a password reset,
+//! or when the access token is deleted.
+use crate::middleware::{reauthenticate, ContextExt};
+use crate::notifications::responses::Notification;
+use crate::{Context, ErrorResponse};
+use async_std::task;
+use domain::repositories::Repository;
+use domain::{DatabaseError, NotificationsQuery, Scope, User};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use log::error;
+use std::io;
+use std::time::Duration;
+use tide::{Request, Response};
+
+/// How often connected clients are looked after.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotificationStream {
+    /// How often each stream checks for new notifications.
+    pub poll_interval: Duration,
+    /// How long a stream can stay silent before a comment is sent down, to keep proxies
+    /// from closing the connection.
+    pub keep_alive: Duration,
+}
+
+impl Default for NotificationStream {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(2),
+            keep_alive: Duration::from_secs(15),
+        }
+    }
+}
+
+/// A connected client.
+struct Subscription<R: 'static + Repository + Sync + Send> {
+    // Owned by the stream, which outlives the handler, to get hold of the repository
+    cx: Request<Context<R>>,
+    user: User,
+    /// The id of the last notification sent to the client.
+    last_id: u64,
+    /// How long it has been since something was sent to the client.
+    idle: Duration,
+}
+
+pub async fn stream<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx
+        .get_claims_with_scope(Scope::NotificationsRead)?
+        .user_id();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let last_id = match last_event_id(&cx) {
+        Some(id) => id,
+        None => latest_notification_id(&user, repository)?,
+    };
+    let subscription = Subscription {
+        cx,
+        user,
+        last_id,
+        idle: Duration::from_secs(0),
+    };
+
+    // Sent straight away, so that clients know they are connected
+    let connected = stream::once(async { Ok(b": connected\n\n".to_vec()) });
+    let frames = connected.chain(stream::unfold(subscription, next_frames));
+    let body = Box::pin(frames).into_async_read();
+    Ok(Response::with_reader(200, body)
+        .set_header("Content-Type", "text/event-stream")
+        .set_header("Cache-Control", "no-cache")
+        // Keeps nginx from buffering the events
+        .set_header("X-Accel-Buffering", "no"))
+}
+
+fn last_event_id<S>(cx: &Request<S>) -> Option<u64> {
+    cx.headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn latest_notification_id(user: &User, repository: &impl Repository) -> Result<u64, DatabaseError> {
+    let query = NotificationsQuery {
+        limit: Some(1),
+        ..NotificationsQuery::default()
+    };
+    let latest = user.notifications(query, repository)?;
+    Ok(latest.first().map(|n| n.id).unwrap_or(0))
+}
+
+/// Waits for new notifications, sending keep-alive comments in the meantime.
+///
+/// The stream ends when the credentials of the client are no longer valid
+/// or the repository fails.
+async fn next_frames<R: 'static + Repository + Sync + Send>(
+    mut subscription: Subscription<R>,
+) -> Option<(io::Result<Vec<u8>>, Subscription<R>)> {
+    let settings = subscription.cx.state().notification_stream.clone();
+    loop {
+        match is_still_authenticated(&subscription) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => {
+                error!("Failed to check the credentials of a client: {}", e);
+                return None;
+            }
+        }
+        let notifications = match new_notifications(&subscription) {
+            Ok(notifications) => notifications,
+            Err(e) => {
+                error!("Failed to poll notifications: {}", e);
+                return None;
+            }
+        };
+        if let Some(latest) = notifications.last() {
+            subscription.last_id = latest.id;
+            subscription.idle = Duration::from_secs(0);
+            let frames = notifications.iter().flat_map(frame).collect();
+            return Some((Ok(frames), subscription));
+        }
+        if subscription.idle >= settings.keep_alive {
+            subscription.idle = Duration::from_secs(0);
+            return Some((Ok(b": keep-alive\n\n".to_vec()), subscription));
+        }
+        task::sleep(settings.poll_interval).await;
+        subscription.idle += settings.poll_interval;
+    }
+}
+
+/// The notifications recorded since the last one sent, oldest first.
+fn new_notifications<R: 'static + Repository + Sync + Send>(
+    subscription: &Subscription<R>,
+) -> Result<Vec<Notification>, DatabaseError> {
+    let repository = &subscription.cx.state().repository;
+    let query = NotificationsQuery {
+        after_id: Some(subscription.last_id),
+        ..NotificationsQuery::default()
+    };
+    let mut notifications = subscription.user.notifications(query, repository)?;
+    notifications.reverse();
+    Notification::for_recipient(&subscription.user, notifications, repository)
+}
+
+/// Whether the credentials the client connected with still authenticate the same user.
+fn is_still_authenticated<R: 'static + Repository + Sync + Send>(
+    subscription: &Subscription<R>,
+) -> Result<bool, DatabaseError> {
+    let repository = &subscription.cx.state().repository;
+    let claims = reauthenticate(subscription.cx.headers(), repository)?;
+    Ok(claims.is_some_and(|claims| claims.user_id() == subscription.user.id))
+}
+
+fn frame(notification: &Notification) -> Vec<u8> {
+    // Serialized JSON spans a single line, as required for a `data` field
+    format!(
+        "id: {}\nevent: notification\ndata: {}\n\n",
+        notification.id,
+        serde_json::to_string(notification).unwrap()
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::articles::responses::Author;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn notifications_are_sent_as_single_events() {
+        let notification = Notification {
+            id: 42,
+            kind: "follow".into(),
+            actor: Author {
+                username: "ferris".into(),
+                bio: Some("Loves\ncrabs".into()),
+                image: None,
+                following: true,
+            },
+            article: None,
+            comment_id: None,
+            read: false,
+            created_at: Utc.ymd(2020, 2, 27).and_hms(10, 15, 0),
+        };
+
+        let frame = String::from_utf8(frame(&notification)).unwrap();
+        let lines: Vec<&str> = frame.split('\n').collect();
+        assert_eq!(lines[0], "id: 42");
+        assert_eq!(lines[1], "event: notification");
+        assert!(lines[2].starts_with("data: {"));
+        assert_eq!(&lines[3..], &["", ""]);
+        let data: Notification = serde_json::from_str(&lines[2]["data: ".len()..]).unwrap();
+        assert_eq!(data, notification);
+    }
+}
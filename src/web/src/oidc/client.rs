@@ -0,0 +1,380 @@
+//! The exchanges with the identity provider that do not go through the browser.
+use crate::oidc::OidcProvider;
+use async_native_tls::{Certificate, TlsConnector};
+use async_std::io::{self, prelude::*};
+use async_std::net::TcpStream;
+use domain::{ExternalIdentity, ExternalLoginError};
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::Deserialize;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// The keys an identity provider signs its ID tokens with, as published at its `jwks_uri`.
+#[derive(Deserialize, Debug)]
+pub struct KeySet {
+    keys: Vec<Key>,
+}
+
+/// A JSON Web Key: only RSA keys are supported.
+#[derive(Deserialize, Debug)]
+struct Key {
+    kty: String,
+    kid: Option<String>,
+    #[serde(rename = "use")]
+    usage: Option<String>,
+    /// The modulus of RSA keys, base64url-encoded.
+    n: Option<String>,
+    /// The exponent of RSA keys, base64url-encoded.
+    e: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    preferred_username: Option<String>,
+}
+
+fn provider_error(message: impl Into<String>) -> ExternalLoginError {
+    ExternalLoginError::Provider(anyhow::anyhow!(message.into()))
+}
+
+/// Exchange an authorization code for the ID token of the user.
+pub async fn exchange_code(
+    provider: &OidcProvider,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String, ExternalLoginError> {
+    let form = serde_urlencoded::to_string([
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", &provider.redirect_uri),
+        ("code_verifier", code_verifier),
+        ("client_id", &provider.client_id),
+        ("client_secret", &provider.client_secret),
+    ])
+    .map_err(|e| ExternalLoginError::Provider(e.into()))?;
+    let (status, body) = send(provider, &provider.token_endpoint, Some(&form)).await?;
+    if status != 200 {
+        return Err(provider_error(format!(
+            "The token endpoint responded with {}: {}",
+            status, body
+        )));
+    }
+    let tokens: TokenResponse =
+        serde_json::from_str(&body).map_err(|e| ExternalLoginError::Provider(e.into()))?;
+    Ok(tokens.id_token)
+}
+
+/// Fetch the keys the provider currently signs its ID tokens with.
+pub async fn fetch_keys(provider: &OidcProvider) -> Result<KeySet, ExternalLoginError> {
+    let (status, body) = send(provider, &provider.jwks_uri, None).await?;
+    if status != 200 {
+        return Err(provider_error(format!(
+            "The JWKS endpoint responded with {}: {}",
+            status, body
+        )));
+    }
+    serde_json::from_str(&body).map_err(|e| ExternalLoginError::Provider(e.into()))
+}
+
+/// The identity asserted by an ID token, once its signature and its claims are checked.
+///
+/// The token must be signed by one of the `keys` of the provider, with an RSA algorithm.
+pub fn identity_from_id_token(
+    provider: &OidcProvider,
+    id_token: &str,
+    nonce: &str,
+    keys: &KeySet,
+) -> Result<ExternalIdentity, ExternalLoginError> {
+    let header = decode_header(id_token)
+        .map_err(|e| provider_error(format!("Malformed ID token: {}", e)))?;
+    match header.alg {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {}
+        alg => {
+            return Err(provider_error(format!(
+                "Unsupported ID token algorithm {:?}",
+                alg
+            )))
+        }
+    }
+    let key = keys.find(header.kid.as_deref())?;
+    // Also checks that the token has not expired
+    let claims = decode::<IdTokenClaims>(id_token, &key, &Validation::new(header.alg))
+        .map_err(|e| provider_error(format!("Invalid ID token: {}", e)))?
+        .claims;
+    if claims.iss != provider.issuer {
+        return Err(provider_error(format!("Unexpected issuer {}", claims.iss)));
+    }
+    if !claims.aud.contains(&provider.client_id) {
+        return Err(provider_error("The ID token was not issued for us"));
+    }
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(provider_error(
+            "The ID token was issued for another login attempt",
+        ));
+    }
+    Ok(ExternalIdentity {
+        provider: provider.name.to_owned(),
+        subject: claims.sub,
+        email: claims.email,
+        email_verified: claims.email_verified,
+        preferred_username: claims.preferred_username,
+    })
+}
+
+impl KeySet {
+    /// The RSA signing key identified by `kid`, or the only one if the token does not say,
+    /// as a DER-encoded `RSAPublicKey`.
+    fn find(&self, kid: Option<&str>) -> Result<Vec<u8>, ExternalLoginError> {
+        let mut candidates = self.keys.iter().filter(|key| {
+            key.kty == "RSA"
+                && key.usage.as_deref().unwrap_or("sig") == "sig"
+                && (kid.is_none() || key.kid.as_deref() == kid)
+        });
+        let key = match (candidates.next(), candidates.next()) {
+            (Some(key), None) => key,
+            (None, _) => return Err(provider_error("No key matches the ID token")),
+            (Some(_), Some(_)) => return Err(provider_error("Several keys match the ID token")),
+        };
+        let decode_component = |component: &Option<String>| {
+            component
+                .as_ref()
+                .and_then(|c| base64::decode_config(c, base64::URL_SAFE_NO_PAD).ok())
+                .ok_or_else(|| provider_error("Malformed RSA key"))
+        };
+        let modulus = decode_component(&key.n)?;
+        let exponent = decode_component(&key.e)?;
+        Ok(rsa_public_key(&modulus, &exponent))
+    }
+}
+
+/// The ASN.1 DER encoding of an `RSAPublicKey` (RFC 8017, appendix A.1.1),
+/// from its big-endian modulus and exponent.
+fn rsa_public_key(modulus: &[u8], exponent: &[u8]) -> Vec<u8> {
+    let mut content = der_integer(modulus);
+    content.extend(der_integer(exponent));
+    der_value(0x30, &content)
+}
+
+fn der_integer(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let mut value = bytes[start..].to_vec();
+    // Integers are signed: positive ones cannot start with the high bit set
+    if value.first().is_none_or(|&b| b >= 0x80) {
+        value.insert(0, 0);
+    }
+    der_value(0x02, &value)
+}
+
+fn der_value(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut value = vec![tag];
+    if content.len() < 0x80 {
+        value.push(content.len() as u8);
+    } else {
+        let length = content.len().to_be_bytes();
+        let start = length.iter().position(|&b| b != 0).unwrap();
+        value.push(0x80 | (length.len() - start) as u8);
+        value.extend_from_slice(&length[start..]);
+    }
+    value.extend_from_slice(content);
+    value
+}
+
+/// Send a request to the provider over HTTPS, returning the status code and the body of the
+/// response: a POST of the urlencoded `form` if there is one, a GET otherwise.
+async fn send(
+    provider: &OidcProvider,
+    url: &str,
+    form: Option<&str>,
+) -> Result<(u16, String), ExternalLoginError> {
+    let uri: http::Uri = url
+        .parse()
+        .map_err(|_| provider_error(format!("Invalid URL {}", url)))?;
+    if uri.scheme_str() != Some("https") {
+        return Err(provider_error(format!(
+            "{} is not an https URL: the provider can only be reached over TLS",
+            url
+        )));
+    }
+    let host = uri
+        .host()
+        .ok_or_else(|| provider_error(format!("Invalid URL {}", url)))?;
+    let port = uri.port_u16().unwrap_or(443);
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    // HTTP/1.0 spares us chunked responses and keep-alive connections
+    let request = match form {
+        Some(form) => format!(
+            "POST {} HTTP/1.0\r\nHost: {}:{}\r\nAccept: application/json\r\n\
+             Content-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{}",
+            path,
+            host,
+            port,
+            form.len(),
+            form
+        ),
+        None => format!(
+            "GET {} HTTP/1.0\r\nHost: {}:{}\r\nAccept: application/json\r\n\r\n",
+            path, host, port
+        ),
+    };
+
+    let mut connector = TlsConnector::new();
+    if let Some(pem) = &provider.ca_certificate {
+        let certificate = Certificate::from_pem(pem.as_bytes())
+            .map_err(|e| ExternalLoginError::Provider(e.into()))?;
+        connector = connector.add_root_certificate(certificate);
+    }
+    let response = io::timeout(TIMEOUT, async {
+        let stream = TcpStream::connect((host, port)).await?;
+        let mut stream = connector
+            .connect(host, stream)
+            .await
+            .map_err(io::Error::other)?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok(response)
+    })
+    .await
+    .map_err(|e| ExternalLoginError::Provider(e.into()))?;
+
+    let response = String::from_utf8_lossy(&response);
+    let mut parts = response.splitn(2, "\r\n\r\n");
+    let head = parts.next().unwrap_or_default();
+    let body = parts.next().unwrap_or_default();
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| provider_error("Malformed HTTP response"))?;
+    Ok((status, body.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, Header};
+    use openssl::rsa::Rsa;
+    use serde_json::json;
+
+    fn provider() -> OidcProvider {
+        OidcProvider {
+            name: "example".into(),
+            issuer: "https://accounts.example.com".into(),
+            client_id: "conduit".into(),
+            client_secret: "a-client-secret".into(),
+            authorization_endpoint: "https://accounts.example.com/authorize".into(),
+            token_endpoint: "http://accounts.example.com/token".into(),
+            jwks_uri: "https://accounts.example.com/jwks".into(),
+            ca_certificate: None,
+            redirect_uri: "https://conduit.example.com/api/auth/example/callback".into(),
+            scopes: vec![],
+        }
+    }
+
+    fn signed_id_token(private_key: &Rsa<openssl::pkey::Private>, kid: &str, exp: i64) -> String {
+        let claims = json!({
+            "iss": "https://accounts.example.com",
+            "sub": "subject-1",
+            "aud": ["conduit", "someone-else"],
+            "exp": exp,
+            "nonce": "a-nonce",
+            "email": "jo@example.com",
+            "email_verified": true,
+        });
+        let header = Header {
+            kid: Some(kid.into()),
+            ..Header::new(Algorithm::RS256)
+        };
+        encode(&header, &claims, &private_key.private_key_to_der().unwrap()).unwrap()
+    }
+
+    fn key_set(private_key: &Rsa<openssl::pkey::Private>, kid: &str) -> KeySet {
+        let encode = |n: &openssl::bn::BigNumRef| {
+            base64::encode_config(&n.to_vec(), base64::URL_SAFE_NO_PAD)
+        };
+        serde_json::from_value(json!({
+            "keys": [{
+                "kty": "RSA",
+                "kid": kid,
+                "use": "sig",
+                "n": encode(private_key.n()),
+                "e": encode(private_key.e()),
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn id_tokens_must_be_signed_by_the_provider() {
+        let provider = provider();
+        let key = Rsa::generate(2048).unwrap();
+        let keys = key_set(&key, "key-1");
+        let in_an_hour = chrono::Utc::now().timestamp() + 3600;
+
+        let id_token = signed_id_token(&key, "key-1", in_an_hour);
+        let identity = identity_from_id_token(&provider, &id_token, "a-nonce", &keys).unwrap();
+        assert_eq!(identity.subject, "subject-1");
+        assert!(identity.email_verified);
+        assert!(identity_from_id_token(&provider, &id_token, "another-nonce", &keys).is_err());
+
+        let expired = signed_id_token(&key, "key-1", in_an_hour - 7200);
+        assert!(identity_from_id_token(&provider, &expired, "a-nonce", &keys).is_err());
+        let unknown_key = signed_id_token(&key, "key-2", in_an_hour);
+        assert!(identity_from_id_token(&provider, &unknown_key, "a-nonce", &keys).is_err());
+        let forged = signed_id_token(&Rsa::generate(2048).unwrap(), "key-1", in_an_hour);
+        assert!(identity_from_id_token(&provider, &forged, "a-nonce", &keys).is_err());
+        // Anybody can compute an HMAC with a public key
+        let claims = jsonwebtoken::dangerous_unsafe_decode::<serde_json::Value>(&id_token)
+            .unwrap()
+            .claims;
+        let hmac = encode(&Header::default(), &claims, &keys.find(None).unwrap()).unwrap();
+        assert!(identity_from_id_token(&provider, &hmac, "a-nonce", &keys).is_err());
+    }
+
+    #[test]
+    fn providers_are_only_reached_over_tls() {
+        let result = async_std::task::block_on(exchange_code(&provider(), "a-code", "a-verifier"));
+        match result {
+            Err(ExternalLoginError::Provider(e)) => {
+                assert!(e.to_string().contains("not an https URL"), "{}", e)
+            }
+            _ => panic!("The token endpoint was called over plain HTTP"),
+        }
+    }
+
+    #[test]
+    fn rsa_public_keys_are_der_encoded() {
+        let key = Rsa::generate(2048).unwrap();
+        let der = rsa_public_key(&key.n().to_vec(), &key.e().to_vec());
+        assert_eq!(der, key.public_key_to_der_pkcs1().unwrap());
+    }
+}
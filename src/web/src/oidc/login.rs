@@ -0,0 +1,154 @@
+use crate::auth::{
+    encode_token, encode_two_factor_challenge_token, TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS,
+};
+use crate::oidc::client::{exchange_code, fetch_keys, identity_from_id_token};
+use crate::oidc::OidcProvider;
+use crate::users::responses::{TwoFactorChallengeResponse, UserResponse};
+use crate::{Context, ErrorResponse};
+use serde::Deserialize;
+
+use domain::repositories::Repository;
+use domain::{sign_in_with, ExternalLoginError, OidcLoginAttempt};
+use tide::{Request, Response};
+
+/// Binds the callback to the browser that started the login,
+/// so that nobody can get a victim logged in as them.
+const STATE_COOKIE: &str = "oidc_state";
+
+fn find_provider<'a>(
+    providers: &'a [OidcProvider],
+    name: &str,
+) -> Result<&'a OidcProvider, ExternalLoginError> {
+    providers
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| ExternalLoginError::UnknownProvider {
+            provider: name.to_owned(),
+        })
+}
+
+fn authorization_url(provider: &OidcProvider, attempt: &OidcLoginAttempt) -> String {
+    let scope = std::iter::once("openid")
+        .chain(provider.scopes.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = serde_urlencoded::to_string([
+        ("response_type", "code"),
+        ("client_id", &provider.client_id),
+        ("redirect_uri", &provider.redirect_uri),
+        ("scope", &scope),
+        ("state", &attempt.state),
+        ("nonce", &attempt.nonce),
+        ("code_challenge", &attempt.code_challenge()),
+        ("code_challenge_method", "S256"),
+    ])
+    .unwrap();
+    let separator = if provider.authorization_endpoint.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    format!("{}{}{}", provider.authorization_endpoint, separator, query)
+}
+
+/// Redirect the user to the identity provider.
+pub async fn authorize<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let provider_name: String = cx.param("provider").map_err(|_| Response::new(400))?;
+    let state = cx.state();
+
+    let provider = find_provider(&state.oidc_providers, &provider_name)?;
+    let attempt = OidcLoginAttempt::generate(&provider.name)?;
+    state.repository.create_oidc_login_attempt(&attempt)?;
+
+    let cookie = format!(
+        "{}={}; Path=/api/auth; Max-Age=600; HttpOnly; Secure; SameSite=Lax",
+        STATE_COOKIE, attempt.state
+    );
+    Ok(Response::new(302)
+        .set_header("Location", authorization_url(provider, &attempt))
+        .set_header("Set-Cookie", cookie))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CallbackQuery {
+    pub state: String,
+    pub code: Option<String>,
+    /// Set by the provider instead of `code` when the user could not be authenticated.
+    pub error: Option<String>,
+}
+
+fn state_cookie<S>(cx: &Request<S>) -> Option<&str> {
+    cx.headers()
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| {
+            let mut parts = pair.trim().splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(STATE_COOKIE), Some(value)) => Some(value),
+                _ => None,
+            }
+        })
+        .next()
+}
+
+/// Where the identity provider sends the user back to: it logs them in with our usual JWT.
+///
+/// Users with two-factor authentication enabled get the same challenge as with `login` instead:
+/// vouching for their email address is not enough for the provider to get into their account.
+pub async fn callback<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let provider_name: String = cx.param("provider").map_err(|_| Response::new(400))?;
+    let query = cx
+        .query::<CallbackQuery>()
+        .map_err(|_| Response::new(400))?;
+    let state = cx.state();
+
+    let provider = find_provider(&state.oidc_providers, &provider_name)?;
+    if state_cookie(&cx) != Some(query.state.as_str()) {
+        return Err(ExternalLoginError::InvalidState.into());
+    }
+    let attempt = state
+        .repository
+        .take_oidc_login_attempt(&query.state)?
+        .filter(|a| a.provider == provider.name)
+        .ok_or(ExternalLoginError::InvalidState)?;
+    let code = match (&query.code, &query.error) {
+        (Some(code), None) => code,
+        (_, error) => {
+            return Err(ExternalLoginError::Provider(anyhow::anyhow!(
+                "The identity provider returned {:?}",
+                error
+            ))
+            .into())
+        }
+    };
+
+    let id_token = exchange_code(provider, code, &attempt.code_verifier).await?;
+    let keys = fetch_keys(provider).await?;
+    let identity = identity_from_id_token(provider, &id_token, &attempt.nonce, &keys)?;
+    let user = sign_in_with(&identity, &state.password_hashing, &state.repository)?;
+
+    let expired_cookie = format!("{}=; Path=/api/auth; Max-Age=0", STATE_COOKIE);
+    if user.requires_two_factor(&state.repository)? {
+        let challenge = TwoFactorChallengeResponse::new(
+            encode_two_factor_challenge_token(user.id, &state.token_secret),
+            TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS,
+        );
+        return Ok(Response::new(200)
+            .set_header("Set-Cookie", expired_cookie)
+            .body_json(&challenge)
+            .unwrap());
+    }
+    let token = encode_token(user.id);
+
+    let response = UserResponse::from((user, token));
+    Ok(Response::new(200)
+        .set_header("Set-Cookie", expired_cookie)
+        .body_json(&response)
+        .unwrap())
+}
@@ -0,0 +1,29 @@
+//! Login with external identity providers, using the OpenID Connect authorization code flow
+//! with PKCE.
+pub mod client;
+pub mod login;
+
+pub use login::{authorize, callback};
+
+/// An OpenID Connect provider users can log in with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OidcProvider {
+    /// Identifies the provider in our URLs, e.g. `/api/auth/<name>/authorize`.
+    pub name: String,
+    /// Expected as the `iss` claim of the ID tokens.
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_endpoint: String,
+    /// Must be an `https` URL, like `jwks_uri`.
+    pub token_endpoint: String,
+    /// Where the provider publishes the keys it signs ID tokens with.
+    pub jwks_uri: String,
+    /// A PEM certificate trusted on top of the system ones to reach the provider,
+    /// for providers using a private certificate authority.
+    pub ca_certificate: Option<String>,
+    /// Where the provider sends users back to: our `/api/auth/<name>/callback` endpoint.
+    pub redirect_uri: String,
+    /// Requested on top of `openid`.
+    pub scopes: Vec<String>,
+}
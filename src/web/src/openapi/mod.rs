@@ -0,0 +1,705 @@
+//! The OpenAPI 3 specification of the API, served at `/api/openapi.json`.
+//!
+//! It is generated from the request and response types (see `Schema`) and from the
+//! operations listed in `operations`, which have to match the routes registered by
+//! `app::add_routes`. A copy is checked in at `src/web/openapi.json`, for client generators
+//! and reviewers: the tests fail when it gets out of date.
+mod operations;
+mod schema;
+
+pub use schema::{schema_of, string_enum, Components, Object, Schema};
+
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use domain::Scope;
+use serde_json::{json, Map, Value};
+use tide::{Request, Response};
+
+pub const OPENAPI_ROUTE: &str = "/api/openapi.json";
+
+/// Who can call an operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Auth {
+    Anonymous,
+    /// Authenticated callers get a personalised response.
+    Optional,
+    /// A session, or an access token with any scope.
+    Required,
+    /// A session, or an access token with the specified scope.
+    Scoped(Scope),
+    /// A session: access tokens are rejected.
+    Session,
+    /// The token Prometheus is configured with, rather than a user.
+    MetricsToken,
+}
+
+/// An endpoint of the API: a method on a route of `app::ROUTES`.
+#[derive(Clone)]
+pub struct Operation {
+    pub method: &'static str,
+    pub route: &'static str,
+    id: &'static str,
+    summary: &'static str,
+    auth: Auth,
+    parameters: Vec<Value>,
+    request_body: Option<Value>,
+    responses: Vec<(u16, Value)>,
+}
+
+impl Operation {
+    /// `method` is lowercase, `route` a template as passed to `Server::at`.
+    pub fn new(
+        method: &'static str,
+        route: &'static str,
+        id: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        let parameters = route
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        Self {
+            method,
+            route,
+            id,
+            summary,
+            auth: Auth::Anonymous,
+            parameters,
+            request_body: None,
+            responses: Vec::new(),
+        }
+    }
+
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Replace the default string schema of the path parameter `name`.
+    pub fn path_schema(mut self, name: &str, schema: Value) -> Self {
+        for parameter in &mut self.parameters {
+            if parameter["in"] == "path" && parameter["name"] == name {
+                parameter["schema"] = schema.clone();
+            }
+        }
+        self
+    }
+
+    pub fn query(mut self, name: &'static str, schema: Value, description: &'static str) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": "query",
+            "required": false,
+            "description": description,
+            "schema": schema,
+        }));
+        self
+    }
+
+    pub fn body(mut self, schema: Value) -> Self {
+        self.request_body = Some(json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema } },
+        }));
+        self
+    }
+
+    pub fn response(mut self, status: u16, description: &'static str, schema: Value) -> Self {
+        self.responses.push((
+            status,
+            json!({
+                "description": description,
+                "content": { "application/json": { "schema": schema } },
+            }),
+        ));
+        self
+    }
+
+    /// A response with an empty body, or a non-JSON one.
+    pub fn empty_response(mut self, status: u16, description: &'static str) -> Self {
+        self.responses
+            .push((status, json!({ "description": description })));
+        self
+    }
+
+    /// Errors come with a plain-text explanation.
+    pub fn error(mut self, status: u16, description: &'static str) -> Self {
+        self.responses.push((
+            status,
+            json!({
+                "description": description,
+                "content": { "text/plain": { "schema": { "type": "string" } } },
+            }),
+        ));
+        self
+    }
+
+    /// The path of the operation, in OpenAPI syntax: `/api/articles/{slug}`.
+    pub fn path(&self) -> String {
+        openapi_path(self.route)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut operation = self.with_implied_errors();
+        let mut description = String::new();
+        let security = match operation.auth {
+            Auth::Anonymous => None,
+            Auth::Optional => Some(json!([{}, { "token": [] }, { "accessToken": [] }])),
+            Auth::Required => Some(json!([{ "token": [] }, { "accessToken": [] }])),
+            Auth::Scoped(scope) => {
+                description = format!("Access tokens need the `{}` scope.", scope.as_str());
+                Some(json!([{ "token": [] }, { "accessToken": [] }]))
+            }
+            Auth::Session => {
+                description = "Access tokens are rejected.".into();
+                Some(json!([{ "token": [] }]))
+            }
+            Auth::MetricsToken => Some(json!([{ "metricsToken": [] }])),
+        };
+        operation.responses.sort_by_key(|(status, _)| *status);
+        let responses: Map<String, Value> = operation
+            .responses
+            .into_iter()
+            .map(|(status, response)| (status.to_string(), response))
+            .collect();
+
+        let mut json = json!({
+            "operationId": self.id,
+            "summary": self.summary,
+            "responses": responses,
+        });
+        if !description.is_empty() {
+            json["description"] = description.into();
+        }
+        if let Some(security) = security {
+            json["security"] = security;
+        }
+        if !operation.parameters.is_empty() {
+            json["parameters"] = Value::Array(operation.parameters);
+        }
+        if let Some(body) = operation.request_body {
+            json["requestBody"] = body;
+        }
+        json
+    }
+
+    /// Add the errors common to every operation with a body, path parameters or authentication.
+    fn with_implied_errors(&self) -> Self {
+        let mut operation = self.clone();
+        let mut implied = Vec::new();
+        if self.request_body.is_some() || self.parameters.iter().any(|p| p["in"] == "query") {
+            implied.push((400, "Malformed request."));
+        }
+        if !matches!(self.auth, Auth::Anonymous | Auth::Optional) {
+            implied.push((401, "Missing or invalid credentials."));
+        }
+        if matches!(self.auth, Auth::Scoped(_) | Auth::Session) {
+            implied.push((403, "The credentials do not allow this operation."));
+        }
+        if self.parameters.iter().any(|p| p["in"] == "path") {
+            implied.push((404, "Not found."));
+        }
+        for (status, description) in implied {
+            if !operation.responses.iter().any(|(s, _)| *s == status) {
+                operation = operation.error(status, description);
+            }
+        }
+        operation
+    }
+}
+
+/// Convert a route template from `:param` to `{param}` syntax.
+fn openapi_path(route: &str) -> String {
+    route
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The OpenAPI document describing the API.
+pub fn document() -> Value {
+    let mut components = Components::default();
+    let mut paths = Map::new();
+    for operation in operations::operations(&mut components) {
+        let path = paths.entry(operation.path()).or_insert_with(|| json!({}));
+        path[operation.method] = operation.to_json();
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Conduit",
+            "description": "The API of the RealWorld example app.\n\n\
+                Requests are rate limited: see the `X-RateLimit-*` response headers.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": components.into_schemas(),
+            "securitySchemes": {
+                "token": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "Authorization",
+                    "description": "A session: `Token <JWT>`, with the JWT returned on login.",
+                },
+                "accessToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "A personal access token, created with `POST /api/user/tokens`.",
+                },
+                "metricsToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The token the service is configured to serve its metrics with.",
+                },
+            },
+        },
+    })
+}
+
+pub async fn openapi<R: 'static + Repository + Sync + Send>(
+    _cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    Ok(Response::new(200).body_json(&document()).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ROUTES;
+    use crate::articles::responses::{
+        Article, ArticleResponse, ArticlesResponse, Author, Revision, RevisionResponse,
+        RevisionsResponse,
+    };
+    use crate::articles::status::Status;
+    use crate::articles::tags::TagsResponse;
+    use crate::articles::{insert, update};
+    use crate::comments::create;
+    use crate::comments::responses::{Comment, CommentResponse, CommentsResponse};
+    use crate::graphql::{GraphQLRequest, GraphQLResponse};
+    use crate::notifications::responses::{
+        Notification, NotificationArticle, NotificationsResponse,
+    };
+    use crate::profiles::responses::{Profile, ProfileResponse, ProfilesResponse};
+    use crate::users::login::{AuthRequest, AuthUser, SecondFactorRequest};
+    use crate::users::password_reset::{
+        ConfirmPasswordResetRequest, ConfirmPasswordResetUser, PasswordResetRequest,
+        PasswordResetUser,
+    };
+    use crate::users::register::{NewUserRequest, RegistrationRequest};
+    use crate::users::responses::{
+        AccessTokenResponse, AccessTokensResponse, NewAccessToken, NewAccessTokenResponse,
+        RecoveryCodesResponse, TwoFactorChallengeResponse, TwoFactorEnrollment,
+        TwoFactorEnrollmentResponse, User, UserResponse,
+    };
+    use crate::users::two_factor::TwoFactorCodeRequest;
+    use crate::users::verify_email::VerifyEmailRequest;
+    use crate::users::{access_tokens, delete};
+    use chrono::Utc;
+    use serde::Serialize;
+    use std::collections::BTreeSet;
+    use uuid::Uuid;
+
+    const CHECKED_IN_SPEC: &str = include_str!("../../openapi.json");
+
+    #[test]
+    fn every_route_is_documented() {
+        let documented: BTreeSet<String> = operations::operations(&mut Components::default())
+            .iter()
+            .map(|operation| operation.route.to_owned())
+            .collect();
+        let routes: BTreeSet<String> = ROUTES.iter().map(|route| (*route).to_owned()).collect();
+        assert_eq!(documented, routes);
+    }
+
+    #[test]
+    fn operations_are_unique() {
+        let operations = operations::operations(&mut Components::default());
+        let mut keys = BTreeSet::new();
+        let mut ids = BTreeSet::new();
+        for operation in &operations {
+            assert!(
+                keys.insert((operation.route, operation.method)),
+                "{} {} is documented twice",
+                operation.method,
+                operation.route
+            );
+            assert!(ids.insert(operation.id), "Duplicated id {}", operation.id);
+        }
+    }
+
+    /// Run with `UPDATE_OPENAPI=1` to regenerate `src/web/openapi.json`, then review the diff.
+    #[test]
+    fn the_checked_in_specification_is_up_to_date() {
+        let generated = document();
+        let checked_in: Value = serde_json::from_str(CHECKED_IN_SPEC).unwrap_or(Value::Null);
+        if generated == checked_in {
+            return;
+        }
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+            let spec = serde_json::to_string_pretty(&generated).unwrap() + "\n";
+            std::fs::write(path, spec).unwrap();
+        } else {
+            panic!(
+                "src/web/openapi.json is out of date: regenerate it with \
+                 `UPDATE_OPENAPI=1 cargo test -p realworld-web openapi`"
+            );
+        }
+    }
+
+    /// The properties and required fields of an object schema, following references.
+    fn object_fields(schema: &Value, schemas: &Value) -> (Map<String, Value>, Vec<Value>) {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return object_fields(&schemas[name], schemas);
+        }
+        let mut properties = schema["properties"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        let mut required = schema["required"].as_array().cloned().unwrap_or_default();
+        for part in schema["allOf"].as_array().into_iter().flatten() {
+            let (p, r) = object_fields(part, schemas);
+            properties.extend(p);
+            required.extend(r);
+        }
+        (properties, required)
+    }
+
+    /// Check that `value` is described by `schema`, returning the mismatches.
+    fn mismatches(value: &Value, schema: &Value, schemas: &Value, at: &str) -> Vec<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return mismatches(value, &schemas[name], schemas, at);
+        }
+        if value.is_null() && schema["nullable"] == true {
+            return vec![];
+        }
+        match value {
+            Value::Object(object) => {
+                let (properties, required) = object_fields(schema, schemas);
+                let mut found: Vec<String> = required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|field| !object.contains_key(*field))
+                    .map(|field| format!("{}.{} is missing", at, field))
+                    .collect();
+                for (field, value) in object {
+                    let at = format!("{}.{}", at, field);
+                    match properties.get(field) {
+                        Some(schema) => found.extend(mismatches(value, schema, schemas, &at)),
+                        None => found.push(format!("{} is not documented", at)),
+                    }
+                }
+                found
+            }
+            Value::Array(items) => items
+                .iter()
+                .flat_map(|item| mismatches(item, &schema["items"], schemas, at))
+                .collect(),
+            _ => {
+                // Nullable references are wrapped in an `allOf`
+                if let Some(expected) = schema["allOf"].get(0) {
+                    return mismatches(value, expected, schemas, at);
+                }
+                let type_matches = match schema["type"].as_str() {
+                    Some("string") => value.is_string(),
+                    Some("integer") => value.is_u64() || value.is_i64(),
+                    Some("boolean") => value.is_boolean(),
+                    _ => false,
+                };
+                let allowed = schema["enum"]
+                    .as_array()
+                    .is_none_or(|values| values.contains(value));
+                if type_matches && allowed {
+                    vec![]
+                } else {
+                    vec![format!("{} = {} does not match {}", at, value, schema)]
+                }
+            }
+        }
+    }
+
+    /// Check `example` against the schema of its type, which is returned.
+    fn assert_documented<T: Schema + Serialize>(example: T) -> Value {
+        let mut components = Components::default();
+        let schema = schema_of::<T>(&mut components);
+        let schemas = components.into_schemas();
+        let value = serde_json::to_value(example).unwrap();
+        let found = mismatches(&value, &schema, &schemas, "$");
+        assert!(found.is_empty(), "{:#?}", found);
+        schema
+    }
+
+    /// The schemas of the JSON bodies of every operation, alternatives included.
+    fn body_schemas(document: &Value) -> Vec<Value> {
+        let operations = document["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .flat_map(|path| path.as_object().unwrap().values());
+        let mut schemas = Vec::new();
+        for operation in operations {
+            let responses = operation["responses"].as_object().unwrap().values();
+            for body in std::iter::once(&operation["requestBody"]).chain(responses) {
+                let schema = &body["content"]["application/json"]["schema"];
+                match schema["oneOf"].as_array() {
+                    Some(alternatives) => schemas.extend(alternatives.iter().cloned()),
+                    None if !schema.is_null() => schemas.push(schema.clone()),
+                    None => {}
+                }
+            }
+        }
+        schemas
+    }
+
+    #[test]
+    fn schemas_match_the_serialized_types() {
+        let author = Author {
+            username: "jake".into(),
+            bio: None,
+            image: Some("https://example.com/jake.png".into()),
+            following: true,
+        };
+        let article = Article {
+            title: "How to train your dragon".into(),
+            slug: "how-to-train-your-dragon".into(),
+            description: "Ever wonder how?".into(),
+            body: "You have to believe".into(),
+            favorited: false,
+            favorites_count: 3,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            author: author.clone(),
+            tag_list: vec!["dragons".into()],
+            status: Status::Scheduled,
+            publish_at: Some(Utc::now()),
+            snippet: Some("<mark>dragon</mark>".into()),
+        };
+        let revision = Revision {
+            id: 1,
+            editor: author.clone(),
+            title: None,
+            description: None,
+            body: Some("You have to believe harder".into()),
+            created_at: Utc::now(),
+        };
+        let comment = Comment {
+            id: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            body: "It takes a Jacobian".into(),
+            author: author.clone(),
+        };
+        let profile = Profile {
+            username: "jake".into(),
+            bio: None,
+            image: None,
+            following: false,
+            followers_count: Some(1),
+            following_count: Some(2),
+        };
+        let access_token = AccessTokenResponse {
+            id: Uuid::new_v4(),
+            name: "ci".into(),
+            scopes: vec!["articles:write".into()],
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+
+        let checked = vec![
+            // Responses
+            assert_documented(ArticleResponse {
+                article: article.clone(),
+            }),
+            assert_documented(ArticlesResponse {
+                articles: vec![article],
+                articles_count: 1,
+                next_cursor: Some("a-cursor".into()),
+                prev_cursor: None,
+            }),
+            assert_documented(RevisionResponse {
+                revision: revision.clone(),
+            }),
+            assert_documented(RevisionsResponse {
+                revisions: vec![revision],
+            }),
+            assert_documented(TagsResponse {
+                tags: vec!["dragons".into()],
+            }),
+            assert_documented(CommentResponse {
+                comment: comment.clone(),
+            }),
+            assert_documented(CommentsResponse {
+                comments: vec![comment],
+            }),
+            assert_documented(ProfileResponse {
+                profile: profile.clone(),
+            }),
+            assert_documented(ProfilesResponse {
+                profiles: vec![profile],
+                profiles_count: 1,
+            }),
+            assert_documented(NotificationsResponse {
+                notifications: vec![Notification {
+                    id: 1,
+                    kind: "comment".into(),
+                    actor: author,
+                    article: Some(NotificationArticle {
+                        slug: "how-to-train-your-dragon".into(),
+                        title: "How to train your dragon".into(),
+                    }),
+                    comment_id: Some(1),
+                    read: false,
+                    created_at: Utc::now(),
+                }],
+                unread_count: 1,
+            }),
+            assert_documented(UserResponse {
+                user: User {
+                    username: "jake".into(),
+                    email: "jake@jake.jake".into(),
+                    token: "a.jwt.token".into(),
+                    bio: Some("I work at statefarm".into()),
+                    image: None,
+                    email_verified: true,
+                },
+            }),
+            assert_documented(TwoFactorChallengeResponse::new("a.jwt.token".into(), 300)),
+            assert_documented(TwoFactorEnrollmentResponse {
+                two_factor: TwoFactorEnrollment {
+                    secret: "JBSWY3DPEHPK3PXP".into(),
+                    otpauth_uri: "otpauth://totp/conduit:jake?secret=JBSWY3DPEHPK3PXP".into(),
+                },
+            }),
+            assert_documented(RecoveryCodesResponse {
+                recovery_codes: vec!["a-recovery-code".into()],
+            }),
+            assert_documented(AccessTokensResponse {
+                tokens: vec![access_token.clone()],
+            }),
+            assert_documented(NewAccessTokenResponse {
+                token: NewAccessToken {
+                    details: access_token,
+                    secret: "cdt_secret".into(),
+                },
+            }),
+            assert_documented(GraphQLResponse {
+                data: Some(json!({})),
+                errors: vec![json!({ "message": "Unknown field `foo` on type `Query`." })],
+            }),
+            // Requests
+            assert_documented(RegistrationRequest {
+                user: NewUserRequest {
+                    username: "jake".into(),
+                    email: "jake@jake.jake".into(),
+                    password: "jakejake".into(),
+                },
+            }),
+            assert_documented(AuthRequest {
+                user: AuthUser {
+                    email: "jake@jake.jake".into(),
+                    password: "jakejake".into(),
+                },
+            }),
+            assert_documented(SecondFactorRequest {
+                token: "a.jwt.token".into(),
+                code: "123456".into(),
+            }),
+            assert_documented(TwoFactorCodeRequest {
+                code: "123456".into(),
+            }),
+            assert_documented(VerifyEmailRequest {
+                token: "a.jwt.token".into(),
+            }),
+            assert_documented(PasswordResetRequest {
+                user: PasswordResetUser {
+                    email: "jake@jake.jake".into(),
+                },
+            }),
+            assert_documented(ConfirmPasswordResetRequest {
+                user: ConfirmPasswordResetUser {
+                    token: "a-reset-token".into(),
+                    password: "a-new-password".into(),
+                },
+            }),
+            assert_documented(crate::users::update::Request {
+                user: crate::users::update::UpdateUserRequest {
+                    email: None,
+                    username: None,
+                    password: None,
+                    image: Some("https://example.com/jake.png".into()),
+                    bio: Some("I like to skateboard".into()),
+                },
+            }),
+            assert_documented(delete::Request {
+                user: delete::DeleteUserRequest {
+                    password: "jakejake".into(),
+                    content: "anonymize".into(),
+                },
+            }),
+            assert_documented(access_tokens::Request {
+                token: access_tokens::NewAccessTokenRequest {
+                    name: "ci".into(),
+                    scopes: vec!["articles:write".into()],
+                },
+            }),
+            assert_documented(insert::Request {
+                article: insert::NewArticleRequest {
+                    title: "How to train your dragon".into(),
+                    description: "Ever wonder how?".into(),
+                    body: "You have to believe".into(),
+                    tag_list: Some(vec!["dragons".into()]),
+                    status: Some(Status::Draft),
+                    publish_at: None,
+                },
+            }),
+            assert_documented(update::Request {
+                article: update::UpdateArticleRequest {
+                    title: Some("How to train your dragon, again".into()),
+                    description: None,
+                    body: None,
+                    status: Some(Status::Published),
+                    publish_at: None,
+                },
+            }),
+            assert_documented(create::Request {
+                comment: create::NewCommentRequest {
+                    body: "It takes a Jacobian".into(),
+                },
+            }),
+            assert_documented(GraphQLRequest {
+                query: "{ articles { slug } }".into(),
+                variables: None,
+                operation_name: None,
+            }),
+        ];
+
+        // Every body of the document is covered by an example
+        for schema in body_schemas(&document()) {
+            assert!(checked.contains(&schema), "No example of {}", schema);
+        }
+    }
+
+    #[test]
+    fn route_templates_are_converted() {
+        assert_eq!(
+            openapi_path("/api/articles/:slug/comments/:id"),
+            "/api/articles/{slug}/comments/{id}"
+        );
+        assert_eq!(openapi_path("/api/tags"), "/api/tags");
+    }
+}
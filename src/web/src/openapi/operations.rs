@@ -0,0 +1,599 @@
+use super::{schema_of, string_enum, Auth, Components, Operation};
+use crate::articles::responses::{
+    ArticleResponse, ArticlesResponse, RevisionResponse, RevisionsResponse,
+};
+use crate::articles::tags::TagsResponse;
+use crate::articles::{insert, update};
+use crate::comments::create;
+use crate::comments::responses::{CommentResponse, CommentsResponse};
+use crate::graphql::{GraphQLRequest, GraphQLResponse, GRAPHQL_ROUTE};
+use crate::notifications::responses::NotificationsResponse;
+use crate::profiles::responses::{ProfileResponse, ProfilesResponse};
+use crate::users::access_tokens;
+use crate::users::delete::Request as DeleteUserRequest;
+use crate::users::login::{AuthRequest, SecondFactorRequest};
+use crate::users::password_reset::{ConfirmPasswordResetRequest, PasswordResetRequest};
+use crate::users::register::RegistrationRequest;
+use crate::users::responses::{
+    AccessTokensResponse, NewAccessTokenResponse, RecoveryCodesResponse,
+    TwoFactorChallengeResponse, TwoFactorEnrollmentResponse, UserResponse,
+};
+use crate::users::two_factor::TwoFactorCodeRequest;
+use crate::users::verify_email::VerifyEmailRequest;
+use domain::Scope;
+use serde_json::{json, Value};
+
+fn integer() -> Value {
+    json!({ "type": "integer", "format": "int64", "minimum": 0 })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+/// Every operation of the API, grouped as in `app::add_routes`.
+pub fn operations(c: &mut Components) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    operations.extend(users(c));
+    operations.extend(external_logins(c));
+    operations.extend(profiles(c));
+    operations.extend(articles(c));
+    operations.extend(comments(c));
+    operations.extend(notifications(c));
+    operations.push(
+        Operation::new("get", "/metrics", "getMetrics", "Prometheus metrics")
+            .auth(Auth::MetricsToken)
+            .empty_response(200, "The metrics, in the Prometheus text format.")
+            .error(404, "No metrics token is configured."),
+    );
+    operations.push(
+        Operation::new("get", super::OPENAPI_ROUTE, "getOpenApi", "This document")
+            .empty_response(200, "The OpenAPI specification of the API."),
+    );
+    operations.push(
+        Operation::new(
+            "post",
+            GRAPHQL_ROUTE,
+            "graphql",
+            "Execute a GraphQL operation",
+        )
+        .auth(Auth::Optional)
+        .body(schema_of::<GraphQLRequest>(c))
+        .response(
+            200,
+            "The operation was executed: fields which failed are `null`, with an error.",
+            schema_of::<GraphQLResponse>(c),
+        )
+        .response(
+            400,
+            "The operation could not be parsed or selected.",
+            schema_of::<GraphQLResponse>(c),
+        ),
+    );
+    operations
+}
+
+fn users(c: &mut Components) -> Vec<Operation> {
+    vec![
+        Operation::new("get", "/api/user", "getCurrentUser", "Get the current user")
+            .auth(Auth::Session)
+            .response(200, "The current user.", schema_of::<UserResponse>(c)),
+        Operation::new(
+            "put",
+            "/api/user",
+            "updateCurrentUser",
+            "Update the current user",
+        )
+        .auth(Auth::Session)
+        .body(schema_of::<crate::users::update::Request>(c))
+        .response(200, "The updated user.", schema_of::<UserResponse>(c)),
+        Operation::new(
+            "delete",
+            "/api/user",
+            "deleteCurrentUser",
+            "Delete the current user",
+        )
+        .auth(Auth::Session)
+        .body(schema_of::<DeleteUserRequest>(c))
+        .empty_response(200, "The user was deleted.")
+        .error(403, "Wrong password."),
+        Operation::new(
+            "post",
+            "/api/user/verification-email",
+            "resendVerificationEmail",
+            "Send the email address verification email again",
+        )
+        .auth(Auth::Session)
+        .empty_response(202, "The email will be sent."),
+        Operation::new(
+            "post",
+            "/api/user/two-factor",
+            "enrollTwoFactor",
+            "Start enabling two-factor authentication",
+        )
+        .auth(Auth::Session)
+        .response(
+            200,
+            "The secret to register in an authenticator app.",
+            schema_of::<TwoFactorEnrollmentResponse>(c),
+        )
+        .error(409, "Two-factor authentication is already enabled."),
+        Operation::new(
+            "delete",
+            "/api/user/two-factor",
+            "disableTwoFactor",
+            "Disable two-factor authentication",
+        )
+        .auth(Auth::Session)
+        .body(schema_of::<TwoFactorCodeRequest>(c))
+        .empty_response(200, "Two-factor authentication was disabled.")
+        .error(409, "Two-factor authentication is not enabled.")
+        .error(422, "Invalid code."),
+        Operation::new(
+            "post",
+            "/api/user/two-factor/confirm",
+            "confirmTwoFactor",
+            "Enable two-factor authentication",
+        )
+        .auth(Auth::Session)
+        .body(schema_of::<TwoFactorCodeRequest>(c))
+        .response(
+            200,
+            "The recovery codes: they are only returned once.",
+            schema_of::<RecoveryCodesResponse>(c),
+        )
+        .error(
+            409,
+            "Two-factor authentication is already enabled, or not enrolled.",
+        )
+        .error(422, "Invalid code."),
+        Operation::new(
+            "get",
+            "/api/user/tokens",
+            "listAccessTokens",
+            "List access tokens",
+        )
+        .auth(Auth::Session)
+        .response(
+            200,
+            "The access tokens of the current user, the most recent first.",
+            schema_of::<AccessTokensResponse>(c),
+        ),
+        Operation::new(
+            "post",
+            "/api/user/tokens",
+            "createAccessToken",
+            "Create an access token",
+        )
+        .auth(Auth::Session)
+        .body(schema_of::<access_tokens::Request>(c))
+        .response(
+            201,
+            "The token: its secret is only returned once.",
+            schema_of::<NewAccessTokenResponse>(c),
+        ),
+        Operation::new(
+            "delete",
+            "/api/user/tokens/:id",
+            "revokeAccessToken",
+            "Revoke an access token",
+        )
+        .auth(Auth::Session)
+        .path_schema("id", json!({ "type": "string", "format": "uuid" }))
+        .empty_response(200, "The token was revoked."),
+        Operation::new(
+            "get",
+            "/api/user/drafts",
+            "listDrafts",
+            "List the current user's drafts",
+        )
+        .auth(Auth::Required)
+        .response(
+            200,
+            "The drafts of the current user.",
+            schema_of::<ArticlesResponse>(c),
+        ),
+        Operation::new("post", "/api/users", "register", "Sign up")
+            .body(schema_of::<RegistrationRequest>(c))
+            .response(200, "The new user.", schema_of::<UserResponse>(c)),
+        Operation::new("post", "/api/users/login", "login", "Log in")
+            .body(schema_of::<AuthRequest>(c))
+            .response(
+                200,
+                "The user or, if they enabled two-factor authentication, a challenge.",
+                json!({
+                    "oneOf": [
+                        schema_of::<UserResponse>(c),
+                        schema_of::<TwoFactorChallengeResponse>(c),
+                    ]
+                }),
+            )
+            .error(401, "Wrong email or password.")
+            .error(429, "Too many failed attempts."),
+        Operation::new(
+            "post",
+            "/api/users/login/two-factor",
+            "loginSecondFactor",
+            "Complete a login with two-factor authentication",
+        )
+        .body(schema_of::<SecondFactorRequest>(c))
+        .response(200, "The user.", schema_of::<UserResponse>(c))
+        .error(401, "Invalid challenge or code.")
+        .error(429, "Too many failed attempts."),
+        Operation::new(
+            "post",
+            "/api/users/verify-email",
+            "verifyEmail",
+            "Verify an email address",
+        )
+        .body(schema_of::<VerifyEmailRequest>(c))
+        .empty_response(200, "The email address was verified."),
+        Operation::new(
+            "post",
+            "/api/users/password-reset",
+            "requestPasswordReset",
+            "Request a password reset email",
+        )
+        .body(schema_of::<PasswordResetRequest>(c))
+        .empty_response(
+            202,
+            "An email will be sent if the address belongs to a user.",
+        ),
+        Operation::new(
+            "post",
+            "/api/users/password-reset/confirm",
+            "confirmPasswordReset",
+            "Set a new password",
+        )
+        .body(schema_of::<ConfirmPasswordResetRequest>(c))
+        .empty_response(200, "The password was changed.")
+        .error(400, "Invalid token."),
+    ]
+}
+
+fn external_logins(c: &mut Components) -> Vec<Operation> {
+    vec![
+        Operation::new(
+            "get",
+            "/api/auth/:provider/authorize",
+            "authorizeExternalLogin",
+            "Log in with an OpenID Connect provider",
+        )
+        .empty_response(302, "A redirection to the provider."),
+        Operation::new(
+            "get",
+            "/api/auth/:provider/callback",
+            "externalLoginCallback",
+            "Complete a login with an OpenID Connect provider",
+        )
+        .query("state", string(), "As returned by the provider.")
+        .query("code", string(), "As returned by the provider.")
+        .query(
+            "error",
+            string(),
+            "Set by the provider instead of `code` on failure.",
+        )
+        .response(
+            200,
+            "The user or, if they enabled two-factor authentication, a challenge.",
+            json!({
+                "oneOf": [
+                    schema_of::<UserResponse>(c),
+                    schema_of::<TwoFactorChallengeResponse>(c),
+                ]
+            }),
+        )
+        .error(401, "The provider did not authenticate the user.")
+        .error(409, "The email address belongs to another user.")
+        .error(422, "The provider did not share a verified email address."),
+    ]
+}
+
+fn profiles(c: &mut Components) -> Vec<Operation> {
+    let mut operations = vec![Operation::new(
+        "get",
+        "/api/profiles/:username",
+        "getProfile",
+        "Get a profile",
+    )
+    .auth(Auth::Optional)
+    .response(200, "The profile.", schema_of::<ProfileResponse>(c))];
+    for &(route, id, summary) in &[
+        (
+            "/api/profiles/:username/followers",
+            "listFollowers",
+            "List the followers of a user",
+        ),
+        (
+            "/api/profiles/:username/following",
+            "listFollowing",
+            "List the users a user follows",
+        ),
+    ] {
+        operations.push(
+            Operation::new("get", route, id, summary)
+                .auth(Auth::Optional)
+                .query("limit", integer(), "Defaults to 20.")
+                .query("offset", integer(), "Defaults to 0.")
+                .response(
+                    200,
+                    "The profiles, sorted by username.",
+                    schema_of::<ProfilesResponse>(c),
+                ),
+        );
+    }
+    for &(method, route, id, summary) in &[
+        (
+            "post",
+            "/api/profiles/:username/follow",
+            "followUser",
+            "Follow a user",
+        ),
+        (
+            "delete",
+            "/api/profiles/:username/follow",
+            "unfollowUser",
+            "Unfollow a user",
+        ),
+        (
+            "post",
+            "/api/profiles/:username/block",
+            "blockUser",
+            "Block a user",
+        ),
+        (
+            "delete",
+            "/api/profiles/:username/block",
+            "unblockUser",
+            "Unblock a user",
+        ),
+        (
+            "post",
+            "/api/profiles/:username/mute",
+            "muteUser",
+            "Mute a user",
+        ),
+        (
+            "delete",
+            "/api/profiles/:username/mute",
+            "unmuteUser",
+            "Unmute a user",
+        ),
+    ] {
+        operations.push(
+            Operation::new(method, route, id, summary)
+                .auth(Auth::Scoped(Scope::ProfilesWrite))
+                .response(200, "The profile.", schema_of::<ProfileResponse>(c))
+                .error(422, "Users cannot target themselves."),
+        );
+    }
+    operations
+}
+
+fn articles(c: &mut Components) -> Vec<Operation> {
+    let pagination = |operation: Operation| {
+        operation
+            .query("limit", integer(), "Defaults to 20, at most 100.")
+            .query("offset", integer(), "Defaults to 0.")
+            .query(
+                "cursor",
+                string(),
+                "An opaque cursor, as returned in `nextCursor` or `prevCursor`.",
+            )
+    };
+    vec![
+        Operation::new("get", "/api/tags", "listTags", "List tags").response(
+            200,
+            "All the tags.",
+            schema_of::<TagsResponse>(c),
+        ),
+        pagination(
+            Operation::new("get", "/api/articles", "listArticles", "List articles")
+                .auth(Auth::Optional)
+                .query("author", string(), "Only the articles of this user.")
+                .query(
+                    "favorited",
+                    string(),
+                    "Only the articles favorited by this user.",
+                )
+                .query("tag", string(), "Only the articles with this tag.")
+                .query("q", string(), "Full-text search."),
+        )
+        .query(
+            "sort",
+            string_enum(&[
+                "newest",
+                "oldest",
+                "most-favorited",
+                "most-commented",
+                "recently-updated",
+            ]),
+            "Defaults to `newest`, or to relevance for full-text searches.",
+        )
+        .response(200, "The articles.", schema_of::<ArticlesResponse>(c)),
+        Operation::new(
+            "post",
+            "/api/articles",
+            "createArticle",
+            "Create an article",
+        )
+        .auth(Auth::Scoped(Scope::ArticlesWrite))
+        .body(schema_of::<insert::Request>(c))
+        .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+        pagination(
+            Operation::new(
+                "get",
+                "/api/articles/feed",
+                "getFeed",
+                "List followed articles",
+            )
+            .auth(Auth::Required),
+        )
+        .response(
+            200,
+            "The articles of the users followed by the current user.",
+            schema_of::<ArticlesResponse>(c),
+        ),
+        Operation::new("get", "/api/articles/:slug", "getArticle", "Get an article")
+            .auth(Auth::Optional)
+            .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+        Operation::new(
+            "put",
+            "/api/articles/:slug",
+            "updateArticle",
+            "Update an article",
+        )
+        .auth(Auth::Scoped(Scope::ArticlesWrite))
+        .body(schema_of::<update::Request>(c))
+        .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+        Operation::new(
+            "delete",
+            "/api/articles/:slug",
+            "deleteArticle",
+            "Delete an article",
+        )
+        .auth(Auth::Scoped(Scope::ArticlesWrite))
+        .empty_response(200, "The article was deleted."),
+        Operation::new(
+            "get",
+            "/api/articles/:slug/revisions",
+            "listRevisions",
+            "List the revisions of an article",
+        )
+        .auth(Auth::Required)
+        .response(
+            200,
+            "The revisions, the oldest first.",
+            schema_of::<RevisionsResponse>(c),
+        ),
+        Operation::new(
+            "get",
+            "/api/articles/:slug/revisions/:id",
+            "getRevision",
+            "Get a revision of an article",
+        )
+        .auth(Auth::Required)
+        .path_schema("id", integer())
+        .response(200, "The revision.", schema_of::<RevisionResponse>(c)),
+        Operation::new(
+            "post",
+            "/api/articles/:slug/revisions/:id/restore",
+            "restoreRevision",
+            "Restore the content of an article as of a revision",
+        )
+        .auth(Auth::Scoped(Scope::ArticlesWrite))
+        .path_schema("id", integer())
+        .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+        Operation::new(
+            "post",
+            "/api/articles/:slug/favorite",
+            "favoriteArticle",
+            "Favorite an article",
+        )
+        .auth(Auth::Scoped(Scope::FavoritesWrite))
+        .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+        Operation::new(
+            "delete",
+            "/api/articles/:slug/favorite",
+            "unfavoriteArticle",
+            "Unfavorite an article",
+        )
+        .auth(Auth::Scoped(Scope::FavoritesWrite))
+        .response(200, "The article.", schema_of::<ArticleResponse>(c)),
+    ]
+}
+
+fn comments(c: &mut Components) -> Vec<Operation> {
+    vec![
+        Operation::new(
+            "get",
+            "/api/articles/:slug/comments",
+            "listComments",
+            "List the comments to an article",
+        )
+        .auth(Auth::Optional)
+        .response(200, "The comments.", schema_of::<CommentsResponse>(c)),
+        Operation::new(
+            "post",
+            "/api/articles/:slug/comments",
+            "createComment",
+            "Comment an article",
+        )
+        .auth(Auth::Scoped(Scope::CommentsWrite))
+        .body(schema_of::<create::Request>(c))
+        .response(200, "The comment.", schema_of::<CommentResponse>(c)),
+        Operation::new(
+            "delete",
+            "/api/articles/:slug/comments/:id",
+            "deleteComment",
+            "Delete a comment",
+        )
+        .auth(Auth::Scoped(Scope::CommentsWrite))
+        .path_schema("id", integer())
+        .empty_response(200, "The comment was deleted."),
+    ]
+}
+
+fn notifications(c: &mut Components) -> Vec<Operation> {
+    vec![
+        Operation::new(
+            "get",
+            "/api/notifications",
+            "listNotifications",
+            "List the notifications of the current user",
+        )
+        .auth(Auth::Scoped(Scope::NotificationsRead))
+        .query(
+            "unread",
+            json!({ "type": "boolean" }),
+            "Only the unread notifications. Defaults to false.",
+        )
+        .query("limit", integer(), "Defaults to 20.")
+        .query("offset", integer(), "Defaults to 0.")
+        .response(
+            200,
+            "The notifications, most recent first.",
+            schema_of::<NotificationsResponse>(c),
+        ),
+        Operation::new(
+            "post",
+            "/api/notifications/read",
+            "markAllNotificationsRead",
+            "Mark all the notifications of the current user as read",
+        )
+        .auth(Auth::Scoped(Scope::NotificationsRead))
+        .empty_response(200, "The notifications were marked as read."),
+        Operation::new(
+            "get",
+            "/api/notifications/stream",
+            "streamNotifications",
+            "Receive the notifications of the current user as they happen",
+        )
+        .auth(Auth::Scoped(Scope::NotificationsRead))
+        .empty_response(
+            200,
+            "A `text/event-stream` of `notification` events, whose data is a `Notification`. \
+             It ends when the credentials of the client stop being valid.",
+        ),
+        Operation::new(
+            "post",
+            "/api/notifications/:id/read",
+            "markNotificationRead",
+            "Mark a notification as read",
+        )
+        .auth(Auth::Scoped(Scope::NotificationsRead))
+        .path_schema("id", integer())
+        .empty_response(200, "The notification was marked as read.")
+        .error(404, "The notification does not exist."),
+        Operation::new(
+            "delete",
+            "/api/notifications/:id/read",
+            "markNotificationUnread",
+            "Mark a notification as unread",
+        )
+        .auth(Auth::Scoped(Scope::NotificationsRead))
+        .path_schema("id", integer())
+        .empty_response(200, "The notification was marked as unread.")
+        .error(404, "The notification does not exist."),
+    ]
+}
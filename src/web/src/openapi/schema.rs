@@ -0,0 +1,237 @@
+//! JSON schemas of the types exchanged with clients, as used in OpenAPI 3.0 documents.
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// A type with a documented JSON representation.
+///
+/// Implementations are kept next to the types they describe and have to mirror their
+/// `serde` attributes: field renames, `skip_serializing_if`, `flatten`, etc.
+pub trait Schema {
+    /// Named schemas are documented once, under `#/components/schemas`, and referenced elsewhere.
+    const NAME: Option<&'static str> = None;
+
+    fn schema(components: &mut Components) -> Value;
+}
+
+/// The named schemas of a document.
+#[derive(Default)]
+pub struct Components {
+    schemas: BTreeMap<&'static str, (&'static str, Value)>,
+}
+
+impl Components {
+    pub fn into_schemas(self) -> Value {
+        let schemas: Map<String, Value> = self
+            .schemas
+            .into_iter()
+            .map(|(name, (_, schema))| (name.to_owned(), schema))
+            .collect();
+        Value::Object(schemas)
+    }
+}
+
+/// The schema of `T`: a reference for named schemas, registered in `components` along the way.
+pub fn schema_of<T: Schema>(components: &mut Components) -> Value {
+    let name = match T::NAME {
+        Some(name) => name,
+        None => return T::schema(components),
+    };
+    let type_name = std::any::type_name::<T>();
+    match components.schemas.get(name) {
+        Some((registered, _)) if *registered != type_name => panic!(
+            "The schema name `{}` is used by both `{}` and `{}`",
+            name, registered, type_name
+        ),
+        Some(_) => {}
+        None => {
+            // Registered before being built, for recursive types
+            components.schemas.insert(name, (type_name, Value::Null));
+            let schema = T::schema(components);
+            components.schemas.insert(name, (type_name, schema));
+        }
+    }
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+/// Builds the schema of a JSON object.
+pub struct Object<'a> {
+    components: &'a mut Components,
+    description: Option<&'static str>,
+    properties: Map<String, Value>,
+    required: Vec<Value>,
+    all_of: Vec<Value>,
+}
+
+impl<'a> Object<'a> {
+    pub fn new(components: &'a mut Components) -> Self {
+        Self {
+            components,
+            description: None,
+            properties: Map::new(),
+            required: Vec::new(),
+            all_of: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// A field which is always present (though it can be `null`, for `Option`s).
+    pub fn field<T: Schema>(mut self, name: &'static str) -> Self {
+        self.required.push(name.into());
+        self.optional::<T>(name)
+    }
+
+    /// A field which is always present, with an ad-hoc schema (e.g. a `string_enum`).
+    pub fn field_schema(mut self, name: &'static str, schema: Value) -> Self {
+        self.required.push(name.into());
+        self.properties.insert(name.to_owned(), schema);
+        self
+    }
+
+    /// A field which can be left out.
+    pub fn optional<T: Schema>(mut self, name: &'static str) -> Self {
+        let schema = schema_of::<T>(self.components);
+        self.properties.insert(name.to_owned(), schema);
+        self
+    }
+
+    /// A field which can be left out, with an ad-hoc schema.
+    pub fn optional_schema(mut self, name: &'static str, schema: Value) -> Self {
+        self.properties.insert(name.to_owned(), schema);
+        self
+    }
+
+    /// The fields of `T`, for `#[serde(flatten)]`.
+    pub fn flatten<T: Schema>(mut self) -> Self {
+        let schema = schema_of::<T>(self.components);
+        self.all_of.push(schema);
+        self
+    }
+
+    pub fn build(self) -> Value {
+        let mut object = json!({
+            "type": "object",
+            "properties": self.properties,
+        });
+        if !self.required.is_empty() {
+            object["required"] = Value::Array(self.required);
+        }
+        let mut schema = if self.all_of.is_empty() {
+            object
+        } else {
+            let mut all_of = self.all_of;
+            all_of.push(object);
+            json!({ "allOf": all_of })
+        };
+        if let Some(description) = self.description {
+            schema["description"] = description.into();
+        }
+        schema
+    }
+}
+
+/// The schema of a string restricted to `values`, e.g. for unit-only enums.
+pub fn string_enum(values: &[&str]) -> Value {
+    json!({ "type": "string", "enum": values })
+}
+
+impl Schema for String {
+    fn schema(_: &mut Components) -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl Schema for bool {
+    fn schema(_: &mut Components) -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl Schema for u64 {
+    fn schema(_: &mut Components) -> Value {
+        json!({ "type": "integer", "format": "int64", "minimum": 0 })
+    }
+}
+
+impl Schema for DateTime<Utc> {
+    fn schema(_: &mut Components) -> Value {
+        json!({ "type": "string", "format": "date-time" })
+    }
+}
+
+impl Schema for Uuid {
+    fn schema(_: &mut Components) -> Value {
+        json!({ "type": "string", "format": "uuid" })
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "array", "items": schema_of::<T>(components) })
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema(components: &mut Components) -> Value {
+        let mut schema = schema_of::<T>(components);
+        // Siblings of `$ref` are ignored
+        if schema.get("$ref").is_some() {
+            schema = json!({ "allOf": [schema] });
+        }
+        schema["nullable"] = true.into();
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Node;
+
+    impl Schema for Node {
+        const NAME: Option<&'static str> = Some("Node");
+
+        fn schema(components: &mut Components) -> Value {
+            Object::new(components)
+                .field::<String>("name")
+                .optional::<Vec<Node>>("children")
+                .field::<Option<Node>>("parent")
+                .build()
+        }
+    }
+
+    #[test]
+    fn named_schemas_are_referenced() {
+        let mut components = Components::default();
+        assert_eq!(
+            schema_of::<Node>(&mut components),
+            json!({ "$ref": "#/components/schemas/Node" })
+        );
+        assert_eq!(
+            components.into_schemas(),
+            json!({
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "children": {
+                            "type": "array",
+                            "items": { "$ref": "#/components/schemas/Node" }
+                        },
+                        "parent": {
+                            "allOf": [{ "$ref": "#/components/schemas/Node" }],
+                            "nullable": true
+                        }
+                    },
+                    "required": ["name", "parent"]
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use crate::middleware::ContextExt;
+use crate::{Context, ErrorResponse};
+
+use crate::profiles::responses::ProfileResponse;
+use domain::repositories::Repository;
+use domain::Scope;
+use tide::{Request, Response};
+
+pub enum Action {
+    Block,
+    Unblock,
+    Mute,
+    Unmute,
+}
+
+pub async fn block<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _block(cx, Action::Block).await
+}
+
+pub async fn unblock<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _block(cx, Action::Unblock).await
+}
+
+pub async fn mute<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _block(cx, Action::Mute).await
+}
+
+pub async fn unmute<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _block(cx, Action::Unmute).await
+}
+
+async fn _block<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+    action: Action,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_claims_with_scope(Scope::ProfilesWrite)?.user_id();
+    let profile_username: String = cx.param("username").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let profile = repository.get_profile(&profile_username)?;
+    match action {
+        Action::Block => user.block(&profile, repository)?,
+        Action::Unblock => user.unblock(&profile, repository)?,
+        Action::Mute => user.mute(&profile, repository)?,
+        Action::Unmute => user.unmute(&profile, repository)?,
+    };
+
+    let view = repository.get_profile_view(&user, &profile.username)?;
+    let response = ProfileResponse::from(view);
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
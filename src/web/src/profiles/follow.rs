@@ -3,6 +3,7 @@ use crate::{Context, ErrorResponse};
 
 use crate::profiles::responses::ProfileResponse;
 use domain::repositories::Repository;
+use domain::Scope;
 use tide::{Request, Response};
 
 pub enum Action {
@@ -26,7 +27,7 @@ async fn _follow<R: 'static + Repository + Sync + Send>(
     cx: Request<Context<R>>,
     action: Action,
 ) -> Result<Response, ErrorResponse> {
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let user_id = cx.get_claims_with_scope(Scope::ProfilesWrite)?.user_id();
     let profile_username: String = cx.param("username").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
@@ -37,6 +38,7 @@ async fn _follow<R: 'static + Repository + Sync + Send>(
         Action::Unfollow => user.unfollow(profile, repository)?,
     };
 
-    let response = ProfileResponse::from(view);
+    let counts = view.profile.follow_counts(repository)?;
+    let response = ProfileResponse::from(view).with_counts(counts);
     Ok(Response::new(200).body_json(&response).unwrap())
 }
@@ -0,0 +1,82 @@
+use crate::middleware::ContextExt;
+use crate::profiles::responses::ProfilesResponse;
+use crate::{Context, ErrorResponse};
+use domain::repositories::Repository;
+use serde::{Deserialize, Serialize};
+use tide::{Request, Response};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct ProfilesQuery {
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+
+    #[serde(default)]
+    pub offset: u64,
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+impl Default for ProfilesQuery {
+    fn default() -> Self {
+        Self {
+            limit: default_limit(),
+            offset: 0,
+        }
+    }
+}
+
+impl From<ProfilesQuery> for domain::ProfilesQuery {
+    fn from(q: ProfilesQuery) -> Self {
+        Self {
+            limit: q.limit,
+            offset: q.offset,
+        }
+    }
+}
+
+pub enum Relation {
+    Followers,
+    Following,
+}
+
+pub async fn followers<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _list(cx, Relation::Followers).await
+}
+
+pub async fn following<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    _list(cx, Relation::Following).await
+}
+
+async fn _list<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+    relation: Relation,
+) -> Result<Response, ErrorResponse> {
+    let user_id: Option<Uuid> = cx.get_claims().map(|c| c.user_id()).ok();
+    let profile_username: String = cx.param("username").map_err(|_| Response::new(400))?;
+    // This can be avoided once https://github.com/http-rs/tide/pull/384 gets merged
+    let query = cx.query::<ProfilesQuery>().unwrap_or_default();
+    let repository = &cx.state().repository;
+
+    let profile = repository.get_profile(&profile_username)?;
+    let profiles = match relation {
+        Relation::Followers => profile.followers(query.into(), repository)?,
+        Relation::Following => profile.following(query.into(), repository)?,
+    };
+
+    let response = match user_id {
+        Some(user_id) => {
+            let user = repository.get_user_by_id(user_id)?;
+            let views = repository.get_profiles_views(&user, profiles)?;
+            ProfilesResponse::from(views)
+        }
+        None => ProfilesResponse::from(profiles),
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
@@ -12,17 +12,20 @@ pub async fn get_profile<R: 'static + Repository + Sync + Send>(
     let profile_username: String = cx.param("username").map_err(|_| Response::new(400))?;
     let repository = &cx.state().repository;
 
-    let response: ProfileResponse = match user_id {
+    let (response, counts) = match user_id {
         Some(user_id) => {
             let user = repository.get_user_by_id(user_id)?;
             let view = repository.get_profile_view(&user, &profile_username)?;
-            ProfileResponse::from(view)
+            let counts = view.profile.follow_counts(repository)?;
+            (ProfileResponse::from(view), counts)
         }
         None => {
             let profile = repository.get_profile(&profile_username)?;
-            ProfileResponse::from(profile)
+            let counts = profile.follow_counts(repository)?;
+            (ProfileResponse::from(profile), counts)
         }
     };
+    let response = response.with_counts(counts);
 
     Ok(Response::new(200).body_json(&response).unwrap())
 }
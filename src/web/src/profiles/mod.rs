@@ -1,6 +1,10 @@
+pub mod block;
 pub mod follow;
+pub mod followers;
 pub mod get;
 pub mod responses;
 
+pub use block::{block, mute, unblock, unmute};
 pub use follow::{follow, unfollow};
+pub use followers::{followers, following};
 pub use get::get_profile;
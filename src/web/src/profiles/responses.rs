@@ -1,16 +1,81 @@
+use crate::openapi::{Components, Object, Schema};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct ProfileResponse {
     pub profile: Profile,
 }
 
+impl Schema for ProfileResponse {
+    const NAME: Option<&'static str> = Some("ProfileResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<Profile>("profile").build()
+    }
+}
+
+impl ProfileResponse {
+    pub fn with_counts(mut self, counts: domain::FollowCounts) -> Self {
+        self.profile.followers_count = Some(counts.followers);
+        self.profile.following_count = Some(counts.following);
+        self
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProfilesResponse {
+    pub profiles: Vec<Profile>,
+    #[serde(rename = "profilesCount")]
+    pub profiles_count: u64,
+}
+
+impl Schema for ProfilesResponse {
+    const NAME: Option<&'static str> = Some("ProfilesResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<Profile>>("profiles")
+            .field::<u64>("profilesCount")
+            .build()
+    }
+}
+
+impl<T: Into<ProfileResponse>> From<Vec<T>> for ProfilesResponse {
+    fn from(profiles: Vec<T>) -> Self {
+        let profiles: Vec<Profile> = profiles.into_iter().map(|p| p.into().profile).collect();
+        Self {
+            profiles_count: profiles.len() as u64,
+            profiles,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Profile {
     pub username: String,
     pub bio: Option<String>,
     pub image: Option<String>,
     pub following: bool,
+    #[serde(rename = "followersCount", skip_serializing_if = "Option::is_none")]
+    pub followers_count: Option<u64>,
+    #[serde(rename = "followingCount", skip_serializing_if = "Option::is_none")]
+    pub following_count: Option<u64>,
+}
+
+impl Schema for Profile {
+    const NAME: Option<&'static str> = Some("Profile");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("username")
+            .field::<Option<String>>("bio")
+            .field::<Option<String>>("image")
+            .field::<bool>("following")
+            .optional::<u64>("followersCount")
+            .optional::<u64>("followingCount")
+            .build()
+    }
 }
 
 impl From<domain::Profile> for ProfileResponse {
@@ -21,6 +86,8 @@ impl From<domain::Profile> for ProfileResponse {
                 bio: p.bio,
                 image: p.image,
                 following: false,
+                followers_count: None,
+                following_count: None,
             },
         }
     }
@@ -34,6 +101,8 @@ impl From<domain::ProfileView> for ProfileResponse {
                 bio: p.profile.bio,
                 image: p.profile.image,
                 following: p.following,
+                followers_count: None,
+                following_count: None,
             },
         }
     }
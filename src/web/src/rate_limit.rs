@@ -0,0 +1,294 @@
+//! Token-bucket rate limiting of API calls, per authenticated user or per IP address.
+use futures::future::BoxFuture;
+use http::Method;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tide::{Middleware, Next, Request, Response};
+
+use crate::auth::Claims;
+use crate::client_address::client_ip;
+
+/// A bucket holding up to `requests` tokens, refilled at a pace of `requests` every `per`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    fn refill_per_second(&self) -> f64 {
+        f64::from(self.requests) / self.per.as_secs_f64()
+    }
+}
+
+/// The endpoints sharing the same rate limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    /// Publishing, editing and deleting articles.
+    Articles,
+    /// Posting and deleting comments.
+    Comments,
+    /// Favoriting and unfavoriting articles.
+    Favorites,
+    /// Everything else.
+    Default,
+}
+
+impl RouteGroup {
+    fn of(method: &Method, path: &str) -> Self {
+        if method == Method::GET || !path.starts_with("/api/articles") {
+            RouteGroup::Default
+        } else if path.ends_with("/favorite") {
+            RouteGroup::Favorites
+        } else if path.split('/').nth(4) == Some("comments") {
+            RouteGroup::Comments
+        } else {
+            RouteGroup::Articles
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RouteGroup::Articles => "articles",
+            RouteGroup::Comments => "comments",
+            RouteGroup::Favorites => "favorites",
+            RouteGroup::Default => "default",
+        }
+    }
+}
+
+/// The rate limit of each route group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimits {
+    pub articles: RateLimit,
+    pub comments: RateLimit,
+    pub favorites: RateLimit,
+    pub default: RateLimit,
+}
+
+impl RateLimits {
+    fn of(&self, group: RouteGroup) -> &RateLimit {
+        match group {
+            RouteGroup::Articles => &self.articles,
+            RouteGroup::Comments => &self.comments,
+            RouteGroup::Favorites => &self.favorites,
+            RouteGroup::Default => &self.default,
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        let per_minute = |requests| RateLimit {
+            requests,
+            per: Duration::from_secs(60),
+        };
+        Self {
+            articles: per_minute(10),
+            comments: per_minute(30),
+            favorites: per_minute(60),
+            default: per_minute(300),
+        }
+    }
+}
+
+/// The outcome of trying to take a token from a bucket.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    /// Tokens left in the bucket.
+    pub remaining: u32,
+    /// How long until the bucket is full again.
+    pub reset_after: Duration,
+    /// How long until the next token is available, if the bucket is empty.
+    pub retry_after: Option<Duration>,
+}
+
+/// Where the buckets live.
+///
+/// Implementations shared between instances of the application (e.g. Redis)
+/// make the limits apply to the whole deployment rather than to each instance.
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the bucket identified by `key`, which starts full.
+    fn take(&self, key: &str, limit: &RateLimit) -> Decision;
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    full_at: Instant,
+}
+
+/// Keeps the buckets in the memory of the current process.
+#[derive(Debug, Default)]
+pub struct InMemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+/// Full buckets carry no information: they are dropped once the map grows past this size.
+const PRUNING_THRESHOLD: usize = 10_000;
+
+impl RateLimitStore for InMemoryStore {
+    fn take(&self, key: &str, limit: &RateLimit) -> Decision {
+        let now = Instant::now();
+        let capacity = f64::from(limit.requests);
+        let refill_per_second = limit.refill_per_second();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() > PRUNING_THRESHOLD {
+            buckets.retain(|_, b| b.full_at > now);
+        }
+
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+            full_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let seconds_until = |tokens: f64| Duration::from_secs_f64(tokens / refill_per_second);
+        let reset_after = seconds_until(capacity - bucket.tokens);
+        bucket.full_at = now + reset_after;
+        Decision {
+            allowed,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after,
+            retry_after: if allowed {
+                None
+            } else {
+                Some(seconds_until(1.0 - bucket.tokens))
+            },
+        }
+    }
+}
+
+/// The buckets of the caller of a request, set as a request local by `RateLimitMiddleware`.
+///
+/// Handlers performing several limited actions in one request (e.g. GraphQL mutations)
+/// take a token for each of them, as if they had been requested one by one.
+#[derive(Clone)]
+pub struct CallerBuckets {
+    caller: String,
+    limits: Arc<RateLimits>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl CallerBuckets {
+    pub fn take(&self, group: RouteGroup) -> Decision {
+        self.store.take(
+            &format!("{}:{}", self.caller, group.name()),
+            self.limits.of(group),
+        )
+    }
+}
+
+/// Applies `RateLimits` to each request, advertising them with `X-RateLimit-*` headers.
+///
+/// Authenticated callers are identified by the claims set by `JwtMiddleware`,
+/// which has to run first; anonymous ones by their IP address (see `client_ip`).
+pub struct RateLimitMiddleware {
+    limits: Arc<RateLimits>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(limits: RateLimits, store: Box<dyn RateLimitStore>) -> Self {
+        Self {
+            limits: Arc::new(limits),
+            store: Arc::from(store),
+        }
+    }
+}
+
+/// Whole seconds, rounded up: acting a fraction of a second too early would be pointless.
+fn ceil_seconds(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for RateLimitMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let group = RouteGroup::of(cx.method(), cx.uri().path());
+            let caller = match (cx.local::<Claims>(), client_ip(&cx)) {
+                (Some(claims), _) => format!("user:{}", claims.user_id()),
+                (None, Some(ip)) => format!("ip:{}", ip),
+                // Callers who cannot be told apart are not limited, rather than sharing a bucket
+                (None, None) => return next.run(cx).await,
+            };
+            let buckets = CallerBuckets {
+                caller,
+                limits: self.limits.clone(),
+                store: self.store.clone(),
+            };
+            let limit = self.limits.of(group);
+            let decision = buckets.take(group);
+
+            let response = match decision.retry_after {
+                Some(retry_after) => Response::new(429)
+                    .set_header("Retry-After", ceil_seconds(retry_after).to_string())
+                    .body_string("Too many requests, try again later.".into()),
+                None => next.run(cx.set_local(buckets)).await,
+            };
+            response
+                .set_header("X-RateLimit-Limit", limit.requests.to_string())
+                .set_header("X-RateLimit-Remaining", decision.remaining.to_string())
+                .set_header(
+                    "X-RateLimit-Reset",
+                    ceil_seconds(decision.reset_after).to_string(),
+                )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_are_drained_and_refilled() {
+        let store = InMemoryStore::default();
+        let limit = RateLimit {
+            requests: 2,
+            per: Duration::from_millis(200),
+        };
+
+        assert_eq!(store.take("a", &limit).remaining, 1);
+        assert_eq!(store.take("a", &limit).remaining, 0);
+        let rejected = store.take("a", &limit);
+        assert!(!rejected.allowed);
+        assert!(rejected.retry_after.unwrap() <= Duration::from_millis(100));
+        // Buckets are independent
+        assert!(store.take("b", &limit).allowed);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(store.take("a", &limit).allowed);
+    }
+
+    #[test]
+    fn writes_are_grouped_by_resource() {
+        let group = |method, path| RouteGroup::of(&method, path);
+        assert_eq!(group(Method::POST, "/api/articles"), RouteGroup::Articles);
+        assert_eq!(
+            group(Method::PUT, "/api/articles/a-slug"),
+            RouteGroup::Articles
+        );
+        assert_eq!(
+            group(Method::POST, "/api/articles/a-slug/comments"),
+            RouteGroup::Comments
+        );
+        assert_eq!(
+            group(Method::DELETE, "/api/articles/a-slug/favorite"),
+            RouteGroup::Favorites
+        );
+        assert_eq!(group(Method::GET, "/api/articles"), RouteGroup::Default);
+        assert_eq!(group(Method::POST, "/api/users"), RouteGroup::Default);
+    }
+}
@@ -0,0 +1,157 @@
+//! Request IDs and structured access logs.
+//!
+//! Each request is identified by the `X-Request-Id` header: it is propagated when the caller
+//! (usually the load balancer) sets it, generated otherwise, and echoed in the response.
+//! The ID is kept in a task-local for the duration of the request, so that every log record
+//! emitted while serving it can be tagged with it: see `current_request_id`.
+use futures::future::BoxFuture;
+use serde_json::json;
+use std::cell::RefCell;
+use std::time::Instant;
+use tide::{Middleware, Next, Request, Response};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// The target of the access log records: their message is a JSON object.
+pub const ACCESS_LOG_TARGET: &str = "access";
+
+/// Query parameters whose values are never logged.
+const REDACTED_PARAMETERS: &[&str] = &["token", "code", "state", "password", "secret"];
+const REDACTED: &str = "[REDACTED]";
+/// Longer incoming request IDs are replaced rather than trusted.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+#[derive(Default)]
+struct RequestScope {
+    id: Option<String>,
+    user_id: Option<Uuid>,
+}
+
+async_std::task_local! {
+    static SCOPE: RefCell<RequestScope> = RefCell::new(RequestScope::default());
+}
+
+/// The ID of the request being served by the current task, if any.
+pub fn current_request_id() -> Option<String> {
+    SCOPE
+        .try_with(|scope| scope.borrow().id.clone())
+        .ok()
+        .flatten()
+}
+
+/// Record who the request being served is from, for the access log.
+pub fn set_current_user_id(user_id: Uuid) {
+    // Outside of a task (e.g. in tests) there is no access log to enrich
+    let _ = SCOPE.try_with(|scope| scope.borrow_mut().user_id = Some(user_id));
+}
+
+fn current_user_id() -> Option<Uuid> {
+    SCOPE
+        .try_with(|scope| scope.borrow().user_id)
+        .ok()
+        .flatten()
+}
+
+/// The request ID set by the caller, provided it is safe to log as is.
+fn incoming_request_id<S>(cx: &Request<S>) -> Option<String> {
+    cx.header(REQUEST_ID_HEADER)
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+        .filter(|id| {
+            id.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        })
+        .map(str::to_owned)
+}
+
+/// The path and query of a request, with the values of sensitive query parameters redacted.
+pub fn redacted_path(path: &str, query: Option<&str>) -> String {
+    let query = match query {
+        Some(q) if !q.is_empty() => q,
+        _ => return path.to_owned(),
+    };
+    let parameters: Vec<String> = query
+        .split('&')
+        .map(|parameter| {
+            let name = parameter.split('=').next().unwrap_or_default();
+            if REDACTED_PARAMETERS.contains(&name.to_ascii_lowercase().as_str()) {
+                format!("{}={}", name, REDACTED)
+            } else {
+                parameter.to_owned()
+            }
+        })
+        .collect();
+    format!("{}?{}", path, parameters.join("&"))
+}
+
+/// Assigns a request ID to each request and logs it as a JSON object once served.
+///
+/// It has to run right after `TracingMiddleware`, so that the logs of the other middlewares
+/// carry the request ID.
+/// Headers and bodies are never logged: they hold credentials.
+#[derive(Clone, Default, Debug)]
+pub struct RequestLogMiddleware {}
+
+impl RequestLogMiddleware {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for RequestLogMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let request_id = incoming_request_id(&cx).unwrap_or_else(|| Uuid::new_v4().to_string());
+            let method = cx.method().to_string();
+            let path = redacted_path(cx.uri().path(), cx.uri().query());
+            let _ = SCOPE.try_with(|scope| {
+                *scope.borrow_mut() = RequestScope {
+                    id: Some(request_id.clone()),
+                    user_id: None,
+                }
+            });
+
+            let response = next.run(cx).await;
+
+            let record = json!({
+                "request_id": request_id,
+                "trace_id": telemetry::current_trace_id().map(|id| id.to_string()),
+                "method": method,
+                "path": path,
+                "status": response.status().as_u16(),
+                "latency_ms": started_at.elapsed().as_secs_f64() * 1000.0,
+                "user_id": current_user_id(),
+            });
+            log::info!(target: ACCESS_LOG_TARGET, "{}", record);
+            // Tasks serve several requests on keep-alive connections
+            let _ = SCOPE.try_with(|scope| *scope.borrow_mut() = RequestScope::default());
+            response.set_header(REQUEST_ID_HEADER, request_id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitive_query_parameters_are_redacted() {
+        assert_eq!(redacted_path("/api/articles", None), "/api/articles");
+        assert_eq!(
+            redacted_path("/api/articles", Some("tag=rust&limit=5")),
+            "/api/articles?tag=rust&limit=5"
+        );
+        assert_eq!(
+            redacted_path(
+                "/api/auth/example/callback",
+                Some("code=abc&state=def&foo=bar")
+            ),
+            "/api/auth/example/callback?code=[REDACTED]&state=[REDACTED]&foo=bar"
+        );
+        assert_eq!(
+            redacted_path("/api/users/verify-email", Some("Token=abc")),
+            "/api/users/verify-email?Token=[REDACTED]"
+        );
+    }
+}
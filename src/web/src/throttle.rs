@@ -0,0 +1,125 @@
+//! Throttling of failed login attempts, to slow down password guessing.
+use std::collections::HashMap;
+use std::iter;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many failed login attempts are tolerated before locking out further ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoginThrottling {
+    /// Failed attempts allowed from the same IP address within `window`.
+    pub max_attempts_per_ip: u32,
+    /// Failed attempts allowed against the same account within `window`.
+    pub max_attempts_per_account: u32,
+    pub window: Duration,
+    /// How long attempts are rejected for once the limit has been reached.
+    pub lockout: Duration,
+}
+
+impl Default for LoginThrottling {
+    fn default() -> Self {
+        Self {
+            max_attempts_per_ip: 50,
+            max_attempts_per_account: 5,
+            window: Duration::from_secs(15 * 60),
+            lockout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Failures {
+    count: u32,
+    window_start: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// The in-memory record of recent failed login attempts, by IP address and by account.
+///
+/// It is not shared between instances of the application:
+/// each of them enforces the limits on its own.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    settings: LoginThrottling,
+    failures: Mutex<HashMap<String, Failures>>,
+}
+
+impl LoginThrottle {
+    pub fn new(settings: LoginThrottling) -> Self {
+        Self {
+            settings,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fails with how long the caller has to wait if either the IP address
+    /// or the account are locked out.
+    ///
+    /// Callers whose IP address is unknown (see `client_ip`) are only throttled by account.
+    pub fn check(&self, ip: Option<IpAddr>, account: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let failures = self.failures.lock().unwrap();
+        let retry_after = ip
+            .map(ip_key)
+            .into_iter()
+            .chain(iter::once(account_key(account)))
+            .filter_map(|key| failures.get(&key))
+            .filter_map(|f| f.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+            .max();
+        match retry_after {
+            Some(retry_after) => Err(retry_after),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&self, ip: Option<IpAddr>, account: &str) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        let limits = ip
+            .map(|ip| (ip_key(ip), self.settings.max_attempts_per_ip))
+            .into_iter()
+            .chain(iter::once((
+                account_key(account),
+                self.settings.max_attempts_per_account,
+            )));
+        for (key, max_attempts) in limits {
+            let f = failures.entry(key).or_insert(Failures {
+                count: 0,
+                window_start: now,
+                locked_until: None,
+            });
+            if now.duration_since(f.window_start) > self.settings.window {
+                f.count = 0;
+                f.window_start = now;
+            }
+            f.count += 1;
+            if f.count >= max_attempts {
+                f.locked_until = Some(now + self.settings.lockout);
+                f.count = 0;
+                f.window_start = now;
+            }
+        }
+        // Don't let the map grow unbounded with entries nobody is going to look at again
+        let retention = self.settings.window.max(self.settings.lockout);
+        failures.retain(|_, f| {
+            now.duration_since(f.window_start) <= retention
+                || matches!(f.locked_until, Some(until) if until > now)
+        });
+    }
+
+    /// A successful login clears the failures recorded against the account.
+    pub fn record_success(&self, account: &str) {
+        self.failures.lock().unwrap().remove(&account_key(account));
+    }
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+fn account_key(account: &str) -> String {
+    format!("account:{}", account.to_lowercase())
+}
@@ -0,0 +1,47 @@
+//! Distributed tracing of the HTTP API.
+use futures::future::BoxFuture;
+use telemetry::{start_span_with_parent, SpanContext, SpanKind};
+use tide::{Middleware, Next, Request, Response};
+
+use crate::monitoring::route_of;
+
+/// The W3C Trace Context header carrying the span of the caller.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Traces each request as a server span, continuing the trace of the caller if it sent
+/// a `traceparent` header. Spans started while serving the request are its children.
+///
+/// It has to run first, so that the logs of the other middlewares carry the trace ID.
+#[derive(Clone, Default, Debug)]
+pub struct TracingMiddleware {}
+
+impl TracingMiddleware {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<State: Send + Sync + 'static> Middleware<State> for TracingMiddleware {
+    fn handle<'a>(&'a self, cx: Request<State>, next: Next<'a, State>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let parent = cx
+                .header(TRACEPARENT_HEADER)
+                .and_then(SpanContext::from_traceparent);
+            let method = cx.method().to_string();
+            let route = route_of(cx.uri().path());
+            let mut span =
+                start_span_with_parent(format!("{} {}", method, route), SpanKind::Server, parent);
+            span.set_attribute("http.method", method);
+            span.set_attribute("http.route", route);
+
+            let response = next.run(cx).await;
+
+            let status = response.status();
+            span.set_attribute("http.status_code", i64::from(status.as_u16()));
+            if status.is_server_error() {
+                span.set_error();
+            }
+            response
+        })
+    }
+}
@@ -0,0 +1,114 @@
+use super::responses::{AccessTokenResponse, AccessTokensResponse, NewAccessTokenResponse};
+use crate::middleware::ContextExt;
+use crate::openapi::{string_enum, Components, Object, Schema};
+use crate::{Context, ErrorResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use domain::repositories::Repository;
+use domain::Scope;
+use itertools::Itertools;
+use tide::Response;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub token: NewAccessTokenRequest,
+}
+
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("NewAccessTokenRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<NewAccessTokenRequest>("token")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NewAccessTokenRequest {
+    pub name: String,
+    /// E.g. `articles:write`.
+    pub scopes: Vec<String>,
+}
+
+impl Schema for NewAccessTokenRequest {
+    const NAME: Option<&'static str> = Some("AccessTokenDetails");
+
+    fn schema(components: &mut Components) -> Value {
+        let scopes: Vec<&str> = Scope::ALL.iter().map(|s| s.as_str()).collect();
+        Object::new(components)
+            .field::<String>("name")
+            .field_schema(
+                "scopes",
+                json!({ "type": "array", "items": string_enum(&scopes) }),
+            )
+            .build()
+    }
+}
+
+pub async fn create_access_token<R: 'static + Repository + Sync + Send>(
+    mut cx: tide::Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let request = cx
+        .body_json::<Request>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .token;
+    let scopes = request
+        .scopes
+        .iter()
+        .map(|s| to_scope(s))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Response::new(400).body_string(e))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let (token, secret) = user.create_access_token(&request.name, &scopes, repository)?;
+
+    let response = NewAccessTokenResponse::from((token, secret));
+    Ok(Response::new(201).body_json(&response).unwrap())
+}
+
+pub async fn list_access_tokens<R: 'static + Repository + Sync + Send>(
+    cx: tide::Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let tokens = repository.get_access_tokens(&user)?;
+
+    let response = AccessTokensResponse {
+        tokens: tokens.into_iter().map(AccessTokenResponse::from).collect(),
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+pub async fn revoke_access_token<R: 'static + Repository + Sync + Send>(
+    cx: tide::Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let token_id: Uuid = cx.param("id").map_err(|_| Response::new(400))?;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    repository.delete_access_token(&user, token_id)?;
+
+    Ok(Response::new(200))
+}
+
+fn to_scope(scope: &str) -> Result<Scope, String> {
+    Scope::parse(scope).ok_or_else(|| {
+        format!(
+            "Unknown scope `{}`: expected one of {}.",
+            scope,
+            Scope::ALL
+                .iter()
+                .map(|s| format!("`{}`", s.as_str()))
+                .join(", ")
+        )
+    })
+}
@@ -10,7 +10,7 @@ use tide::{Request, Response};
 pub async fn get_current_user<R: 'static + Repository + Sync + Send>(
     cx: Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
+    let user_id = cx.get_session_claims()?.user_id();
     let repository = &cx.state().repository;
     info!("Get user {}", user_id);
 
@@ -0,0 +1,80 @@
+use crate::middleware::ContextExt;
+use crate::openapi::{string_enum, Components, Object, Schema};
+use crate::{Context, ErrorResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use domain::repositories::Repository;
+use domain::AccountDeletion;
+use tide::Response;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub user: DeleteUserRequest,
+}
+
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("DeleteUserRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<DeleteUserRequest>("user")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeleteUserRequest {
+    pub password: String,
+    /// What to do with the user's articles and comments: `delete` or `anonymize`.
+    pub content: String,
+}
+
+impl Schema for DeleteUserRequest {
+    const NAME: Option<&'static str> = Some("UserDeletion");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("password")
+            .field_schema("content", string_enum(&["delete", "anonymize"]))
+            .build()
+    }
+}
+
+pub async fn delete_user<R: 'static + Repository + Sync + Send>(
+    mut cx: tide::Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let request = cx
+        .body_json::<Request>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .user;
+    let mode =
+        to_account_deletion(&request.content).map_err(|e| Response::new(400).body_string(e))?;
+    let state = cx.state();
+
+    let user = state.repository.get_user_by_id(user_id)?;
+    user.delete_account(
+        &request.password,
+        mode,
+        &state.password_hashing,
+        &state.repository,
+    )?;
+
+    Ok(Response::new(200))
+}
+
+fn to_account_deletion(content: &str) -> Result<AccountDeletion, String> {
+    let mode = match content {
+        "delete" => AccountDeletion::Delete,
+        "anonymize" => AccountDeletion::Anonymize,
+        _ => {
+            return Err(format!(
+                "Unknown content option `{}`: expected either `delete` or `anonymize`.",
+                content
+            ))
+        }
+    };
+    Ok(mode)
+}
@@ -1,20 +1,47 @@
-use super::responses::UserResponse;
+use super::responses::{TwoFactorChallengeResponse, UserResponse};
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::auth::encode_token;
+use crate::auth::{
+    decode_two_factor_challenge_token, encode_token, encode_two_factor_challenge_token,
+    TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS,
+};
+use crate::client_address::client_ip;
 use domain::repositories::Repository;
+use domain::{LoginError, TwoFactorError};
+use std::time::Duration;
 use tide::{Request, Response};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct AuthRequest {
-    user: AuthUser,
+    pub user: AuthUser,
 }
 
-#[derive(Deserialize)]
+impl Schema for AuthRequest {
+    const NAME: Option<&'static str> = Some("LoginRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<AuthUser>("user").build()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct AuthUser {
-    email: String,
-    password: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl Schema for AuthUser {
+    const NAME: Option<&'static str> = Some("Credentials");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("email")
+            .field::<String>("password")
+            .build()
+    }
 }
 
 pub async fn login<R: 'static + Repository + Sync + Send>(
@@ -25,12 +52,104 @@ pub async fn login<R: 'static + Repository + Sync + Send>(
         .await
         .map_err(|_| Response::new(400))?
         .user;
-    let repository = &cx.state().repository;
+    let ip = client_ip(&cx);
+    let state = cx.state();
 
-    let logged_in_user = repository.get_user_by_email_and_password(&user.email, &user.password)?;
+    if let Err(retry_after) = state.login_throttle.check(ip, &user.email) {
+        return Err(too_many_attempts(retry_after).into());
+    }
+
+    let logged_in_user = match state.repository.get_user_by_email_and_password(
+        &user.email,
+        &user.password,
+        &state.password_hashing,
+    ) {
+        Ok(u) => u,
+        Err(e) => {
+            if let LoginError::NotFound = e {
+                state.login_throttle.record_failure(ip, &user.email);
+            }
+            return Err(e.into());
+        }
+    };
+    if logged_in_user.requires_two_factor(&state.repository)? {
+        // Failures are only cleared once the second factor is provided too:
+        // otherwise knowing the password would allow guessing codes without limits
+        let challenge = TwoFactorChallengeResponse::new(
+            encode_two_factor_challenge_token(logged_in_user.id, &state.token_secret),
+            TWO_FACTOR_CHALLENGE_VALIDITY_SECONDS,
+        );
+        return Ok(Response::new(200).body_json(&challenge).unwrap());
+    }
+    state.login_throttle.record_success(&user.email);
     let token = encode_token(logged_in_user.id);
 
     let response = UserResponse::from((logged_in_user, token));
 
     Ok(Response::new(200).body_json(&response).unwrap())
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SecondFactorRequest {
+    /// The challenge token returned by `login`.
+    pub token: String,
+    /// A code from the authenticator app, or a recovery code.
+    pub code: String,
+}
+
+impl Schema for SecondFactorRequest {
+    const NAME: Option<&'static str> = Some("SecondFactorRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("token")
+            .field::<String>("code")
+            .build()
+    }
+}
+
+/// Complete the login of a user with two-factor authentication enabled.
+pub async fn login_second_factor<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let request = cx
+        .body_json::<SecondFactorRequest>()
+        .await
+        .map_err(|_| Response::new(400))?;
+    let ip = client_ip(&cx);
+    let state = cx.state();
+
+    let claims = decode_two_factor_challenge_token(&request.token, &state.token_secret)
+        .ok_or_else(|| {
+            Response::new(401).body_string("The two-factor challenge token is not valid.".into())
+        })?;
+    let user = state.repository.get_user_by_id(claims.user_id())?;
+    if let Err(retry_after) = state.login_throttle.check(ip, &user.email) {
+        return Err(too_many_attempts(retry_after).into());
+    }
+
+    match user.verify_second_factor(&request.code, &state.repository) {
+        Ok(()) => {}
+        Err(TwoFactorError::InvalidCode) => {
+            state.login_throttle.record_failure(ip, &user.email);
+            return Err(Response::new(401)
+                .body_string(TwoFactorError::InvalidCode.to_string())
+                .into());
+        }
+        Err(e) => return Err(e.into()),
+    }
+    state.login_throttle.record_success(&user.email);
+    let token = encode_token(user.id);
+
+    let response = UserResponse::from((user, token));
+
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+fn too_many_attempts(retry_after: Duration) -> Response {
+    // Round up: retrying a fraction of a second too early would be rejected again
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    Response::new(429)
+        .set_header("Retry-After", seconds.to_string())
+        .body_string("Too many failed login attempts, try again later.".into())
+}
@@ -1,10 +1,20 @@
+pub mod access_tokens;
 pub mod current_user;
+pub mod delete;
 pub mod login;
+pub mod password_reset;
 pub mod register;
 pub mod responses;
+pub mod two_factor;
 pub mod update;
+pub mod verify_email;
 
+pub use access_tokens::{create_access_token, list_access_tokens, revoke_access_token};
 pub use current_user::get_current_user;
-pub use login::login;
+pub use delete::delete_user;
+pub use login::{login, login_second_factor};
+pub use password_reset::{confirm_password_reset, request_password_reset};
 pub use register::register;
+pub use two_factor::{confirm_two_factor, disable_two_factor, enroll_two_factor};
 pub use update::update_user;
+pub use verify_email::{resend_verification_email, verify_email};
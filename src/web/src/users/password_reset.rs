@@ -0,0 +1,127 @@
+use crate::openapi::{Components, Object, Schema};
+use crate::{Context, ErrorResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use domain::repositories::Repository;
+use domain::{reset_password, Email, Mailer, MailerError, Password, PasswordResetToken};
+use log::error;
+use std::sync::Arc;
+use std::thread;
+use tide::{Request, Response};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PasswordResetRequest {
+    pub user: PasswordResetUser,
+}
+
+impl Schema for PasswordResetRequest {
+    const NAME: Option<&'static str> = Some("PasswordResetRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<PasswordResetUser>("user")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PasswordResetUser {
+    pub email: String,
+}
+
+impl Schema for PasswordResetUser {
+    const NAME: Option<&'static str> = Some("PasswordResetUser");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<String>("email").build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConfirmPasswordResetRequest {
+    pub user: ConfirmPasswordResetUser,
+}
+
+impl Schema for ConfirmPasswordResetRequest {
+    const NAME: Option<&'static str> = Some("ConfirmPasswordResetRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<ConfirmPasswordResetUser>("user")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConfirmPasswordResetUser {
+    pub token: String,
+    pub password: String,
+}
+
+impl Schema for ConfirmPasswordResetUser {
+    const NAME: Option<&'static str> = Some("ConfirmPasswordResetUser");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("token")
+            .field::<String>("password")
+            .build()
+    }
+}
+
+/// Sends emails from a thread of its own, logging failures: responses do not wait for the
+/// mail server, so they take as long whether the email belongs to a user or not.
+struct BackgroundMailer(Arc<dyn Mailer + Sync + Send>);
+
+impl Mailer for BackgroundMailer {
+    fn send(&self, email: Email) -> Result<(), MailerError> {
+        let mailer = self.0.clone();
+        thread::spawn(move || {
+            if let Err(e) = mailer.send(email) {
+                error!("Failed to send a password reset email: {}", e);
+            }
+        });
+        Ok(())
+    }
+}
+
+pub async fn request_password_reset<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let email = cx
+        .body_json::<PasswordResetRequest>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .user
+        .email;
+    let state = cx.state();
+
+    // The response is the same whether the email belongs to a user or not:
+    // it must not be possible to find out who is registered.
+    if let Some(user) = state.repository.find_user_by_email(&email)? {
+        let mailer = BackgroundMailer(state.mailer.clone());
+        if let Err(e) = user.request_password_reset(&state.repository, &mailer) {
+            error!("Failed to request a password reset for {}: {}", user.id, e);
+        }
+    }
+
+    Ok(Response::new(202))
+}
+
+pub async fn confirm_password_reset<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let request = cx
+        .body_json::<ConfirmPasswordResetRequest>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .user;
+    let state = cx.state();
+
+    let token = PasswordResetToken::from_clear_text(request.token);
+    let password = Password::from_clear_text(request.password, &state.password_hashing)?;
+    reset_password(&token, password, &state.repository)?;
+
+    Ok(Response::new(200))
+}
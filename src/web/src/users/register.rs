@@ -1,32 +1,54 @@
 use super::responses::UserResponse;
 use crate::auth::encode_token;
+use crate::openapi::{Components, Object, Schema};
+use crate::users::verify_email::send_verification_email;
 use crate::{Context, ErrorResponse};
 use domain::repositories::Repository;
-use domain::SignUp;
-use serde::Deserialize;
-use std::convert::{TryFrom, TryInto};
+use domain::{PasswordHashing, SignUp};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tide::{Request, Response};
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct RegistrationRequest {
-    user: NewUserRequest,
+    pub user: NewUserRequest,
 }
 
-#[derive(Deserialize, Debug)]
+impl Schema for RegistrationRequest {
+    const NAME: Option<&'static str> = Some("RegistrationRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<NewUserRequest>("user")
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct NewUserRequest {
     pub username: String,
     pub email: String,
     pub password: String,
 }
 
-impl TryFrom<RegistrationRequest> for SignUp {
-    type Error = domain::PasswordError;
+impl Schema for NewUserRequest {
+    const NAME: Option<&'static str> = Some("NewUser");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("username")
+            .field::<String>("email")
+            .field::<String>("password")
+            .build()
+    }
+}
 
-    fn try_from(r: RegistrationRequest) -> Result<Self, Self::Error> {
-        let sign_up = Self {
-            username: r.user.username,
-            password: domain::Password::from_clear_text(r.user.password)?,
-            email: r.user.email,
+impl RegistrationRequest {
+    fn into_sign_up(self, hashing: &PasswordHashing) -> Result<SignUp, domain::PasswordError> {
+        let sign_up = SignUp {
+            username: self.user.username,
+            password: domain::Password::from_clear_text(self.user.password, hashing)?,
+            email: self.user.email,
         };
         Ok(sign_up)
     }
@@ -39,10 +61,11 @@ pub async fn register<R: 'static + Repository + Sync + Send>(
         .body_json()
         .await
         .map_err(|e| Response::new(400).body_string(e.to_string()))?;
-    let repository = &cx.state().repository;
+    let state = cx.state();
 
-    let sign_up: SignUp = registration.try_into()?;
-    let new_user = repository.sign_up(sign_up)?;
+    let sign_up = registration.into_sign_up(&state.password_hashing)?;
+    let new_user = state.repository.sign_up(sign_up)?;
+    send_verification_email(&new_user, &state.token_secret, state.mailer.as_ref());
     let token = encode_token(new_user.id);
 
     let response = UserResponse::from((new_user, token));
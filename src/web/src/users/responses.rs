@@ -1,10 +1,23 @@
+use crate::openapi::{Components, Object, Schema};
+use chrono::{DateTime, Utc};
+use domain::{AccessToken, AccessTokenSecret};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct UserResponse {
     pub user: User,
 }
 
+impl Schema for UserResponse {
+    const NAME: Option<&'static str> = Some("UserResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<User>("user").build()
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct User {
     pub username: String,
@@ -12,6 +25,23 @@ pub struct User {
     pub token: String,
     pub bio: Option<String>,
     pub image: Option<String>,
+    #[serde(rename = "emailVerified")]
+    pub email_verified: bool,
+}
+
+impl Schema for User {
+    const NAME: Option<&'static str> = Some("User");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("username")
+            .field::<String>("email")
+            .field::<String>("token")
+            .field::<Option<String>>("bio")
+            .field::<Option<String>>("image")
+            .field::<bool>("emailVerified")
+            .build()
+    }
 }
 
 impl From<(domain::User, String)> for UserResponse {
@@ -24,6 +54,200 @@ impl From<(domain::User, String)> for UserResponse {
                 token,
                 bio: u.profile.bio,
                 image: u.profile.image,
+                email_verified: u.email_verified,
+            },
+        }
+    }
+}
+
+/// Returned by `login` instead of a `UserResponse` when the user enabled two-factor authentication.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TwoFactorChallengeResponse {
+    #[serde(rename = "twoFactorChallenge")]
+    pub two_factor_challenge: TwoFactorChallenge,
+}
+
+impl Schema for TwoFactorChallengeResponse {
+    const NAME: Option<&'static str> = Some("TwoFactorChallengeResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<TwoFactorChallenge>("twoFactorChallenge")
+            .build()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TwoFactorChallenge {
+    pub token: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: u64,
+}
+
+impl Schema for TwoFactorChallenge {
+    const NAME: Option<&'static str> = Some("TwoFactorChallenge");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("token")
+            .field::<u64>("expiresIn")
+            .build()
+    }
+}
+
+impl TwoFactorChallengeResponse {
+    pub fn new(token: String, expires_in: u64) -> Self {
+        Self {
+            two_factor_challenge: TwoFactorChallenge { token, expires_in },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TwoFactorEnrollmentResponse {
+    #[serde(rename = "twoFactor")]
+    pub two_factor: TwoFactorEnrollment,
+}
+
+impl Schema for TwoFactorEnrollmentResponse {
+    const NAME: Option<&'static str> = Some("TwoFactorEnrollmentResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<TwoFactorEnrollment>("twoFactor")
+            .build()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TwoFactorEnrollment {
+    /// Base32-encoded, for users typing it in their authenticator app.
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+impl Schema for TwoFactorEnrollment {
+    const NAME: Option<&'static str> = Some("TwoFactorEnrollment");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<String>("secret")
+            .field::<String>("otpauthUri")
+            .build()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+impl Schema for RecoveryCodesResponse {
+    const NAME: Option<&'static str> = Some("RecoveryCodesResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<String>>("recoveryCodes")
+            .build()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AccessTokensResponse {
+    pub tokens: Vec<AccessTokenResponse>,
+}
+
+impl Schema for AccessTokensResponse {
+    const NAME: Option<&'static str> = Some("AccessTokensResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Vec<AccessTokenResponse>>("tokens")
+            .build()
+    }
+}
+
+/// An access token, without its secret.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AccessTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl Schema for AccessTokenResponse {
+    const NAME: Option<&'static str> = Some("AccessToken");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<Uuid>("id")
+            .field::<String>("name")
+            .field::<Vec<String>>("scopes")
+            .field::<DateTime<Utc>>("createdAt")
+            .field::<Option<DateTime<Utc>>>("lastUsedAt")
+            .build()
+    }
+}
+
+impl From<AccessToken> for AccessTokenResponse {
+    fn from(t: AccessToken) -> Self {
+        Self {
+            id: t.id,
+            name: t.name,
+            scopes: t.scopes.iter().map(|s| s.as_str().to_owned()).collect(),
+            created_at: t.created_at,
+            last_used_at: t.last_used_at,
+        }
+    }
+}
+
+/// The secret of an access token is only returned once, when it is created.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct NewAccessTokenResponse {
+    pub token: NewAccessToken,
+}
+
+impl Schema for NewAccessTokenResponse {
+    const NAME: Option<&'static str> = Some("NewAccessTokenResponse");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<NewAccessToken>("token")
+            .build()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct NewAccessToken {
+    #[serde(flatten)]
+    pub details: AccessTokenResponse,
+    pub secret: String,
+}
+
+impl Schema for NewAccessToken {
+    const NAME: Option<&'static str> = Some("NewAccessToken");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .flatten::<AccessTokenResponse>()
+            .field::<String>("secret")
+            .build()
+    }
+}
+
+impl From<(AccessToken, AccessTokenSecret)> for NewAccessTokenResponse {
+    fn from(x: (AccessToken, AccessTokenSecret)) -> Self {
+        let (token, secret) = x;
+        Self {
+            token: NewAccessToken {
+                details: AccessTokenResponse::from(token),
+                secret: secret.as_str().to_owned(),
             },
         }
     }
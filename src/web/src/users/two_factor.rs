@@ -0,0 +1,83 @@
+use super::responses::{RecoveryCodesResponse, TwoFactorEnrollment, TwoFactorEnrollmentResponse};
+use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
+use crate::{Context, ErrorResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use domain::repositories::Repository;
+use tide::{Request, Response};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+impl Schema for TwoFactorCodeRequest {
+    const NAME: Option<&'static str> = Some("TwoFactorCodeRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<String>("code").build()
+    }
+}
+
+/// Generate a new TOTP secret: two-factor authentication is enforced once it gets confirmed.
+pub async fn enroll_two_factor<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let secret = user.enroll_two_factor(repository)?;
+
+    let response = TwoFactorEnrollmentResponse {
+        two_factor: TwoFactorEnrollment {
+            secret: secret.to_base32(),
+            otpauth_uri: secret.otpauth_uri(&user.email),
+        },
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+/// Enforce two-factor authentication, given a first code from the authenticator app.
+/// The recovery codes are only ever returned here.
+pub async fn confirm_two_factor<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let code = cx
+        .body_json::<TwoFactorCodeRequest>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .code;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    let recovery_codes = user.confirm_two_factor(&code, repository)?;
+
+    let response = RecoveryCodesResponse {
+        recovery_codes: recovery_codes
+            .iter()
+            .map(|c| c.as_str().to_owned())
+            .collect(),
+    };
+    Ok(Response::new(200).body_json(&response).unwrap())
+}
+
+pub async fn disable_two_factor<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let code = cx
+        .body_json::<TwoFactorCodeRequest>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .code;
+    let repository = &cx.state().repository;
+
+    let user = repository.get_user_by_id(user_id)?;
+    user.disable_two_factor(&code, repository)?;
+
+    Ok(Response::new(200))
+}
@@ -1,11 +1,14 @@
 use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
 use crate::{Context, ErrorResponse};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::auth::encode_token;
 use crate::users::responses::UserResponse;
+use crate::users::verify_email::send_verification_email;
 use domain::repositories::Repository;
-use std::convert::{TryFrom, TryInto};
+use domain::PasswordHashing;
 use tide::Response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -13,6 +16,16 @@ pub struct Request {
     pub user: UpdateUserRequest,
 }
 
+impl Schema for Request {
+    const NAME: Option<&'static str> = Some("UpdateUserRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .field::<UpdateUserRequest>("user")
+            .build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UpdateUserRequest {
     pub email: Option<String>,
@@ -22,19 +35,35 @@ pub struct UpdateUserRequest {
     pub bio: Option<String>,
 }
 
-impl TryFrom<UpdateUserRequest> for domain::UserUpdate {
-    type Error = domain::PasswordError;
+impl Schema for UpdateUserRequest {
+    const NAME: Option<&'static str> = Some("UserUpdate");
 
-    fn try_from(u: UpdateUserRequest) -> Result<Self, Self::Error> {
-        let update = Self {
-            email: u.email,
-            username: u.username,
-            password: u
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components)
+            .description("Only the fields which are set get updated.")
+            .optional::<Option<String>>("email")
+            .optional::<Option<String>>("username")
+            .optional::<Option<String>>("password")
+            .optional::<Option<String>>("image")
+            .optional::<Option<String>>("bio")
+            .build()
+    }
+}
+
+impl UpdateUserRequest {
+    fn into_user_update(
+        self,
+        hashing: &PasswordHashing,
+    ) -> Result<domain::UserUpdate, domain::PasswordError> {
+        let update = domain::UserUpdate {
+            email: self.email,
+            username: self.username,
+            password: self
                 .password
-                .map(domain::Password::from_clear_text)
+                .map(|p| domain::Password::from_clear_text(p, hashing))
                 .transpose()?,
-            image: u.image,
-            bio: u.bio,
+            image: self.image,
+            bio: self.bio,
         };
         Ok(update)
     }
@@ -43,16 +72,23 @@ impl TryFrom<UpdateUserRequest> for domain::UserUpdate {
 pub async fn update_user<R: 'static + Repository + Sync + Send>(
     mut cx: tide::Request<Context<R>>,
 ) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
     let update_params = cx
         .body_json::<Request>()
         .await
         .map_err(|_| Response::new(400))?
         .user;
-    let user_id = cx.get_claims().map_err(|_| Response::new(401))?.user_id();
-    let repository = &cx.state().repository;
+    let state = cx.state();
 
-    let user = repository.get_user_by_id(user_id)?;
-    let updated_user = user.update(update_params.try_into()?, repository)?;
+    let user = state.repository.get_user_by_id(user_id)?;
+    let previous_email = user.email.clone();
+    let update = update_params.into_user_update(&state.password_hashing)?;
+    let updated_user = user.update(update, &state.repository)?;
+    // The verification is reset when the email address changes: other updates leave it be,
+    // a new verification email can be requested explicitly
+    if updated_user.email != previous_email {
+        send_verification_email(&updated_user, &state.token_secret, state.mailer.as_ref());
+    }
     let token = encode_token(updated_user.id);
 
     let response = UserResponse::from((updated_user, token));
@@ -0,0 +1,73 @@
+use crate::auth::{decode_email_verification_token, encode_email_verification_token};
+use crate::middleware::ContextExt;
+use crate::openapi::{Components, Object, Schema};
+use crate::{Context, ErrorResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use domain::repositories::Repository;
+use domain::{Mailer, User, VerifyEmailError};
+use log::error;
+use tide::{Request, Response};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+impl Schema for VerifyEmailRequest {
+    const NAME: Option<&'static str> = Some("VerifyEmailRequest");
+
+    fn schema(components: &mut Components) -> Value {
+        Object::new(components).field::<String>("token").build()
+    }
+}
+
+/// Send a verification token to the user's email address.
+///
+/// Failures are logged but not surfaced: the account has already been created or updated,
+/// and a new token can always be requested.
+pub fn send_verification_email(user: &User, token_secret: &str, mailer: &dyn Mailer) {
+    let token = encode_email_verification_token(user.id, &user.email, token_secret);
+    if let Err(e) = user.send_email_verification(&token, mailer) {
+        error!(
+            "Failed to send the verification email to {}: {}",
+            user.id, e
+        );
+    }
+}
+
+pub async fn verify_email<R: 'static + Repository + Sync + Send>(
+    mut cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let token = cx
+        .body_json::<VerifyEmailRequest>()
+        .await
+        .map_err(|_| Response::new(400))?
+        .token;
+    let state = cx.state();
+
+    let claims = decode_email_verification_token(&token, &state.token_secret)
+        .ok_or(VerifyEmailError::InvalidToken)?;
+    state
+        .repository
+        .verify_email(claims.user_id(), claims.email())?;
+
+    Ok(Response::new(200))
+}
+
+pub async fn resend_verification_email<R: 'static + Repository + Sync + Send>(
+    cx: Request<Context<R>>,
+) -> Result<Response, ErrorResponse> {
+    let user_id = cx.get_session_claims()?.user_id();
+    let state = cx.state();
+
+    let user = state.repository.get_user_by_id(user_id)?;
+    if !user.email_verified {
+        let token = encode_email_verification_token(user.id, &user.email, &state.token_secret);
+        user.send_email_verification(&token, state.mailer.as_ref())
+            .map_err(|_| Response::new(500))?;
+    }
+
+    Ok(Response::new(202))
+}
@@ -0,0 +1,283 @@
+//! A stand-in for an OpenID Connect provider: users are authenticated by the test itself,
+//! while the token and JWKS endpoints are served over HTTPS for the application to call.
+use async_native_tls::{TlsAcceptor, TlsStream};
+use async_std::io::prelude::*;
+use async_std::net::{TcpListener, TcpStream};
+use async_std::task;
+use chrono::Utc;
+use domain::OidcLoginAttempt;
+use jsonwebtoken::{encode, Algorithm, Header};
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509};
+use realworld_web::oidc::OidcProvider;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+const CLIENT_ID: &str = "conduit";
+const CLIENT_SECRET: &str = "a-client-secret";
+const REDIRECT_URI: &str = "http://localhost/api/auth/mock/callback";
+const KEY_ID: &str = "mock-key";
+
+/// The user who authenticated, along with the authorization request they came with.
+#[derive(Clone)]
+struct Grant {
+    code_challenge: String,
+    nonce: String,
+    subject: String,
+    email: String,
+    email_verified: bool,
+}
+
+pub struct MockOidcServer {
+    pub issuer: String,
+    /// The self-signed certificate of the server, in PEM.
+    certificate: String,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+}
+
+/// What the server signs with: the same key for its certificate and its ID tokens.
+struct Keys {
+    private_key: Rsa<Private>,
+    /// The DER-encoded PKCS #12 archive the TLS acceptor is built from.
+    identity: Vec<u8>,
+    certificate: X509,
+}
+
+impl Keys {
+    fn generate() -> Self {
+        let private_key = Rsa::generate(2048).unwrap();
+        let pkey = PKey::from_rsa(private_key.clone()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "127.0.0.1").unwrap();
+        let name = name.build();
+        let mut certificate = X509::builder().unwrap();
+        certificate.set_version(2).unwrap();
+        certificate.set_subject_name(&name).unwrap();
+        certificate.set_issuer_name(&name).unwrap();
+        certificate.set_pubkey(&pkey).unwrap();
+        certificate
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        certificate
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let alternative_names = SubjectAlternativeName::new()
+            .ip("127.0.0.1")
+            .build(&certificate.x509v3_context(None, None))
+            .unwrap();
+        certificate.append_extension(alternative_names).unwrap();
+        certificate.sign(&pkey, MessageDigest::sha256()).unwrap();
+        let certificate = certificate.build();
+        let identity = Pkcs12::builder()
+            .build("", "mock", &pkey, &certificate)
+            .unwrap()
+            .to_der()
+            .unwrap();
+        Self {
+            private_key,
+            identity,
+            certificate,
+        }
+    }
+
+    fn jwks(&self) -> Value {
+        let encode = |n: &openssl::bn::BigNumRef| {
+            base64::encode_config(&n.to_vec(), base64::URL_SAFE_NO_PAD)
+        };
+        json!({
+            "keys": [{
+                "kty": "RSA",
+                "kid": KEY_ID,
+                "use": "sig",
+                "alg": "RS256",
+                "n": encode(self.private_key.n()),
+                "e": encode(self.private_key.e()),
+            }]
+        })
+    }
+}
+
+/// The query string parameters of an authorization request, or of a callback.
+pub fn query_parameters(url: &str) -> HashMap<String, String> {
+    let query = url.split_once('?').map_or("", |(_, query)| query);
+    serde_urlencoded::from_str(query).unwrap()
+}
+
+impl MockOidcServer {
+    pub fn start() -> Self {
+        let listener = task::block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let address = listener.local_addr().unwrap();
+        let issuer = format!("https://{}", address);
+        let grants = Arc::new(Mutex::new(HashMap::new()));
+        let keys = Arc::new(Keys::generate());
+        let identity = native_tls::Identity::from_pkcs12(&keys.identity, "").unwrap();
+        let acceptor: TlsAcceptor = native_tls::TlsAcceptor::new(identity).unwrap().into();
+        let certificate = String::from_utf8(keys.certificate.to_pem().unwrap()).unwrap();
+
+        let server_issuer = issuer.clone();
+        let server_grants = grants.clone();
+        task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let grants = server_grants.clone();
+                let issuer = server_issuer.clone();
+                let keys = keys.clone();
+                task::spawn(async move { handle_request(stream, &issuer, &grants, &keys).await });
+            }
+        });
+
+        Self {
+            issuer,
+            certificate,
+            grants,
+        }
+    }
+
+    /// How the application is configured to use the mock, under the name `mock`.
+    pub fn provider(&self) -> OidcProvider {
+        OidcProvider {
+            name: "mock".into(),
+            issuer: self.issuer.clone(),
+            client_id: CLIENT_ID.into(),
+            client_secret: CLIENT_SECRET.into(),
+            authorization_endpoint: format!("{}/authorize", self.issuer),
+            token_endpoint: format!("{}/token", self.issuer),
+            jwks_uri: format!("{}/jwks", self.issuer),
+            ca_certificate: Some(self.certificate.clone()),
+            redirect_uri: REDIRECT_URI.into(),
+            scopes: vec!["email".into()],
+        }
+    }
+
+    /// Authenticate a user following `authorization_url`,
+    /// returning the `code` and the `state` the provider sends them back with.
+    pub fn authenticate(
+        &self,
+        authorization_url: &str,
+        subject: &str,
+        email: &str,
+        email_verified: bool,
+    ) -> (String, String) {
+        let request = query_parameters(authorization_url);
+        assert_eq!(request["client_id"], CLIENT_ID);
+        assert_eq!(request["redirect_uri"], REDIRECT_URI);
+        assert_eq!(request["response_type"], "code");
+        assert_eq!(request["code_challenge_method"], "S256");
+        assert!(request["scope"].split(' ').any(|s| s == "openid"));
+
+        let code = Uuid::new_v4().to_string();
+        let grant = Grant {
+            code_challenge: request["code_challenge"].clone(),
+            nonce: request["nonce"].clone(),
+            subject: subject.into(),
+            email: email.into(),
+            email_verified,
+        };
+        self.grants.lock().unwrap().insert(code.clone(), grant);
+        (code, request["state"].clone())
+    }
+}
+
+/// The challenge matching `code_verifier`, as the provider computes it.
+fn code_challenge(code_verifier: &str) -> String {
+    OidcLoginAttempt {
+        code_verifier: code_verifier.into(),
+        ..OidcLoginAttempt::generate("mock").unwrap()
+    }
+    .code_challenge()
+}
+
+async fn handle_request(
+    mut stream: TlsStream<TcpStream>,
+    issuer: &str,
+    grants: &Mutex<HashMap<String, Grant>>,
+    keys: &Keys,
+) {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let (request_line, form) = loop {
+        let read = stream.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(end_of_head) = text.find("\r\n\r\n") {
+            let content_length: usize = text[..end_of_head]
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .map_or(0, |l| l.trim().parse().unwrap());
+            let body = &text[end_of_head + 4..];
+            if body.len() >= content_length || read == 0 {
+                let form: HashMap<String, String> = serde_urlencoded::from_str(body).unwrap();
+                break (text.lines().next().unwrap().to_owned(), form);
+            }
+        }
+    };
+
+    let (status, body) = if request_line.starts_with("GET /jwks ") {
+        ("200 OK", keys.jwks())
+    } else {
+        token_response(&form, issuer, grants, keys)
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.0 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+    // Tells the client that the response is complete
+    futures::io::AsyncWriteExt::close(&mut stream)
+        .await
+        .unwrap();
+}
+
+fn token_response(
+    form: &HashMap<String, String>,
+    issuer: &str,
+    grants: &Mutex<HashMap<String, Grant>>,
+    keys: &Keys,
+) -> (&'static str, Value) {
+    // Codes are single-use
+    let grant = grants.lock().unwrap().remove(&form["code"]);
+    match grant {
+        Some(grant)
+            if form["grant_type"] == "authorization_code"
+                && form["client_id"] == CLIENT_ID
+                && form["client_secret"] == CLIENT_SECRET
+                && form["redirect_uri"] == REDIRECT_URI
+                && code_challenge(&form["code_verifier"]) == grant.code_challenge =>
+        {
+            let claims = json!({
+                "iss": issuer,
+                "sub": grant.subject,
+                "aud": CLIENT_ID,
+                "exp": Utc::now().timestamp() + 300,
+                "nonce": grant.nonce,
+                "email": grant.email,
+                "email_verified": grant.email_verified,
+            });
+            let header = Header {
+                kid: Some(KEY_ID.into()),
+                ..Header::new(Algorithm::RS256)
+            };
+            let private_key = keys.private_key.private_key_to_der().unwrap();
+            let id_token = encode(&header, &claims, &private_key).unwrap();
+            (
+                "200 OK",
+                json!({ "id_token": id_token, "token_type": "Bearer" }),
+            )
+        }
+        _ => ("400 Bad Request", json!({ "error": "invalid_grant" })),
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
 pub mod generate;
+pub mod mock_oidc;
 pub mod test_db;
+pub mod test_mailer;
 pub mod test_server;
 
 use crate::helpers::generate::With;
@@ -24,6 +26,9 @@ pub fn create_user(repo: &Repo) -> (User, String) {
         id: Uuid::new_v4(),
     };
     let new_user = users::insert(&repo, new_user).expect("Failed to create user");
+    // Most tests need users who are allowed to publish and to comment
+    let new_user = users::verify_email(&repo, new_user.id, &new_user.email)
+        .expect("Failed to verify the user's email");
     (new_user, clear_text_password)
 }
 
@@ -33,10 +38,10 @@ pub fn create_users2(repo: &Repository, num_users: i32) -> Vec<(domain::User, St
 
 pub fn create_user2(repo: &Repository) -> (domain::User, String) {
     let (new_user, password) = generate::new_user();
+    let new_user: domain::User = repo.sign_up(new_user).expect("Failed to create user");
     let new_user = repo
-        .sign_up(new_user)
-        .expect("Failed to create user")
-        .into();
+        .verify_email(new_user.id, &new_user.email)
+        .expect("Failed to verify the user's email");
     (new_user, password)
 }
 
@@ -27,7 +27,11 @@ pub fn get_test_repo() -> Repo {
 pub fn clean_db(repo: &Repository) {
     repo.0
         .conn()
-        .batch_execute("DELETE FROM users; DELETE FROM articles;")
+        // The deleted user placeholder is created by a migration: keep it around
+        .batch_execute(
+            "DELETE FROM users WHERE id <> '00000000-0000-0000-0000-000000000000'; \
+             DELETE FROM articles;",
+        )
         .expect("Failed to clean database");
 }
 
@@ -0,0 +1,15 @@
+use domain::{Email, Mailer, MailerError};
+use std::sync::{Arc, Mutex};
+
+/// Keeps the emails in memory, so that tests can inspect what has been sent.
+#[derive(Clone, Default)]
+pub struct TestMailer {
+    pub outbox: Arc<Mutex<Vec<Email>>>,
+}
+
+impl Mailer for TestMailer {
+    fn send(&self, email: Email) -> Result<(), MailerError> {
+        self.outbox.lock().unwrap().push(email);
+        Ok(())
+    }
+}
@@ -1,38 +1,107 @@
 use realworld_web::get_app;
-use realworld_web::users::responses::UserResponse;
+use realworld_web::users::responses::{
+    AccessTokensResponse, NewAccessTokenResponse, RecoveryCodesResponse,
+    TwoFactorChallengeResponse, TwoFactorEnrollmentResponse, UserResponse,
+};
 
 use crate::helpers::test_db::{clean_db, get_repo};
+use crate::helpers::test_mailer::TestMailer;
+use application::configuration::Settings;
 use async_std::io::prelude::ReadExt;
 use db::Repository;
 use domain::articles::ArticleQuery;
-use domain::SignUp;
+use domain::{Email, PasswordHashing, SignUp};
 use http_service::Response;
 use http_service_mock::{make_server, TestBackend};
-use realworld_web::articles::responses::{ArticleResponse, ArticlesResponse};
+use realworld_web::articles::responses::{
+    ArticleResponse, ArticlesResponse, RevisionResponse, RevisionsResponse,
+};
+use realworld_web::client_address::WithPeerAddr;
 use realworld_web::comments::responses::{CommentResponse, CommentsResponse};
-use realworld_web::profiles::responses::ProfileResponse;
-use realworld_web::Context;
+use realworld_web::cors::CorsPolicy;
+use realworld_web::notifications::responses::NotificationsResponse;
+use realworld_web::profiles::responses::{ProfileResponse, ProfilesResponse};
+use realworld_web::throttle::LoginThrottling;
+use realworld_web::{AppSettings, Context};
 use serde::de::DeserializeOwned;
 use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tide::server::Service;
+use uuid::Uuid;
 
-pub type TestServer = TestBackend<Service<Context<Repository>>>;
+pub type TestServer = TestBackend<WithPeerAddr<Service<Context<Repository>>>>;
 
 pub struct TestApp {
     pub server: TestServer,
     pub repository: Repository,
+    pub mailer: TestMailer,
 }
 
 impl TestApp {
     pub fn new() -> Self {
-        let app = get_app(get_repo());
-        let server = make_server(app.into_http_service()).unwrap();
+        Self::with_settings(TestApp::settings())
+    }
+
+    pub fn with_password_hashing(password_hashing: PasswordHashing) -> Self {
+        Self::with_settings(AppSettings {
+            password_hashing,
+            ..TestApp::settings()
+        })
+    }
+
+    pub fn with_login_throttling(login_throttling: LoginThrottling) -> Self {
+        Self::with_settings(AppSettings {
+            login_throttling,
+            ..TestApp::settings()
+        })
+    }
+
+    pub fn with_cors(cors: CorsPolicy) -> Self {
+        Self::with_settings(AppSettings {
+            cors,
+            ..TestApp::settings()
+        })
+    }
+
+    fn settings() -> AppSettings {
+        Settings::new(PathBuf::from("../../"))
+            .expect("Failed to load configuration")
+            .app_settings()
+    }
+
+    pub fn with_settings(settings: AppSettings) -> Self {
+        let mailer = TestMailer::default();
+        let app = get_app(get_repo(), Box::new(mailer.clone()), settings);
+        // All requests come from the same client
+        let peer = SocketAddr::from(([127, 0, 0, 1], 49152));
+        let server =
+            make_server(WithPeerAddr::new(Arc::new(app.into_http_service()), peer)).unwrap();
         Self {
             server,
             repository: get_repo(),
+            mailer,
         }
     }
 
+    /// The emails sent by the application so far.
+    pub fn sent_emails(&self) -> Vec<Email> {
+        self.mailer.outbox.lock().unwrap().clone()
+    }
+
+    /// Waits for the emails sent in the background (e.g. password resets) to reach `count`.
+    pub async fn wait_for_emails(&self, count: usize) -> Vec<Email> {
+        for _ in 0..100 {
+            let emails = self.sent_emails();
+            if emails.len() >= count {
+                return emails;
+            }
+            async_std::task::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("Expected {} emails, got {:?}", count, self.sent_emails());
+    }
+
     pub async fn register_user(
         &mut self,
         user: &SignUp,
@@ -65,8 +134,22 @@ impl TestApp {
         email: &str,
         password: &str,
     ) -> Result<UserResponse, Response> {
-        let response = self
-            .server
+        let response = self.post_login(email, password).await;
+        response_json_if_success(response).await
+    }
+
+    /// Log in a user with two-factor authentication enabled, up to the challenge.
+    pub async fn start_login(
+        &mut self,
+        email: &str,
+        password: &str,
+    ) -> Result<TwoFactorChallengeResponse, Response> {
+        let response = self.post_login(email, password).await;
+        response_json_if_success(response).await
+    }
+
+    async fn post_login(&mut self, email: &str, password: &str) -> Response {
+        self.server
             .simulate(
                 http::Request::post("/api/users/login")
                     .body(
@@ -82,10 +165,154 @@ impl TestApp {
                     )
                     .unwrap(),
             )
+            .unwrap()
+    }
+
+    pub async fn login_second_factor(
+        &mut self,
+        challenge_token: &str,
+        code: &str,
+    ) -> Result<UserResponse, Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/users/login/two-factor")
+                    .body(
+                        json!({ "token": challenge_token, "code": code })
+                            .to_string()
+                            .into_bytes()
+                            .into(),
+                    )
+                    .unwrap(),
+            )
             .unwrap();
         response_json_if_success(response).await
     }
 
+    pub async fn enroll_two_factor(
+        &mut self,
+        token: &str,
+    ) -> Result<TwoFactorEnrollmentResponse, Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/user/two-factor")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn confirm_two_factor(
+        &mut self,
+        code: &str,
+        token: &str,
+    ) -> Result<RecoveryCodesResponse, Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/user/two-factor/confirm")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(json!({ "code": code }).to_string().into_bytes().into())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn disable_two_factor(&mut self, code: &str, token: &str) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::delete("/api/user/two-factor")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(json!({ "code": code }).to_string().into_bytes().into())
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    pub async fn verify_email(&mut self, token: &str) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/users/verify-email")
+                    .body(json!({ "token": token }).to_string().into_bytes().into())
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    /// Verify an email address with the token contained in the last email that was sent.
+    pub async fn confirm_email_from_last_message(&mut self) -> Result<(), Response> {
+        let email = self.sent_emails().pop().expect("No email has been sent");
+        let token = email
+            .body
+            .split_whitespace()
+            .find(|w| w.matches('.').count() == 2)
+            .expect("No verification token in the email")
+            .to_string();
+        self.verify_email(&token).await
+    }
+
+    pub async fn request_password_reset(&mut self, email: &str) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/users/password-reset")
+                    .body(
+                        json!({ "user": { "email": email } })
+                            .to_string()
+                            .into_bytes()
+                            .into(),
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    pub async fn confirm_password_reset(
+        &mut self,
+        token: &str,
+        password: &str,
+    ) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/users/password-reset/confirm")
+                    .body(
+                        json!({ "user": { "token": token, "password": password } })
+                            .to_string()
+                            .into_bytes()
+                            .into(),
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
     pub async fn get_current_user(&mut self, token: &String) -> Result<UserResponse, Response> {
         let auth_header = format!("token: {}", token);
         let response = self
@@ -100,6 +327,64 @@ impl TestApp {
         response_json_if_success(response).await
     }
 
+    /// Start logging in with an identity provider: the response redirects to the provider.
+    pub async fn authorize_external_login(&mut self, provider: &str) -> Response {
+        self.server
+            .simulate(
+                http::Request::get(format!("/api/auth/{}/authorize", provider))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap()
+    }
+
+    /// Where the identity provider sends users back to, with the `state` cookie set by
+    /// `authorize_external_login`, if any.
+    pub async fn external_login_callback(
+        &mut self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        state_cookie: Option<&str>,
+    ) -> Result<UserResponse, Response> {
+        let response = self
+            .get_external_login_callback(provider, code, state, state_cookie)
+            .await;
+        response_json_if_success(response).await
+    }
+
+    /// Complete an external login for a user with two-factor authentication enabled,
+    /// up to the challenge.
+    pub async fn external_login_challenge(
+        &mut self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        state_cookie: Option<&str>,
+    ) -> Result<TwoFactorChallengeResponse, Response> {
+        let response = self
+            .get_external_login_callback(provider, code, state, state_cookie)
+            .await;
+        response_json_if_success(response).await
+    }
+
+    async fn get_external_login_callback(
+        &mut self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        state_cookie: Option<&str>,
+    ) -> Response {
+        let query = serde_urlencoded::to_string([("code", code), ("state", state)]).unwrap();
+        let mut request = http::Request::get(format!("/api/auth/{}/callback?{}", provider, query));
+        if let Some(cookie) = state_cookie {
+            request.header("Cookie", cookie);
+        }
+        self.server
+            .simulate(request.body(http_service::Body::empty()).unwrap())
+            .unwrap()
+    }
+
     pub async fn update_user_details(
         &mut self,
         details: &realworld_web::users::update::Request,
@@ -117,6 +402,79 @@ impl TestApp {
         response_json_if_success(response).await
     }
 
+    pub async fn delete_user(
+        &mut self,
+        details: &realworld_web::users::delete::Request,
+        token: &str,
+    ) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::delete("/api/user")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(serde_json::to_string(details).unwrap().into_bytes().into())
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    pub async fn create_access_token(
+        &mut self,
+        name: &str,
+        scopes: &[&str],
+        token: &str,
+    ) -> Result<NewAccessTokenResponse, Response> {
+        let body = json!({ "token": { "name": name, "scopes": scopes } });
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/user/tokens")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(body.to_string().into_bytes().into())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn list_access_tokens(
+        &mut self,
+        token: &str,
+    ) -> Result<AccessTokensResponse, Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::get("/api/user/tokens")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn revoke_access_token(&mut self, id: &Uuid, token: &str) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::delete(format!("/api/user/tokens/{}", id))
+                    .header("Authorization", format!("token: {}", token))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
     pub async fn create_article(
         &mut self,
         article: &realworld_web::articles::insert::Request,
@@ -162,6 +520,13 @@ impl TestApp {
         query: Option<ArticleQuery>,
     ) -> Result<ArticlesResponse, Response> {
         let query_string = serde_qs::to_string(&query).unwrap();
+        self.get_articles_by_query_string(&query_string).await
+    }
+
+    pub async fn get_articles_by_query_string(
+        &mut self,
+        query_string: &str,
+    ) -> Result<ArticlesResponse, Response> {
         let url = format!("/api/articles?{}", query_string);
         let response = self
             .server
@@ -174,6 +539,79 @@ impl TestApp {
         response_json_if_success(response).await
     }
 
+    pub async fn get_drafts(&mut self, token: &str) -> Result<ArticlesResponse, Response> {
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::get("/api/user/drafts")
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn get_revisions(
+        &mut self,
+        slug: &str,
+        token: &str,
+    ) -> Result<RevisionsResponse, Response> {
+        let url = format!("/api/articles/{}/revisions", slug);
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::get(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn get_revision(
+        &mut self,
+        slug: &str,
+        revision_id: u64,
+        token: &str,
+    ) -> Result<RevisionResponse, Response> {
+        let url = format!("/api/articles/{}/revisions/{}", slug, revision_id);
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::get(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn restore_revision(
+        &mut self,
+        slug: &str,
+        revision_id: u64,
+        token: &str,
+    ) -> Result<ArticleResponse, Response> {
+        let url = format!("/api/articles/{}/revisions/{}/restore", slug, revision_id);
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::post(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
     pub async fn get_article(
         &mut self,
         slug: &str,
@@ -275,6 +713,45 @@ impl TestApp {
         response_json_if_success(response).await
     }
 
+    pub async fn get_followers(
+        &mut self,
+        username: &str,
+        token: Option<&str>,
+    ) -> Result<ProfilesResponse, Response> {
+        let url = format!("/api/profiles/{}/followers", username);
+        self.get_profiles(url, token).await
+    }
+
+    pub async fn get_following(
+        &mut self,
+        username: &str,
+        token: Option<&str>,
+    ) -> Result<ProfilesResponse, Response> {
+        let url = format!("/api/profiles/{}/following", username);
+        self.get_profiles(url, token).await
+    }
+
+    async fn get_profiles(
+        &mut self,
+        url: String,
+        token: Option<&str>,
+    ) -> Result<ProfilesResponse, Response> {
+        let request = match token {
+            Some(token) => {
+                let auth_header = format!("token: {}", token);
+                http::Request::get(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap()
+            }
+            None => http::Request::get(url)
+                .body(http_service::Body::empty())
+                .unwrap(),
+        };
+        let response = self.server.simulate(request).unwrap();
+        response_json_if_success(response).await
+    }
+
     pub async fn follow_profile(
         &mut self,
         username: &str,
@@ -294,6 +771,44 @@ impl TestApp {
         response_json_if_success(response).await
     }
 
+    pub async fn block_profile(
+        &mut self,
+        username: &str,
+        token: &str,
+    ) -> Result<ProfileResponse, Response> {
+        let url = format!("/api/profiles/{}/block", username);
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::post(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn mute_profile(
+        &mut self,
+        username: &str,
+        token: &str,
+    ) -> Result<ProfileResponse, Response> {
+        let url = format!("/api/profiles/{}/mute", username);
+        let auth_header = format!("token: {}", token);
+        let response = self
+            .server
+            .simulate(
+                http::Request::post(url)
+                    .header("Authorization", auth_header)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
     pub async fn unfollow_profile(
         &mut self,
         username: &str,
@@ -379,6 +894,103 @@ impl TestApp {
             Err(response)
         }
     }
+
+    /// Executes a GraphQL operation: the response is returned as is, errors included.
+    pub async fn graphql(
+        &mut self,
+        query: &str,
+        variables: serde_json::Value,
+        token: Option<&str>,
+    ) -> Response {
+        let body = json!({ "query": query, "variables": variables });
+        let mut request = http::Request::post("/graphql");
+        if let Some(token) = token {
+            request.header("Authorization", format!("token: {}", token));
+        }
+        let request = request.body(body.to_string().into_bytes().into()).unwrap();
+        self.server.simulate(request).unwrap()
+    }
+
+    pub async fn get_notifications(
+        &mut self,
+        query: &str,
+        token: &str,
+    ) -> Result<NotificationsResponse, Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::get(format!("/api/notifications{}", query))
+                    .header("Authorization", format!("token: {}", token))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        response_json_if_success(response).await
+    }
+
+    pub async fn mark_notification(
+        &mut self,
+        id: u64,
+        read: bool,
+        token: &str,
+    ) -> Result<(), Response> {
+        let url = format!("/api/notifications/{}/read", id);
+        let request = if read {
+            http::Request::post(url)
+        } else {
+            http::Request::delete(url)
+        }
+        .header("Authorization", format!("token: {}", token))
+        .body(http_service::Body::empty())
+        .unwrap();
+        let response = self.server.simulate(request).unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    pub async fn mark_all_notifications_read(&mut self, token: &str) -> Result<(), Response> {
+        let response = self
+            .server
+            .simulate(
+                http::Request::post("/api/notifications/read")
+                    .header("Authorization", format!("token: {}", token))
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(response)
+        }
+    }
+
+    /// The response streams events for as long as its body is read.
+    pub fn notification_stream(&mut self, token: &str, last_event_id: Option<u64>) -> Response {
+        self.get_notification_stream(format!("token: {}", token), last_event_id)
+    }
+
+    /// Connect to the notification stream with an access token rather than a session.
+    pub fn notification_stream_with_access_token(&mut self, secret: &str) -> Response {
+        self.get_notification_stream(format!("Bearer {}", secret), None)
+    }
+
+    fn get_notification_stream(
+        &mut self,
+        authorization: String,
+        last_event_id: Option<u64>,
+    ) -> Response {
+        let mut request = http::Request::get("/api/notifications/stream");
+        request.header("Authorization", authorization);
+        if let Some(id) = last_event_id {
+            request.header("Last-Event-ID", id.to_string());
+        }
+        let request = request.body(http_service::Body::empty()).unwrap();
+        self.server.simulate(request).unwrap()
+    }
 }
 
 impl std::ops::Drop for TestApp {
@@ -0,0 +1,113 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::create_users;
+use helpers::test_server::TestApp;
+
+use async_std::task;
+use http::StatusCode;
+use realworld_web::auth::encode_token;
+use serde_json::json;
+
+/// A request authenticated with an access token rather than a session.
+fn bearer_request(
+    method: http::Method,
+    url: &str,
+    secret: &str,
+    body: serde_json::Value,
+) -> http::Request<http_service::Body> {
+    http::Request::builder()
+        .method(method)
+        .uri(url)
+        .header("Authorization", format!("Bearer {}", secret))
+        .body(body.to_string().into_bytes().into())
+        .unwrap()
+}
+
+#[test]
+fn access_tokens_are_limited_to_their_scopes() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let session = encode_token(user.id);
+
+        let response = server
+            .create_access_token("ci", &["articles:delete"], &session)
+            .await
+            .expect_err("Unknown scope");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let secret = server
+            .create_access_token("ci", &["articles:write"], &session)
+            .await
+            .unwrap()
+            .token
+            .secret;
+
+        let article = json!({
+            "article": { "title": "Cross-posted", "description": "From CI", "body": "Hello" }
+        });
+        let request = bearer_request(http::Method::POST, "/api/articles", &secret, article);
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let comment = json!({ "comment": { "body": "Not allowed" } });
+        let request = bearer_request(
+            http::Method::POST,
+            "/api/articles/cross-posted/comments",
+            &secret,
+            comment,
+        );
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            "Bearer error=\"insufficient_scope\", scope=\"comments:write\"",
+            response.headers()["WWW-Authenticate"]
+        );
+
+        // Reading is always allowed, managing the account never
+        let request = bearer_request(http::Method::GET, "/api/articles/feed", &secret, json!({}));
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = bearer_request(http::Method::GET, "/api/user", &secret, json!({}));
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    })
+}
+
+#[test]
+fn revoked_access_tokens_are_rejected() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let session = encode_token(user.id);
+        let secret = server
+            .create_access_token("ci", &["favorites:write"], &session)
+            .await
+            .unwrap()
+            .token
+            .secret;
+        let request = bearer_request(http::Method::GET, "/api/articles/feed", &secret, json!({}));
+        server.server.simulate(request).unwrap();
+
+        let tokens = server.list_access_tokens(&session).await.unwrap().tokens;
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].name, "ci");
+        assert_eq!(tokens[0].scopes, vec!["favorites:write".to_string()]);
+        assert!(tokens[0].last_used_at.is_some());
+
+        server
+            .revoke_access_token(&tokens[0].id, &session)
+            .await
+            .unwrap();
+        let request = bearer_request(http::Method::GET, "/api/articles/feed", &secret, json!({}));
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(server
+            .list_access_tokens(&session)
+            .await
+            .unwrap()
+            .tokens
+            .is_empty());
+    })
+}
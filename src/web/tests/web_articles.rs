@@ -7,10 +7,12 @@ use helpers::test_server::TestApp;
 use helpers::{create_article, create_articles, create_user, create_users};
 
 use async_std::task;
-use domain::articles::ArticleQuery;
+use chrono::{Duration, SubsecRound, Utc};
+use domain::articles::{ArticleQuery, ArticleSort};
 use fake::fake;
 use itertools::Itertools;
 use realworld_web::articles::insert::NewArticleRequest;
+use realworld_web::articles::status::Status;
 use realworld_web::articles::update::UpdateArticleRequest;
 use realworld_web::auth::encode_token;
 
@@ -91,6 +93,11 @@ fn should_get_articles_by_author() {
             author: Some(author.username),
             tag: None,
             favorited: None,
+            q: None,
+            sort: None,
+            limit: None,
+            offset: None,
+            cursor: None,
         };
         let articles = server.get_articles(Some(query)).await.unwrap().articles;
 
@@ -103,6 +110,163 @@ fn should_get_articles_by_author() {
     })
 }
 
+#[test]
+fn should_search_articles() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 3)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        create_articles(&server.repository.0, users);
+
+        let user = create_user(&server.repository.0).0;
+        let token = encode_token(user.id);
+        let new_article_request = realworld_web::articles::insert::Request {
+            article: NewArticleRequest {
+                title: "How to train your borrow checker".into(),
+                description: "Lifetimes explained".into(),
+                body: "Fighting the borrow checker is a rite of passage".into(),
+                tag_list: None,
+                status: None,
+                publish_at: None,
+            },
+        };
+        server
+            .create_article(&new_article_request, &token)
+            .await
+            .unwrap();
+
+        let query = Some(ArticleQuery {
+            q: Some("borrow checker".into()),
+            ..Default::default()
+        });
+        let articles = server.get_articles(query).await.unwrap().articles;
+        assert_eq!(articles.len(), 1);
+        let snippet = articles[0]
+            .snippet
+            .as_ref()
+            .expect("Search results have a snippet");
+        assert!(snippet.contains("<mark>borrow</mark> <mark>checker</mark>"));
+
+        // Unrelated listings don't carry snippets
+        let articles = server.get_articles(None).await.unwrap().articles;
+        assert_eq!(articles.len(), 4);
+        assert!(articles.iter().all(|a| a.snippet.is_none()));
+    })
+}
+
+#[test]
+fn should_sort_articles() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 3)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let articles = create_articles(&server.repository.0, users.clone());
+        let most_favorited = &articles[1].slug;
+        server
+            .favorite_article(most_favorited, &encode_token(users[0].id))
+            .await
+            .unwrap();
+
+        let newest = server
+            .get_articles_by_query_string("sort=newest")
+            .await
+            .unwrap()
+            .articles;
+        let oldest = server
+            .get_articles_by_query_string("sort=oldest")
+            .await
+            .unwrap()
+            .articles;
+        let reversed_oldest: Vec<String> = oldest.iter().rev().map(|a| a.slug.clone()).collect();
+        assert_eq!(
+            newest.iter().map(|a| a.slug.clone()).collect_vec(),
+            reversed_oldest
+        );
+
+        // The ordering is stable across requests
+        let default = server.get_articles(None).await.unwrap().articles;
+        assert_eq!(
+            default.iter().map(|a| a.slug.clone()).collect_vec(),
+            newest.iter().map(|a| a.slug.clone()).collect_vec()
+        );
+
+        let query = Some(ArticleQuery {
+            sort: Some(ArticleSort::MostFavorited),
+            ..Default::default()
+        });
+        let by_favorites = server.get_articles(query).await.unwrap().articles;
+        assert_eq!(&by_favorites[0].slug, most_favorited);
+
+        let response = server
+            .get_articles_by_query_string("sort=most-liked")
+            .await
+            .expect_err("Unknown sort orders are rejected");
+        assert_eq!(response.status(), 400);
+    })
+}
+
+#[test]
+fn should_paginate_articles_with_cursors() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 5)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        create_articles(&server.repository.0, users);
+        let slugs = |articles: &[realworld_web::articles::responses::Article]| {
+            articles.iter().map(|a| a.slug.clone()).collect_vec()
+        };
+
+        let all = server.get_articles(None).await.unwrap();
+        assert!(all.next_cursor.is_none());
+        assert!(all.prev_cursor.is_none());
+
+        let first_page = server
+            .get_articles_by_query_string("limit=2")
+            .await
+            .unwrap();
+        assert_eq!(slugs(&first_page.articles), slugs(&all.articles[..2]));
+        assert!(first_page.prev_cursor.is_none());
+
+        let next_cursor = first_page.next_cursor.expect("There is a next page");
+        let second_page = server
+            .get_articles_by_query_string(&format!("limit=2&cursor={}", next_cursor))
+            .await
+            .unwrap();
+        assert_eq!(slugs(&second_page.articles), slugs(&all.articles[2..4]));
+
+        let prev_cursor = second_page.prev_cursor.expect("There is a previous page");
+        let previous_page = server
+            .get_articles_by_query_string(&format!("limit=2&cursor={}", prev_cursor))
+            .await
+            .unwrap();
+        assert_eq!(slugs(&previous_page.articles), slugs(&first_page.articles));
+
+        // Offset paging keeps working
+        let offset_page = server
+            .get_articles_by_query_string("limit=2&offset=2")
+            .await
+            .unwrap();
+        assert_eq!(slugs(&offset_page.articles), slugs(&second_page.articles));
+
+        let response = server
+            .get_articles_by_query_string("cursor=not-a-cursor")
+            .await
+            .expect_err("Invalid cursors are rejected");
+        assert_eq!(response.status(), 400);
+        let response = server
+            .get_articles_by_query_string(&format!("sort=most-favorited&cursor={}", next_cursor))
+            .await
+            .expect_err("Cursors require sorting by creation date");
+        assert_eq!(response.status(), 400);
+    })
+}
+
 #[test]
 fn should_create_article() {
     task::block_on(async move {
@@ -114,6 +278,7 @@ fn should_create_article() {
             .expect("Failed to create user")
             .user;
         let token = user.token;
+        server.confirm_email_from_last_message().await.unwrap();
 
         let article = generate::article_content();
         let new_article_request = realworld_web::articles::insert::Request {
@@ -122,6 +287,8 @@ fn should_create_article() {
                 description: article.description.clone(),
                 body: article.body.clone(),
                 tag_list: Some(article.tag_list.clone()),
+                status: None,
+                publish_at: None,
             },
         };
         server
@@ -133,6 +300,11 @@ fn should_create_article() {
             author: Some(user.username),
             tag: None,
             favorited: None,
+            q: None,
+            sort: None,
+            limit: None,
+            offset: None,
+            cursor: None,
         });
         let articles = server.get_articles(query).await.unwrap().articles;
 
@@ -145,6 +317,126 @@ fn should_create_article() {
     })
 }
 
+#[test]
+fn drafts_are_only_visible_to_their_author_until_published() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 2)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let author_token = encode_token(users[0].id);
+        let reader_token = encode_token(users[1].id);
+
+        let article = generate::article_content();
+        let new_article_request = realworld_web::articles::insert::Request {
+            article: NewArticleRequest {
+                title: article.title.clone(),
+                description: article.description.clone(),
+                body: article.body.clone(),
+                tag_list: Some(article.tag_list.clone()),
+                status: Some(Status::Draft),
+                publish_at: None,
+            },
+        };
+        let draft = server
+            .create_article(&new_article_request, &author_token)
+            .await
+            .unwrap()
+            .article;
+        assert_eq!(draft.status, Status::Draft);
+
+        // Nobody but the author can see it
+        let articles = server.get_articles(None).await.unwrap().articles;
+        assert!(articles.is_empty());
+        assert!(server.get_article(&draft.slug, None).await.is_err());
+        assert!(server
+            .get_article(&draft.slug, Some(&reader_token))
+            .await
+            .is_err());
+        server
+            .get_article(&draft.slug, Some(&author_token))
+            .await
+            .unwrap();
+
+        let drafts = server.get_drafts(&author_token).await.unwrap().articles;
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].slug, draft.slug);
+        assert!(server
+            .get_drafts(&reader_token)
+            .await
+            .unwrap()
+            .articles
+            .is_empty());
+
+        // Publish it
+        let update = realworld_web::articles::update::Request {
+            article: UpdateArticleRequest {
+                title: None,
+                description: None,
+                body: None,
+                status: Some(Status::Published),
+                publish_at: None,
+            },
+        };
+        let published = server
+            .update_article(&update, &draft.slug, &author_token)
+            .await
+            .unwrap()
+            .article;
+        assert_eq!(published.status, Status::Published);
+
+        let articles = server.get_articles(None).await.unwrap().articles;
+        assert_eq!(articles.len(), 1);
+        assert!(server
+            .get_drafts(&author_token)
+            .await
+            .unwrap()
+            .articles
+            .is_empty());
+    })
+}
+
+#[test]
+fn scheduled_articles_require_a_publication_time() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let user = create_user(&server.repository.0).0;
+        let token = encode_token(user.id);
+
+        let article = generate::article_content();
+        let mut request = realworld_web::articles::insert::Request {
+            article: NewArticleRequest {
+                title: article.title.clone(),
+                description: article.description.clone(),
+                body: article.body.clone(),
+                tag_list: None,
+                status: Some(Status::Scheduled),
+                publish_at: None,
+            },
+        };
+        let response = server.create_article(&request, &token).await;
+        assert_eq!(
+            http::StatusCode::BAD_REQUEST,
+            response.err().unwrap().status()
+        );
+
+        let publish_at = (Utc::now() + Duration::days(1)).trunc_subsecs(0);
+        request.article.publish_at = Some(publish_at);
+        let scheduled = server
+            .create_article(&request, &token)
+            .await
+            .unwrap()
+            .article;
+        assert_eq!(scheduled.status, Status::Scheduled);
+        assert_eq!(scheduled.publish_at, Some(publish_at));
+
+        assert!(server.get_articles(None).await.unwrap().articles.is_empty());
+        let drafts = server.get_drafts(&token).await.unwrap().articles;
+        assert_eq!(drafts.len(), 1);
+    })
+}
+
 #[test]
 fn should_update_article() {
     task::block_on(async move {
@@ -158,6 +450,8 @@ fn should_update_article() {
                 title: Some(fake!(Lorem.sentence(4, 10)).to_string()),
                 description: None,
                 body: Some(fake!(Lorem.paragraph(10, 5))),
+                status: None,
+                publish_at: None,
             },
         };
         let updated_article = server
@@ -170,6 +464,131 @@ fn should_update_article() {
     })
 }
 
+#[test]
+fn edits_are_recorded_and_can_be_restored() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let author = create_user(&server.repository.0).0;
+        let author_token = encode_token(author.id);
+        let other_user = create_user(&server.repository.0).0;
+        let other_token = encode_token(other_user.id);
+        let article = create_article(&server.repository.0, &author);
+
+        let update = realworld_web::articles::update::Request {
+            article: UpdateArticleRequest {
+                title: None,
+                description: None,
+                body: Some(fake!(Lorem.paragraph(10, 5))),
+                status: None,
+                publish_at: None,
+            },
+        };
+        server
+            .update_article(&update, &article.slug, &author_token)
+            .await
+            .unwrap();
+
+        let revisions = server
+            .get_revisions(&article.slug, &author_token)
+            .await
+            .unwrap()
+            .revisions;
+        assert_eq!(revisions.len(), 2);
+        let (initial, edit) = (&revisions[0], &revisions[1]);
+        assert_eq!(initial.body, Some(article.body.clone()));
+        assert_eq!(edit.title, None);
+        assert_eq!(edit.body, update.article.body);
+        assert_eq!(edit.editor.username, author.username);
+
+        let revision = server
+            .get_revision(&article.slug, edit.id, &author_token)
+            .await
+            .unwrap()
+            .revision;
+        assert_eq!(&revision, edit);
+
+        let restored = server
+            .restore_revision(&article.slug, initial.id, &author_token)
+            .await
+            .unwrap()
+            .article;
+        assert_eq!(restored.body, article.body);
+        assert_eq!(restored.title, article.title);
+
+        let revisions = server
+            .get_revisions(&article.slug, &author_token)
+            .await
+            .unwrap()
+            .revisions;
+        assert_eq!(revisions.len(), 3);
+
+        // Only the author can look at the edit history of an article
+        let response = server
+            .get_revisions(&article.slug, &other_token)
+            .await
+            .expect_err("Only the author can read revisions");
+        assert_eq!(response.status(), 403);
+        let response = server
+            .restore_revision(&article.slug, initial.id, &other_token)
+            .await
+            .expect_err("Only the author can restore revisions");
+        assert_eq!(response.status(), 403);
+
+        let response = server
+            .get_revision(&article.slug, edit.id + 1000, &author_token)
+            .await
+            .expect_err("Unknown revisions can't be retrieved");
+        assert_eq!(response.status(), 404);
+    })
+}
+
+#[test]
+fn you_cannot_update_an_article_which_you_did_not_write() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 2)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let article = create_article(&server.repository.0, &users[0]);
+
+        let update = realworld_web::articles::update::Request {
+            article: UpdateArticleRequest {
+                title: None,
+                description: None,
+                body: Some(fake!(Lorem.paragraph(10, 5))),
+                status: None,
+                publish_at: None,
+            },
+        };
+        let token = encode_token(users[1].id);
+        let response = server.update_article(&update, &article.slug, &token).await;
+        assert_eq!(
+            http::StatusCode::FORBIDDEN,
+            response.err().unwrap().status()
+        );
+    })
+}
+
+#[test]
+fn you_cannot_delete_an_article_which_you_did_not_write() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 2)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let article = create_article(&server.repository.0, &users[0]);
+
+        let token = encode_token(users[1].id);
+        let response = server.delete_article(&article.slug, &token).await;
+        assert_eq!(http::StatusCode::FORBIDDEN, response.unwrap_err().status());
+
+        // The article is still there
+        server.get_article(&article.slug, None).await.unwrap();
+    })
+}
+
 #[test]
 fn should_delete_article() {
     task::block_on(async move {
@@ -0,0 +1,117 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::TestApp;
+use helpers::{create_article, create_user};
+
+use async_std::task;
+use http::StatusCode;
+
+/// All the endpoints that require an authenticated caller.
+fn authenticated_endpoints(slug: &str, username: &str) -> Vec<(http::Method, String)> {
+    use http::Method;
+    vec![
+        (Method::GET, "/api/user".into()),
+        (Method::PUT, "/api/user".into()),
+        (Method::DELETE, "/api/user".into()),
+        (Method::POST, "/api/user/verification-email".into()),
+        (Method::POST, "/api/user/two-factor".into()),
+        (Method::DELETE, "/api/user/two-factor".into()),
+        (Method::POST, "/api/user/two-factor/confirm".into()),
+        (Method::GET, "/api/user/tokens".into()),
+        (Method::POST, "/api/user/tokens".into()),
+        (
+            Method::DELETE,
+            "/api/user/tokens/00000000-0000-0000-0000-000000000000".into(),
+        ),
+        (Method::POST, format!("/api/profiles/{}/follow", username)),
+        (Method::DELETE, format!("/api/profiles/{}/follow", username)),
+        (Method::POST, format!("/api/profiles/{}/block", username)),
+        (Method::DELETE, format!("/api/profiles/{}/block", username)),
+        (Method::POST, format!("/api/profiles/{}/mute", username)),
+        (Method::DELETE, format!("/api/profiles/{}/mute", username)),
+        (Method::GET, "/api/user/drafts".into()),
+        (Method::POST, "/api/articles".into()),
+        (Method::GET, "/api/articles/feed".into()),
+        (Method::PUT, format!("/api/articles/{}", slug)),
+        (Method::DELETE, format!("/api/articles/{}", slug)),
+        (Method::GET, format!("/api/articles/{}/revisions", slug)),
+        (Method::GET, format!("/api/articles/{}/revisions/1", slug)),
+        (
+            Method::POST,
+            format!("/api/articles/{}/revisions/1/restore", slug),
+        ),
+        (Method::POST, format!("/api/articles/{}/comments", slug)),
+        (Method::DELETE, format!("/api/articles/{}/comments/1", slug)),
+        (Method::POST, format!("/api/articles/{}/favorite", slug)),
+        (Method::DELETE, format!("/api/articles/{}/favorite", slug)),
+    ]
+}
+
+#[test]
+fn missing_token_is_rejected_with_a_challenge() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let user = create_user(&server.repository.0).0;
+        let article = create_article(&server.repository.0, &user);
+
+        for (method, url) in authenticated_endpoints(&article.slug, &user.username) {
+            let request = http::Request::builder()
+                .method(method.clone())
+                .uri(url.as_str())
+                .body(http_service::Body::empty())
+                .unwrap();
+            let response = server.server.simulate(request).unwrap();
+
+            assert_eq!(
+                StatusCode::UNAUTHORIZED,
+                response.status(),
+                "{} {}",
+                method,
+                url
+            );
+            assert_eq!(
+                "Token",
+                response.headers()["WWW-Authenticate"],
+                "{} {}",
+                method,
+                url
+            );
+        }
+    })
+}
+
+#[test]
+fn invalid_token_is_rejected_with_a_challenge() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let user = create_user(&server.repository.0).0;
+        let article = create_article(&server.repository.0, &user);
+
+        for (method, url) in authenticated_endpoints(&article.slug, &user.username) {
+            let request = http::Request::builder()
+                .method(method.clone())
+                .uri(url.as_str())
+                .header("Authorization", "Token not-a-valid-jwt")
+                .body(http_service::Body::empty())
+                .unwrap();
+            let response = server.server.simulate(request).unwrap();
+
+            assert_eq!(
+                StatusCode::UNAUTHORIZED,
+                response.status(),
+                "{} {}",
+                method,
+                url
+            );
+            assert_eq!(
+                "Token error=\"invalid_token\"",
+                response.headers()["WWW-Authenticate"],
+                "{} {}",
+                method,
+                url
+            );
+        }
+    })
+}
@@ -32,8 +32,8 @@ fn comments_api() {
         assert_eq!(first_comment.comment.author.username, user.username);
         assert_eq!(first_comment.comment.author.bio, user.bio);
         assert_eq!(first_comment.comment.author.image, user.image);
-        // A user follows themselves, by definition
-        assert_eq!(first_comment.comment.author.following, true);
+        // Users don't follow themselves
+        assert_eq!(first_comment.comment.author.following, false);
 
         // A user can create more than one comment for the same article
         let request = realworld_web::comments::create::Request {
@@ -109,9 +109,6 @@ fn you_cannot_delete_a_comment_which_you_did_not_write() {
             .delete_comment(&article.slug, &comment.comment.id, &token)
             .await;
         assert!(response.is_err());
-        assert_eq!(
-            http::StatusCode::UNAUTHORIZED,
-            response.unwrap_err().status()
-        );
+        assert_eq!(http::StatusCode::FORBIDDEN, response.unwrap_err().status());
     })
 }
@@ -0,0 +1,133 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::TestApp;
+
+use async_std::task;
+use http::Response;
+use http_service::Body;
+use realworld_web::cors::{AllowedOrigins, CorsPolicy};
+
+const FRONTEND: &str = "https://conduit.example.com";
+
+fn credentialed_policy() -> CorsPolicy {
+    CorsPolicy {
+        allowed_origins: AllowedOrigins::List(vec![FRONTEND.into()]),
+        allow_credentials: true,
+        ..CorsPolicy::default()
+    }
+}
+
+fn preflight(server: &mut TestApp, path: &str, origin: &str, method: &str) -> Response<Body> {
+    let request = http::Request::options(path)
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", method)
+        .header(
+            "Access-Control-Request-Headers",
+            "authorization, content-type",
+        )
+        .body(Body::empty())
+        .unwrap();
+    server.server.simulate(request).unwrap()
+}
+
+fn header<'a>(response: &'a Response<Body>, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|v| v.to_str().unwrap())
+}
+
+#[test]
+fn preflight_requests_are_answered_for_every_route() {
+    task::block_on(async move {
+        let mut server = TestApp::with_cors(credentialed_policy());
+
+        for (path, method) in &[
+            ("/api/articles", "POST"),
+            ("/api/articles/a-slug", "PATCH"),
+            ("/api/articles/a-slug/comments/1", "DELETE"),
+            ("/api/user", "PUT"),
+        ] {
+            let response = preflight(&mut server, path, FRONTEND, method);
+            assert_eq!(response.status(), 204);
+            assert_eq!(
+                header(&response, "Access-Control-Allow-Origin"),
+                Some(FRONTEND)
+            );
+            assert_eq!(
+                header(&response, "Access-Control-Allow-Credentials"),
+                Some("true")
+            );
+            assert!(header(&response, "Access-Control-Allow-Methods")
+                .unwrap()
+                .contains(method));
+            // A wildcard would be taken literally by browsers for credentialed requests
+            assert_eq!(
+                header(&response, "Access-Control-Allow-Headers"),
+                Some("authorization, content-type")
+            );
+            assert_eq!(header(&response, "Access-Control-Max-Age"), Some("86400"));
+        }
+
+        let response = preflight(
+            &mut server,
+            "/api/articles",
+            "https://evil.example.com",
+            "POST",
+        );
+        assert_eq!(response.status(), 403);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+    })
+}
+
+#[test]
+fn only_allowed_origins_get_cors_headers() {
+    task::block_on(async move {
+        let mut server = TestApp::with_cors(credentialed_policy());
+        let mut get_tags = |origin: Option<&str>| {
+            let mut request = http::Request::get("/api/tags");
+            if let Some(origin) = origin {
+                request.header("Origin", origin);
+            }
+            server
+                .server
+                .simulate(request.body(Body::empty()).unwrap())
+                .unwrap()
+        };
+
+        let response = get_tags(Some(FRONTEND));
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            header(&response, "Access-Control-Allow-Origin"),
+            Some(FRONTEND)
+        );
+        assert_eq!(
+            header(&response, "Access-Control-Allow-Credentials"),
+            Some("true")
+        );
+        assert_eq!(header(&response, "Vary"), Some("Origin"));
+
+        // Browsers block the response: other clients are not subject to CORS
+        let response = get_tags(Some("https://evil.example.com"));
+        assert_eq!(response.status(), 200);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+
+        let response = get_tags(None);
+        assert_eq!(response.status(), 200);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+    })
+}
+
+#[test]
+fn credentials_are_never_allowed_for_any_origin() {
+    task::block_on(async move {
+        let mut server = TestApp::with_cors(CorsPolicy {
+            allow_credentials: true,
+            ..CorsPolicy::default()
+        });
+
+        let response = preflight(&mut server, "/api/articles", FRONTEND, "POST");
+        assert_eq!(response.status(), 204);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("*"));
+        assert_eq!(header(&response, "Access-Control-Allow-Credentials"), None);
+    })
+}
@@ -0,0 +1,223 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::generate::With;
+use helpers::test_server::{response_json, TestApp};
+use helpers::{create_article2, create_user2};
+
+use async_std::task;
+use domain::repositories::Repository;
+use domain::CommentContent;
+use http::StatusCode;
+use realworld_web::auth::encode_token;
+use serde_json::{json, Value};
+
+const ARTICLE_PAGE: &str = r#"
+    query ArticlePage($slug: String!) {
+      article(slug: $slug) {
+        slug
+        favorited
+        favoritesCount
+        author { ...ProfileFields }
+        comments { id body author { ...ProfileFields } }
+      }
+    }
+
+    fragment ProfileFields on Profile { username following }
+"#;
+
+#[test]
+fn an_article_its_author_comments_and_the_viewer_state_are_fetched_at_once() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (author, _) = create_user2(&server.repository);
+        let (reader, _) = create_user2(&server.repository);
+        let article = create_article2(&server.repository, With::Value(&author));
+        let token = encode_token(reader.id);
+
+        let response = server
+            .graphql(
+                r#"
+                mutation Engage($slug: String!, $username: String!) {
+                  follow(username: $username) { username following }
+                  favorite(slug: $slug) { favorited favoritesCount }
+                  comment(slug: $slug, body: "Nice one") { body author { username } }
+                }
+                "#,
+                json!({ "slug": article.slug, "username": author.profile.username }),
+                Some(&token),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response_json(response).await;
+        assert_eq!(body.get("errors"), None);
+        assert_eq!(
+            body["data"],
+            json!({
+                "follow": { "username": author.profile.username, "following": true },
+                "favorite": { "favorited": true, "favoritesCount": 1 },
+                "comment": { "body": "Nice one", "author": { "username": reader.profile.username } },
+            })
+        );
+
+        let response = server
+            .graphql(ARTICLE_PAGE, json!({ "slug": article.slug }), Some(&token))
+            .await;
+        let body: Value = response_json(response).await;
+        let page = &body["data"]["article"];
+        assert_eq!(page["favorited"], true);
+        assert_eq!(page["favoritesCount"], 1);
+        assert_eq!(
+            page["author"],
+            json!({ "username": author.profile.username, "following": true })
+        );
+        let comments = page["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0]["body"], "Nice one");
+        assert_eq!(
+            comments[0]["author"],
+            json!({ "username": reader.profile.username, "following": false })
+        );
+
+        // Anonymous callers get the same article, without any viewer state
+        let response = server
+            .graphql(ARTICLE_PAGE, json!({ "slug": article.slug }), None)
+            .await;
+        let body: Value = response_json(response).await;
+        let page = &body["data"]["article"];
+        assert_eq!(page["favorited"], false);
+        assert_eq!(page["author"]["following"], false);
+        assert_eq!(page["comments"].as_array().unwrap().len(), 1);
+    })
+}
+
+#[test]
+fn the_comments_of_a_list_of_articles_are_resolved_per_article() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (author, _) = create_user2(&server.repository);
+        let (commenter, _) = create_user2(&server.repository);
+        let articles: Vec<_> = (0..3)
+            .map(|_| create_article2(&server.repository, With::Value(&author)))
+            .collect();
+        for (i, article) in articles.iter().enumerate() {
+            for j in 0..i {
+                commenter
+                    .comment(
+                        article,
+                        CommentContent(format!("{} on {}", j, article.slug)),
+                        &server.repository,
+                    )
+                    .unwrap();
+            }
+        }
+
+        let response = server
+            .graphql(
+                "{ articles(author: $author) { slug comments { body } } }",
+                json!({}),
+                None,
+            )
+            .await;
+        let body: Value = response_json(response).await;
+        // `$author` was not declared: the field fails on its own
+        assert_eq!(body["data"]["articles"], Value::Null);
+        assert_eq!(body["errors"][0]["path"], json!(["articles"]));
+
+        let response = server
+            .graphql(
+                "query ($author: String) { articles(author: $author) { slug comments { body } } }",
+                json!({ "author": author.profile.username }),
+                None,
+            )
+            .await;
+        let body: Value = response_json(response).await;
+        let listed = body["data"]["articles"].as_array().unwrap();
+        assert_eq!(listed.len(), 3);
+        for listed in listed {
+            let slug = listed["slug"].as_str().unwrap();
+            let article = server.repository.get_article_by_slug(slug).unwrap();
+            let expected: Vec<Value> = server
+                .repository
+                .get_comments(&article, None)
+                .unwrap()
+                .into_iter()
+                .map(|c| json!({ "body": c.body }))
+                .collect();
+            assert_eq!(listed["comments"], Value::Array(expected));
+        }
+    })
+}
+
+#[test]
+fn mutations_require_credentials_with_the_right_scope() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let article = create_article2(&server.repository, With::Random);
+        let mutation = r#"mutation ($slug: String!) { favorite(slug: $slug) { favorited } }"#;
+
+        let response = server
+            .graphql(mutation, json!({ "slug": article.slug }), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response_json(response).await;
+        assert_eq!(body["data"]["favorite"], Value::Null);
+        assert_eq!(body["errors"][0]["extensions"]["status"], 401);
+
+        let (user, _) = create_user2(&server.repository);
+        let session = encode_token(user.id);
+        let secret = server
+            .create_access_token("mobile", &["comments:write"], &session)
+            .await
+            .unwrap()
+            .token
+            .secret;
+        let request = http::Request::post("/graphql")
+            .header("Authorization", format!("Bearer {}", secret))
+            .body(
+                json!({ "query": mutation, "variables": { "slug": article.slug } })
+                    .to_string()
+                    .into_bytes()
+                    .into(),
+            )
+            .unwrap();
+        let response = server.server.simulate(request).unwrap();
+        let body: Value = response_json(response).await;
+        assert_eq!(body["data"]["favorite"], Value::Null);
+        assert_eq!(body["errors"][0]["extensions"]["status"], 403);
+        assert_eq!(
+            body["errors"][0]["message"],
+            "The access token lacks the `favorites:write` scope."
+        );
+    })
+}
+
+#[test]
+fn documents_which_cannot_be_executed_are_rejected() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+
+        let response = server.graphql("{ article(slug: ) }", json!({}), None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = response_json(response).await;
+        assert_eq!(body.get("data"), None);
+        assert_eq!(
+            body["errors"][0]["locations"],
+            json!([{ "line": 1, "column": 17 }])
+        );
+
+        let response = server
+            .graphql("query A { tags } query B { tags }", json!({}), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = server
+            .graphql("{ article(slug: \"missing\") { title } }", json!({}), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response_json(response).await;
+        assert_eq!(body["data"], json!({ "article": null }));
+        assert_eq!(body["errors"][0]["extensions"]["status"], 404);
+    })
+}
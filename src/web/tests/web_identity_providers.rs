@@ -0,0 +1,186 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::mock_oidc::MockOidcServer;
+use helpers::test_server::TestApp;
+use helpers::{create_users, generate};
+
+use async_std::task;
+use chrono::Utc;
+use domain::TotpSecret;
+use http_service::Response;
+use realworld_web::auth::encode_token;
+use realworld_web::AppSettings;
+
+fn test_app(provider: &MockOidcServer) -> TestApp {
+    TestApp::with_settings(AppSettings {
+        oidc_providers: vec![provider.provider()],
+        ..AppSettings::default()
+    })
+}
+
+/// The URL the user is redirected to, along with the cookie tying them to the login attempt.
+fn redirection(response: &Response) -> (String, String) {
+    assert_eq!(response.status(), 302);
+    let location = response.headers()["Location"].to_str().unwrap().to_owned();
+    let set_cookie = response.headers()["Set-Cookie"].to_str().unwrap();
+    let cookie = set_cookie.split(';').next().unwrap().to_owned();
+    (location, cookie)
+}
+
+#[test]
+fn sign_up_and_log_in_with_an_identity_provider() {
+    task::block_on(async move {
+        let provider = MockOidcServer::start();
+        let mut server = test_app(&provider);
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        assert!(location.starts_with(&format!("{}/authorize?", provider.issuer)));
+        let (code, state) = provider.authenticate(&location, "subject-1", "jo@example.com", true);
+        let user = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .unwrap()
+            .user;
+        assert_eq!(user.email, "jo@example.com");
+        assert_eq!(user.username, "jo");
+        assert!(user.email_verified);
+        server.get_current_user(&user.token).await.unwrap();
+
+        let response = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .expect_err("Login attempts are single-use");
+        assert_eq!(response.status(), 400);
+
+        // The identity is linked to the account from now on
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-1", "jo@example.com", true);
+        let same_user = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .unwrap()
+            .user;
+        assert_eq!(same_user.username, user.username);
+    })
+}
+
+#[test]
+fn identities_are_linked_to_accounts_with_the_same_verified_email() {
+    task::block_on(async move {
+        let provider = MockOidcServer::start();
+        let mut server = test_app(&provider);
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-2", &user.email, false);
+        let response = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .expect_err("Unverified emails cannot take over an account");
+        assert_eq!(response.status(), 409);
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-2", &user.email, true);
+        let linked_user = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .unwrap()
+            .user;
+        assert_eq!(linked_user.username, user.username);
+    })
+}
+
+#[test]
+fn identities_are_not_linked_to_accounts_with_an_unverified_email() {
+    task::block_on(async move {
+        let provider = MockOidcServer::start();
+        let mut server = test_app(&provider);
+        // Someone signs up with an address they do not own, before its owner comes along
+        let (squatter, password) = generate::new_user();
+        server.register_user(&squatter, &password).await.unwrap();
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-6", &squatter.email, true);
+        let response = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .expect_err("Unverified accounts cannot be taken over, nor take over an identity");
+        assert_eq!(response.status(), 409);
+    })
+}
+
+#[test]
+fn accounts_with_two_factor_authentication_still_require_the_second_factor() {
+    task::block_on(async move {
+        let provider = MockOidcServer::start();
+        let mut server = test_app(&provider);
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let token = encode_token(user.id);
+        let enrollment = server.enroll_two_factor(&token).await.unwrap().two_factor;
+        let secret = TotpSecret::from_base32(&enrollment.secret).unwrap();
+        server
+            .confirm_two_factor(&secret.code(Utc::now()), &token)
+            .await
+            .unwrap();
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-5", &user.email, true);
+        let challenge = server
+            .external_login_challenge("mock", &code, &state, Some(&cookie))
+            .await
+            .unwrap()
+            .two_factor_challenge;
+        let response = server
+            .get_current_user(&challenge.token)
+            .await
+            .expect_err("Challenge tokens are not authentication tokens");
+        assert_eq!(response.status(), 401);
+
+        let next_code = secret.code(Utc::now() + chrono::Duration::seconds(30));
+        let logged_in = server
+            .login_second_factor(&challenge.token, &next_code)
+            .await
+            .unwrap()
+            .user;
+        assert_eq!(logged_in.username, user.username);
+    })
+}
+
+#[test]
+fn callbacks_must_match_the_login_attempt() {
+    task::block_on(async move {
+        let provider = MockOidcServer::start();
+        let mut server = test_app(&provider);
+
+        let response = server.authorize_external_login("unknown").await;
+        assert_eq!(response.status(), 404);
+
+        let (location, cookie) = redirection(&server.authorize_external_login("mock").await);
+        let (code, state) = provider.authenticate(&location, "subject-3", "al@example.com", true);
+        let response = server
+            .external_login_callback("mock", &code, &state, None)
+            .await
+            .expect_err("The browser that started the login has to finish it");
+        assert_eq!(response.status(), 400);
+
+        // A code intercepted from another attempt is useless without its PKCE verifier
+        let (other_location, other_cookie) =
+            redirection(&server.authorize_external_login("mock").await);
+        let (_, other_state) =
+            provider.authenticate(&other_location, "subject-4", "eve@example.com", true);
+        let response = server
+            .external_login_callback("mock", &code, &other_state, Some(&other_cookie))
+            .await
+            .expect_err("The verifier does not match the challenge");
+        assert_eq!(response.status(), 401);
+
+        // The original attempt is still pending, but its code was spent
+        let response = server
+            .external_login_callback("mock", &code, &state, Some(&cookie))
+            .await
+            .expect_err("Codes are single-use");
+        assert_eq!(response.status(), 401);
+    })
+}
@@ -0,0 +1,96 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::generate;
+use helpers::test_server::TestApp;
+
+use async_std::io::prelude::ReadExt;
+use async_std::task;
+use http_service::Response;
+use realworld_web::AppSettings;
+
+const METRICS_TOKEN: &str = "a-metrics-token";
+
+fn with_metrics_token() -> AppSettings {
+    AppSettings {
+        metrics_token: Some(METRICS_TOKEN.into()),
+        ..AppSettings::default()
+    }
+}
+
+fn get_metrics(server: &mut TestApp, token: Option<&str>) -> Response {
+    let mut request = http::Request::get("/metrics");
+    if let Some(token) = token {
+        request.header("Authorization", format!("Bearer {}", token));
+    }
+    server
+        .server
+        .simulate(request.body(http_service::Body::empty()).unwrap())
+        .unwrap()
+}
+
+async fn scrape(server: &mut TestApp) -> String {
+    let mut response = get_metrics(server, Some(METRICS_TOKEN));
+    assert_eq!(response.status(), 200);
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await.unwrap();
+    body
+}
+
+/// The value of the series starting with `series`, or 0 if it was not reported yet.
+fn value(metrics: &str, series: &str) -> f64 {
+    metrics
+        .lines()
+        .find(|line| line.starts_with(series) && line[series.len()..].starts_with(' '))
+        .map_or(0.0, |line| {
+            line.rsplit(' ').next().unwrap().parse().unwrap()
+        })
+}
+
+#[test]
+fn requests_queries_and_sign_ups_are_measured() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(with_metrics_token());
+        // Metrics are global to the process: other tests move them too
+        let before = scrape(&mut server).await;
+
+        let (user, password) = generate::new_user();
+        server.register_user(&user, &password).await.unwrap();
+        server
+            .get_article("not-an-article", None)
+            .await
+            .expect_err("Not found");
+
+        let after = scrape(&mut server).await;
+        let increase = |series: &str| value(&after, series) - value(&before, series);
+        assert!(increase("sign_ups_total") >= 1.0);
+        assert!(
+            increase(r#"http_requests_total{method="POST",route="/api/users",status="200"}"#)
+                >= 1.0
+        );
+        assert!(
+            increase(
+                r#"http_requests_total{method="GET",route="/api/articles/:slug",status="404"}"#
+            ) >= 1.0
+        );
+        assert!(increase(r#"db_query_duration_seconds_count{method="sign_up"}"#) >= 1.0);
+        assert!(after.contains("# TYPE db_pool_wait_seconds histogram"));
+    })
+}
+
+#[test]
+fn metrics_are_only_served_with_the_configured_token() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(with_metrics_token());
+        assert_eq!(get_metrics(&mut server, None).status(), 401);
+        assert_eq!(
+            get_metrics(&mut server, Some("not-the-metrics-token")).status(),
+            401
+        );
+        scrape(&mut server).await;
+
+        let mut server = TestApp::new();
+        assert_eq!(get_metrics(&mut server, Some(METRICS_TOKEN)).status(), 404);
+    })
+}
@@ -0,0 +1,396 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::generate::With;
+use helpers::test_server::TestApp;
+use helpers::{create_article2, create_users, create_users2};
+
+use async_std::io::prelude::ReadExt;
+use async_std::{future, task};
+use http::StatusCode;
+use http_service::Response;
+use realworld_web::auth::encode_token;
+use realworld_web::comments::create::{NewCommentRequest, Request as CommentRequest};
+use realworld_web::notifications::responses::Notification;
+use realworld_web::notifications::stream::NotificationStream;
+use realworld_web::AppSettings;
+use std::time::Duration;
+
+/// The events sent down a notification stream.
+struct Events {
+    response: Response,
+    buffer: String,
+}
+
+impl Events {
+    fn new(response: Response) -> Self {
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["Content-Type"], "text/event-stream");
+        Self {
+            response,
+            buffer: String::new(),
+        }
+    }
+
+    /// Skips comments, and fails if no event gets sent in a reasonable time.
+    async fn next(&mut self) -> (u64, Notification) {
+        future::timeout(Duration::from_secs(5), self.read_event())
+            .await
+            .expect("No event was sent")
+    }
+
+    async fn read_event(&mut self) -> (u64, Notification) {
+        loop {
+            while let Some(end) = self.buffer.find("\n\n") {
+                let frame: String = self.buffer.drain(..end + 2).collect();
+                if frame.starts_with(':') {
+                    continue;
+                }
+                let mut lines = frame.lines();
+                let id = lines.next().unwrap().trim_start_matches("id: ");
+                assert_eq!(lines.next(), Some("event: notification"));
+                let data = lines.next().unwrap().trim_start_matches("data: ");
+                return (id.parse().unwrap(), serde_json::from_str(data).unwrap());
+            }
+            let mut chunk = [0; 4096];
+            let read = self.response.body_mut().read(&mut chunk).await.unwrap();
+            assert!(read > 0, "The stream ended");
+            self.buffer
+                .push_str(std::str::from_utf8(&chunk[..read]).unwrap());
+        }
+    }
+
+    /// Fails if the stream does not end in a reasonable time, or sends an event before it does.
+    async fn end(&mut self) {
+        future::timeout(Duration::from_secs(5), async {
+            loop {
+                let mut chunk = [0; 4096];
+                let read = self.response.body_mut().read(&mut chunk).await.unwrap();
+                if read == 0 {
+                    return;
+                }
+                let text = std::str::from_utf8(&chunk[..read]).unwrap();
+                assert!(
+                    text.split("\n\n")
+                        .all(|frame| frame.is_empty() || frame.starts_with(':')),
+                    "An event was sent: {}",
+                    text
+                );
+            }
+        })
+        .await
+        .expect("The stream did not end")
+    }
+}
+
+fn fast_polling() -> AppSettings {
+    AppSettings {
+        notification_stream: NotificationStream {
+            poll_interval: Duration::from_millis(10),
+            keep_alive: Duration::from_millis(50),
+        },
+        ..AppSettings::default()
+    }
+}
+
+fn comment_request(body: &str) -> CommentRequest {
+    CommentRequest {
+        comment: NewCommentRequest {
+            body: body.to_owned(),
+        },
+    }
+}
+
+#[test]
+fn authors_are_notified_of_follows_comments_and_favorites() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let mut users = create_users2(&server.repository, 2);
+        let (reader, _) = users.pop().unwrap();
+        let (author, _) = users.pop().unwrap();
+        let article = create_article2(&server.repository, With::Value(&author));
+        let author_token = encode_token(author.id);
+        let reader_token = encode_token(reader.id);
+
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        // Following someone again does not notify them again
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        let comment = server
+            .create_comment(&article.slug, &comment_request("Nice one"), &reader_token)
+            .await
+            .unwrap()
+            .comment;
+        server
+            .favorite_article(&article.slug, &reader_token)
+            .await
+            .unwrap();
+        // Users are not notified of what they do themselves
+        server
+            .create_comment(&article.slug, &comment_request("Thanks!"), &author_token)
+            .await
+            .unwrap();
+
+        let response = server.get_notifications("", &author_token).await.unwrap();
+        assert_eq!(response.unread_count, 3);
+        let kinds: Vec<&str> = response
+            .notifications
+            .iter()
+            .map(|n| n.kind.as_str())
+            .collect();
+        assert_eq!(kinds, vec!["favorite", "comment", "follow"]);
+        for notification in &response.notifications {
+            assert_eq!(notification.actor.username, reader.profile.username);
+            assert!(!notification.actor.following);
+            assert!(!notification.read);
+        }
+        let commented = &response.notifications[1];
+        assert_eq!(commented.comment_id, Some(comment.id));
+        let commented_article = commented.article.as_ref().unwrap();
+        assert_eq!(commented_article.slug, article.slug);
+        assert_eq!(commented_article.title, article.content.title);
+        assert_eq!(response.notifications[2].article, None);
+
+        let response = server.get_notifications("", &reader_token).await.unwrap();
+        assert!(response.notifications.is_empty());
+        assert_eq!(response.unread_count, 0);
+    })
+}
+
+#[test]
+fn notifications_can_be_marked_as_read_and_unread() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let mut users = create_users2(&server.repository, 3);
+        let (other, _) = users.pop().unwrap();
+        let (reader, _) = users.pop().unwrap();
+        let (author, _) = users.pop().unwrap();
+        let article = create_article2(&server.repository, With::Value(&author));
+        let author_token = encode_token(author.id);
+        let reader_token = encode_token(reader.id);
+        let other_token = encode_token(other.id);
+
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        server
+            .favorite_article(&article.slug, &reader_token)
+            .await
+            .unwrap();
+        let notifications = server
+            .get_notifications("", &author_token)
+            .await
+            .unwrap()
+            .notifications;
+        let (favorited, followed) = (notifications[0].id, notifications[1].id);
+
+        server
+            .mark_notification(followed, true, &author_token)
+            .await
+            .unwrap();
+        let response = server
+            .get_notifications("?unread=true", &author_token)
+            .await
+            .unwrap();
+        assert_eq!(response.unread_count, 1);
+        assert_eq!(response.notifications.len(), 1);
+        assert_eq!(response.notifications[0].id, favorited);
+
+        server
+            .mark_notification(followed, false, &author_token)
+            .await
+            .unwrap();
+        let response = server.get_notifications("", &author_token).await.unwrap();
+        assert_eq!(response.unread_count, 2);
+
+        // The notifications of other users cannot be told apart from missing ones
+        let response = server
+            .mark_notification(followed, true, &other_token)
+            .await
+            .expect_err("Marked the notification of another user");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        server
+            .mark_all_notifications_read(&author_token)
+            .await
+            .unwrap();
+        let response = server.get_notifications("", &author_token).await.unwrap();
+        assert_eq!(response.unread_count, 0);
+        assert!(response.notifications.iter().all(|n| n.read));
+        assert_eq!(response.notifications.len(), 2);
+    })
+}
+
+#[test]
+fn notifications_from_blocked_users_are_hidden() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let mut users = create_users2(&server.repository, 2);
+        let (reader, _) = users.pop().unwrap();
+        let (author, _) = users.pop().unwrap();
+        let author_token = encode_token(author.id);
+        let reader_token = encode_token(reader.id);
+
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        server
+            .block_profile(&reader.profile.username, &author_token)
+            .await
+            .unwrap();
+
+        let response = server.get_notifications("", &author_token).await.unwrap();
+        assert!(response.notifications.is_empty());
+        assert_eq!(response.unread_count, 0);
+    })
+}
+
+#[test]
+fn new_notifications_are_pushed_to_connected_clients() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(fast_polling());
+        let mut users = create_users2(&server.repository, 2);
+        let (reader, _) = users.pop().unwrap();
+        let (author, _) = users.pop().unwrap();
+        let article = create_article2(&server.repository, With::Value(&author));
+        let author_token = encode_token(author.id);
+        let reader_token = encode_token(reader.id);
+
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        // Only the notifications recorded after connecting are sent
+        let mut events = Events::new(server.notification_stream(&author_token, None));
+        server
+            .favorite_article(&article.slug, &reader_token)
+            .await
+            .unwrap();
+
+        let (id, notification) = events.next().await;
+        assert_eq!(id, notification.id);
+        assert_eq!(notification.kind, "favorite");
+        assert_eq!(notification.actor.username, reader.profile.username);
+        assert_eq!(notification.article.unwrap().slug, article.slug);
+
+        server
+            .create_comment(&article.slug, &comment_request("Nice one"), &reader_token)
+            .await
+            .unwrap();
+        let (_, notification) = events.next().await;
+        assert_eq!(notification.kind, "comment");
+    })
+}
+
+#[test]
+fn reconnecting_clients_catch_up_on_missed_notifications() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(fast_polling());
+        let mut users = create_users2(&server.repository, 2);
+        let (reader, _) = users.pop().unwrap();
+        let (author, _) = users.pop().unwrap();
+        let article = create_article2(&server.repository, With::Value(&author));
+        let author_token = encode_token(author.id);
+        let reader_token = encode_token(reader.id);
+
+        server
+            .follow_profile(&author.profile.username, &reader_token)
+            .await
+            .unwrap();
+        server
+            .favorite_article(&article.slug, &reader_token)
+            .await
+            .unwrap();
+        let notifications = server
+            .get_notifications("", &author_token)
+            .await
+            .unwrap()
+            .notifications;
+        let (favorited, followed) = (notifications[0].id, notifications[1].id);
+
+        let mut events = Events::new(server.notification_stream(&author_token, Some(followed)));
+        let (id, notification) = events.next().await;
+        assert_eq!(id, favorited);
+        assert_eq!(notification.kind, "favorite");
+    })
+}
+
+#[test]
+fn the_stream_requires_authentication() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let request = http::Request::get("/api/notifications/stream")
+            .body(http_service::Body::empty())
+            .unwrap();
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    })
+}
+
+#[test]
+fn the_stream_ends_when_the_session_is_revoked() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(fast_polling());
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let token = encode_token(user.id);
+        let mut events = Events::new(server.notification_stream(&token, None));
+
+        server.request_password_reset(&user.email).await.unwrap();
+        let body = server.wait_for_emails(1).await.remove(0).body;
+        let reset_token = body.split_whitespace().find(|w| w.len() == 43).unwrap();
+        // Sessions are revoked with a granularity of one second
+        task::sleep(Duration::from_secs(1)).await;
+        server
+            .confirm_password_reset(reset_token, "a-brand-new-password")
+            .await
+            .unwrap();
+        events.end().await;
+    })
+}
+
+#[test]
+fn the_stream_ends_when_the_access_token_is_deleted() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(fast_polling());
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let session = encode_token(user.id);
+        let secret = server
+            .create_access_token("notifier", &["favorites:write"], &session)
+            .await
+            .unwrap()
+            .token
+            .secret;
+        // Notifications are private: tokens need to have been granted access to them
+        let response = server.notification_stream_with_access_token(&secret);
+        assert_eq!(response.status(), 403);
+
+        let secret = server
+            .create_access_token("notifier", &["notifications:read"], &session)
+            .await
+            .unwrap()
+            .token
+            .secret;
+        let mut events = Events::new(server.notification_stream_with_access_token(&secret));
+
+        // Checking the token again on every poll does not count as using it
+        let tokens = server.list_access_tokens(&session).await.unwrap().tokens;
+        task::sleep(Duration::from_millis(50)).await;
+        let polled = server.list_access_tokens(&session).await.unwrap().tokens;
+        assert!(tokens[0].last_used_at.is_some());
+        assert_eq!(polled[0].last_used_at, tokens[0].last_used_at);
+
+        server
+            .revoke_access_token(&tokens[0].id, &session)
+            .await
+            .unwrap();
+        events.end().await;
+    })
+}
@@ -0,0 +1,112 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::{response_json, TestApp};
+
+use async_std::task;
+use realworld_web::openapi::{document, OPENAPI_ROUTE};
+use realworld_web::rate_limit::{RateLimit, RateLimits};
+use realworld_web::AppSettings;
+use serde_json::Value;
+use std::time::Duration;
+
+const METHODS: &[&str] = &["get", "post", "put", "patch", "delete"];
+
+fn unlimited() -> RateLimits {
+    let unlimited = RateLimit {
+        requests: 10_000,
+        per: Duration::from_secs(60),
+    };
+    RateLimits {
+        articles: unlimited.clone(),
+        comments: unlimited.clone(),
+        favorites: unlimited.clone(),
+        default: unlimited,
+    }
+}
+
+/// A concrete path for an OpenAPI path template: `/api/articles/{slug}` -> `/api/articles/slug`.
+fn concrete(path: &str) -> String {
+    path.replace(['{', '}'], "")
+}
+
+/// Whether a concrete path is matched by an OpenAPI path template.
+fn matches(template: &str, path: &str) -> bool {
+    let template: Vec<&str> = template.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    template.len() == path.len()
+        && template
+            .iter()
+            .zip(path)
+            .all(|(t, p)| t.starts_with('{') || *t == p)
+}
+
+fn status(server: &mut TestApp, method: &str, path: &str) -> u16 {
+    let request = http::Request::builder()
+        .method(method.to_ascii_uppercase().as_str())
+        .uri(path)
+        .body(http_service::Body::empty())
+        .unwrap();
+    server.server.simulate(request).unwrap().status().as_u16()
+}
+
+#[test]
+fn the_specification_is_served() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let request = http::Request::get(OPENAPI_ROUTE)
+            .body(http_service::Body::empty())
+            .unwrap();
+        let response = server.server.simulate(request).unwrap();
+        assert_eq!(response.status(), 200);
+        let served: Value = response_json(response).await;
+        assert_eq!(served, document());
+    })
+}
+
+#[test]
+fn documented_methods_are_the_routed_ones() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(AppSettings {
+            rate_limits: unlimited(),
+            ..AppSettings::default()
+        });
+        let document = document();
+        let paths = document["paths"].as_object().unwrap();
+
+        for (path, operations) in paths {
+            for method in METHODS {
+                let documented = operations.get(*method).is_some();
+                // E.g. `PUT /api/articles/feed` is routed to `PUT /api/articles/{slug}`
+                let shadowed = paths.iter().any(|(other, operations)| {
+                    other != path && matches(other, path) && operations.get(*method).is_some()
+                });
+                if !documented && shadowed {
+                    continue;
+                }
+                let routed = status(&mut server, method, &concrete(path)) != 405;
+                assert_eq!(
+                    documented, routed,
+                    "{} {}: documented = {}, routed = {}",
+                    method, path, documented, routed
+                );
+            }
+        }
+    })
+}
+
+#[test]
+fn unknown_articles_get_the_documented_not_found() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let document = document();
+        let get_article = &document["paths"]["/api/articles/{slug}"]["get"];
+        assert!(get_article["responses"].get("404").is_some());
+
+        assert_eq!(
+            status(&mut server, "get", "/api/articles/unknown-slug"),
+            404
+        );
+    })
+}
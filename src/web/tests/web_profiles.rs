@@ -2,12 +2,13 @@
 
 mod helpers;
 
-use helpers::create_users;
 use helpers::test_server::TestApp;
+use helpers::{create_article, create_users};
 
 use async_std::task;
 use itertools::Itertools;
 use realworld_web::auth::encode_token;
+use realworld_web::comments::create::{NewCommentRequest, Request as CommentRequest};
 
 #[test]
 fn profiles_api() {
@@ -64,3 +65,221 @@ fn profiles_api() {
         assert_eq!(p.profile.following, false);
     })
 }
+
+#[test]
+fn followers_and_following_are_listed() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 3)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let (celebrity, fan, other_fan) = (&users[0], &users[1], &users[2]);
+        let fan_token = encode_token(fan.id);
+        let other_fan_token = encode_token(other_fan.id);
+
+        server
+            .follow_profile(&celebrity.username, &fan_token)
+            .await
+            .unwrap();
+        let profile = server
+            .follow_profile(&celebrity.username, &other_fan_token)
+            .await
+            .unwrap()
+            .profile;
+        assert_eq!(profile.followers_count, Some(2));
+        assert_eq!(profile.following_count, Some(0));
+        server
+            .follow_profile(&other_fan.username, &fan_token)
+            .await
+            .unwrap();
+
+        let profile = server
+            .get_profile(&fan.username, None)
+            .await
+            .unwrap()
+            .profile;
+        assert_eq!(profile.followers_count, Some(0));
+        assert_eq!(profile.following_count, Some(2));
+
+        let followers = server
+            .get_followers(&celebrity.username, Some(&fan_token))
+            .await
+            .unwrap();
+        assert_eq!(followers.profiles_count, 2);
+        // Each listed profile is annotated with whether the viewer follows it
+        let listed_other_fan = followers
+            .profiles
+            .iter()
+            .find(|p| p.username == other_fan.username)
+            .unwrap();
+        assert!(listed_other_fan.following);
+        let followers = server
+            .get_followers(&celebrity.username, Some(&encode_token(celebrity.id)))
+            .await
+            .unwrap();
+        assert!(followers.profiles.iter().all(|p| !p.following));
+
+        let following = server
+            .get_following(&fan.username, None)
+            .await
+            .unwrap()
+            .profiles;
+        let mut expected = vec![celebrity.username.clone(), other_fan.username.clone()];
+        expected.sort();
+        assert_eq!(
+            following.into_iter().map(|p| p.username).collect_vec(),
+            expected
+        );
+    })
+}
+
+#[test]
+fn you_cannot_follow_yourself() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let user = create_users(&server.repository.0, 1).remove(0).0;
+        let token = encode_token(user.id);
+
+        let response = server
+            .follow_profile(&user.username, &token)
+            .await
+            .expect_err("Self-follows are rejected");
+        assert_eq!(response.status(), 422);
+
+        let profile = server
+            .get_profile(&user.username, Some(&token))
+            .await
+            .unwrap()
+            .profile;
+        assert_eq!(profile.following, false);
+        assert_eq!(profile.followers_count, Some(0));
+    })
+}
+
+fn comment_request(body: &str) -> CommentRequest {
+    CommentRequest {
+        comment: NewCommentRequest { body: body.into() },
+    }
+}
+
+#[test]
+fn blocks_hide_content_and_prevent_interactions() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 2)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let (blocker, blocked) = (&users[0], &users[1]);
+        let blocker_token = encode_token(blocker.id);
+        let blocked_token = encode_token(blocked.id);
+        let blocker_article = create_article(&server.repository.0, blocker);
+        let blocked_article = create_article(&server.repository.0, blocked);
+
+        server
+            .follow_profile(&blocker.username, &blocked_token)
+            .await
+            .unwrap();
+        server
+            .block_profile(&blocked.username, &blocker_token)
+            .await
+            .unwrap();
+
+        // The block removed the existing follow
+        let profile = server
+            .get_profile(&blocker.username, Some(&blocked_token))
+            .await
+            .unwrap()
+            .profile;
+        assert_eq!(profile.following, false);
+
+        // The blocker's articles are hidden from the blocked user
+        let response = server
+            .get_article(&blocker_article.slug, Some(&blocked_token))
+            .await
+            .expect_err("Articles of the blocker are hidden");
+        assert_eq!(response.status(), 404);
+        let response = server
+            .create_comment(
+                &blocker_article.slug,
+                &comment_request("Hi"),
+                &blocked_token,
+            )
+            .await
+            .expect_err("Articles of the blocker are hidden");
+        assert_eq!(response.status(), 404);
+
+        // Follows and comments are prevented in both directions
+        let response = server
+            .follow_profile(&blocker.username, &blocked_token)
+            .await
+            .expect_err("Blocked users cannot follow the blocker");
+        assert_eq!(response.status(), 403);
+        let response = server
+            .follow_profile(&blocked.username, &blocker_token)
+            .await
+            .expect_err("Blockers cannot follow the blocked user");
+        assert_eq!(response.status(), 403);
+        let response = server
+            .create_comment(
+                &blocked_article.slug,
+                &comment_request("Hi"),
+                &blocker_token,
+            )
+            .await
+            .expect_err("Blockers cannot comment the articles of the blocked user");
+        assert_eq!(response.status(), 403);
+
+        let response = server
+            .block_profile(&blocker.username, &blocker_token)
+            .await
+            .expect_err("Users cannot block themselves");
+        assert_eq!(response.status(), 422);
+    })
+}
+
+#[test]
+fn mutes_hide_comments() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let users = create_users(&server.repository.0, 2)
+            .into_iter()
+            .map(|(u, _)| u)
+            .collect_vec();
+        let (muter, muted) = (&users[0], &users[1]);
+        let muter_token = encode_token(muter.id);
+        let muted_token = encode_token(muted.id);
+        let article = create_article(&server.repository.0, muter);
+
+        server
+            .create_comment(&article.slug, &comment_request("Hello"), &muted_token)
+            .await
+            .unwrap();
+        server
+            .mute_profile(&muted.username, &muter_token)
+            .await
+            .unwrap();
+
+        let comments = server
+            .get_comments(&article.slug, Some(&muter_token))
+            .await
+            .unwrap()
+            .comments;
+        assert!(comments.is_empty());
+
+        // Muted users are not aware of it
+        let comments = server
+            .get_comments(&article.slug, Some(&muted_token))
+            .await
+            .unwrap()
+            .comments;
+        assert_eq!(comments.len(), 1);
+        let comments = server
+            .get_comments(&article.slug, None)
+            .await
+            .unwrap()
+            .comments;
+        assert_eq!(comments.len(), 1);
+    })
+}
@@ -0,0 +1,163 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::{response_json, TestApp};
+use helpers::{create_article, create_users};
+
+use async_std::task;
+use realworld_web::auth::encode_token;
+use realworld_web::rate_limit::{RateLimit, RateLimits};
+use realworld_web::AppSettings;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+fn header(response: &http_service::Response, name: &str) -> String {
+    response.headers()[name].to_str().unwrap().to_owned()
+}
+
+#[test]
+fn favorites_are_rate_limited_per_user() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(AppSettings {
+            rate_limits: RateLimits {
+                favorites: RateLimit {
+                    requests: 2,
+                    per: Duration::from_secs(60),
+                },
+                ..RateLimits::default()
+            },
+            ..AppSettings::default()
+        });
+        let mut users = create_users(&server.repository.0, 2);
+        let (other_user, _) = users.pop().unwrap();
+        let (user, _) = users.pop().unwrap();
+        let article = create_article(&server.repository.0, &other_user);
+        let token = encode_token(user.id);
+
+        server
+            .favorite_article(&article.slug, &token)
+            .await
+            .unwrap();
+        server
+            .unfavorite_article(&article.slug, &token)
+            .await
+            .unwrap();
+        let response = server
+            .favorite_article(&article.slug, &token)
+            .await
+            .expect_err("The bucket is empty");
+        assert_eq!(response.status(), 429);
+        assert_eq!(header(&response, "X-RateLimit-Limit"), "2");
+        assert_eq!(header(&response, "X-RateLimit-Remaining"), "0");
+        let retry_after: u64 = header(&response, "Retry-After").parse().unwrap();
+        assert!(retry_after > 0 && retry_after <= 30);
+
+        // Other users and other route groups have their own buckets
+        server
+            .favorite_article(&article.slug, &encode_token(other_user.id))
+            .await
+            .unwrap();
+        server
+            .get_article(&article.slug, Some(&token))
+            .await
+            .unwrap();
+    })
+}
+
+#[test]
+fn graphql_mutations_are_charged_against_their_route_group() {
+    task::block_on(async move {
+        let mut server = TestApp::with_settings(AppSettings {
+            rate_limits: RateLimits {
+                favorites: RateLimit {
+                    requests: 2,
+                    per: Duration::from_secs(60),
+                },
+                ..RateLimits::default()
+            },
+            ..AppSettings::default()
+        });
+        let mut users = create_users(&server.repository.0, 2);
+        let (other_user, _) = users.pop().unwrap();
+        let (user, _) = users.pop().unwrap();
+        let article = create_article(&server.repository.0, &other_user);
+        let token = encode_token(user.id);
+
+        // Aliases do not get around the limit
+        let response = server
+            .graphql(
+                r#"
+                mutation Spam($slug: String!) {
+                  a: favorite(slug: $slug) { favorited }
+                  b: unfavorite(slug: $slug) { favorited }
+                  c: favorite(slug: $slug) { favorited }
+                }
+                "#,
+                json!({ "slug": article.slug }),
+                Some(&token),
+            )
+            .await;
+        assert_eq!(response.status(), 200);
+        let body: Value = response_json(response).await;
+        assert_eq!(body["data"]["a"], json!({ "favorited": true }));
+        assert_eq!(body["data"]["b"], json!({ "favorited": false }));
+        assert_eq!(body["data"]["c"], Value::Null);
+        assert_eq!(body["errors"][0]["path"], json!(["c"]));
+        assert_eq!(body["errors"][0]["extensions"]["status"], 429);
+
+        // The bucket is shared with the REST API
+        let response = server
+            .favorite_article(&article.slug, &token)
+            .await
+            .expect_err("The bucket is empty");
+        assert_eq!(response.status(), 429);
+    })
+}
+
+/// The status of an anonymous request, forwarded on behalf of `client`.
+fn get_tags_for(server: &mut TestApp, client: &str) -> u16 {
+    let request = http::Request::get("/api/tags")
+        .header("X-Forwarded-For", client)
+        .body(http_service::Body::empty())
+        .unwrap();
+    server.server.simulate(request).unwrap().status().as_u16()
+}
+
+#[test]
+fn anonymous_callers_are_rate_limited_per_address() {
+    task::block_on(async move {
+        let settings = AppSettings {
+            rate_limits: RateLimits {
+                default: RateLimit {
+                    requests: 2,
+                    per: Duration::from_secs(60),
+                },
+                ..RateLimits::default()
+            },
+            ..AppSettings::default()
+        };
+        let clients = ["198.51.100.1", "198.51.100.2", "198.51.100.3"];
+
+        // Callers cannot get a fresh bucket by making up an address
+        let mut server = TestApp::with_settings(settings.clone());
+        let statuses: Vec<u16> = clients
+            .iter()
+            .map(|client| get_tags_for(&mut server, client))
+            .collect();
+        assert_eq!(statuses, vec![200, 200, 429]);
+
+        // The clients behind our proxies have their own buckets
+        let mut server = TestApp::with_settings(AppSettings {
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            ..settings
+        });
+        let statuses: Vec<u16> = clients
+            .iter()
+            .chain(clients.iter())
+            .map(|client| get_tags_for(&mut server, client))
+            .collect();
+        assert_eq!(statuses, vec![200, 200, 200, 200, 200, 200]);
+        assert_eq!(get_tags_for(&mut server, clients[0]), 429);
+    })
+}
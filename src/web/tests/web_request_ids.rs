@@ -0,0 +1,45 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::TestApp;
+
+use async_std::task;
+use realworld_web::request_log::REQUEST_ID_HEADER;
+
+fn request_id(server: &mut TestApp, incoming: Option<&str>) -> String {
+    let mut request = http::Request::get("/api/tags");
+    if let Some(id) = incoming {
+        request.header(REQUEST_ID_HEADER, id);
+    }
+    let response = server
+        .server
+        .simulate(request.body(http_service::Body::empty()).unwrap())
+        .unwrap();
+    response.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_owned()
+}
+
+#[test]
+fn request_ids_are_propagated_or_generated() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+
+        assert_eq!(
+            request_id(&mut server, Some("lb-7f3a.2")),
+            "lb-7f3a.2".to_string()
+        );
+
+        let generated = request_id(&mut server, None);
+        assert!(!generated.is_empty());
+        assert_ne!(request_id(&mut server, None), generated);
+
+        // IDs that could tamper with the logs are replaced
+        let replaced = request_id(&mut server, Some("\"forged\": true"));
+        assert_ne!(replaced, "\"forged\": true");
+        let replaced = request_id(&mut server, Some(&"a".repeat(200)));
+        assert!(replaced.len() < 200);
+    })
+}
@@ -0,0 +1,71 @@
+// These tests are "integration" tests that exercise a workflow via the http service.
+
+mod helpers;
+
+use helpers::test_server::TestApp;
+use helpers::{create_article, create_users};
+
+use async_std::task;
+use realworld_web::tracing::TRACEPARENT_HEADER;
+use std::sync::Arc;
+use telemetry::{InMemoryExporter, SpanContext, SpanData, SpanKind, Value};
+
+fn find<'a>(spans: &'a [SpanData], name: &str) -> &'a SpanData {
+    spans
+        .iter()
+        .find(|s| s.name == name)
+        .unwrap_or_else(|| panic!("No {} span in {:?}", name, spans))
+}
+
+#[test]
+fn requests_repository_calls_and_queries_are_traced() {
+    task::block_on(async move {
+        let exporter = InMemoryExporter::default();
+        telemetry::install(Arc::new(exporter.clone()));
+        let mut server = TestApp::new();
+        let (user, _) = create_users(&server.repository.0, 1).remove(0);
+        let article = create_article(&server.repository.0, &user);
+
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let caller = SpanContext::from_traceparent(traceparent).unwrap();
+        let response = server
+            .server
+            .simulate(
+                http::Request::get(format!("/api/articles/{}", article.slug))
+                    .header(TRACEPARENT_HEADER, traceparent)
+                    .body(http_service::Body::empty())
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let spans = exporter.spans_of(caller.trace_id);
+        let request = find(&spans, "GET /api/articles/:slug");
+        assert_eq!(request.kind, SpanKind::Server);
+        assert_eq!(request.parent_span_id, Some(caller.span_id));
+        assert!(request
+            .attributes
+            .contains(&("http.status_code", Value::Int(200))));
+
+        let repository_call = find(&spans, "Repository::get_article_by_slug");
+        assert_eq!(
+            repository_call.parent_span_id,
+            Some(request.context.span_id)
+        );
+        let query = spans
+            .iter()
+            .find(|s| s.parent_span_id == Some(repository_call.context.span_id))
+            .expect("No query span");
+        assert_eq!(query.kind, SpanKind::Client);
+        let statement = query
+            .attributes
+            .iter()
+            .find(|(key, _)| *key == "db.statement")
+            .map(|(_, value)| value.clone());
+        match statement {
+            // Bind parameters are not recorded
+            Some(Value::String(sql)) => assert!(!sql.contains(&article.slug)),
+            other => panic!("Unexpected statement {:?}", other),
+        }
+    })
+}
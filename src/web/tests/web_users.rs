@@ -2,12 +2,21 @@
 
 mod helpers;
 
-use helpers::generate;
 use helpers::test_server::TestApp;
+use helpers::{create_article, create_users, generate};
 
 use async_std::task;
+use chrono::Utc;
+use db::queries::users;
+use domain::{Password, PasswordHashing, TotpSecret};
+use realworld_web::articles::insert::NewArticleRequest;
+use realworld_web::auth::encode_token;
+use realworld_web::throttle::LoginThrottling;
+use realworld_web::users::delete::DeleteUserRequest;
 use realworld_web::users::responses::UserResponse;
 use realworld_web::users::update::UpdateUserRequest;
+use realworld_web::AppSettings;
+use serde_json::json;
 
 #[test]
 fn register_and_login() {
@@ -69,3 +78,411 @@ fn update_and_retrieve_user_details() {
         assert_eq!(current_user.user.image, new_details.user.image);
     })
 }
+
+fn delete_request(password: &str, content: &str) -> realworld_web::users::delete::Request {
+    realworld_web::users::delete::Request {
+        user: DeleteUserRequest {
+            password: password.to_string(),
+            content: content.to_string(),
+        },
+    }
+}
+
+#[test]
+fn delete_account_and_anonymize_content() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+        let token = encode_token(user.id);
+        let article = create_article(&server.repository.0, &user);
+
+        let response = server
+            .delete_user(&delete_request("not-my-password", "anonymize"), &token)
+            .await
+            .expect_err("The password has to be confirmed");
+        assert_eq!(response.status(), 403);
+        let response = server
+            .delete_user(&delete_request(&password, "forget"), &token)
+            .await
+            .expect_err("Unknown content option");
+        assert_eq!(response.status(), 400);
+
+        server
+            .delete_user(&delete_request(&password, "anonymize"), &token)
+            .await
+            .unwrap();
+
+        let response = server
+            .login_user(&user.email, &password)
+            .await
+            .expect_err("The account is gone");
+        assert_eq!(response.status(), 401);
+        // The article is still there, attributed to the placeholder
+        let kept_article = server
+            .get_article(&article.slug, None)
+            .await
+            .unwrap()
+            .article;
+        assert_eq!(kept_article.author.username, "deleted-user");
+        // Nobody can log in as the placeholder
+        let response = server
+            .login_user("deleted-user", "!")
+            .await
+            .expect_err("The placeholder has no password");
+        assert_eq!(response.status(), 401);
+    })
+}
+
+#[test]
+fn delete_account_and_content() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+        let token = encode_token(user.id);
+        let article = create_article(&server.repository.0, &user);
+
+        server
+            .delete_user(&delete_request(&password, "delete"), &token)
+            .await
+            .unwrap();
+
+        let response = server
+            .get_article(&article.slug, None)
+            .await
+            .expect_err("The article was deleted with its author");
+        assert_eq!(response.status(), 404);
+    })
+}
+
+/// The reset token is the only word of the email body made of 43 url-safe base64 characters.
+fn extract_reset_token(body: &str) -> String {
+    body.split_whitespace()
+        .find(|w| w.len() == 43)
+        .expect("No reset token in the email")
+        .to_string()
+}
+
+#[test]
+fn reset_a_forgotten_password() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+        let session = encode_token(user.id);
+        server
+            .create_access_token("ci", &["articles:write"], &session)
+            .await
+            .unwrap();
+
+        // Unknown emails are not disclosed
+        server
+            .request_password_reset("nobody@example.com")
+            .await
+            .unwrap();
+        assert!(server.sent_emails().is_empty());
+
+        server.request_password_reset(&user.email).await.unwrap();
+        let emails = server.wait_for_emails(1).await;
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].to, user.email);
+        let reset_token = extract_reset_token(&emails[0].body);
+
+        // Sessions are revoked with a granularity of one second
+        task::sleep(std::time::Duration::from_secs(1)).await;
+        let new_password = "a-brand-new-password";
+        server
+            .confirm_password_reset(&reset_token, new_password)
+            .await
+            .unwrap();
+
+        let response = server
+            .login_user(&user.email, &password)
+            .await
+            .expect_err("The old password does not work anymore");
+        assert_eq!(response.status(), 401);
+        let new_session = server
+            .login_user(&user.email, new_password)
+            .await
+            .unwrap()
+            .user
+            .token;
+        let response = server
+            .get_current_user(&session)
+            .await
+            .expect_err("Existing sessions are revoked");
+        assert_eq!(response.status(), 401);
+        let tokens = server
+            .list_access_tokens(&new_session)
+            .await
+            .unwrap()
+            .tokens;
+        assert!(tokens.is_empty(), "Access tokens are deleted");
+
+        let response = server
+            .confirm_password_reset(&reset_token, "yet-another-password")
+            .await
+            .expect_err("Reset tokens can only be used once");
+        assert_eq!(response.status(), 400);
+    })
+}
+
+#[test]
+fn publishing_requires_a_verified_email() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (new_user, password) = generate::new_user();
+        let user = server
+            .register_user(&new_user, &password)
+            .await
+            .unwrap()
+            .user;
+        assert!(!user.email_verified);
+        let emails = server.sent_emails();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].to, new_user.email);
+
+        let content = generate::article_content();
+        let new_article = realworld_web::articles::insert::Request {
+            article: NewArticleRequest {
+                title: content.title,
+                description: content.description,
+                body: content.body,
+                tag_list: None,
+                status: None,
+                publish_at: None,
+            },
+        };
+        let response = server
+            .create_article(&new_article, &user.token)
+            .await
+            .expect_err("Unverified users cannot publish");
+        assert_eq!(response.status(), 403);
+
+        let response = server
+            .verify_email("not-a-token")
+            .await
+            .expect_err("Invalid verification token");
+        assert_eq!(response.status(), 400);
+        server.confirm_email_from_last_message().await.unwrap();
+        let current_user = server.get_current_user(&user.token).await.unwrap().user;
+        assert!(current_user.email_verified);
+        server
+            .create_article(&new_article, &user.token)
+            .await
+            .unwrap();
+
+        // A new email address has to be verified again
+        let new_details = realworld_web::users::update::Request {
+            user: UpdateUserRequest {
+                email: Some(generate::new_user().0.email),
+                bio: None,
+                image: None,
+                password: None,
+                username: None,
+            },
+        };
+        let updated_user = server
+            .update_user_details(&new_details, &user.token)
+            .await
+            .unwrap()
+            .user;
+        assert!(!updated_user.email_verified);
+        assert_eq!(server.sent_emails().len(), 2);
+
+        // Other updates do not send it again
+        let new_details = realworld_web::users::update::Request {
+            user: UpdateUserRequest {
+                email: None,
+                bio: Some("Still unverified".into()),
+                image: None,
+                password: None,
+                username: None,
+            },
+        };
+        server
+            .update_user_details(&new_details, &user.token)
+            .await
+            .unwrap();
+        assert_eq!(server.sent_emails().len(), 2);
+    })
+}
+
+#[test]
+fn outdated_password_hashes_are_upgraded_on_login() {
+    task::block_on(async move {
+        let hashing = PasswordHashing::Argon2id {
+            memory_cost: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let mut server = TestApp::with_password_hashing(hashing.clone());
+        // Test users get a bcrypt hash
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+        assert!(Password::from_hash(user.password).needs_rehash(&hashing));
+
+        server.login_user(&user.email, &password).await.unwrap();
+
+        let stored_user = users::find(&server.repository.0, user.id).unwrap();
+        let stored_password = Password::from_hash(stored_user.password);
+        assert!(!stored_password.needs_rehash(&hashing));
+        assert!(stored_password.verify(&password).unwrap());
+        // The new hash can be used to log in
+        server.login_user(&user.email, &password).await.unwrap();
+    })
+}
+
+#[test]
+fn repeated_failed_logins_lock_the_account_out() {
+    task::block_on(async move {
+        let mut server = TestApp::with_login_throttling(LoginThrottling {
+            max_attempts_per_account: 3,
+            ..LoginThrottling::default()
+        });
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+
+        // A successful login clears the previous failures
+        for _ in 0..2 {
+            let response = server
+                .login_user(&user.email, "a-wrong-password")
+                .await
+                .expect_err("Wrong password");
+            assert_eq!(response.status(), 401);
+        }
+        server.login_user(&user.email, &password).await.unwrap();
+
+        for _ in 0..3 {
+            let response = server
+                .login_user(&user.email, "a-wrong-password")
+                .await
+                .expect_err("Wrong password");
+            assert_eq!(response.status(), 401);
+        }
+        let response = server
+            .login_user(&user.email, &password)
+            .await
+            .expect_err("Even the right password is rejected during the lockout");
+        assert_eq!(response.status(), 429);
+        let retry_after: u64 = response.headers()["Retry-After"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0 && retry_after <= 15 * 60);
+    })
+}
+
+/// The status of a failed login of each of `emails`, each from a different forwarded address.
+fn failed_logins_behind_a_proxy(server: &mut TestApp, emails: &[String]) -> Vec<u16> {
+    emails
+        .iter()
+        .enumerate()
+        .map(|(i, email)| {
+            let body = json!({ "user": { "email": email, "password": "a-wrong-password" } });
+            let request = http::Request::post("/api/users/login")
+                .header("X-Forwarded-For", format!("198.51.100.{}", i + 1))
+                .body(body.to_string().into_bytes().into())
+                .unwrap();
+            server.server.simulate(request).unwrap().status().as_u16()
+        })
+        .collect()
+}
+
+#[test]
+fn failed_logins_are_throttled_per_client_address() {
+    task::block_on(async move {
+        let throttling = LoginThrottling {
+            max_attempts_per_ip: 2,
+            ..LoginThrottling::default()
+        };
+        let mut server = TestApp::with_login_throttling(throttling.clone());
+        let emails: Vec<String> = create_users(&server.repository.0, 3)
+            .into_iter()
+            .map(|(user, _)| user.email)
+            .collect();
+
+        // Clients cannot pick their own address
+        let statuses = failed_logins_behind_a_proxy(&mut server, &emails);
+        assert_eq!(statuses, vec![401, 401, 429]);
+
+        // Unless they are behind one of our proxies, which reports it
+        let mut server = TestApp::with_settings(AppSettings {
+            login_throttling: throttling,
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            ..AppSettings::default()
+        });
+        let statuses = failed_logins_behind_a_proxy(&mut server, &emails);
+        assert_eq!(statuses, vec![401, 401, 401]);
+    })
+}
+
+#[test]
+fn login_with_two_factor_authentication() {
+    task::block_on(async move {
+        let mut server = TestApp::new();
+        let (user, password) = create_users(&server.repository.0, 1).remove(0);
+        let token = encode_token(user.id);
+
+        let enrollment = server.enroll_two_factor(&token).await.unwrap().two_factor;
+        assert!(enrollment
+            .otpauth_uri
+            .starts_with("otpauth://totp/Conduit:"));
+        let secret = TotpSecret::from_base32(&enrollment.secret).unwrap();
+        // Not enforced until confirmed
+        server.login_user(&user.email, &password).await.unwrap();
+
+        let response = server
+            .confirm_two_factor("000000x", &token)
+            .await
+            .expect_err("Invalid code");
+        assert_eq!(response.status(), 422);
+        let confirmation_code = secret.code(Utc::now());
+        let recovery_codes = server
+            .confirm_two_factor(&confirmation_code, &token)
+            .await
+            .unwrap()
+            .recovery_codes;
+        assert_eq!(recovery_codes.len(), 10);
+
+        let challenge = server
+            .start_login(&user.email, &password)
+            .await
+            .unwrap()
+            .two_factor_challenge;
+        let response = server
+            .get_current_user(&challenge.token)
+            .await
+            .expect_err("Challenge tokens are not authentication tokens");
+        assert_eq!(response.status(), 401);
+        let response = server
+            .login_second_factor(&challenge.token, &confirmation_code)
+            .await
+            .expect_err("The code used for the confirmation cannot be replayed");
+        assert_eq!(response.status(), 401);
+        // Codes of the next time step are accepted, to tolerate clock drift
+        let next_code = secret.code(Utc::now() + chrono::Duration::seconds(30));
+        let logged_in = server
+            .login_second_factor(&challenge.token, &next_code)
+            .await
+            .unwrap();
+        server
+            .get_current_user(&logged_in.user.token)
+            .await
+            .unwrap();
+
+        // Recovery codes can be used once
+        server
+            .login_second_factor(&challenge.token, &recovery_codes[0])
+            .await
+            .unwrap();
+        let response = server
+            .login_second_factor(&challenge.token, &recovery_codes[0])
+            .await
+            .expect_err("Recovery codes are single-use");
+        assert_eq!(response.status(), 401);
+
+        server
+            .disable_two_factor(&recovery_codes[1], &token)
+            .await
+            .unwrap();
+        server.login_user(&user.email, &password).await.unwrap();
+    })
+}